@@ -3,13 +3,19 @@ use std::{cell::RefMut, fmt::Debug};
 
 use crate::{
     borrowing_market::borrowing_operations::utils::assert_not_zero,
-    stability_pool::{liquidations_queue, stability_pool_operations},
+    stability_pool::{
+        liquidations_queue, stability_pool_operations,
+        types::{LiquidationRouting, StabilityPoolTranche},
+    },
     staking_pool::staking_pool_operations,
     state::{
         epoch_to_scale_to_sum::EpochToScaleToSum, LiquidationEvent, LiquidationsQueue, UserStatus,
     },
     utils::{
-        consts::{BORROW_MIN, STABLECOIN_FACTOR},
+        consts::{
+            BORROW_MIN, INTEREST_RATE_ADJUSTMENT_COOLDOWN, MAX_INTEREST_RATE, MAX_STABILITY_POOLS,
+            STABLECOIN_FACTOR,
+        },
         coretypes::CheckedAssign,
     },
     BorrowError, BorrowingMarketState, CollateralAmounts, CollateralToken, StabilityPoolState,
@@ -41,8 +47,10 @@ use super::{
     borrowing_rate::{self, BorrowSplit, FeeEvent},
     liquidation_calcs::{self, SystemMode},
     types::{
-        BorrowStablecoinEffects, DepositAndBorrowEffects, DepositCollateralEffects,
-        LiquidationEffects, RepayLoanEffects, WithdrawCollateralEffects,
+        AdjustTroveEffects, BorrowStablecoinEffects, ClaimRedemptionSurplusEffects,
+        CloseTroveEffects, DepositAndBorrowEffects, DepositCollateralEffects,
+        MintAccruedInterestEffects, RepayLoanEffects, TrancheLiquidationEffects,
+        WithdrawCollateralEffects,
     },
 };
 
@@ -90,16 +98,18 @@ pub fn deposit_collateral(
     user: &mut UserMetadata,
     amount: u64,
     asset: CollateralToken,
+    now: u64,
 ) -> Result<DepositCollateralEffects, crate::BorrowError> {
     assert_not_zero(amount, BorrowError::CannotDepositZeroAmount)?;
     apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
 
     use utils::CollateralStatus::*;
     match UserStatus::from_u8(user.status) {
         Some(UserStatus::Active) => {
             utils::deposit_collateral(market, user, amount, asset, Deposited)
         }
-        Some(UserStatus::Inactive) => {
+        Some(UserStatus::Inactive) | Some(UserStatus::Redeemed) => {
             utils::deposit_collateral(market, user, amount, asset, Inactive)
         }
         _ => unreachable!(),
@@ -124,6 +134,7 @@ pub fn borrow_stablecoin(
 
     let old_debt = user.borrowed_stablecoin;
     apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
 
     let (mode, tcr) = liquidation_calcs::calc_system_mode(
         &market.deposited_collateral,
@@ -178,11 +189,15 @@ pub fn borrow_stablecoin(
     user.borrowed_stablecoin = new_debt;
 
     redistribution::update_user_stake_and_total_stakes(market, user);
+
+    // Interest accrued by all troves since the last borrow
+    // is minted along with the fees of this one
+    let interest = std::mem::take(&mut market.interest_not_yet_minted);
     let (staking_fee, treasury_fee) = staking_pool_operations::split_fees(
-        borrow_and_fee.fees_to_pay,
+        borrow_and_fee.fees_to_pay.checked_add(interest).unwrap(),
         staking_pool.treasury_fee_rate,
     );
-    staking_pool_operations::distribute_fees(staking_pool, staking_fee);
+    staking_pool_operations::distribute_fees(staking_pool, staking_fee, now);
 
     if old_debt == 0 && new_debt > 0 {
         user.status = UserStatus::Active as u8;
@@ -200,9 +215,11 @@ pub fn repay_loan(
     market: &mut BorrowingMarketState,
     user: &mut UserMetadata,
     amount: u64,
+    now: u64,
 ) -> Result<RepayLoanEffects, crate::BorrowError> {
     // If there was a redistribution event, update user's balance
     apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
     assert_not_zero(user.borrowed_stablecoin, BorrowError::NothingToRepay)?;
     assert_not_zero(amount, BorrowError::CannotRepayZeroAmount)?;
 
@@ -253,9 +270,11 @@ pub fn withdraw_collateral(
     requested_amount: u64,
     asset: CollateralToken,
     prices: &TokenPrices,
+    now: u64,
 ) -> Result<WithdrawCollateralEffects, crate::BorrowError> {
     assert_not_zero(requested_amount, BorrowError::CannotWithdrawZeroAmount)?;
     apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
 
     let user_inactive_token = user.inactive_collateral.token_amount(asset);
     let (withdrawing_active_amt, withdrawing_active, withdrawing_inactive) =
@@ -293,14 +312,153 @@ pub fn withdraw_collateral(
 
     redistribution::update_user_stake_and_total_stakes(market, user);
 
+    let close_user_metadata = user.inactive_collateral.is_zero()
+        && user.deposited_collateral.is_zero()
+        && user.borrowed_stablecoin == 0;
+    if close_user_metadata {
+        market.num_closed_users = market.num_closed_users.checked_add(1).unwrap();
+    }
+
     Ok(WithdrawCollateralEffects {
         collateral_to_transfer_to_user: CollateralAmounts::of_token(requested_amount, asset),
-        close_user_metadata: user.inactive_collateral.is_zero()
-            && user.deposited_collateral.is_zero()
-            && user.borrowed_stablecoin == 0,
+        close_user_metadata,
+    })
+}
+
+pub fn claim_redemption_surplus(
+    market: &mut BorrowingMarketState,
+    user: &mut UserMetadata,
+    asset: CollateralToken,
+    close: bool,
+) -> Result<ClaimRedemptionSurplusEffects, crate::BorrowError> {
+    // A fully redeemed trove is left with whatever collateral was
+    // not needed to pay off its debt, it's claimed one token at a
+    // time and the account can only be closed with the last one
+    if user.status != (UserStatus::Redeemed as u8) {
+        return Err(BorrowError::TroveNotRedeemed);
+    }
+
+    let surplus = CollateralAmounts::of_token(user.inactive_collateral.token_amount(asset), asset);
+    market.inactive_collateral.sub_assign(&surplus);
+    user.inactive_collateral.sub_assign(&surplus);
+
+    let fully_claimed = user.inactive_collateral.is_zero();
+    if fully_claimed {
+        user.status = UserStatus::Inactive as u8;
+    }
+
+    let close_user_metadata = close && fully_claimed;
+    if close_user_metadata {
+        market.num_closed_users = market.num_closed_users.checked_add(1).unwrap();
+    }
+
+    Ok(ClaimRedemptionSurplusEffects {
+        collateral_to_transfer_to_user: surplus,
+        close_user_metadata,
+    })
+}
+
+pub fn close_trove_account(
+    market: &mut BorrowingMarketState,
+    user: &UserMetadata,
+) -> Result<(), crate::BorrowError> {
+    // Redeemed troves close through claim_redemption_surplus
+    if user.status == (UserStatus::Active as u8)
+        || user.status == (UserStatus::Redeemed as u8)
+        || user.borrowed_stablecoin > 0
+        || !user.deposited_collateral.is_zero()
+        || !user.inactive_collateral.is_zero()
+    {
+        return Err(BorrowError::TroveAccountNotEmpty);
+    }
+
+    market.num_closed_users = market.num_closed_users.checked_add(1).unwrap();
+
+    Ok(())
+}
+
+pub fn close_trove(
+    market: &mut BorrowingMarketState,
+    user: &mut UserMetadata,
+    now: u64,
+) -> Result<CloseTroveEffects, crate::BorrowError> {
+    // Repays the whole debt, redistributed debt and interest
+    // included, and hands back all of the collateral
+    apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
+
+    let amount_to_burn = user.borrowed_stablecoin;
+    market.stablecoin_borrowed = market
+        .stablecoin_borrowed
+        .checked_sub(amount_to_burn)
+        .unwrap();
+
+    let collateral_to_transfer_to_user = user.deposited_collateral.add(&user.inactive_collateral);
+    market
+        .deposited_collateral
+        .sub_assign(&user.deposited_collateral);
+    market
+        .inactive_collateral
+        .sub_assign(&user.inactive_collateral);
+
+    if user.status == (UserStatus::Active as u8) {
+        market.num_active_users -= 1;
+    }
+
+    user.borrowed_stablecoin = 0;
+    user.deposited_collateral = CollateralAmounts::default();
+    user.inactive_collateral = CollateralAmounts::default();
+    user.status = UserStatus::Inactive as u8;
+
+    redistribution::update_user_stake_and_total_stakes(market, user);
+
+    close_trove_account(market, user)?;
+
+    Ok(CloseTroveEffects {
+        amount_to_burn,
+        collateral_to_transfer_to_user,
     })
 }
 
+pub fn set_interest_rate(
+    market: &mut BorrowingMarketState,
+    user: &mut UserMetadata,
+    interest_rate_bps: u16,
+    now: u64,
+) -> Result<(), crate::BorrowError> {
+    // Borrowers paying more interest are redeemed against later,
+    // what accrued so far is charged at the old rate
+    if interest_rate_bps > MAX_INTEREST_RATE {
+        return Err(BorrowError::InterestRateAboveMaximum);
+    }
+
+    apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
+
+    // Raising the rate, or changing it again before the cooldown
+    // is over, is charged upfront, otherwise a trove could sit at
+    // a low rate and only raise it when a redemption comes
+    let in_cooldown = now
+        < user
+            .last_interest_rate_change
+            .saturating_add(INTEREST_RATE_ADJUSTMENT_COOLDOWN);
+    if interest_rate_bps > user.interest_rate_bps || in_cooldown {
+        let fee = borrowing_rate::calc_interest_rate_adjustment_fee(
+            user.borrowed_stablecoin,
+            user.interest_rate_bps,
+            interest_rate_bps,
+        );
+        utils::charge_interest(market, user, fee)?;
+    }
+
+    user.interest_rate_bps = interest_rate_bps;
+    user.last_interest_rate_change = now;
+
+    redistribution::update_user_stake_and_total_stakes(market, user);
+
+    Ok(())
+}
+
 pub fn deposit_and_borrow(
     market: &mut BorrowingMarketState,
     user: &mut UserMetadata,
@@ -317,7 +475,7 @@ pub fn deposit_and_borrow(
 
     match (borrow, deposit) {
         (0, 0) => Err(BorrowError::CannotDepositZeroAmount),
-        (0, _) => Ok(deposit_collateral(market, user, deposit, deposit_asset)?.into()),
+        (0, _) => Ok(deposit_collateral(market, user, deposit, deposit_asset, now)?.into()),
         (_, 0) => Ok(borrow_stablecoin(market, user, staking_pool, borrow, prices, now)?.into()),
         (_, _) => {
             // First, deposit inactive collateral
@@ -346,6 +504,214 @@ pub fn deposit_and_borrow(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn adjust_trove(
+    market: &mut BorrowingMarketState,
+    user: &mut UserMetadata,
+    staking_pool: &mut StakingPoolState,
+    collateral_deltas: &[(CollateralToken, i64)],
+    debt_delta: i64,
+    prices: &TokenPrices,
+    now: u64,
+) -> Result<AdjustTroveEffects, crate::BorrowError> {
+    // Rebalances a trove in one go, for example repay and withdraw
+    // or swap one collateral for another. The position may go
+    // through an unhealthy state midway, solvency is only checked
+    // against the final one
+    utils::assert_valid_adjustment(collateral_deltas, debt_delta)?;
+
+    apply_pending_rewards(market, user)?;
+    utils::accrue_interest(market, user, now)?;
+
+    let old_debt = user.borrowed_stablecoin;
+    let (mode, tcr) = liquidation_calcs::calc_system_mode(
+        &market.deposited_collateral,
+        market.stablecoin_borrowed,
+        prices,
+    );
+
+    let mut effects = AdjustTroveEffects::default();
+
+    // 1. Move the collateral, withdrawing the inactive collateral first
+    let mut withdrawing_active = CollateralAmounts::default();
+    for &(asset, delta) in collateral_deltas {
+        let amount = delta.unsigned_abs();
+        if delta > 0 {
+            use utils::CollateralStatus::*;
+            match UserStatus::from_u8(user.status) {
+                Some(UserStatus::Active) => {
+                    utils::deposit_collateral(market, user, amount, asset, Deposited)
+                }
+                Some(UserStatus::Inactive) | Some(UserStatus::Redeemed) => {
+                    utils::deposit_collateral(market, user, amount, asset, Inactive)
+                }
+                _ => unreachable!(),
+            }
+            effects
+                .collateral_to_transfer_from_user
+                .add_assign(&CollateralAmounts::of_token(amount, asset));
+        } else {
+            let user_inactive_token = user.inactive_collateral.token_amount(asset);
+            let user_deposited_token = user.deposited_collateral.token_amount(asset);
+            if user_inactive_token + user_deposited_token < amount {
+                return Err(BorrowError::NotEnoughCollateral);
+            }
+
+            let inactive_amount = u64::min(user_inactive_token, amount);
+            let withdrawing_inactive = CollateralAmounts::of_token(inactive_amount, asset);
+            let withdrawing_deposited =
+                CollateralAmounts::of_token(amount - inactive_amount, asset);
+
+            market.inactive_collateral.sub_assign(&withdrawing_inactive);
+            market
+                .deposited_collateral
+                .sub_assign(&withdrawing_deposited);
+            user.inactive_collateral.sub_assign(&withdrawing_inactive);
+            user.deposited_collateral.sub_assign(&withdrawing_deposited);
+
+            withdrawing_active.add_assign(&withdrawing_deposited);
+            effects
+                .collateral_to_transfer_to_user
+                .add_assign(&CollateralAmounts::of_token(amount, asset));
+        }
+    }
+
+    // 2. Borrow or repay
+    if debt_delta > 0 {
+        let fee = match mode {
+            SystemMode::Normal => {
+                borrowing_rate::refresh_base_rate(market, FeeEvent::Borrowing, now)?;
+                borrowing_rate::calc_borrowing_fee(market.base_rate_bps)
+            }
+            SystemMode::Recovery => 0,
+        };
+
+        let borrow_and_fee = BorrowSplit::split_fees(debt_delta.unsigned_abs(), fee);
+        msg!("Borrowed {:?}", borrow_and_fee);
+
+        let new_debt = user
+            .borrowed_stablecoin
+            .checked_add(borrow_and_fee.amount_to_borrow)
+            .unwrap();
+        if new_debt < BORROW_MIN {
+            return Err(BorrowError::CannotBorrowLessThanMinimum);
+        }
+
+        // Same as borrow_stablecoin, the inactive collateral backs the loan
+        let user_inactive = user.inactive_collateral;
+        market.inactive_collateral.sub_assign(&user_inactive);
+        market.deposited_collateral.add_assign(&user_inactive);
+        user.deposited_collateral.add_assign(&user_inactive);
+        user.inactive_collateral = CollateralAmounts::default();
+
+        market
+            .stablecoin_borrowed
+            .checked_add_assign(borrow_and_fee.amount_to_borrow)?;
+        user.borrowed_stablecoin = new_debt;
+
+        let interest = std::mem::take(&mut market.interest_not_yet_minted);
+        let (staking_fee, treasury_fee) = staking_pool_operations::split_fees(
+            borrow_and_fee.fees_to_pay.checked_add(interest).unwrap(),
+            staking_pool.treasury_fee_rate,
+        );
+        staking_pool_operations::distribute_fees(staking_pool, staking_fee, now);
+
+        if old_debt == 0 {
+            user.status = UserStatus::Active as u8;
+            market.num_active_users += 1;
+        }
+
+        effects.amount_mint_to_user = borrow_and_fee.amount_to_borrow - borrow_and_fee.fees_to_pay;
+        effects.amount_mint_to_fees_vault = staking_fee;
+        effects.amount_mint_to_treasury_vault = treasury_fee;
+    } else if debt_delta < 0 {
+        assert_not_zero(user.borrowed_stablecoin, BorrowError::NothingToRepay)?;
+
+        let payment_amount = u64::min(user.borrowed_stablecoin, debt_delta.unsigned_abs());
+        market.stablecoin_borrowed = market
+            .stablecoin_borrowed
+            .checked_sub(payment_amount)
+            .unwrap();
+
+        let updated_stablecoin_borrowed = user.borrowed_stablecoin - payment_amount;
+        if updated_stablecoin_borrowed > 0 && updated_stablecoin_borrowed < BORROW_MIN {
+            return Err(BorrowError::TooLowDebt);
+        }
+        user.borrowed_stablecoin = updated_stablecoin_borrowed;
+
+        if updated_stablecoin_borrowed == 0 {
+            // Same as repay_loan, the collateral turns inactive
+            user.status = UserStatus::Inactive as u8;
+            market.num_active_users -= 1;
+
+            let user_deposited = user.deposited_collateral;
+            market.deposited_collateral.sub_assign(&user_deposited);
+            market.inactive_collateral.add_assign(&user_deposited);
+            user.inactive_collateral.add_assign(&user_deposited);
+            user.deposited_collateral = CollateralAmounts::default();
+        }
+
+        effects.amount_to_burn = payment_amount;
+    }
+
+    // 3. Check the final position, only if it became riskier
+    if user.borrowed_stablecoin > 0 && (debt_delta > 0 || !withdrawing_active.is_zero()) {
+        if mode == SystemMode::Recovery && !withdrawing_active.is_zero() {
+            return Err(BorrowError::CannotWithdrawInRecoveryMode);
+        }
+
+        liquidation_calcs::try_borrow(
+            0,
+            &market.deposited_collateral,
+            market.stablecoin_borrowed,
+            &user.deposited_collateral,
+            user.borrowed_stablecoin,
+            &user.inactive_collateral,
+            prices,
+            mode,
+            tcr,
+        )?;
+    }
+
+    redistribution::update_user_stake_and_total_stakes(market, user);
+
+    Ok(effects)
+}
+
+pub fn mint_accrued_interest(
+    market: &mut BorrowingMarketState,
+    staking_pool: &mut StakingPoolState,
+    now: u64,
+) -> MintAccruedInterestEffects {
+    // The interest is already part of the debt of the troves,
+    // the USDH backing it goes to the stakers and the treasury
+    let interest = std::mem::take(&mut market.interest_not_yet_minted);
+    let (staking_fee, treasury_fee) =
+        staking_pool_operations::split_fees(interest, staking_pool.treasury_fee_rate);
+    staking_pool_operations::distribute_fees(staking_pool, staking_fee, now);
+
+    MintAccruedInterestEffects {
+        amount_mint_to_fees_vault: staking_fee,
+        amount_mint_to_treasury_vault: treasury_fee,
+    }
+}
+
+pub fn register_stability_pool(
+    market: &mut BorrowingMarketState,
+    stability_pool_state: Pubkey,
+) -> Result<(), crate::BorrowError> {
+    let num_stability_pools = market.num_stability_pools as usize;
+    if num_stability_pools == MAX_STABILITY_POOLS {
+        return Err(BorrowError::TooManyStabilityPools);
+    }
+
+    market.stability_pools[num_stability_pools] = stability_pool_state;
+    market.num_stability_pools += 1;
+
+    Ok(())
+}
+
+#[cfg(test)]
 #[allow(clippy::too_many_arguments)]
 pub fn try_liquidate(
     liquidator: Pubkey,
@@ -356,21 +722,124 @@ pub fn try_liquidate(
     token_prices: &TokenPrices,
     liquidations_queue: &mut RefMut<LiquidationsQueue>,
     now_timestamp: u64,
-) -> Result<LiquidationEffects, crate::BorrowError> {
-    let liquidation_amounts =
-        liquidation::liquidate_user(market, user, stability_pool_state, token_prices)?;
+) -> Result<super::types::LiquidationEffects, crate::BorrowError> {
+    let TrancheLiquidationEffects {
+        liquidation_event,
+        usd_to_burn_from_stability_pool,
+        ..
+    } = try_liquidate_with_tranches(
+        liquidator,
+        market,
+        user,
+        stability_pool_state,
+        epoch_to_scale_to_sum,
+        token_prices,
+        liquidations_queue,
+        &mut [],
+        now_timestamp,
+    )?;
 
-    if liquidation_amounts.usd_debt_to_stability_pool > 0 {
+    Ok(super::types::LiquidationEffects {
+        liquidation_event,
+        usd_to_burn_from_stability_pool,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_liquidate_with_tranches(
+    liquidator: Pubkey,
+    market: &mut BorrowingMarketState,
+    user: &mut UserMetadata,
+    stability_pool_state: &mut StabilityPoolState,
+    epoch_to_scale_to_sum: &mut EpochToScaleToSum,
+    token_prices: &TokenPrices,
+    liquidations_queue: &mut RefMut<LiquidationsQueue>,
+    tranches: &mut [StabilityPoolTranche],
+    now_timestamp: u64,
+) -> Result<TrancheLiquidationEffects, crate::BorrowError> {
+    // Interest up to now is part of the debt being liquidated
+    utils::accrue_interest(market, user, now_timestamp)?;
+
+    // The main stability pool comes first, followed by the tranches
+    let stability_pools: Vec<&StabilityPoolState> = std::iter::once(&*stability_pool_state)
+        .chain(
+            tranches
+                .iter()
+                .map(|tranche| &*tranche.stability_pool_state),
+        )
+        .collect();
+
+    let (liquidation_amounts, routing) =
+        liquidation::liquidate_user(market, user, &stability_pools, token_prices)?;
+
+    let LiquidationRouting {
+        usd_debt_to_stability_pools,
+        coll_to_stability_pools,
+        ..
+    } = routing;
+
+    if usd_debt_to_stability_pools[0] > 0 {
         stability_pool_operations::liquidate(
             stability_pool_state,
             epoch_to_scale_to_sum,
-            liquidation_amounts.coll_to_stability_pool,
-            liquidation_amounts.usd_debt_to_stability_pool,
+            coll_to_stability_pools[0],
+            usd_debt_to_stability_pools[0],
             now_timestamp,
         )?;
     }
 
-    if liquidation_amounts.usd_debt_to_redistribute > 0 {
+    // The liquidator and clearer gains are taken from each pool's event
+    // pro rata to the collateral it receives, the main pool's event
+    // carries the rest, including the tokens no pool covers
+    let coll_to_all_stability_pools = coll_to_stability_pools
+        .iter()
+        .fold(CollateralAmounts::default(), |total, coll| total.add(coll));
+    let mut coll_to_liquidator = liquidation_amounts.coll_to_liquidator;
+    let mut coll_to_clearer = liquidation_amounts.coll_to_clearer;
+
+    for (i, tranche) in tranches.iter_mut().enumerate() {
+        let (usd_debt, coll) = (
+            usd_debt_to_stability_pools[i + 1],
+            coll_to_stability_pools[i + 1],
+        );
+        if usd_debt == 0 && coll.is_zero() {
+            continue;
+        }
+
+        stability_pool_operations::liquidate(
+            tranche.stability_pool_state,
+            tranche.epoch_to_scale_to_sum,
+            coll,
+            usd_debt,
+            now_timestamp,
+        )?;
+
+        let tranche_coll_to_liquidator = liquidation::pool_share(
+            &liquidation_amounts.coll_to_liquidator,
+            &coll,
+            &coll_to_all_stability_pools,
+        );
+        let tranche_coll_to_clearer = liquidation::pool_share(
+            &liquidation_amounts.coll_to_clearer,
+            &coll,
+            &coll_to_all_stability_pools,
+        );
+        coll_to_liquidator.sub_assign(&tranche_coll_to_liquidator);
+        coll_to_clearer.sub_assign(&tranche_coll_to_clearer);
+
+        let tranche_event = LiquidationEvent::new(
+            liquidator,
+            tranche_coll_to_liquidator,
+            tranche_coll_to_clearer,
+            coll,
+            now_timestamp,
+        );
+        liquidations_queue::add_liquidation_event(tranche_event, tranche.liquidations_queue)?;
+    }
+
+    if liquidation_amounts.usd_debt_to_redistribute > 0
+        || !liquidation_amounts.coll_to_redistribute.is_zero()
+    {
         redistribution::redistribute(
             market,
             liquidation_amounts.usd_debt_to_redistribute,
@@ -382,16 +851,17 @@ pub fn try_liquidate(
 
     let liquidation_event = LiquidationEvent::new(
         liquidator,
-        liquidation_amounts.coll_to_liquidator,
-        liquidation_amounts.coll_to_clearer,
-        liquidation_amounts.coll_to_stability_pool,
+        coll_to_liquidator,
+        coll_to_clearer,
+        coll_to_stability_pools[0],
         now_timestamp,
     );
     liquidations_queue::add_liquidation_event(liquidation_event, liquidations_queue)?;
 
-    Ok(LiquidationEffects {
+    Ok(TrancheLiquidationEffects {
         liquidation_event,
-        usd_to_burn_from_stability_pool: liquidation_amounts.usd_debt_to_stability_pool,
+        usd_to_burn_from_stability_pool: usd_debt_to_stability_pools[0],
+        usd_to_burn_from_tranches: usd_debt_to_stability_pools[1..].to_vec(),
     })
 }
 
@@ -406,7 +876,9 @@ pub fn refresh_positions(
 }
 
 pub mod utils {
-    use crate::state::CollateralToken;
+    use crate::{
+        borrowing_market::borrowing_rate, state::CollateralToken, utils::coretypes::CheckedAssign,
+    };
 
     use super::{BorrowingMarketState, CollateralAmounts, Pubkey, UserMetadata};
     pub enum CollateralStatus {
@@ -434,6 +906,40 @@ pub mod utils {
         }
     }
 
+    pub fn accrue_interest(
+        market: &mut BorrowingMarketState,
+        user: &mut UserMetadata,
+        now: u64,
+    ) -> Result<(), crate::BorrowError> {
+        // Interest is charged whenever the debt or the rate of
+        // the trove changes, for all the time since the last one
+        let interest = borrowing_rate::calc_accrued_interest(
+            user.borrowed_stablecoin,
+            user.interest_rate_bps,
+            user.last_interest_accrual,
+            now,
+        );
+
+        charge_interest(market, user, interest)?;
+        user.last_interest_accrual = u64::max(now, user.last_interest_accrual);
+
+        Ok(())
+    }
+
+    pub fn charge_interest(
+        market: &mut BorrowingMarketState,
+        user: &mut UserMetadata,
+        interest: u64,
+    ) -> Result<(), crate::BorrowError> {
+        user.borrowed_stablecoin.checked_add_assign(interest)?;
+        market.stablecoin_borrowed.checked_add_assign(interest)?;
+        market
+            .interest_not_yet_minted
+            .checked_add_assign(interest)?;
+
+        Ok(())
+    }
+
     pub fn set_addresses(user: &mut UserMetadata, owner: Pubkey, metadata: Pubkey) {
         user.owner = owner;
         user.metadata_pk = metadata;
@@ -446,6 +952,24 @@ pub mod utils {
             Ok(())
         }
     }
+
+    pub fn assert_valid_adjustment(
+        collateral_deltas: &[(CollateralToken, i64)],
+        debt_delta: i64,
+    ) -> Result<(), crate::BorrowError> {
+        let no_change = debt_delta == 0 && collateral_deltas.is_empty();
+        let zero_delta = collateral_deltas.iter().any(|&(_, delta)| delta == 0);
+        let repeated_token = collateral_deltas
+            .iter()
+            .enumerate()
+            .any(|(i, &(token, _))| collateral_deltas[..i].iter().any(|&(t, _)| t == token));
+
+        if no_change || zero_delta || repeated_token {
+            Err(crate::BorrowError::InvalidTroveAdjustment)
+        } else {
+            Ok(())
+        }
+    }
 }
 
 pub mod redistribution {
@@ -636,7 +1160,8 @@ mod liquidation {
 
     use crate::{
         borrowing_market::liquidation_calcs::{self},
-        state::UserStatus,
+        stability_pool::{stability_pool_operations, types::LiquidationRouting},
+        state::{CollateralToken, UserStatus},
         BorrowError, BorrowingMarketState, CollateralAmounts, StabilityPoolState, TokenPrices,
         UserMetadata,
     };
@@ -649,7 +1174,7 @@ mod liquidation {
     fn calculate_liquidation_effects(
         market: &BorrowingMarketState,
         user: &UserMetadata,
-        stability_pool_state: &StabilityPoolState,
+        stability_pools: &[&StabilityPoolState],
         prices: &TokenPrices,
     ) -> Result<(UserBalances, LiquidationBreakdownAmounts), crate::BorrowError> {
         // apply pending redistribution amounts
@@ -664,12 +1189,19 @@ mod liquidation {
             .user_current_collateral
             .add(&user_balances.user_pending_collateral);
 
+        // How much the pools covering this collateral can offset
+        let usdh_in_sp = stability_pool_operations::stability_pools_capacity(
+            stability_pools,
+            &total_user_collateral,
+            prices,
+        );
+
         let liquidation_breakdown = liquidation_calcs::calculate_liquidation_effects(
             total_user_debt,
             &total_user_collateral,
             market.stablecoin_borrowed,
             &market.deposited_collateral,
-            stability_pool_state.stablecoin_deposited,
+            usdh_in_sp,
             prices,
         )?;
 
@@ -681,16 +1213,35 @@ mod liquidation {
     pub fn liquidate_user(
         market: &mut BorrowingMarketState,
         user: &mut UserMetadata,
-        stability_pool_state: &StabilityPoolState,
+        stability_pools: &[&StabilityPoolState],
         token_prices: &TokenPrices,
-    ) -> Result<LiquidationBreakdownAmounts, crate::BorrowError> {
+    ) -> Result<(LiquidationBreakdownAmounts, LiquidationRouting), crate::BorrowError> {
         if market.num_active_users <= 1 {
             msg!("Last user, cannot liquidate the last user");
             return Err(BorrowError::LastUser);
         }
 
-        let (user_balances, liquidation_amounts) =
-            calculate_liquidation_effects(market, user, stability_pool_state, token_prices)?;
+        let (user_balances, mut liquidation_amounts) =
+            calculate_liquidation_effects(market, user, stability_pools, token_prices)?;
+
+        // Split the stability pool share between the pools,
+        // what none of them covers is redistributed instead
+        let routing = stability_pool_operations::route_liquidation(
+            stability_pools,
+            liquidation_amounts.usd_debt_to_stability_pool,
+            &liquidation_amounts.coll_to_stability_pool,
+            token_prices,
+        );
+        if routing.usd_debt_not_covered > 0 || !routing.coll_not_covered.is_zero() {
+            liquidation_amounts.usd_debt_to_stability_pool -= routing.usd_debt_not_covered;
+            liquidation_amounts.usd_debt_to_redistribute += routing.usd_debt_not_covered;
+            liquidation_amounts.coll_to_stability_pool = liquidation_amounts
+                .coll_to_stability_pool
+                .sub(&routing.coll_not_covered);
+            liquidation_amounts
+                .coll_to_redistribute
+                .add_assign(&routing.coll_not_covered);
+        }
 
         let remaining_user_coll = user
             .deposited_collateral
@@ -724,7 +1275,30 @@ mod liquidation {
 
         redistribution::remove_stake(market, user);
 
-        Ok(liquidation_amounts)
+        Ok((liquidation_amounts, routing))
+    }
+
+    pub fn pool_share(
+        amounts: &CollateralAmounts,
+        coll_to_pool: &CollateralAmounts,
+        coll_to_all_pools: &CollateralAmounts,
+    ) -> CollateralAmounts {
+        let mut share = CollateralAmounts::default();
+        for token in (0..6).map(CollateralToken::from) {
+            let total = coll_to_all_pools.token_amount(token);
+            if total == 0 {
+                continue;
+            }
+
+            let amount = (amounts.token_amount(token) as u128)
+                .checked_mul(coll_to_pool.token_amount(token) as u128)
+                .unwrap()
+                .checked_div(total as u128)
+                .unwrap() as u64;
+            share.add_assign(&CollateralAmounts::of_token(amount, token));
+        }
+
+        share
     }
 
     pub fn update_system_snapshots_after_liquidation(market: &mut BorrowingMarketState, debt: u64) {
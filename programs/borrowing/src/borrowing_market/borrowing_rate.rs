@@ -1,6 +1,6 @@
 use crate::utils::consts::{
-    BORROWING_FEE_FLOOR, MAX_BORROWING_FEE, MAX_REDEMPTION_FEE, MINUTE_DECAY_FACTOR,
-    REDEMPTION_FEE_FLOOR, SECONDS_PER_MINUTE,
+    BORROWING_FEE_FLOOR, INTEREST_RATE_ADJUSTMENT_COOLDOWN, MAX_BORROWING_FEE, MAX_REDEMPTION_FEE,
+    MINUTE_DECAY_FACTOR, REDEMPTION_FEE_FLOOR, SECONDS_PER_MINUTE, SECONDS_PER_YEAR,
 };
 use crate::BorrowingMarketState;
 use decimal_wad::{
@@ -76,6 +76,40 @@ pub(crate) fn calc_borrowing_fee(base_rate: u16) -> u16 {
     )
 }
 
+pub(crate) fn calc_accrued_interest(
+    debt: u64,
+    interest_rate_bps: u16,
+    last_interest_accrual: u64,
+    now: u64,
+) -> u64 {
+    // Simple interest since the last accrual
+    let seconds_diff = now.saturating_sub(last_interest_accrual);
+    let scaled_interest = Decimal::from(debt)
+        .try_mul(Rate::from_bps(interest_rate_bps))
+        .unwrap()
+        .try_mul(seconds_diff)
+        .unwrap()
+        .try_div(SECONDS_PER_YEAR)
+        .unwrap();
+    // favour the protocol & stakers
+    scaled_interest.try_ceil_u64().unwrap()
+}
+
+pub(crate) fn calc_interest_rate_adjustment_fee(
+    debt: u64,
+    old_interest_rate_bps: u16,
+    new_interest_rate_bps: u16,
+) -> u64 {
+    // A cooldown's worth of interest at the higher of the two rates,
+    // so that jumping ahead of a redemption costs more than it saves
+    calc_accrued_interest(
+        debt,
+        u16::max(old_interest_rate_bps, new_interest_rate_bps),
+        0,
+        INTEREST_RATE_ADJUSTMENT_COOLDOWN,
+    )
+}
+
 pub(crate) fn decay_base_rate(base_rate: u16, last_fee_event: u64, now: u64) -> u16 {
     // Due to borrowing
 
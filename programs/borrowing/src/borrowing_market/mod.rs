@@ -1,6 +1,9 @@
 pub mod borrowing_operations;
 pub mod borrowing_rate;
 pub mod liquidation_calcs;
+pub mod sorted_troves;
+#[cfg(test)]
+pub mod tests_adjust_trove;
 #[cfg(test)]
 pub mod tests_borrowing_multi_collateral;
 #[cfg(test)]
@@ -8,6 +11,8 @@ pub mod tests_borrowing_rate;
 #[cfg(test)]
 pub mod tests_borrowing_single_collateral;
 #[cfg(test)]
+pub mod tests_close_trove;
+#[cfg(test)]
 pub mod tests_deposit_and_borrow;
 #[cfg(test)]
 pub mod tests_liquidation_calcs;
@@ -16,5 +21,7 @@ pub mod tests_redistribution_multi_collateral;
 #[cfg(test)]
 pub mod tests_redistribution_single_collateral;
 #[cfg(test)]
+pub mod tests_sorted_troves;
+#[cfg(test)]
 pub mod tests_utils;
 pub mod types;
@@ -0,0 +1,160 @@
+use std::convert::TryFrom;
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::{
+    borrowing_market::borrowing_operations::{
+        apply_pending_rewards, redistribution::update_user_stake_and_total_stakes,
+        utils::accrue_interest,
+    },
+    state::UserStatus,
+    utils::{consts::NORMAL_MCR, finance::CollateralInfo},
+    BorrowError, BorrowingMarketState, SortedTrove, SortedTroves, TokenPrices, UserMetadata,
+};
+
+// Keeps the active troves in the order they get redeemed against,
+// lowest interest rate first, then lowest collateral ratio.
+// Multi collateral troves don't keep their order as prices move, so
+// each entry holds the ratio as of the last time the trove was
+// repositioned, either by an instruction that changed the trove or by
+// anyone refreshing it at current prices.
+// Every instruction that changes a trove's debt, collateral, rate or
+// status repositions it, so the index never keeps a stale entry for it.
+// The index holds at most 1000 troves. Once full, a trove that is not
+// in it yet cannot be added and the instruction fails, so every active
+// trove with debt stays within reach of instant redemptions.
+
+pub fn initialize_sorted_troves(sorted_troves: &mut SortedTroves, market: Pubkey) {
+    sorted_troves.borrowing_market_state = market;
+    sorted_troves.len = 0;
+}
+
+pub fn len(sorted_troves: &SortedTroves) -> usize {
+    sorted_troves.len as usize
+}
+
+pub fn refresh_trove(
+    market: &mut BorrowingMarketState,
+    sorted_troves: &mut SortedTroves,
+    user: &mut UserMetadata,
+    prices: &TokenPrices,
+    now: u64,
+) -> Result<(), BorrowError> {
+    // The ratio has to account for redistributions
+    // the trove has not picked up yet, and for interest
+    if user.status == (UserStatus::Active as u8) {
+        apply_pending_rewards(market, user)?;
+        accrue_interest(market, user, now)?;
+        update_user_stake_and_total_stakes(market, user);
+    }
+
+    reposition_trove(sorted_troves, user, prices)
+}
+
+pub fn reposition_trove(
+    sorted_troves: &mut SortedTroves,
+    user: &UserMetadata,
+    prices: &TokenPrices,
+) -> Result<(), BorrowError> {
+    remove_trove(sorted_troves, user.user_id);
+
+    if user.status != (UserStatus::Active as u8) || user.borrowed_stablecoin == 0 {
+        return Ok(());
+    }
+
+    insert_trove(sorted_troves, current_trove(user, prices)?)
+}
+
+pub fn current_trove(
+    user: &UserMetadata,
+    prices: &TokenPrices,
+) -> Result<SortedTrove, BorrowError> {
+    let CollateralInfo {
+        collateral_ratio, ..
+    } = CollateralInfo::from(user, prices);
+    let collateral_ratio_bps = u64::try_from(collateral_ratio.to_bps()?).unwrap_or(u64::MAX);
+
+    Ok(SortedTrove {
+        user_id: user.user_id,
+        user_metadata: user.metadata_pk,
+        collateral_ratio_bps,
+        interest_rate_bps: user.interest_rate_bps,
+    })
+}
+
+pub fn remove_trove(sorted_troves: &mut SortedTroves, user_id: u64) {
+    let len = len(sorted_troves);
+    if let Some(position) = sorted_troves.troves[..len]
+        .iter()
+        .position(|trove| trove.user_id == user_id)
+    {
+        sorted_troves
+            .troves
+            .copy_within(position + 1..len, position);
+        sorted_troves.troves[len - 1] = SortedTrove::default();
+        sorted_troves.len -= 1;
+    }
+}
+
+fn insert_trove(sorted_troves: &mut SortedTroves, trove: SortedTrove) -> Result<(), BorrowError> {
+    // Troves with the same rate and ratio keep the order they were inserted in
+    let len = len(sorted_troves);
+    if len == sorted_troves.troves.len() {
+        return Err(BorrowError::SortedTrovesFull);
+    }
+
+    let position = sorted_troves.troves[..len]
+        .partition_point(|other| redemption_order(other) <= redemption_order(&trove));
+    sorted_troves
+        .troves
+        .copy_within(position..len, position + 1);
+    sorted_troves.troves[position] = trove;
+    sorted_troves.len += 1;
+
+    Ok(())
+}
+
+pub fn assert_lowest_troves(
+    sorted_troves: &SortedTroves,
+    submitted: &[SortedTrove],
+) -> Result<(), BorrowError> {
+    // The submitted troves are taken at their current ratio, they
+    // have to be redeemable, in order, and no other redeemable
+    // trove in the index may come before the last of them.
+    // Troves under the minimum collateral ratio are up for
+    // liquidation and are skipped.
+    let mcr_bps = NORMAL_MCR as u64 * 100;
+    let troves = &sorted_troves.troves[..len(sorted_troves)];
+
+    for (i, trove) in submitted.iter().enumerate() {
+        if trove.collateral_ratio_bps < mcr_bps
+            || (i > 0 && redemption_order(&submitted[i - 1]) > redemption_order(trove))
+            || !troves
+                .iter()
+                .any(|other| other.user_metadata == trove.user_metadata)
+        {
+            return Err(BorrowError::TrovesNotLowestInIndex);
+        }
+    }
+
+    let highest = match submitted.last() {
+        Some(trove) => redemption_order(trove),
+        None => return Ok(()),
+    };
+    let skipped = troves.iter().any(|trove| {
+        trove.collateral_ratio_bps >= mcr_bps
+            && redemption_order(trove) < highest
+            && !submitted
+                .iter()
+                .any(|other| other.user_metadata == trove.user_metadata)
+    });
+    if skipped {
+        return Err(BorrowError::TrovesNotLowestInIndex);
+    }
+
+    Ok(())
+}
+
+fn redemption_order(trove: &SortedTrove) -> (u16, u64) {
+    (trove.interest_rate_bps, trove.collateral_ratio_bps)
+}
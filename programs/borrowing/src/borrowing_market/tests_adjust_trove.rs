@@ -0,0 +1,271 @@
+use crate::{
+    borrowing_market::{borrowing_operations, types::AdjustTroveEffects},
+    state::CollateralToken::{self, *},
+    utils::{coretypes::USDH, math::coll_to_lamports},
+    BorrowError, BorrowingMarketState, StakingPoolState, TokenPrices, UserMetadata,
+};
+// Tests
+// - [x] Open a position in a single adjustment
+// - [x] Repay and withdraw when withdrawing alone would fail
+// - [x] Swap one collateral for another
+// - [x] Swap leaving the trove undercollateralized is not allowed
+// - [x] Close a position in a single adjustment
+// - [x] Invalid adjustments
+
+#[test]
+fn test_adjust_trove_open_position() {
+    let (mut market, mut spool, px, now) = utils::setup();
+    let mut user = UserMetadata::default();
+    borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
+
+    let AdjustTroveEffects {
+        collateral_to_transfer_from_user,
+        amount_mint_to_user,
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        amount_to_burn,
+        ..
+    } = borrowing_operations::adjust_trove(
+        &mut market,
+        &mut user,
+        &mut spool,
+        &[(SOL, coll_to_lamports(100.0, SOL) as i64)],
+        USDH::from(600.0) as i64,
+        &px,
+        now,
+    )
+    .unwrap();
+
+    assert_eq!(user.deposited_collateral.sol, coll_to_lamports(100.0, SOL));
+    assert_eq!(user.inactive_collateral.sol, 0);
+    assert_eq!(user.borrowed_stablecoin, USDH::from(603.0));
+    assert_eq!(market.num_active_users, 1);
+
+    assert_eq!(
+        collateral_to_transfer_from_user.sol,
+        coll_to_lamports(100.0, SOL)
+    );
+    assert_eq!(amount_mint_to_user, USDH::from(600.0));
+    assert_eq!(
+        amount_mint_to_fees_vault + amount_mint_to_treasury_vault,
+        USDH::from(3.0)
+    );
+    assert_eq!(amount_to_burn, 0);
+}
+
+#[test]
+fn test_adjust_trove_repay_and_withdraw() {
+    let (mut market, mut spool, px, now, mut user) = utils::setup_with_user();
+
+    // 800 / 603, withdrawing alone brings the system to recovery mode
+    let res = borrowing_operations::withdraw_collateral(
+        &mut market,
+        &mut user,
+        coll_to_lamports(20.0, SOL),
+        SOL,
+        &px,
+        0,
+    );
+    assert_eq!(
+        res.err().unwrap(),
+        BorrowError::OperationBringsSystemToRecoveryMode
+    );
+
+    // 800 / 403 once repaid
+    let AdjustTroveEffects {
+        collateral_to_transfer_to_user,
+        amount_to_burn,
+        amount_mint_to_user,
+        ..
+    } = borrowing_operations::adjust_trove(
+        &mut market,
+        &mut user,
+        &mut spool,
+        &[(SOL, -(coll_to_lamports(20.0, SOL) as i64))],
+        -(USDH::from(200.0) as i64),
+        &px,
+        now,
+    )
+    .unwrap();
+
+    assert_eq!(user.deposited_collateral.sol, coll_to_lamports(80.0, SOL));
+    assert_eq!(user.borrowed_stablecoin, USDH::from(403.0));
+    assert_eq!(market.deposited_collateral.sol, coll_to_lamports(80.0, SOL));
+    assert_eq!(market.stablecoin_borrowed, USDH::from(403.0));
+
+    assert_eq!(
+        collateral_to_transfer_to_user.sol,
+        coll_to_lamports(20.0, SOL)
+    );
+    assert_eq!(amount_to_burn, USDH::from(200.0));
+    assert_eq!(amount_mint_to_user, 0);
+}
+
+#[test]
+fn test_adjust_trove_swap_collateral() {
+    let (mut market, mut spool, px, now, mut user) = utils::setup_with_user();
+
+    borrowing_operations::adjust_trove(
+        &mut market,
+        &mut user,
+        &mut spool,
+        &[
+            (ETH, coll_to_lamports(40.0, ETH) as i64),
+            (SOL, -(coll_to_lamports(40.0, SOL) as i64)),
+        ],
+        0,
+        &px,
+        now,
+    )
+    .unwrap();
+
+    // Deposited straight as active collateral
+    assert_eq!(user.deposited_collateral.sol, coll_to_lamports(60.0, SOL));
+    assert_eq!(user.deposited_collateral.eth, coll_to_lamports(40.0, ETH));
+    assert_eq!(user.inactive_collateral.eth, 0);
+    assert_eq!(market.deposited_collateral.eth, coll_to_lamports(40.0, ETH));
+    assert_eq!(user.borrowed_stablecoin, USDH::from(603.0));
+}
+
+#[test]
+fn test_adjust_trove_swap_collateral_undercollateralized_disallowed() {
+    let (mut market, mut spool, px, now, mut user) = utils::setup_with_user();
+
+    // 700 / 603
+    let res = borrowing_operations::adjust_trove(
+        &mut market,
+        &mut user,
+        &mut spool,
+        &[
+            (ETH, coll_to_lamports(10.0, ETH) as i64),
+            (SOL, -(coll_to_lamports(40.0, SOL) as i64)),
+        ],
+        0,
+        &px,
+        now,
+    );
+
+    assert_eq!(
+        res.err().unwrap(),
+        BorrowError::OperationBringsSystemToRecoveryMode
+    );
+}
+
+#[test]
+fn test_adjust_trove_close_position() {
+    let (mut market, mut spool, px, now, mut user) = utils::setup_with_user();
+
+    let AdjustTroveEffects {
+        collateral_to_transfer_to_user,
+        amount_to_burn,
+        ..
+    } = borrowing_operations::adjust_trove(
+        &mut market,
+        &mut user,
+        &mut spool,
+        &[(SOL, -(coll_to_lamports(100.0, SOL) as i64))],
+        -(USDH::from(603.0) as i64),
+        &px,
+        now,
+    )
+    .unwrap();
+
+    assert_eq!(user.status, 0);
+    assert!(user.deposited_collateral.is_zero());
+    assert!(user.inactive_collateral.is_zero());
+    assert_eq!(user.borrowed_stablecoin, 0);
+    assert_eq!(market.num_active_users, 0);
+    assert!(market.deposited_collateral.is_zero());
+    assert!(market.inactive_collateral.is_zero());
+
+    assert_eq!(
+        collateral_to_transfer_to_user.sol,
+        coll_to_lamports(100.0, SOL)
+    );
+    assert_eq!(amount_to_burn, USDH::from(603.0));
+}
+
+#[test]
+fn test_adjust_trove_invalid_adjustments() {
+    let (mut market, mut spool, px, now, mut user) = utils::setup_with_user();
+
+    let invalid: [(&[(CollateralToken, i64)], i64); 3] = [
+        (&[], 0),
+        (&[(SOL, 0)], USDH::from(10.0) as i64),
+        (&[(SOL, 1), (SOL, -1)], 0),
+    ];
+    for (collateral_deltas, debt_delta) in invalid {
+        let res = borrowing_operations::adjust_trove(
+            &mut market,
+            &mut user,
+            &mut spool,
+            collateral_deltas,
+            debt_delta,
+            &px,
+            now,
+        );
+        assert_eq!(res.err().unwrap(), BorrowError::InvalidTroveAdjustment);
+    }
+
+    let res = borrowing_operations::adjust_trove(
+        &mut market,
+        &mut user,
+        &mut spool,
+        &[(ETH, -1)],
+        0,
+        &px,
+        now,
+    );
+    assert_eq!(res.err().unwrap(), BorrowError::NotEnoughCollateral);
+}
+
+mod utils {
+    use crate::borrowing_market::borrowing_operations;
+
+    use super::*;
+
+    pub fn setup() -> (BorrowingMarketState, StakingPoolState, TokenPrices, u64) {
+        let mut market = BorrowingMarketState::new();
+        let spool = StakingPoolState {
+            treasury_fee_rate: 1_500, // bps: 15%
+            ..Default::default()
+        };
+
+        borrowing_operations::initialize_borrowing_market(&mut market, 0);
+
+        (market, spool, TokenPrices::new_all(10.0), 0)
+    }
+
+    pub fn setup_with_user() -> (
+        BorrowingMarketState,
+        StakingPoolState,
+        TokenPrices,
+        u64,
+        UserMetadata,
+    ) {
+        // 100 SOL worth 1000 USDH backing 603 USDH of debt
+        let (mut market, mut spool, px, now) = setup();
+        let mut user = UserMetadata::default();
+
+        borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
+        borrowing_operations::deposit_collateral(
+            &mut market,
+            &mut user,
+            coll_to_lamports(100.0, SOL),
+            SOL,
+            0,
+        )
+        .unwrap();
+        borrowing_operations::borrow_stablecoin(
+            &mut market,
+            &mut user,
+            &mut spool,
+            USDH::from(600.0),
+            &px,
+            now,
+        )
+        .unwrap();
+
+        (market, spool, px, now, user)
+    }
+}
@@ -32,7 +32,7 @@ mod tests {
             (coll_to_lamports(8.3, FTT), FTT),
         ];
         for (amount, asset) in deposits {
-            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                 .unwrap();
         }
 
@@ -69,7 +69,7 @@ mod tests {
             borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
 
             for (amount, asset) in deposits {
-                borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+                borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                     .unwrap();
             }
             let inactive = user.inactive_collateral;
@@ -110,7 +110,7 @@ mod tests {
         borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
 
         for (amount, asset) in deposits {
-            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                 .unwrap();
         }
 
@@ -179,7 +179,7 @@ mod tests {
             borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
 
             for (amount, asset) in deposits {
-                borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+                borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                     .unwrap();
             }
 
@@ -240,7 +240,7 @@ mod tests {
         borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
 
         for (amount, asset) in deposits {
-            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                 .unwrap();
         }
 
@@ -254,7 +254,8 @@ mod tests {
         )
         .unwrap();
 
-        borrowing_operations::repay_loan(&mut market, &mut user, total_debt).unwrap();
+        borrowing_operations::repay_loan(&mut market, &mut user, total_debt, now_timestamp)
+            .unwrap();
         let mkt_inac = market.inactive_collateral;
         let usr_inac = user.inactive_collateral;
 
@@ -327,6 +328,7 @@ mod tests {
             &mut last_user,
             last_user_sol,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
         borrowing_operations::borrow_stablecoin(
@@ -340,7 +342,7 @@ mod tests {
         .unwrap();
 
         for (amount, asset) in deposits {
-            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                 .unwrap();
         }
 
@@ -360,6 +362,7 @@ mod tests {
             withdrawable,
             CollateralToken::SOL,
             &prices,
+            0,
         )
         .unwrap();
 
@@ -421,7 +424,7 @@ mod tests {
         borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
 
         for (amount, asset) in deposits {
-            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset)
+            borrowing_operations::deposit_collateral(&mut market, &mut user, amount, asset, 0)
                 .unwrap();
         }
 
@@ -449,6 +452,7 @@ mod tests {
             withdrawable_lamports * 2,
             CollateralToken::SOL,
             &prices,
+            0,
         );
         assert!(err.is_err());
     }
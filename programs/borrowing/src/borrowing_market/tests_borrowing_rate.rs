@@ -8,24 +8,27 @@ mod tests {
         borrowing_market::{
             borrowing_operations,
             borrowing_rate::{
-                calc_borrowing_fee, calc_redemption_fee, decay_base_rate, increase_base_rate,
-                refresh_base_rate, FeeEvent,
+                calc_accrued_interest, calc_borrowing_fee, calc_interest_rate_adjustment_fee,
+                calc_redemption_fee, decay_base_rate, increase_base_rate, refresh_base_rate,
+                FeeEvent,
             },
             tests_utils::utils::{
                 new_borrower, new_borrowing_users_with_amounts,
                 new_borrowing_users_with_amounts_and_price,
             },
-            types::BorrowStablecoinEffects,
+            types::{BorrowStablecoinEffects, MintAccruedInterestEffects},
         },
         redemption::test_redemptions::utils::{
             add_fill_and_clear_order, setup_redemption_borrowing_program,
         },
         state::CollateralToken,
         utils::{
+            consts::{MAX_INTEREST_RATE, SECONDS_PER_YEAR},
             coretypes::{SOL, USDH},
             finance::CollateralInfo,
         },
-        BorrowingMarketState, CollateralAmounts, StakingPoolState, TokenPrices, UserMetadata,
+        BorrowError, BorrowingMarketState, CollateralAmounts, StakingPoolState, TokenPrices,
+        UserMetadata,
     };
     // ## Tests borrowing fee
     // - [x] 50 bps normal mode
@@ -51,7 +54,8 @@ mod tests {
 
         borrowing_operations::initialize_borrowing_market(&mut market, 0);
         borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
-        borrowing_operations::deposit_collateral(&mut market, &mut user, deposit, token).unwrap();
+        borrowing_operations::deposit_collateral(&mut market, &mut user, deposit, token, 0)
+            .unwrap();
         let BorrowStablecoinEffects {
             amount_mint_to_user,
             amount_mint_to_fees_vault,
@@ -653,4 +657,151 @@ mod tests {
         assert_eq!(market.inactive_collateral.sol, SOL::from(20000.0 + 990.0));
         assert_eq!(market.deposited_collateral.sol, SOL::from(80000.0 - 990.0));
     }
+
+    #[test]
+    fn test_interest_accrues_into_debt_and_minted_with_next_borrow() {
+        let mut market = BorrowingMarketState::new();
+        let mut staking_pool = StakingPoolState::default();
+        let prices = TokenPrices::new(1.0);
+        let (deposit, token) = (sol_to_lamports(10000.0), CollateralToken::SOL);
+        let borrow = USDH::from(1000.0);
+
+        borrowing_operations::initialize_borrowing_market(&mut market, 0);
+        let mut users = [UserMetadata::default(), UserMetadata::default()];
+        for user in users.iter_mut() {
+            borrowing_operations::approve_trove(&mut market, user).unwrap();
+            borrowing_operations::deposit_collateral(&mut market, user, deposit, token, 0).unwrap();
+        }
+
+        borrowing_operations::borrow_stablecoin(
+            &mut market,
+            &mut users[0],
+            &mut staking_pool,
+            borrow,
+            &prices,
+            0,
+        )
+        .unwrap();
+        assert_eq!(users[0].borrowed_stablecoin, USDH::from(1005.0));
+
+        assert_eq!(
+            borrowing_operations::set_interest_rate(&mut market, &mut users[0], 2501, 0).err(),
+            Some(BorrowError::InterestRateAboveMaximum)
+        );
+        // Raising the rate is charged a week of interest upfront
+        let upfront_fee = calc_interest_rate_adjustment_fee(USDH::from(1005.0), 0, 1000);
+        assert_eq!(upfront_fee, 1927398);
+        borrowing_operations::set_interest_rate(&mut market, &mut users[0], 1000, 0).unwrap();
+        let debt = USDH::from(1005.0) + upfront_fee;
+        assert_eq!(users[0].borrowed_stablecoin, debt);
+
+        // 10% of the debt a year later, lowering
+        // the rate after the cooldown is free
+        let one_year = SECONDS_PER_YEAR;
+        let interest = calc_accrued_interest(debt, 1000, 0, one_year);
+        borrowing_operations::set_interest_rate(&mut market, &mut users[0], 500, one_year).unwrap();
+        assert_eq!(users[0].borrowed_stablecoin, debt + interest);
+        assert_eq!(market.stablecoin_borrowed, debt + interest);
+        assert_eq!(market.interest_not_yet_minted, upfront_fee + interest);
+        assert_eq!(users[0].last_interest_accrual, one_year);
+        assert_eq!(users[0].last_interest_rate_change, one_year);
+
+        // Changing it again within the cooldown is charged
+        // at the higher of the two rates, even when lowering
+        let debt = users[0].borrowed_stablecoin;
+        borrowing_operations::set_interest_rate(&mut market, &mut users[0], 0, one_year).unwrap();
+        assert_eq!(
+            users[0].borrowed_stablecoin,
+            debt + calc_interest_rate_adjustment_fee(debt, 500, 0)
+        );
+
+        // The instruction that charged it mints it to the stakers
+        let MintAccruedInterestEffects {
+            amount_mint_to_fees_vault,
+            amount_mint_to_treasury_vault,
+        } = borrowing_operations::mint_accrued_interest(&mut market, &mut staking_pool, one_year);
+        assert_eq!(
+            amount_mint_to_fees_vault + amount_mint_to_treasury_vault,
+            users[0].borrowed_stablecoin - USDH::from(1005.0)
+        );
+        assert_eq!(market.interest_not_yet_minted, 0);
+
+        // The next borrower only pays their own fee
+        let BorrowStablecoinEffects {
+            amount_mint_to_fees_vault,
+            ..
+        } = borrowing_operations::borrow_stablecoin(
+            &mut market,
+            &mut users[1],
+            &mut staking_pool,
+            borrow,
+            &prices,
+            one_year,
+        )
+        .unwrap();
+        assert_eq!(amount_mint_to_fees_vault, USDH::from(5.0));
+        assert_eq!(users[1].borrowed_stablecoin, USDH::from(1005.0));
+    }
+
+    #[test]
+    fn test_interest_accrues_before_withdrawing_collateral() {
+        let mut market = BorrowingMarketState::new();
+        let mut staking_pool = StakingPoolState::default();
+        let prices = TokenPrices::new(1.0);
+        let (deposit, token) = (sol_to_lamports(10000.0), CollateralToken::SOL);
+
+        borrowing_operations::initialize_borrowing_market(&mut market, 0);
+        let mut users = [UserMetadata::default(), UserMetadata::default()];
+        for (user, (deposit, borrow)) in users.iter_mut().zip([
+            (deposit, USDH::from(5000.0)),
+            (sol_to_lamports(100000.0), USDH::from(2000.0)),
+        ]) {
+            borrowing_operations::approve_trove(&mut market, user).unwrap();
+            borrowing_operations::deposit_collateral(&mut market, user, deposit, token, 0).unwrap();
+            borrowing_operations::borrow_stablecoin(
+                &mut market,
+                user,
+                &mut staking_pool,
+                borrow,
+                &prices,
+                0,
+            )
+            .unwrap();
+        }
+        let user = &mut users[0];
+        borrowing_operations::set_interest_rate(&mut market, user, MAX_INTEREST_RATE, 0).unwrap();
+
+        // 6500 SOL backs the debt of the trove at 128%, but
+        // only at 103% once the interest of the year is charged
+        let one_year = SECONDS_PER_YEAR;
+        let debt = user.borrowed_stablecoin;
+        let interest = calc_accrued_interest(debt, MAX_INTEREST_RATE, 0, one_year);
+        assert_eq!(
+            borrowing_operations::withdraw_collateral(
+                &mut market,
+                user,
+                sol_to_lamports(3500.0),
+                token,
+                &prices,
+                one_year,
+            )
+            .err(),
+            Some(BorrowError::NotEnoughCollateral)
+        );
+
+        borrowing_operations::withdraw_collateral(
+            &mut market,
+            user,
+            sol_to_lamports(1.0),
+            token,
+            &prices,
+            one_year,
+        )
+        .unwrap();
+        assert_eq!(user.borrowed_stablecoin, debt + interest);
+        assert_eq!(
+            market.interest_not_yet_minted,
+            debt - USDH::from(5025.0) + interest
+        );
+    }
 }
@@ -68,6 +68,7 @@ mod tests {
             &mut user,
             lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -92,6 +93,7 @@ mod tests {
             &mut user,
             lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -111,6 +113,7 @@ mod tests {
             lamports,
             CollateralToken::SOL,
             &TokenPrices::new(10.0),
+            0,
         )
         .unwrap();
 
@@ -121,6 +124,53 @@ mod tests {
         assert_eq!(close_user_metadata, true);
     }
 
+    #[test]
+    fn test_borrowing_close_trove_account() {
+        let mut market = BorrowingMarketState::new();
+        let mut user = UserMetadata::default();
+        let mut idle_user = UserMetadata::default();
+
+        borrowing_operations::initialize_borrowing_market(&mut market, 0);
+        borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
+        borrowing_operations::approve_trove(&mut market, &mut idle_user).unwrap();
+
+        let lamports = sol_to_lamports(10.0);
+        borrowing_operations::deposit_collateral(
+            &mut market,
+            &mut user,
+            lamports,
+            CollateralToken::SOL,
+            0,
+        )
+        .unwrap();
+
+        let res = borrowing_operations::close_trove_account(&mut market, &user);
+        assert_eq!(res.err(), Some(BorrowError::TroveAccountNotEmpty));
+        assert_eq!(market.num_closed_users, 0);
+
+        // Withdrawing everything closes the trove too
+        let WithdrawCollateralEffects {
+            close_user_metadata,
+            ..
+        } = borrowing_operations::withdraw_collateral(
+            &mut market,
+            &mut user,
+            lamports,
+            CollateralToken::SOL,
+            &TokenPrices::new(10.0),
+            0,
+        )
+        .unwrap();
+        assert!(close_user_metadata);
+        assert_eq!(market.num_closed_users, 1);
+
+        borrowing_operations::close_trove_account(&mut market, &idle_user).unwrap();
+        assert_eq!(market.num_closed_users, 2);
+
+        // User ids are never handed out twice
+        assert_eq!(market.num_users, 2);
+    }
+
     #[test]
     fn test_borrowing_deposit_collateral_multi() {
         let mut market = BorrowingMarketState::new();
@@ -138,6 +188,7 @@ mod tests {
                 &mut user,
                 lamports,
                 CollateralToken::SOL,
+                0,
             )
             .unwrap();
 
@@ -166,6 +217,7 @@ mod tests {
             &mut user,
             lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -225,6 +277,7 @@ mod tests {
                 &mut user,
                 lamports,
                 CollateralToken::SOL,
+                0,
             )
             .unwrap();
 
@@ -270,6 +323,7 @@ mod tests {
             &mut user,
             lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -287,7 +341,8 @@ mod tests {
         println!("Market {:#?}", market);
 
         let amount_borrowed = user.borrowed_stablecoin;
-        borrowing_operations::repay_loan(&mut market, &mut user, amount_borrowed).unwrap();
+        borrowing_operations::repay_loan(&mut market, &mut user, amount_borrowed, now_timestamp)
+            .unwrap();
 
         println!("User {:#?}", user);
         println!("Market {:#?}", market);
@@ -336,6 +391,7 @@ mod tests {
             withdrawable,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         )
         .unwrap();
 
@@ -376,6 +432,7 @@ mod tests {
             &mut user,
             deposit_lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -395,6 +452,7 @@ mod tests {
             fail_withdraw_amount,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         );
 
         assert_eq!(err.err(), Some(BorrowError::NotEnoughCollateral.into()));
@@ -427,6 +485,7 @@ mod tests {
             &mut user,
             lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -440,7 +499,8 @@ mod tests {
         )
         .unwrap();
 
-        borrowing_operations::repay_loan(&mut market, &mut user, total_borrowed).unwrap();
+        borrowing_operations::repay_loan(&mut market, &mut user, total_borrowed, now_timestamp)
+            .unwrap();
 
         let effects = borrowing_operations::withdraw_collateral(
             &mut market,
@@ -448,6 +508,7 @@ mod tests {
             lamports,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         )
         .unwrap();
 
@@ -487,8 +548,13 @@ mod tests {
         let lamports = borrowers[1].deposited_collateral.sol;
 
         // We borrowed 200 USDH, repaying it back so we can fully withdraw
-        borrowing_operations::repay_loan(&mut market, &mut borrowers[1], USDH::from(201.0))
-            .unwrap();
+        borrowing_operations::repay_loan(
+            &mut market,
+            &mut borrowers[1],
+            USDH::from(201.0),
+            now_timestamp,
+        )
+        .unwrap();
 
         let effects = borrowing_operations::withdraw_collateral(
             &mut market,
@@ -496,6 +562,7 @@ mod tests {
             lamports,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         )
         .unwrap();
 
@@ -528,6 +595,7 @@ mod tests {
             &mut user,
             lamports,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -537,6 +605,7 @@ mod tests {
             lamports + 1,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         );
 
         assert_eq!(err.err(), Some(BorrowError::NotEnoughCollateral.into()));
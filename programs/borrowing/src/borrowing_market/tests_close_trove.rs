@@ -0,0 +1,273 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{
+    borrowing_market::{
+        borrowing_operations, borrowing_rate::BorrowSplit, tests_utils, types::CloseTroveEffects,
+    },
+    state::{CollateralToken::*, UserStatus},
+    utils::{coretypes::USDH, math::coll_to_lamports},
+    BorrowingMarketState, StakingPoolState, TokenPrices, UserMetadata,
+};
+// Tests
+// - [x] Close a multi collateral position in one go
+// - [x] Closing includes pending redistributed debt and collateral
+// - [x] Closing a liquidated trove only closes the account
+// - [x] Closing one trove leaves the others untouched
+
+#[test]
+fn test_close_trove_multi_collateral() {
+    let (mut market, mut spool, px, now) = utils::setup();
+    let mut user = utils::new_user(&mut market, &mut spool, &px, now);
+
+    let CloseTroveEffects {
+        amount_to_burn,
+        collateral_to_transfer_to_user,
+    } = borrowing_operations::close_trove(&mut market, &mut user, now).unwrap();
+
+    assert_eq!(amount_to_burn, USDH::from(603.0));
+    assert_eq!(
+        collateral_to_transfer_to_user.sol,
+        coll_to_lamports(50.0, SOL)
+    );
+    assert_eq!(
+        collateral_to_transfer_to_user.eth,
+        coll_to_lamports(50.0, ETH)
+    );
+    assert_eq!(collateral_to_transfer_to_user.btc, 0);
+
+    assert_eq!(user.borrowed_stablecoin, 0);
+    assert_eq!(user.deposited_collateral.sol, 0);
+    assert_eq!(user.deposited_collateral.eth, 0);
+    assert_eq!(user.status, UserStatus::Inactive as u8);
+
+    assert_eq!(market.stablecoin_borrowed, 0);
+    assert_eq!(market.deposited_collateral.sol, 0);
+    assert_eq!(market.deposited_collateral.eth, 0);
+    assert_eq!(market.total_stake, 0);
+    assert_eq!(market.num_active_users, 0);
+    assert_eq!(market.num_closed_users, 1);
+}
+
+#[test]
+fn test_close_trove_includes_pending_redistribution() {
+    let (
+        mut market,
+        mut stability_pool_state,
+        mut epoch_to_scale_to_sum,
+        liquidations,
+        mut spool,
+        now,
+    ) = tests_utils::utils::set_up_market();
+
+    let amount_to_borrow = USDH::from(200.0);
+    let borrow_split = BorrowSplit::from_amount(amount_to_borrow, market.base_rate_bps);
+    let liquidation_price = 110.5;
+
+    let count = 10;
+    let mut users: Vec<UserMetadata> = (0..count)
+        .map(|i| {
+            let mut user = UserMetadata::default();
+            borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
+            let deposit = if i == 0 { 2.0 } else { 4.0 };
+            borrowing_operations::deposit_collateral(
+                &mut market,
+                &mut user,
+                coll_to_lamports(deposit, SOL),
+                SOL,
+                0,
+            )
+            .unwrap();
+            borrowing_operations::borrow_stablecoin(
+                &mut market,
+                &mut user,
+                &mut spool,
+                amount_to_borrow,
+                &TokenPrices::new(liquidation_price + 100.0),
+                now,
+            )
+            .unwrap();
+            user
+        })
+        .collect();
+
+    // Nobody in the stability pool, the whole position is redistributed
+    borrowing_operations::try_liquidate(
+        Pubkey::new_unique(),
+        &mut market,
+        &mut users[0],
+        &mut stability_pool_state,
+        &mut epoch_to_scale_to_sum,
+        &TokenPrices::new(liquidation_price),
+        &mut liquidations.borrow_mut(),
+        now,
+    )
+    .unwrap();
+
+    // Pending rewards have not been applied to the trove yet
+    assert_eq!(users[1].borrowed_stablecoin, borrow_split.amount_to_borrow);
+
+    let liquidator_fee = (0.005 * coll_to_lamports(2.0, SOL) as f64) as u64;
+    let total_deposited = coll_to_lamports(2.0 + 4.0 * 9.0, SOL) - liquidator_fee;
+
+    let CloseTroveEffects {
+        amount_to_burn,
+        collateral_to_transfer_to_user,
+    } = borrowing_operations::close_trove(&mut market, &mut users[1], now).unwrap();
+
+    assert_eq!(
+        amount_to_burn,
+        borrow_split.amount_to_borrow + borrow_split.amount_to_borrow / (count - 1)
+    );
+    assert_eq!(
+        collateral_to_transfer_to_user.sol,
+        total_deposited / (count - 1)
+    );
+
+    assert_eq!(
+        market.stablecoin_borrowed,
+        borrow_split.amount_to_borrow * count - amount_to_burn
+    );
+    assert_eq!(
+        market.deposited_collateral.sol,
+        total_deposited - collateral_to_transfer_to_user.sol
+    );
+    assert_eq!(market.num_active_users, count - 2);
+    assert_eq!(market.num_closed_users, 1);
+}
+
+#[test]
+fn test_close_trove_liquidated() {
+    let (
+        mut market,
+        mut stability_pool_state,
+        mut epoch_to_scale_to_sum,
+        liquidations,
+        mut spool,
+        now,
+    ) = tests_utils::utils::set_up_market();
+
+    let mut users: Vec<UserMetadata> = (0..2)
+        .map(|i| {
+            let mut user = UserMetadata::default();
+            borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
+            let deposit = if i == 0 { 2.0 } else { 4.0 };
+            borrowing_operations::deposit_collateral(
+                &mut market,
+                &mut user,
+                coll_to_lamports(deposit, SOL),
+                SOL,
+                0,
+            )
+            .unwrap();
+            borrowing_operations::borrow_stablecoin(
+                &mut market,
+                &mut user,
+                &mut spool,
+                USDH::from(200.0),
+                &TokenPrices::new(210.5),
+                now,
+            )
+            .unwrap();
+            user
+        })
+        .collect();
+
+    borrowing_operations::try_liquidate(
+        Pubkey::new_unique(),
+        &mut market,
+        &mut users[0],
+        &mut stability_pool_state,
+        &mut epoch_to_scale_to_sum,
+        &TokenPrices::new(110.5),
+        &mut liquidations.borrow_mut(),
+        now,
+    )
+    .unwrap();
+
+    let stablecoin_borrowed = market.stablecoin_borrowed;
+
+    let CloseTroveEffects {
+        amount_to_burn,
+        collateral_to_transfer_to_user,
+    } = borrowing_operations::close_trove(&mut market, &mut users[0], now).unwrap();
+
+    assert_eq!(amount_to_burn, 0);
+    assert_eq!(collateral_to_transfer_to_user.sol, 0);
+    assert_eq!(market.stablecoin_borrowed, stablecoin_borrowed);
+    assert_eq!(market.num_active_users, 1);
+    assert_eq!(market.num_closed_users, 1);
+}
+
+#[test]
+fn test_close_trove_leaves_others_untouched() {
+    let (mut market, mut spool, px, now) = utils::setup();
+    let mut first = utils::new_user(&mut market, &mut spool, &px, now);
+    let mut second = utils::new_user(&mut market, &mut spool, &px, now);
+
+    borrowing_operations::close_trove(&mut market, &mut first, now).unwrap();
+
+    assert_eq!(second.borrowed_stablecoin, USDH::from(603.0));
+    assert_eq!(market.stablecoin_borrowed, USDH::from(603.0));
+    assert_eq!(market.deposited_collateral.sol, coll_to_lamports(50.0, SOL));
+    assert_eq!(market.deposited_collateral.eth, coll_to_lamports(50.0, ETH));
+    assert_eq!(market.total_stake, second.user_stake);
+    assert_eq!(market.num_active_users, 1);
+
+    borrowing_operations::close_trove(&mut market, &mut second, now).unwrap();
+    assert_eq!(market.stablecoin_borrowed, 0);
+    assert_eq!(market.num_active_users, 0);
+    assert_eq!(market.num_closed_users, 2);
+}
+
+mod utils {
+    use super::*;
+
+    pub fn setup() -> (BorrowingMarketState, StakingPoolState, TokenPrices, u64) {
+        let mut market = BorrowingMarketState::new();
+        let spool = StakingPoolState {
+            treasury_fee_rate: 1_500, // bps: 15%
+            ..Default::default()
+        };
+
+        borrowing_operations::initialize_borrowing_market(&mut market, 0);
+
+        (market, spool, TokenPrices::new_all(10.0), 0)
+    }
+
+    pub fn new_user(
+        market: &mut BorrowingMarketState,
+        spool: &mut StakingPoolState,
+        px: &TokenPrices,
+        now: u64,
+    ) -> UserMetadata {
+        // 50 SOL and 50 ETH worth 1000 USDH backing 603 USDH of debt
+        let mut user = UserMetadata::default();
+        borrowing_operations::approve_trove(market, &mut user).unwrap();
+        borrowing_operations::deposit_collateral(
+            market,
+            &mut user,
+            coll_to_lamports(50.0, SOL),
+            SOL,
+            0,
+        )
+        .unwrap();
+        borrowing_operations::deposit_collateral(
+            market,
+            &mut user,
+            coll_to_lamports(50.0, ETH),
+            ETH,
+            0,
+        )
+        .unwrap();
+        borrowing_operations::borrow_stablecoin(
+            market,
+            &mut user,
+            spool,
+            USDH::from(600.0),
+            px,
+            now,
+        )
+        .unwrap();
+        user
+    }
+}
@@ -441,7 +441,7 @@ fn test_deposit_and_borrow_adjusts_correctly_inactive_position() {
     );
 
     // Deposit some
-    borrowing_operations::deposit_collateral(&mut market, &mut user, deposit, asset).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, deposit, asset, 0).unwrap();
     // Deposit more and borrow
     let DepositAndBorrowEffects {
         amount_mint_to_user,
@@ -509,7 +509,7 @@ mod utils {
 
         // First user, deposit and borrow in normal mode, result is overcollateralized
         borrowing_operations::approve_trove(&mut market, &mut first_user).unwrap();
-        borrowing_operations::deposit_collateral(&mut market, &mut first_user, deposit, asset)
+        borrowing_operations::deposit_collateral(&mut market, &mut first_user, deposit, asset, 0)
             .unwrap();
         borrowing_operations::borrow_stablecoin(
             &mut market,
@@ -532,8 +532,14 @@ mod utils {
     ) -> UserMetadata {
         let mut user = UserMetadata::default();
         borrowing_operations::approve_trove(market, &mut user).unwrap();
-        borrowing_operations::deposit_collateral(market, &mut user, deposit, CollateralToken::SOL)
-            .unwrap();
+        borrowing_operations::deposit_collateral(
+            market,
+            &mut user,
+            deposit,
+            CollateralToken::SOL,
+            0,
+        )
+        .unwrap();
         borrowing_operations::borrow_stablecoin(
             market,
             &mut user,
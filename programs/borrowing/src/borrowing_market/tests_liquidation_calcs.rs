@@ -463,7 +463,8 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_open_position() {
     );
 
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     borrowing_operations::borrow_stablecoin(
         &mut market,
         &mut new_user,
@@ -492,7 +493,7 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_add_coll() {
     let new_deposit = sol_to_lamports(100.0);
 
     // unwrap shouldn't panic
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -510,7 +511,7 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_withdraw_coll() {
     let (mut market, _spool, px, _now, mut user) = utils::set_up_above_ccr_market();
 
     let new_deposit = sol_to_lamports(100.0);
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -524,7 +525,7 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_withdraw_coll() {
 
     // now withdraw
     let new_withdraw = sol_to_lamports(90.0);
-    borrowing_operations::withdraw_collateral(&mut market, &mut user, new_withdraw, SOL, &px)
+    borrowing_operations::withdraw_collateral(&mut market, &mut user, new_withdraw, SOL, &px, 0)
         .unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
@@ -544,7 +545,7 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_borrow_more() {
     let (mut market, mut spool, px, now, mut user) = utils::set_up_above_ccr_market();
 
     let new_deposit = sol_to_lamports(100.0);
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -581,10 +582,10 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_borrow_more() {
 #[test]
 fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_repay() {
     use CollateralToken::SOL;
-    let (mut market, _spool, px, _now, mut user) = utils::set_up_above_ccr_market();
+    let (mut market, _spool, px, now, mut user) = utils::set_up_above_ccr_market();
 
     let new_deposit = sol_to_lamports(100.0);
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -598,7 +599,7 @@ fn test_position_adjustment_normal_mode_allow_if_tcr_above_ccr_repay() {
 
     // now repay
     let repay_amount = USDH::from(90.0);
-    borrowing_operations::repay_loan(&mut market, &mut user, repay_amount).unwrap();
+    borrowing_operations::repay_loan(&mut market, &mut user, repay_amount, now).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -623,7 +624,8 @@ fn test_position_adjustment_normal_mode_block_if_tcr_below_ccr_open_position() {
     );
 
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     let res = borrowing_operations::borrow_stablecoin(
         &mut market,
         &mut new_user,
@@ -645,7 +647,7 @@ fn test_position_adjustment_normal_mode_block_if_tcr_below_ccr_withdraw_coll() {
     let (mut market, _spool, px, _now, mut user) = utils::set_up_above_ccr_market();
 
     let new_deposit = sol_to_lamports(100.0);
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -659,8 +661,14 @@ fn test_position_adjustment_normal_mode_block_if_tcr_below_ccr_withdraw_coll() {
 
     // now withdraw
     let new_withdraw = sol_to_lamports(110.0);
-    let res =
-        borrowing_operations::withdraw_collateral(&mut market, &mut user, new_withdraw, SOL, &px);
+    let res = borrowing_operations::withdraw_collateral(
+        &mut market,
+        &mut user,
+        new_withdraw,
+        SOL,
+        &px,
+        0,
+    );
 
     assert_eq!(
         res.err().unwrap(),
@@ -674,7 +682,7 @@ fn test_position_adjustment_normal_mode_block_if_tcr_below_ccr_borrow_more() {
     let (mut market, mut spool, px, now, mut user) = utils::set_up_above_ccr_market();
 
     let new_deposit = sol_to_lamports(100.0);
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 
     let tcr = CollateralInfo::calc_coll_ratio(
         market.stablecoin_borrowed,
@@ -715,12 +723,12 @@ fn test_position_adjustment_recovery_mode_allow_top_up_coll() {
     // prices not even taken as arg
     // but this is just to prove it's allowed
     let new_deposit = sol_to_lamports(100.0);
-    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut user, new_deposit, SOL, 0).unwrap();
 }
 
 #[test]
 fn test_position_adjustment_recovery_mode_allow_repayment() {
-    let (mut market, _spool, _px, _now, mut user) = utils::set_up_above_ccr_market();
+    let (mut market, _spool, _px, now, mut user) = utils::set_up_above_ccr_market();
 
     // prev prices at 1.52
     let _new_prices = TokenPrices::new(1.4);
@@ -729,7 +737,7 @@ fn test_position_adjustment_recovery_mode_allow_repayment() {
     // prices not even taken as arg
     // but this is just to prove it's allowed
     let repay_amount = USDH::from(10.0);
-    borrowing_operations::repay_loan(&mut market, &mut user, repay_amount).unwrap();
+    borrowing_operations::repay_loan(&mut market, &mut user, repay_amount, now).unwrap();
 }
 
 #[test]
@@ -745,6 +753,7 @@ fn test_position_adjustment_recovery_mode_disallow_coll_withdraw() {
         withdraw_amount,
         CollateralToken::SOL,
         &new_prices,
+        0,
     );
 
     assert_eq!(
@@ -766,7 +775,8 @@ fn test_position_adjustment_recovery_mode_disallow_extra_borrow_even_if_above_mc
 
     // This is equivalent to an open (due to inactive collateral)
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     borrowing_operations::borrow_stablecoin(
         &mut market,
         &mut new_user,
@@ -855,7 +865,8 @@ fn test_position_adjustment_recovery_mode_allow_open_position_above_150() {
         sol_to_lamports(1140.0),
     );
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     borrowing_operations::borrow_stablecoin(
         &mut market,
         &mut new_user,
@@ -903,7 +914,8 @@ fn test_position_adjustment_recovery_mode_disallow_open_position_below_150() {
         sol_to_lamports(100.0),
     );
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     let res = borrowing_operations::borrow_stablecoin(
         &mut market,
         &mut new_user,
@@ -946,7 +958,8 @@ fn test_after_full_repayment_coll_surplus_is_inactive() {
     assert_eq!(market.num_active_users, 1);
 
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     assert_eq!(market.num_active_users, 1);
 
     borrowing_operations::borrow_stablecoin(
@@ -963,7 +976,7 @@ fn test_after_full_repayment_coll_surplus_is_inactive() {
     assert_eq!(new_user.borrowed_stablecoin, USDH::from(1005.0));
     assert_eq!(new_user.deposited_collateral.sol, new_deposit);
 
-    borrowing_operations::repay_loan(&mut market, &mut new_user, USDH::from(1005.0)).unwrap();
+    borrowing_operations::repay_loan(&mut market, &mut new_user, USDH::from(1005.0), now).unwrap();
 
     assert_eq!(market.num_active_users, 1);
     assert_eq!(new_user.borrowed_stablecoin, 0);
@@ -985,7 +998,8 @@ fn test_allow_withdraw_inactive_collateral_during_recovery_mode() {
     assert_eq!(market.num_active_users, 1);
 
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     assert_eq!(market.num_active_users, 1);
 
     // prev prices at 1.52
@@ -1008,6 +1022,7 @@ fn test_allow_withdraw_inactive_collateral_during_recovery_mode() {
         new_deposit,
         SOL,
         &new_prices,
+        0,
     )
     .unwrap();
 }
@@ -1065,7 +1080,8 @@ fn test_liquidation_coll_surplus_is_inactive() {
     );
 
     borrowing_operations::approve_trove(&mut market, &mut new_user).unwrap();
-    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL).unwrap();
+    borrowing_operations::deposit_collateral(&mut market, &mut new_user, new_deposit, SOL, 0)
+        .unwrap();
     borrowing_operations::borrow_stablecoin(
         &mut market,
         &mut new_user,
@@ -1150,6 +1166,7 @@ fn test_liquidation_coll_surplus_is_inactive() {
         coll_to_lamports(100.0, SOL),
         SOL,
         &new_prices,
+        0,
     )
     .unwrap();
 
@@ -1190,7 +1207,7 @@ mod utils {
         // Start with 150%
         let (borrow, deposit) = (USDH::from(1000.0), sol_to_lamports(1000.0));
         borrowing_operations::approve_trove(&mut market, &mut user).unwrap();
-        borrowing_operations::deposit_collateral(&mut market, &mut user, deposit, SOL).unwrap();
+        borrowing_operations::deposit_collateral(&mut market, &mut user, deposit, SOL, 0).unwrap();
         borrowing_operations::borrow_stablecoin(
             &mut market,
             &mut user,
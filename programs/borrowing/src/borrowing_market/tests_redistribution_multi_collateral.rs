@@ -75,6 +75,7 @@ mod tests {
                             &mut user,
                             amount as u64,
                             token,
+                            0,
                         )
                         .map_err(|e| {
                             println!("Error depositing {:?}", e);
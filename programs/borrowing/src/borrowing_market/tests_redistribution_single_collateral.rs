@@ -68,6 +68,7 @@ mod tests {
                     &mut user,
                     deposit_amount,
                     CollateralToken::SOL,
+                    0,
                 )
                 .unwrap();
                 total_amount_deposited += deposit_amount;
@@ -790,6 +791,7 @@ mod tests {
             &mut borrowing_users[1],
             extra_collateral_deposit,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -896,6 +898,7 @@ mod tests {
             amount_to_withdraw,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         )
         .unwrap();
 
@@ -973,6 +976,7 @@ mod tests {
             amount_to_withdraw,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         );
 
         assert_eq!(
@@ -1016,6 +1020,7 @@ mod tests {
             amount_to_withdraw,
             CollateralToken::SOL,
             &TokenPrices::new(40.0),
+            0,
         )
         .unwrap();
 
@@ -1417,6 +1422,7 @@ mod tests {
             &mut borrowing_users[1],
             extra_collateral_deposit,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -1450,6 +1456,7 @@ mod tests {
             &mut borrowing_users[2],
             extra_collateral_deposit,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -1483,6 +1490,7 @@ mod tests {
             &mut borrowing_users[3],
             extra_collateral_deposit,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -1722,6 +1730,7 @@ mod tests {
             &mut borrowing_users[2],
             extra_collateral_deposit,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -0,0 +1,304 @@
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use anchor_lang::prelude::Pubkey;
+
+    use crate::borrowing_market::borrowing_operations;
+    use crate::borrowing_market::sorted_troves;
+    use crate::borrowing_market::tests_utils::utils::{
+        new_borrowing_users_with_amounts_and_price, set_up_market,
+    };
+    use crate::state::*;
+    use crate::utils::consts::MAX_SORTED_TROVES;
+    use crate::utils::coretypes::USDH;
+    use crate::utils::math::coll_to_lamports;
+    use crate::BorrowError;
+
+    fn trove(user_id: u64, sol: f64, debt: f64) -> UserMetadata {
+        UserMetadata {
+            status: UserStatus::Active as u8,
+            user_id,
+            metadata_pk: Pubkey::new_unique(),
+            borrowed_stablecoin: USDH::from(debt),
+            deposited_collateral: CollateralAmounts::of_token(
+                coll_to_lamports(sol, CollateralToken::SOL),
+                CollateralToken::SOL,
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn user_ids(sorted_troves: &SortedTroves) -> Vec<u64> {
+        (0..sorted_troves::len(sorted_troves))
+            .map(|i| sorted_troves.troves[i].user_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_troves_lowest_ratio_first() {
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        let prices = TokenPrices::new_all(40.0);
+
+        // 200%, 800%, 400%, 600%
+        for (user_id, sol) in [10.0, 40.0, 20.0, 30.0].iter().enumerate() {
+            let user = trove(user_id as u64, *sol, 200.0);
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), &user, &prices)
+                .unwrap();
+        }
+
+        let sorted_troves = sorted_troves.borrow();
+        assert_eq!(user_ids(&sorted_troves), vec![0, 2, 3, 1]);
+        assert_eq!(sorted_troves.troves[0].collateral_ratio_bps, 20_000);
+    }
+
+    #[test]
+    fn test_sorted_troves_lowest_interest_rate_first() {
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        let prices = TokenPrices::new_all(40.0);
+
+        // 200% at 5%, 800% at 0%, 400% at 5%, 600% at 1%
+        for (user_id, (sol, rate)) in [(10.0, 500), (40.0, 0), (20.0, 500), (30.0, 100)]
+            .iter()
+            .enumerate()
+        {
+            let user = UserMetadata {
+                interest_rate_bps: *rate,
+                ..trove(user_id as u64, *sol, 200.0)
+            };
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), &user, &prices)
+                .unwrap();
+        }
+
+        assert_eq!(user_ids(&sorted_troves.borrow()), vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_sorted_troves_follow_trove_changes() {
+        let (mut market, _, _, _, mut staking_pool_state, now_timestamp) = set_up_market();
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        let prices = TokenPrices::new_all(40.0);
+
+        let deposit = CollateralAmounts::of_token(
+            coll_to_lamports(20.0, CollateralToken::SOL),
+            CollateralToken::SOL,
+        );
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut staking_pool_state,
+            3,
+            &[USDH::from(200.0); 3],
+            &[deposit; 3],
+            40.0,
+            now_timestamp,
+        );
+        for borrower in borrowers.iter() {
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), borrower, &prices)
+                .unwrap();
+        }
+        assert_eq!(user_ids(&sorted_troves.borrow()), vec![0, 1, 2]);
+
+        // Borrowing more makes the last trove the riskiest
+        borrowing_operations::borrow_stablecoin(
+            &mut market,
+            &mut borrowers[2],
+            &mut staking_pool_state,
+            USDH::from(200.0),
+            &prices,
+            now_timestamp,
+        )
+        .unwrap();
+        sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), &borrowers[2], &prices)
+            .unwrap();
+        assert_eq!(user_ids(&sorted_troves.borrow()), vec![2, 0, 1]);
+
+        // Troves without debt are taken out
+        let debt = borrowers[0].borrowed_stablecoin;
+        borrowing_operations::repay_loan(&mut market, &mut borrowers[0], debt, now_timestamp)
+            .unwrap();
+        sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), &borrowers[0], &prices)
+            .unwrap();
+        assert_eq!(user_ids(&sorted_troves.borrow()), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_sorted_troves_refresh_at_new_prices() {
+        let (mut market, _, _, _, mut staking_pool_state, now_timestamp) = set_up_market();
+        let sorted_troves = RefCell::new(SortedTroves::default());
+
+        // Same ratio, one backed by SOL, the other by ETH
+        let collaterals = [
+            CollateralAmounts::of_token(
+                coll_to_lamports(10.0, CollateralToken::SOL),
+                CollateralToken::SOL,
+            ),
+            CollateralAmounts::of_token(
+                coll_to_lamports(10.0, CollateralToken::ETH),
+                CollateralToken::ETH,
+            ),
+        ];
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut staking_pool_state,
+            2,
+            &[USDH::from(200.0); 2],
+            &collaterals,
+            40.0,
+            now_timestamp,
+        );
+        for borrower in borrowers.iter_mut() {
+            sorted_troves::refresh_trove(
+                &mut market,
+                &mut sorted_troves.borrow_mut(),
+                borrower,
+                &TokenPrices::new_all(40.0),
+                now_timestamp,
+            )
+            .unwrap();
+        }
+        assert_eq!(user_ids(&sorted_troves.borrow()), vec![0, 1]);
+
+        // ETH drops, the ETH trove only moves once refreshed
+        let prices = TokenPrices {
+            eth: Price::from_f64(30.0, CollateralToken::ETH),
+            ..TokenPrices::new_all(40.0)
+        };
+        sorted_troves::refresh_trove(
+            &mut market,
+            &mut sorted_troves.borrow_mut(),
+            &mut borrowers[1],
+            &prices,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(user_ids(&sorted_troves.borrow()), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_sorted_troves_full_rejects_new_troves() {
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        let prices = TokenPrices::new_all(40.0);
+
+        for user_id in 0..MAX_SORTED_TROVES as u64 {
+            let user = trove(user_id, 20.0, 200.0);
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), &user, &prices)
+                .unwrap();
+        }
+        assert_eq!(
+            sorted_troves::len(&sorted_troves.borrow()),
+            MAX_SORTED_TROVES
+        );
+
+        // No trove is dropped to make room, whether safer or riskier
+        for new_trove in [
+            trove(MAX_SORTED_TROVES as u64, 30.0, 200.0),
+            trove(MAX_SORTED_TROVES as u64 + 1, 10.0, 200.0),
+        ] {
+            let res = sorted_troves::reposition_trove(
+                &mut sorted_troves.borrow_mut(),
+                &new_trove,
+                &prices,
+            );
+            assert_eq!(res.unwrap_err(), BorrowError::SortedTrovesFull);
+        }
+        assert_eq!(
+            user_ids(&sorted_troves.borrow()),
+            (0..MAX_SORTED_TROVES as u64).collect::<Vec<_>>()
+        );
+
+        // Troves already in the index still move
+        let riskier = trove(MAX_SORTED_TROVES as u64 - 1, 10.0, 200.0);
+        sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), &riskier, &prices)
+            .unwrap();
+        let ids = user_ids(&sorted_troves.borrow());
+        assert_eq!(ids.len(), MAX_SORTED_TROVES);
+        assert_eq!(ids[0], riskier.user_id);
+    }
+
+    #[test]
+    fn test_sorted_troves_lowest_skips_liquidatable() {
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        let prices = TokenPrices::new_all(40.0);
+
+        // 100%, 200%, 400%
+        let troves = [
+            trove(0, 5.0, 200.0),
+            trove(1, 10.0, 200.0),
+            trove(2, 20.0, 200.0),
+        ];
+        for user in troves.iter() {
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), user, &prices)
+                .unwrap();
+        }
+
+        let current = |user: &UserMetadata| sorted_troves::current_trove(user, &prices).unwrap();
+        let sorted_troves = sorted_troves.borrow();
+        sorted_troves::assert_lowest_troves(
+            &sorted_troves,
+            &[current(&troves[1]), current(&troves[2])],
+        )
+        .unwrap();
+
+        for submitted in [
+            vec![current(&troves[2])],
+            vec![current(&troves[0])],
+            vec![current(&troves[2]), current(&troves[1])],
+            vec![
+                current(&troves[1]),
+                current(&troves[2]),
+                current(&trove(3, 30.0, 200.0)),
+            ],
+        ] {
+            assert_eq!(
+                sorted_troves::assert_lowest_troves(&sorted_troves, &submitted).unwrap_err(),
+                BorrowError::TrovesNotLowestInIndex
+            );
+        }
+    }
+
+    #[test]
+    fn test_sorted_troves_lowest_at_current_ratios() {
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        let prices = TokenPrices::new_all(40.0);
+
+        // 200% backed by SOL, 400% backed by ETH
+        let sol_trove = trove(0, 10.0, 200.0);
+        let eth_trove = UserMetadata {
+            deposited_collateral: CollateralAmounts::of_token(
+                coll_to_lamports(20.0, CollateralToken::ETH),
+                CollateralToken::ETH,
+            ),
+            ..trove(1, 0.0, 200.0)
+        };
+        for user in [&sol_trove, &eth_trove] {
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), user, &prices)
+                .unwrap();
+        }
+        let sorted_troves = sorted_troves.borrow();
+        assert_eq!(user_ids(&sorted_troves), vec![0, 1]);
+
+        // ETH drops, the ETH trove is now at 150% and comes first,
+        // even though the index has not been refreshed
+        let prices = TokenPrices {
+            eth: Price::from_f64(15.0, CollateralToken::ETH),
+            ..prices
+        };
+        let current = |user: &UserMetadata| sorted_troves::current_trove(user, &prices).unwrap();
+        sorted_troves::assert_lowest_troves(&sorted_troves, &[current(&eth_trove)]).unwrap();
+        sorted_troves::assert_lowest_troves(
+            &sorted_troves,
+            &[current(&eth_trove), current(&sol_trove)],
+        )
+        .unwrap();
+
+        // In the stored order they are no longer sorted
+        assert_eq!(
+            sorted_troves::assert_lowest_troves(
+                &sorted_troves,
+                &[current(&sol_trove), current(&eth_trove)]
+            )
+            .unwrap_err(),
+            BorrowError::TrovesNotLowestInIndex
+        );
+    }
+}
@@ -46,8 +46,14 @@ pub mod utils {
     ) -> (UserMetadata, BorrowStablecoinEffects) {
         let mut user = UserMetadata::default();
         borrowing_operations::approve_trove(market, &mut user).unwrap();
-        borrowing_operations::deposit_collateral(market, &mut user, deposit, CollateralToken::SOL)
-            .unwrap();
+        borrowing_operations::deposit_collateral(
+            market,
+            &mut user,
+            deposit,
+            CollateralToken::SOL,
+            0,
+        )
+        .unwrap();
 
         let effects = borrowing_operations::borrow_stablecoin(
             market,
@@ -153,6 +159,7 @@ pub mod utils {
                             &mut user,
                             amount as u64,
                             token,
+                            0,
                         )
                         .unwrap();
                     }
@@ -7,6 +7,12 @@ pub struct BorrowStablecoinEffects {
     pub amount_mint_to_treasury_vault: u64,
 }
 
+#[derive(Debug, Default)]
+pub struct MintAccruedInterestEffects {
+    pub amount_mint_to_fees_vault: u64,
+    pub amount_mint_to_treasury_vault: u64,
+}
+
 #[derive(Debug)]
 pub struct DepositCollateralEffects {
     pub collateral_to_transfer_from_user: CollateralAmounts,
@@ -24,12 +30,42 @@ pub struct WithdrawCollateralEffects {
     pub close_user_metadata: bool,
 }
 
+#[derive(Debug, Default)]
+pub struct AdjustTroveEffects {
+    pub collateral_to_transfer_from_user: CollateralAmounts,
+    pub collateral_to_transfer_to_user: CollateralAmounts,
+    pub amount_mint_to_user: u64,
+    pub amount_mint_to_fees_vault: u64,
+    pub amount_mint_to_treasury_vault: u64,
+    pub amount_to_burn: u64,
+}
+
+#[derive(Debug)]
+pub struct CloseTroveEffects {
+    pub amount_to_burn: u64,
+    pub collateral_to_transfer_to_user: CollateralAmounts,
+}
+
+#[derive(Debug)]
+pub struct ClaimRedemptionSurplusEffects {
+    pub collateral_to_transfer_to_user: CollateralAmounts,
+    pub close_user_metadata: bool,
+}
+
+#[cfg(test)]
 #[derive(Debug)]
 pub struct LiquidationEffects {
     pub liquidation_event: LiquidationEvent,
     pub usd_to_burn_from_stability_pool: u64,
 }
 
+#[derive(Debug)]
+pub struct TrancheLiquidationEffects {
+    pub liquidation_event: LiquidationEvent,
+    pub usd_to_burn_from_stability_pool: u64,
+    pub usd_to_burn_from_tranches: Vec<u64>,
+}
+
 #[derive(Debug)]
 pub struct ClearLiquidationGainsEffects {
     pub clearing_agent_gains: CollateralAmounts,
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::msg;
+use anchor_lang::{prelude::ProgramResult, Context, Key};
+
+use crate::redemption::redemption_operations;
+use crate::AddRedemptionCandidatesPage;
+
+pub fn process(ctx: Context<AddRedemptionCandidatesPage>) -> ProgramResult {
+    let redemption_order = &mut ctx.accounts.redemption_order.load_mut()?;
+    let candidates_page = &mut ctx.accounts.candidates_page.load_init()?;
+
+    let page = redemption_operations::add_redemption_candidates_page(redemption_order)?;
+
+    let order_id = redemption_order.id;
+    msg!(
+        "Added candidates page {} to redemption order {}",
+        page,
+        order_id
+    );
+
+    candidates_page.redemption_order = ctx.accounts.redemption_order.key();
+    candidates_page.page = page;
+
+    Ok(())
+}
@@ -1,13 +1,21 @@
-use anchor_lang::{prelude::ProgramResult, Context};
+use anchor_lang::{prelude::ProgramResult, Context, Key};
 
 use crate::redemption::redemption_operations;
 use crate::redemption::types::AddRedemptionOrderEffects;
 use crate::utils::oracle::get_prices;
-use crate::AddRedemptionOrder;
+use crate::{AddRedemptionOrder, CollateralToken};
 
-pub fn process(ctx: Context<AddRedemptionOrder>, stablecoin_amount: u64) -> ProgramResult {
+pub fn process(
+    ctx: Context<AddRedemptionOrder>,
+    stablecoin_amount: u64,
+    max_fee_bps: u16,
+    min_collateral_value_usdh: Option<u64>,
+    preferred_collateral: Option<CollateralToken>,
+) -> ProgramResult {
     let redeemer_metadata = &mut ctx.accounts.redeemer_metadata;
     let redemptions_queue = &mut ctx.accounts.redemptions_queue.load_mut()?;
+    let redemption_order = &mut ctx.accounts.redemption_order.load_init()?;
+    let candidates_page = &mut ctx.accounts.candidates_page.load_init()?;
     let timestamp = ctx.accounts.clock.unix_timestamp as u64;
 
     let prices = get_prices(
@@ -21,22 +29,33 @@ pub fn process(ctx: Context<AddRedemptionOrder>, stablecoin_amount: u64) -> Prog
 
     let AddRedemptionOrderEffects {
         transfer_stablecoin_amount,
+        escrowed_fee_usdh,
         ..
     } = redemption_operations::add_redemption_order(
         redeemer_metadata,
         redemptions_queue,
+        redemption_order,
         &mut ctx.accounts.borrowing_market_state,
         &prices,
         timestamp,
         stablecoin_amount,
+        max_fee_bps,
+        min_collateral_value_usdh,
+        preferred_collateral,
+        ctx.accounts.global_config.redemption_stakers_fee_in_usdh,
     )?;
 
+    redemption_order.redemptions_queue = ctx.accounts.redemptions_queue.key();
+    candidates_page.redemption_order = ctx.accounts.redemption_order.key();
+    candidates_page.page = 0;
+
     crate::stablecoin::transfer(
-        transfer_stablecoin_amount,
+        transfer_stablecoin_amount + escrowed_fee_usdh,
         &ctx.accounts.redeemer_stablecoin_associated_account,
         &ctx.accounts.burning_vault,
         &ctx.accounts.redeemer,
         &ctx.accounts.token_program,
     )?;
+
     Ok(())
 }
@@ -0,0 +1,206 @@
+use anchor_lang::__private::ErrorCode;
+use anchor_lang::prelude::*;
+
+use crate::{
+    borrowing_market::{borrowing_operations, sorted_troves, types::AdjustTroveEffects},
+    pda, stablecoin,
+    state::CollateralToken,
+    token_operations::{soltoken, spltoken},
+    utils::{oracle::get_prices, pda::PDA},
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, crate::AdjustTrove<'info>>,
+    collateral_deltas: Vec<(CollateralToken, i64)>,
+    debt_delta: i64,
+) -> ProgramResult {
+    msg!("Ix=AdjustTrove {:?} {}", collateral_deltas, debt_delta);
+
+    // Remaining accounts are pairs of (user account, collateral vault),
+    // one per collateral delta and in the same order
+    let collateral_accounts = ctx.remaining_accounts;
+    if collateral_accounts.len() != 2 * collateral_deltas.len() {
+        return Err(ErrorCode::AccountNotEnoughKeys.into());
+    }
+    for (&(collateral, _), accounts) in collateral_deltas.iter().zip(collateral_accounts.chunks(2))
+    {
+        utils::assert_permissions(&ctx, collateral, &accounts[0], &accounts[1])?;
+    }
+
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
+
+    let AdjustTroveEffects {
+        collateral_to_transfer_from_user,
+        collateral_to_transfer_to_user,
+        amount_mint_to_user,
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        amount_to_burn,
+    } = borrowing_operations::adjust_trove(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.user_metadata,
+        &mut ctx.accounts.staking_pool_state,
+        &collateral_deltas,
+        debt_delta,
+        &prices,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
+    let borrowing_market_state = &ctx.accounts.borrowing_market_state;
+    let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+
+    // Collateral first, the repayment is funded by the user
+    for (&(collateral, _), accounts) in collateral_deltas.iter().zip(collateral_accounts.chunks(2))
+    {
+        let (user_account, collateral_vault) = (&accounts[0], &accounts[1]);
+
+        let deposit = collateral_to_transfer_from_user.token_amount(collateral);
+        if deposit > 0 {
+            match collateral {
+                CollateralToken::SOL => soltoken::transfer_from_user(
+                    deposit,
+                    user_account,
+                    collateral_vault,
+                    &ctx.accounts.system_program,
+                ),
+                _ => spltoken::transfer_from_user(
+                    deposit,
+                    user_account,
+                    collateral_vault,
+                    &ctx.accounts.owner,
+                    &ctx.accounts.token_program,
+                ),
+            }?;
+        }
+
+        let withdrawal = collateral_to_transfer_to_user.token_amount(collateral);
+        if withdrawal > 0 {
+            match collateral {
+                CollateralToken::SOL => {
+                    soltoken::transfer_from_vault(withdrawal, collateral_vault, user_account)
+                }
+                _ => spltoken::transfer_from_vault(
+                    withdrawal,
+                    PDA::collateral_vault_from(&borrowing_market_state.initial_market_owner),
+                    user_account,
+                    collateral_vault,
+                    &ctx.accounts.collateral_vaults_authority,
+                    borrowing_vaults.collateral_vaults_seed,
+                    &ctx.accounts.token_program,
+                    ctx.program_id,
+                ),
+            }?;
+        }
+    }
+
+    if amount_to_burn > 0 {
+        stablecoin::transfer(
+            amount_to_burn,
+            &ctx.accounts.stablecoin_borrowing_associated_account,
+            &ctx.accounts.burning_vault,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+
+        stablecoin::burn(
+            amount_to_burn,
+            &ctx.accounts.burning_vault,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.burning_vault_authority,
+            borrowing_vaults.burning_vault_seed,
+            pda::PDA::BurningPotAccount {
+                owner: borrowing_market_state.initial_market_owner,
+            },
+            ctx.program_id,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    for (amount, destination) in [
+        (
+            amount_mint_to_user,
+            &ctx.accounts.stablecoin_borrowing_associated_account,
+        ),
+        (
+            amount_mint_to_fees_vault,
+            &ctx.accounts.borrowing_fees_vault,
+        ),
+        (amount_mint_to_treasury_vault, &ctx.accounts.treasury_vault),
+    ] {
+        if amount > 0 {
+            stablecoin::mint(
+                amount,
+                borrowing_market_state.stablecoin_mint_seed,
+                borrowing_market_state.initial_market_owner,
+                ctx.program_id,
+                ctx.accounts.stablecoin_mint.clone(),
+                destination.clone(),
+                ctx.accounts.stablecoin_mint_authority.clone(),
+                ctx.accounts.token_program.to_account_info(),
+            )?;
+        }
+    }
+
+    msg!(
+        "Borrowed {} USDH + stakers fee {} + treasury fee {}",
+        amount_mint_to_user,
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault
+    );
+    msg!("Repaid {} USDH", amount_to_burn);
+
+    Ok(())
+}
+
+mod utils {
+    use crate::CollateralToken;
+    use anchor_lang::{
+        prelude::{msg, AccountInfo, ProgramResult},
+        Context, Key,
+    };
+    use vipers::{assert_ata, assert_keys_eq};
+
+    pub fn assert_permissions(
+        ctx: &Context<crate::AdjustTrove>,
+        collateral: CollateralToken,
+        user_account: &AccountInfo,
+        collateral_vault: &AccountInfo,
+    ) -> ProgramResult {
+        let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+
+        assert_keys_eq!(
+            borrowing_vaults.vault_address(collateral),
+            collateral_vault.key,
+            "Vault does not match borrowing market collateral vault"
+        );
+
+        if collateral != CollateralToken::SOL {
+            assert_ata!(
+                *user_account,
+                ctx.accounts.user_metadata.owner,
+                borrowing_vaults.mint_address(collateral),
+            );
+        } else {
+            assert_keys_eq!(
+                ctx.accounts.owner.key,
+                user_account.key,
+                "SOL should be moved from and to the owner native account"
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::msg;
+use anchor_lang::{prelude::ProgramResult, AccountsClose, Context, Key, ToAccountInfo};
+
+use crate::handler_fill_redemption_order::utils::{
+    candidates_to_pages, deserialize_remaining_candidate_pages, pages_to_candidates,
+};
+use crate::redemption::redemption_operations;
+use crate::redemption::types::CancelRedemptionOrderEffects;
+use crate::state::redemptions_queue::RedemptionOrderStatus;
+use crate::CancelRedemptionOrder;
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, CancelRedemptionOrder<'info>>,
+    order_id: u64,
+) -> ProgramResult {
+    let candidate_pages = deserialize_remaining_candidate_pages(
+        &ctx,
+        &ctx.accounts.redemption_order.key(),
+        ctx.accounts.redemption_order.load()?.candidate_pages,
+    )?;
+    let mut candidate_users = pages_to_candidates(&candidate_pages)?;
+    let redeemer_metadata = &ctx.accounts.redeemer_metadata;
+    let borrowing_market_state = &ctx.accounts.borrowing_market_state;
+    let redemptions_queue = &mut ctx.accounts.redemptions_queue.load_mut()?;
+    let mut redemption_order = ctx.accounts.redemption_order.load_mut()?;
+    let timestamp = ctx.accounts.clock.unix_timestamp as u64;
+
+    msg!(
+        "User {:?} cancelling redemption order {}",
+        redeemer_metadata.metadata_pk,
+        order_id,
+    );
+
+    let CancelRedemptionOrderEffects {
+        refunded_stablecoin,
+    } = redemption_operations::cancel_redemption_order(
+        order_id,
+        redeemer_metadata,
+        redemptions_queue,
+        &mut redemption_order,
+        &mut candidate_users,
+        ctx.accounts.global_config.redemption_order_expiry_seconds,
+        timestamp,
+    )?;
+
+    // The USDH was held in the burning pot, waiting to be burned
+    crate::stablecoin::transfer_from_burning_vault(
+        refunded_stablecoin,
+        borrowing_market_state.initial_market_owner,
+        &ctx.accounts.redeemer_stablecoin_associated_account,
+        &ctx.accounts.burning_vault,
+        &ctx.accounts.burning_vault_authority,
+        ctx.accounts.borrowing_vaults.burning_vault_seed,
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.program_id,
+    )?;
+
+    candidates_to_pages(&candidate_users, &candidate_pages)?;
+
+    // A fully refunded order and its candidate pages give their rent back
+    let order_closed = redemption_order.status == RedemptionOrderStatus::Inactive as u8;
+    drop(redemption_order);
+    if order_closed {
+        for page in candidate_pages.iter() {
+            page.close(ctx.accounts.redeemer.clone())?;
+        }
+        ctx.accounts
+            .redemption_order
+            .close(ctx.accounts.redeemer.clone())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use crate::staking_pool::{staking_pool_operations, types::TreasuryDisbursementEffects};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::CancelTreasuryDisbursement>) -> ProgramResult {
+    let TreasuryDisbursementEffects { amount, recipient } =
+        staking_pool_operations::cancel_treasury_disbursement(
+            &mut ctx.accounts.staking_pool_state,
+        )?;
+
+    msg!(
+        "Treasury disbursement of {} USDH to {} cancelled",
+        amount,
+        recipient
+    );
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use anchor_lang::{prelude::*, AccountsClose};
+
+use crate::{
+    borrowing_market::{borrowing_operations, sorted_troves, types::ClaimRedemptionSurplusEffects},
+    soltoken,
+    token_operations::spltoken,
+    utils::pda::PDA,
+    CollateralToken,
+};
+
+pub fn process(
+    ctx: Context<crate::ClaimRedemptionSurplus>,
+    collateral: CollateralToken,
+    close: bool,
+) -> ProgramResult {
+    msg!("Ix=ClaimRedemptionSurplus {:?} {}", collateral, close);
+    utils::assert_permissions(&ctx, collateral)?;
+
+    let ClaimRedemptionSurplusEffects {
+        collateral_to_transfer_to_user,
+        close_user_metadata,
+    } = borrowing_operations::claim_redemption_surplus(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.user_metadata,
+        collateral,
+        close,
+    )?;
+
+    // A redeemed trove has no place in the index, whether it is closed or not
+    sorted_troves::remove_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        ctx.accounts.user_metadata.user_id,
+    );
+
+    let amount = collateral_to_transfer_to_user.token_amount(collateral);
+    if amount > 0 {
+        match collateral {
+            CollateralToken::SOL => soltoken::transfer_from_vault(
+                amount,
+                &ctx.accounts.collateral_from,
+                &ctx.accounts.owner,
+            ),
+            _ => spltoken::transfer_from_vault(
+                amount,
+                PDA::collateral_vault_from(
+                    &ctx.accounts.borrowing_market_state.initial_market_owner,
+                ),
+                &ctx.accounts.collateral_to,
+                &ctx.accounts.collateral_from,
+                &ctx.accounts.collateral_from_authority,
+                ctx.accounts.borrowing_vaults.collateral_vaults_seed,
+                &ctx.accounts.token_program,
+                ctx.program_id,
+            ),
+        }?;
+    }
+
+    if close_user_metadata {
+        ctx.accounts
+            .user_metadata
+            .close(ctx.accounts.owner.clone())?;
+    }
+
+    Ok(())
+}
+
+mod utils {
+
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context, Key,
+    };
+    use vipers::{assert_ata, assert_keys_eq};
+
+    use crate::CollateralToken;
+
+    pub fn assert_permissions(
+        ctx: &Context<crate::ClaimRedemptionSurplus>,
+        collateral: CollateralToken,
+    ) -> ProgramResult {
+        let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+
+        let from_vault = ctx.accounts.collateral_from.key;
+        let from_authority = ctx.accounts.collateral_from_authority.key;
+
+        assert_keys_eq!(
+            borrowing_vaults.vault_address(collateral),
+            from_vault,
+            "From vault does not match borrowing market collateral vault"
+        );
+
+        if collateral != CollateralToken::SOL {
+            assert_keys_eq!(
+                borrowing_vaults.collateral_vaults_authority,
+                from_authority,
+                "From vault authority does not match borrowing market collateral vaults authority"
+            );
+            assert_ata!(
+                ctx.accounts.collateral_to,
+                ctx.accounts.user_metadata.owner,
+                borrowing_vaults.mint_address(collateral)
+            );
+        } else {
+            assert_keys_eq!(
+                ctx.accounts.owner.key,
+                ctx.accounts.collateral_to.key,
+                "To account should be the owner native account"
+            );
+        }
+
+        Ok(())
+    }
+}
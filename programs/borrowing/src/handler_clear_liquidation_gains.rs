@@ -1,12 +1,18 @@
 use crate::{
-    borrowing_market::types::ClearLiquidationGainsEffects, key, soltoken,
-    stability_pool::liquidations_queue, token_operations::spltoken, utils::pda::PDA,
-    CollateralToken,
+    borrowing_market::types::ClearLiquidationGainsEffects,
+    handler_stability_settle_withdrawal, key, soltoken,
+    stability_pool::{
+        liquidations_queue, stability_pool_operations, types::SettleStabilityWithdrawalEffects,
+    },
+    token_operations::{spltoken, stablecoin},
+    utils::pda::PDA,
+    CollateralToken, StabilityProviderState,
 };
+use anchor_lang::__private::ErrorCode;
 use anchor_lang::prelude::*;
 
-pub fn process(
-    ctx: Context<crate::ClearLiquidationGains>,
+pub fn process<'info>(
+    mut ctx: Context<'_, '_, '_, 'info, crate::ClearLiquidationGains<'info>>,
     token: CollateralToken,
 ) -> ProgramResult {
     msg!("ix=ClearLiquidationGains");
@@ -18,6 +24,7 @@ pub fn process(
     // 1. Move funds from collateral vault to rewards vault for x token
     // 2. Move funds from collateral vault to clearing agent ATA
     // 3. Close out pending liquidation events if possible
+    // 4. Settle the stability withdrawal requests if the queue is drained
 
     utils::assert_permissions(&ctx, token)?;
 
@@ -67,6 +74,72 @@ pub fn process(
         }
     }
 
+    settle_withdrawal_requests(&mut ctx)?;
+
+    Ok(())
+}
+
+fn settle_withdrawal_requests<'info>(
+    ctx: &mut Context<'_, '_, '_, 'info, crate::ClearLiquidationGains<'info>>,
+) -> ProgramResult {
+    // Withdrawal requests are submitted as remaining accounts,
+    // pairs of (stability_provider_state, stablecoin_ata)
+    let remaining_accounts = ctx.remaining_accounts;
+    if remaining_accounts.is_empty()
+        || liquidations_queue::has_pending_liquidation_events(
+            &mut ctx.accounts.liquidations_queue.load_mut()?,
+        )
+    {
+        return Ok(());
+    }
+
+    if remaining_accounts.len() % 2 != 0 {
+        return Err(ErrorCode::AccountNotEnoughKeys.into());
+    }
+
+    for accounts in remaining_accounts.chunks(2) {
+        let (provider_account, stablecoin_ata) = (&accounts[0], &accounts[1]);
+        let mut stability_provider_state =
+            ProgramAccount::<StabilityProviderState>::try_from(ctx.program_id, provider_account)?;
+
+        if stability_provider_state.stability_pool_state != key!(ctx, stability_pool_state) {
+            return Err(ErrorCode::ConstraintHasOne.into());
+        }
+
+        if stability_provider_state.pending_withdrawal_stablecoin == 0 {
+            // already settled
+            continue;
+        }
+
+        handler_stability_settle_withdrawal::utils::assert_provider_ata(
+            &stability_provider_state,
+            &ctx.accounts.borrowing_market_state,
+            stablecoin_ata,
+        )?;
+
+        let SettleStabilityWithdrawalEffects { usd_to_withdraw } =
+            stability_pool_operations::settle_stability_withdrawal(
+                &mut ctx.accounts.stability_pool_state,
+                &mut stability_provider_state,
+                &mut ctx.accounts.liquidations_queue.load_mut()?,
+            )?;
+
+        stablecoin::transfer_from_stability_pool(
+            usd_to_withdraw,
+            ctx.accounts.borrowing_market_state.initial_market_owner,
+            stablecoin_ata,
+            &ctx.accounts.stablecoin_stability_pool_vault,
+            &ctx.accounts.stablecoin_stability_pool_vault_authority,
+            ctx.accounts
+                .stability_vaults
+                .stablecoin_stability_pool_vault_seed,
+            &ctx.accounts.token_program,
+            ctx.program_id,
+        )?;
+
+        anchor_lang::AccountsExit::exit(&stability_provider_state, ctx.program_id)?;
+    }
+
     Ok(())
 }
 
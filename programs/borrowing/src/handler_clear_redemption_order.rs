@@ -1,27 +1,54 @@
 use anchor_lang::prelude::msg;
 use anchor_lang::solana_program::log::sol_log_compute_units;
-use anchor_lang::{prelude::ProgramResult, Context, ToAccountInfo};
+use anchor_lang::{prelude::ProgramResult, AccountsClose, Context, Key, ToAccountInfo};
 
+use crate::borrowing_market::borrowing_operations;
+use crate::borrowing_market::types::MintAccruedInterestEffects;
 use crate::handler_fill_redemption_order::utils::{
-    accounts_to_metadatas, deserialize_remaining_user_metadatas, serialize_user_metadatas,
+    accounts_to_metadatas, candidates_to_pages, deserialize_remaining_candidate_pages,
+    deserialize_remaining_user_metadatas, pages_to_candidates, remaining_closed_accounts,
+    serialize_user_metadatas,
 };
 use crate::key;
 use crate::redemption::redemption_operations;
 use crate::redemption::types::ClearRedemptionOrderEffects;
-use crate::utils::pda;
+use crate::stablecoin;
+use crate::state::redemptions_queue::RedemptionOrderStatus;
+use crate::utils::{oracle::get_prices, pda};
 use crate::ClearRedemptionOrder;
 
-pub fn process(ctx: Context<ClearRedemptionOrder>, order_id: u64) -> ProgramResult {
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, ClearRedemptionOrder<'info>>,
+    order_id: u64,
+) -> ProgramResult {
     let borrowing_market_state_pk = key!(ctx, borrowing_market_state);
     let mut metadata_accounts =
         deserialize_remaining_user_metadatas(&ctx, &borrowing_market_state_pk)?;
     let mut fillers_and_borrowers = accounts_to_metadatas(&mut metadata_accounts);
+    let closed_troves = remaining_closed_accounts(&ctx);
+    let candidate_pages = deserialize_remaining_candidate_pages(
+        &ctx,
+        &ctx.accounts.redemption_order.key(),
+        ctx.accounts.redemption_order.load()?.candidate_pages,
+    )?;
+    let mut candidate_users = pages_to_candidates(&candidate_pages)?;
     let clearer_metadata = &mut ctx.accounts.clearer_metadata;
     let redeemer_metadata = &mut ctx.accounts.redeemer_metadata;
     let borrowing_market_state = &mut ctx.accounts.borrowing_market_state;
     let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+    let staking_pool_state = &mut ctx.accounts.staking_pool_state;
     let redemptions_queue = &mut ctx.accounts.redemptions_queue.load_mut()?;
+    let mut redemption_order = ctx.accounts.redemption_order.load_mut()?;
+    let sorted_troves = &mut ctx.accounts.sorted_troves.load_mut()?;
     let timestamp = ctx.accounts.clock.unix_timestamp as u64;
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
 
     msg!("BEFORE EXTRACT CANDIDATES OR FILLERS");
     sol_log_compute_units();
@@ -38,13 +65,22 @@ pub fn process(ctx: Context<ClearRedemptionOrder>, order_id: u64) -> ProgramResu
     let ClearRedemptionOrderEffects {
         redeemed_stablecoin,
         redeemed_collateral: _redeemed_collateral,
+        stakers_fee_usdh,
+        treasury_fee_usdh,
+        refunded_stablecoin,
     } = redemption_operations::clear_redemption_order(
         order_id,
         redeemer_metadata,
         clearer_metadata,
         borrowing_market_state,
+        staking_pool_state,
         redemptions_queue,
+        &mut redemption_order,
+        &mut candidate_users,
         &mut fillers_and_borrowers,
+        &closed_troves,
+        sorted_troves,
+        &prices,
         timestamp,
     )?;
 
@@ -63,9 +99,71 @@ pub fn process(ctx: Context<ClearRedemptionOrder>, order_id: u64) -> ProgramResu
         ctx.program_id,
         &ctx.accounts.token_program,
     )?;
+
+    // The escrowed USDH fee is paid out of the burning pot as well
+    for (amount, to_vault) in [
+        (
+            refunded_stablecoin,
+            &ctx.accounts.redeemer_stablecoin_associated_account,
+        ),
+        (stakers_fee_usdh, &ctx.accounts.borrowing_fees_vault),
+        (treasury_fee_usdh, &ctx.accounts.treasury_vault),
+    ]
+    .iter()
+    {
+        if *amount > 0 {
+            crate::stablecoin::transfer_from_burning_vault(
+                *amount,
+                borrowing_market_state.initial_market_owner,
+                to_vault,
+                &ctx.accounts.burning_vault,
+                &ctx.accounts.burning_vault_authority,
+                borrowing_vaults.burning_vault_seed,
+                &ctx.accounts.token_program.to_account_info(),
+                ctx.program_id,
+            )?;
+        }
+    }
+
+    // Mint the interest charged on the troves to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        borrowing_market_state,
+        staking_pool_state,
+        timestamp,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        borrowing_market_state.stablecoin_mint_seed,
+        borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
     msg!("BEFORE WRITE");
     sol_log_compute_units();
 
+    candidates_to_pages(&candidate_users, &candidate_pages)?;
     serialize_user_metadatas(&ctx, &mut metadata_accounts);
+
+    // A completed order and its candidate pages give their rent back to the redeemer
+    let order_closed = redemption_order.status == RedemptionOrderStatus::Inactive as u8;
+    drop(redemption_order);
+    if order_closed {
+        for page in candidate_pages.iter() {
+            page.close(ctx.accounts.redeemer.clone())?;
+        }
+        ctx.accounts
+            .redemption_order
+            .close(ctx.accounts.redeemer.clone())?;
+    }
+
     Ok(())
 }
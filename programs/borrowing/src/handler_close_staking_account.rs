@@ -0,0 +1,17 @@
+use crate::staking_pool::staking_pool_operations;
+use anchor_lang::{prelude::*, AccountsClose};
+
+pub fn process(ctx: Context<crate::CloseStakingAccount>) -> ProgramResult {
+    msg!("ix=CloseStakingAccount");
+
+    staking_pool_operations::close_user_staking_state(
+        &mut ctx.accounts.staking_pool_state,
+        &ctx.accounts.user_staking_state,
+    )?;
+
+    ctx.accounts
+        .user_staking_state
+        .close(ctx.accounts.owner.clone())?;
+
+    Ok(())
+}
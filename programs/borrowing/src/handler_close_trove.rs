@@ -0,0 +1,178 @@
+use anchor_lang::__private::ErrorCode;
+use anchor_lang::{prelude::*, AccountsClose};
+
+use crate::{
+    borrowing_market::{
+        borrowing_operations, sorted_troves,
+        types::{CloseTroveEffects, MintAccruedInterestEffects},
+    },
+    pda, stablecoin,
+    state::CollateralToken,
+    token_operations::{soltoken, spltoken},
+    utils::pda::PDA,
+};
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, crate::CloseTrove<'info>>) -> ProgramResult {
+    msg!("Ix=CloseTrove");
+
+    // Remaining accounts are pairs of (user account, collateral vault),
+    // one for each collateral the trove holds, in any order
+    let collateral_pairs = ctx.remaining_accounts.chunks_exact(2);
+    if !collateral_pairs.remainder().is_empty() {
+        return Err(ErrorCode::AccountNotEnoughKeys.into());
+    }
+    let collateral_accounts = collateral_pairs
+        .map(|accounts| utils::assert_permissions(&ctx, &accounts[0], &accounts[1]))
+        .collect::<Result<Vec<_>, ProgramError>>()?;
+
+    let CloseTroveEffects {
+        amount_to_burn,
+        collateral_to_transfer_to_user,
+    } = borrowing_operations::close_trove(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.user_metadata,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    sorted_troves::remove_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        ctx.accounts.user_metadata.user_id,
+    );
+
+    // Mint the interest charged on the trove to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        ctx.accounts.borrowing_market_state.stablecoin_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    let borrowing_market_state = &ctx.accounts.borrowing_market_state;
+    let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+
+    if amount_to_burn > 0 {
+        stablecoin::transfer(
+            amount_to_burn,
+            &ctx.accounts.stablecoin_borrowing_associated_account,
+            &ctx.accounts.burning_vault,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+
+        stablecoin::burn(
+            amount_to_burn,
+            &ctx.accounts.burning_vault,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.burning_vault_authority,
+            borrowing_vaults.burning_vault_seed,
+            pda::PDA::BurningPotAccount {
+                owner: borrowing_market_state.initial_market_owner,
+            },
+            ctx.program_id,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    use CollateralToken::*;
+    for collateral in [SOL, ETH, BTC, SRM, RAY, FTT].iter().copied() {
+        let amount = collateral_to_transfer_to_user.token_amount(collateral);
+        if amount == 0 {
+            continue;
+        }
+
+        let (user_account, collateral_vault) = collateral_accounts
+            .iter()
+            .find(|(token, _, _)| *token == collateral)
+            .map(|(_, user_account, collateral_vault)| (*user_account, *collateral_vault))
+            .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+
+        match collateral {
+            SOL => soltoken::transfer_from_vault(amount, collateral_vault, user_account),
+            _ => spltoken::transfer_from_vault(
+                amount,
+                PDA::collateral_vault_from(&borrowing_market_state.initial_market_owner),
+                user_account,
+                collateral_vault,
+                &ctx.accounts.collateral_vaults_authority,
+                borrowing_vaults.collateral_vaults_seed,
+                &ctx.accounts.token_program,
+                ctx.program_id,
+            ),
+        }?;
+    }
+
+    msg!(
+        "Repaid {} USDH, withdrew {:?}",
+        amount_to_burn,
+        collateral_to_transfer_to_user
+    );
+
+    ctx.accounts
+        .user_metadata
+        .close(ctx.accounts.owner.clone())?;
+
+    Ok(())
+}
+
+mod utils {
+    use crate::CollateralToken;
+    use anchor_lang::{
+        __private::ErrorCode,
+        prelude::{msg, AccountInfo, ProgramError},
+        Context, Key,
+    };
+    use vipers::{assert_ata, assert_keys_eq};
+
+    #[allow(clippy::type_complexity)]
+    pub fn assert_permissions<'a, 'info>(
+        ctx: &Context<crate::CloseTrove>,
+        user_account: &'a AccountInfo<'info>,
+        collateral_vault: &'a AccountInfo<'info>,
+    ) -> Result<
+        (
+            CollateralToken,
+            &'a AccountInfo<'info>,
+            &'a AccountInfo<'info>,
+        ),
+        ProgramError,
+    > {
+        let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+
+        use CollateralToken::*;
+        let collateral = [SOL, ETH, BTC, SRM, RAY, FTT]
+            .iter()
+            .copied()
+            .find(|token| borrowing_vaults.vault_address(*token) == *collateral_vault.key)
+            .ok_or(ErrorCode::ConstraintHasOne)?;
+
+        if collateral != SOL {
+            assert_ata!(
+                *user_account,
+                ctx.accounts.user_metadata.owner,
+                borrowing_vaults.mint_address(collateral),
+            );
+        } else {
+            assert_keys_eq!(
+                ctx.accounts.owner.key,
+                user_account.key,
+                "SOL should be withdrawn to the owner native account"
+            );
+        }
+
+        Ok((collateral, user_account, collateral_vault))
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::{prelude::*, AccountsClose};
+
+use crate::borrowing_market::{borrowing_operations, sorted_troves};
+
+pub fn process(ctx: Context<crate::CloseTroveAccount>) -> ProgramResult {
+    msg!("Ix=CloseTroveAccount");
+
+    borrowing_operations::close_trove_account(
+        &mut ctx.accounts.borrowing_market_state,
+        &ctx.accounts.user_metadata,
+    )?;
+
+    // A repaid trove is still indexed, nobody could remove it once closed
+    sorted_troves::remove_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        ctx.accounts.user_metadata.user_id,
+    );
+
+    ctx.accounts
+        .user_metadata
+        .close(ctx.accounts.owner.clone())?;
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::staking_pool::{staking_pool_operations, types::CompleteUnstakeEffects};
+use crate::token_operations::hbb;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::CompleteUnstakeHbbStakingPool>) -> ProgramResult {
+    msg!("ix=CompleteUnstakeHbb");
+    utils::assert_permissions(&ctx)?;
+
+    let borrowing_market_state = &ctx.accounts.borrowing_market_state;
+    let staking_pool_state = &mut ctx.accounts.staking_pool_state;
+
+    let CompleteUnstakeEffects { amount_to_withdraw } = staking_pool_operations::complete_unstake(
+        staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    hbb::transfer_from_staking_pool(
+        amount_to_withdraw,
+        borrowing_market_state.initial_market_owner,
+        &ctx.accounts.user_hbb_staking_ata,
+        &ctx.accounts.staking_vault,
+        &ctx.accounts.staking_vault_authority,
+        staking_pool_state.staking_vault_seed,
+        &ctx.accounts.token_program,
+        ctx.program_id,
+    )?;
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context,
+    };
+    use vipers::assert_ata;
+
+    pub fn assert_permissions(
+        ctx: &Context<crate::CompleteUnstakeHbbStakingPool>,
+    ) -> ProgramResult {
+        assert_ata!(
+            ctx.accounts.user_hbb_staking_ata,
+            ctx.accounts.owner,
+            ctx.accounts.borrowing_market_state.hbb_mint,
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{utils::pda, BorrowError};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use vipers::assert_keys_eq;
+
+/// Initializes the open orders the compounding orders go through, owned by
+/// the borrowing fees vault authority on the market set by the admin
+pub fn process(ctx: Context<crate::CompoundInitOpenOrders>) -> ProgramResult {
+    msg!("Ix=CompoundInitOpenOrders");
+    assert_keys_eq!(ctx.accounts.dex_program.key, dex::ID);
+
+    if ctx.accounts.market.key() != ctx.accounts.staking_pool_state.compound_dex_market {
+        return Err(BorrowError::InvalidDexInputs.into());
+    }
+
+    let mode = pda::PDA::BorrowingFeesAccount {
+        owner: ctx.accounts.borrowing_market_state.initial_market_owner,
+    };
+    let pda_seeds = pda::make_pda_seeds(&mode, ctx.program_id);
+    let bump = vec![ctx.accounts.borrowing_vaults.borrowing_fees_vault_seed];
+    let seeds = [pda_seeds[0].as_ref(), pda_seeds[1].as_ref(), bump.as_ref()];
+    let signer = &[&seeds[..]];
+
+    let dex_accs = dex::InitOpenOrders {
+        open_orders: ctx.accounts.open_orders.clone(),
+        authority: ctx.accounts.borrowing_fees_vault_authority.clone(),
+        market: ctx.accounts.market.clone(),
+        rent: ctx.accounts.rent.to_account_info().clone(),
+    };
+
+    let ctx = CpiContext::new(ctx.accounts.dex_program.clone(), dex_accs).with_signer(signer);
+    dex::init_open_orders(ctx)?;
+
+    Ok(())
+}
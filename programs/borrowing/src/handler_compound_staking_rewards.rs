@@ -0,0 +1,149 @@
+use std::num::NonZeroU64;
+
+use crate::staking_pool::staking_pool_operations;
+use crate::utils::pda;
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::dex::serum_dex::{
+    instruction::SelfTradeBehavior,
+    matching::{OrderType, Side},
+};
+use anchor_spl::token;
+
+/// Buys HBB on the Serum USDH/HBB market with the USDH rewards of the
+/// auto-compounding stakers, same flow as `handler_serum_swap`.
+///
+/// Only the market set by the admin is accepted. The order is paid straight
+/// from the borrowing fees vault, its PDA owns the open orders and signs, so
+/// nothing passes through an account of the keeper. The bought HBB is settled
+/// into the staking vault and any unmatched USDH back into the fees vault,
+/// and the swap fails if it bought HBB above the admin's max price.
+///
+/// # Arguments
+///
+/// * `limit_price` - the highest price, in USDH lots per HBB lot, the keeper accepts to pay
+pub fn process(ctx: Context<crate::CompoundStakingRewards>, limit_price: u64) -> ProgramResult {
+    msg!("Ix=CompoundStakingRewards");
+    utils::assert_permissions(&ctx)?;
+
+    let usdh_to_swap =
+        staking_pool_operations::rewards_to_compound(&ctx.accounts.staking_pool_state)?;
+
+    let fees_vault_amount_before = token::accessor::amount(&ctx.accounts.borrowing_fees_vault)?;
+    let staking_vault_amount_before = token::accessor::amount(&ctx.accounts.staking_vault)?;
+
+    let mode = pda::PDA::BorrowingFeesAccount {
+        owner: ctx.accounts.borrowing_market_state.initial_market_owner,
+    };
+    let pda_seeds = pda::make_pda_seeds(&mode, ctx.program_id);
+    let bump = vec![ctx.accounts.borrowing_vaults.borrowing_fees_vault_seed];
+    let seeds = [pda_seeds[0].as_ref(), pda_seeds[1].as_ref(), bump.as_ref()];
+    let signer = &[&seeds[..]];
+
+    let dex_accs = dex::NewOrderV3 {
+        market: ctx.accounts.market.clone(),
+        open_orders: ctx.accounts.open_orders.clone(),
+        request_queue: ctx.accounts.request_queue.clone(),
+        event_queue: ctx.accounts.event_queue.clone(),
+        market_bids: ctx.accounts.bids.clone(),
+        market_asks: ctx.accounts.asks.clone(),
+        order_payer_token_account: ctx.accounts.borrowing_fees_vault.clone(),
+        open_orders_authority: ctx.accounts.borrowing_fees_vault_authority.clone(),
+        coin_vault: ctx.accounts.coin_vault.clone(),
+        pc_vault: ctx.accounts.pc_vault.clone(),
+        token_program: ctx.accounts.token_program.clone(),
+        rent: ctx.accounts.rent.to_account_info().clone(),
+    };
+
+    let ctx_order = CpiContext::new(ctx.accounts.dex_program.clone(), dex_accs).with_signer(signer);
+
+    // Buying HBB (coin) with all the USDH (pc) to compound
+    dex::new_order_v3(
+        ctx_order,
+        Side::Bid,
+        NonZeroU64::new(limit_price).ok_or(crate::BorrowError::InvalidDexInputs)?,
+        NonZeroU64::new(u64::MAX).unwrap(),
+        NonZeroU64::new(usdh_to_swap).unwrap(),
+        SelfTradeBehavior::DecrementTake,
+        OrderType::ImmediateOrCancel,
+        0,     // ok to hardcode this (only used for cancels)
+        65535, // dex's custom compute budget parameter
+    )?;
+
+    let settle_accs = dex::SettleFunds {
+        market: ctx.accounts.market.clone(),
+        open_orders: ctx.accounts.open_orders.clone(),
+        open_orders_authority: ctx.accounts.borrowing_fees_vault_authority.clone(),
+        coin_vault: ctx.accounts.coin_vault.clone(),
+        pc_vault: ctx.accounts.pc_vault.clone(),
+        coin_wallet: ctx.accounts.staking_vault.clone(),
+        pc_wallet: ctx.accounts.borrowing_fees_vault.clone(),
+        vault_signer: ctx.accounts.vault_signer.clone(),
+        token_program: ctx.accounts.token_program.clone(),
+    };
+
+    let ctx_settle =
+        CpiContext::new(ctx.accounts.dex_program.clone(), settle_accs).with_signer(signer);
+
+    dex::settle_funds(ctx_settle)?;
+
+    let fees_vault_amount_after = token::accessor::amount(&ctx.accounts.borrowing_fees_vault)?;
+    let staking_vault_amount_after = token::accessor::amount(&ctx.accounts.staking_vault)?;
+
+    let usdh_spent = fees_vault_amount_before
+        .checked_sub(fees_vault_amount_after)
+        .unwrap();
+    let hbb_bought = staking_vault_amount_after
+        .checked_sub(staking_vault_amount_before)
+        .unwrap();
+
+    msg!("Compounded {} USDH into {} HBB", usdh_spent, hbb_bought);
+
+    utils::assert_swap_not_zero(usdh_spent, hbb_bought)?;
+
+    staking_pool_operations::compound_rewards(
+        &mut ctx.accounts.staking_pool_state,
+        usdh_spent,
+        hbb_bought,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context, Key,
+    };
+    use anchor_spl::dex;
+    use vipers::assert_keys_eq;
+
+    use crate::{handler_serum_swap, BorrowError};
+
+    pub fn assert_permissions(ctx: &Context<crate::CompoundStakingRewards>) -> ProgramResult {
+        assert_keys_eq!(ctx.accounts.dex_program.key, dex::ID);
+
+        // Only the market whitelisted by the admin
+        if ctx.accounts.market.key() != ctx.accounts.staking_pool_state.compound_dex_market {
+            return Err(BorrowError::InvalidDexInputs.into());
+        }
+
+        handler_serum_swap::utils::assert_dex_inputs(
+            &ctx.accounts.market,
+            ctx.accounts.dex_program.key,
+            &ctx.accounts.borrowing_market_state.hbb_mint,
+            &ctx.accounts.borrowing_market_state.stablecoin_mint,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn assert_swap_not_zero(usdh_spent: u64, hbb_bought: u64) -> ProgramResult {
+        if usdh_spent == 0 || hbb_bought == 0 {
+            return Err(BorrowError::ZeroSwap.into());
+        }
+
+        Ok(())
+    }
+}
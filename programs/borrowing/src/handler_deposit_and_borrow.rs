@@ -2,7 +2,7 @@ use anchor_lang::prelude::*;
 pub use anchor_lang::solana_program::native_token::{lamports_to_sol, sol_to_lamports};
 
 use crate::{
-    borrowing_market::{borrowing_operations, types::DepositAndBorrowEffects},
+    borrowing_market::{borrowing_operations, sorted_troves, types::DepositAndBorrowEffects},
     stablecoin,
     state::CollateralToken,
     token_operations::{soltoken, spltoken},
@@ -45,6 +45,12 @@ pub fn process(
         ctx.accounts.clock.unix_timestamp as u64,
     )?;
 
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
     // Mint USDH to user
     stablecoin::mint(
         amount_mint_to_user,
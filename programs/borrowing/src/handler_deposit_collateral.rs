@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    borrowing_market::{borrowing_operations, types::DepositCollateralEffects},
+    borrowing_market::{
+        borrowing_operations, sorted_troves,
+        types::{DepositCollateralEffects, MintAccruedInterestEffects},
+    },
+    stablecoin,
     token_operations::{soltoken, spltoken},
+    utils::oracle::get_prices,
     CollateralToken,
 };
 
@@ -14,6 +19,15 @@ pub fn process(
     msg!("Depositing {:?}", collateral);
     utils::assert_permissions(&ctx, collateral)?;
 
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
+
     let DepositCollateralEffects {
         collateral_to_transfer_from_user,
     } = borrowing_operations::deposit_collateral(
@@ -21,6 +35,35 @@ pub fn process(
         &mut ctx.accounts.user_metadata,
         amount_in_lamports,
         collateral,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
+    // Mint the interest charged on the trove to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        ctx.accounts.borrowing_market_state.stablecoin_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
     )?;
 
     let amount = collateral_to_transfer_from_user.token_amount(collateral) as u64;
@@ -0,0 +1,62 @@
+use crate::staking_pool::{staking_pool_operations, types::TreasuryDisbursementEffects};
+use crate::token_operations::spltoken;
+use crate::utils::pda::PDA;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::ExecuteTreasuryDisbursement>) -> ProgramResult {
+    // Permissionless, anyone can pay out a scheduled
+    // disbursement once its timelock has elapsed
+
+    let staking_pool_state = &mut ctx.accounts.staking_pool_state;
+
+    let TreasuryDisbursementEffects { amount, recipient } =
+        staking_pool_operations::execute_treasury_disbursement(
+            staking_pool_state,
+            ctx.accounts.clock.unix_timestamp as u64,
+        )?;
+
+    utils::assert_permissions(&ctx.accounts.recipient, &recipient)?;
+
+    spltoken::transfer_from_vault(
+        amount,
+        PDA::Treasury {
+            owner: ctx.accounts.borrowing_market_state.initial_market_owner,
+        },
+        &ctx.accounts.recipient,
+        &ctx.accounts.treasury_vault,
+        &ctx.accounts.treasury_vault_authority,
+        staking_pool_state.treasury_vault_seed,
+        &ctx.accounts.token_program,
+        ctx.program_id,
+    )?;
+
+    msg!(
+        "Treasury disbursed {} USDH from {} to {}",
+        amount,
+        staking_pool_state.treasury_vault,
+        recipient
+    );
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, AccountInfo, ProgramResult, Pubkey},
+        Key,
+    };
+    use vipers::assert_keys_eq;
+
+    pub fn assert_permissions(
+        recipient: &AccountInfo,
+        expected_recipient: &Pubkey,
+    ) -> ProgramResult {
+        assert_keys_eq!(
+            recipient.key,
+            expected_recipient,
+            "Recipient does not match the scheduled disbursement"
+        );
+
+        Ok(())
+    }
+}
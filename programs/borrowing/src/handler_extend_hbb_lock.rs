@@ -0,0 +1,20 @@
+use crate::staking_pool::staking_pool_operations;
+
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<crate::ExtendHbbLockStakingPool>,
+    lock_expiry_ts: u64,
+) -> ProgramResult {
+    msg!("ix=ExtendHbbLock until {}", lock_expiry_ts);
+
+    staking_pool_operations::extend_lock(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        lock_expiry_ts,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    Ok(())
+}
@@ -1,10 +1,13 @@
 use anchor_lang::prelude::msg;
 #[cfg(not(test))]
 use anchor_lang::solana_program::log::sol_log_compute_units;
-use anchor_lang::{prelude::ProgramResult, Context, ToAccountInfo};
+use anchor_lang::{prelude::ProgramResult, Context, Key, ToAccountInfo};
 
+use crate::borrowing_market::borrowing_operations;
+use crate::borrowing_market::types::MintAccruedInterestEffects;
 use crate::key;
 use crate::redemption::redemption_operations;
+use crate::stablecoin;
 use crate::{log_compute_units, FillRedemptionOrder};
 
 pub fn process(ctx: Context<FillRedemptionOrder>, order_id: u64) -> ProgramResult {
@@ -13,8 +16,14 @@ pub fn process(ctx: Context<FillRedemptionOrder>, order_id: u64) -> ProgramResul
     let mut metadata_accounts =
         utils::deserialize_remaining_user_metadatas(&ctx, &borrowing_market_state_pk)?;
     let mut submitted_candidates = utils::accounts_to_metadatas(&mut metadata_accounts);
+    let candidate_pages = utils::deserialize_remaining_candidate_pages(
+        &ctx,
+        &ctx.accounts.redemption_order.key(),
+        ctx.accounts.redemption_order.load()?.candidate_pages,
+    )?;
+    let mut candidate_users = utils::pages_to_candidates(&candidate_pages)?;
+    let redemption_order = &mut ctx.accounts.redemption_order.load_mut()?;
     let filler_metadata = &mut ctx.accounts.filler_metadata;
-    let redemptions_queue = &mut ctx.accounts.redemptions_queue.load_mut()?;
     let borrowing_market_state = &mut ctx.accounts.borrowing_market_state;
     let timestamp = ctx.accounts.clock.unix_timestamp as u64;
 
@@ -29,13 +38,37 @@ pub fn process(ctx: Context<FillRedemptionOrder>, order_id: u64) -> ProgramResul
     redemption_operations::fill_redemption_order(
         order_id,
         borrowing_market_state,
-        redemptions_queue,
+        redemption_order,
+        &mut candidate_users,
         &mut submitted_candidates,
         filler_metadata,
         timestamp,
     )?;
 
+    // Mint the interest charged on the troves to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        timestamp,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        borrowing_market_state.stablecoin_mint_seed,
+        borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
     log_compute_units!("Fill Redemption Order - After Merge");
+    utils::candidates_to_pages(&candidate_users, &candidate_pages)?;
     utils::serialize_user_metadatas(&ctx, &mut metadata_accounts);
 
     Ok(())
@@ -45,10 +78,11 @@ pub mod utils {
     use std::ops::DerefMut;
 
     use anchor_lang::__private::ErrorCode;
-    use anchor_lang::prelude::Pubkey;
-    use anchor_lang::{Context, ProgramAccount};
+    use anchor_lang::prelude::{ProgramError, ProgramResult, Pubkey};
+    use anchor_lang::{Context, Loader, ProgramAccount};
 
-    use crate::UserMetadata;
+    use crate::state::{CandidateRedemptionUser, RedemptionCandidatesPage};
+    use crate::{BorrowError, UserMetadata};
 
     pub fn deserialize_remaining_user_metadatas<'a, 'info, T>(
         ctx: &'a Context<'_, '_, '_, 'info, T>,
@@ -74,6 +108,71 @@ pub mod utils {
         metadata_program_accounts
     }
 
+    pub fn remaining_closed_accounts<T>(ctx: &Context<T>) -> Vec<Pubkey> {
+        // Accounts that no longer exist, such as closed trove metadatas
+        ctx.remaining_accounts
+            .iter()
+            .filter(|unsafe_acc| {
+                unsafe_acc.is_writable && unsafe_acc.lamports() == 0 && unsafe_acc.data_is_empty()
+            })
+            .map(|unsafe_acc| *unsafe_acc.key)
+            .collect()
+    }
+
+    pub fn deserialize_remaining_candidate_pages<'info, T>(
+        ctx: &Context<'_, '_, '_, 'info, T>,
+        redemption_order: &Pubkey,
+        candidate_pages: u64,
+    ) -> Result<Vec<Loader<'info, RedemptionCandidatesPage>>, ProgramError> {
+        let pages: Vec<Loader<RedemptionCandidatesPage>> = ctx
+            .remaining_accounts
+            .iter()
+            .filter_map(|unsafe_acc| {
+                if !unsafe_acc.is_writable {
+                    None
+                } else {
+                    Loader::<RedemptionCandidatesPage>::try_from(ctx.program_id, unsafe_acc).ok()
+                }
+            })
+            .collect();
+
+        // All the pages of the order, in order, so that the
+        // candidates stay sorted across page boundaries
+        if pages.len() as u64 != candidate_pages {
+            return Err(BorrowError::InvalidRedemptionCandidatesPage.into());
+        }
+        for (i, page) in pages.iter().enumerate() {
+            let page = page.load()?;
+            if &page.redemption_order != redemption_order || page.page != i as u64 {
+                return Err(BorrowError::InvalidRedemptionCandidatesPage.into());
+            }
+        }
+
+        Ok(pages)
+    }
+
+    pub fn pages_to_candidates(
+        pages: &[Loader<RedemptionCandidatesPage>],
+    ) -> Result<Vec<CandidateRedemptionUser>, ProgramError> {
+        let mut candidates = Vec::with_capacity(pages.len() * 32);
+        for page in pages.iter() {
+            candidates.extend_from_slice(&page.load()?.candidate_users);
+        }
+        Ok(candidates)
+    }
+
+    pub fn candidates_to_pages(
+        candidates: &[CandidateRedemptionUser],
+        pages: &[Loader<RedemptionCandidatesPage>],
+    ) -> ProgramResult {
+        for (page, page_candidates) in pages.iter().zip(candidates.chunks(32)) {
+            page.load_mut()?
+                .candidate_users
+                .copy_from_slice(page_candidates);
+        }
+        Ok(())
+    }
+
     pub fn accounts_to_metadatas<'a>(
         submitted_candidates_p: &'a mut Vec<ProgramAccount<UserMetadata>>,
     ) -> Vec<&'a mut UserMetadata> {
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    soltoken,
+    staking_pool::{staking_pool_operations, types::HarvestCollateralEffects},
+    token_operations::spltoken,
+    utils::pda::PDA,
+    CollateralToken,
+};
+
+pub fn process(
+    ctx: Context<crate::HarvestCollateralRewardStakingPool>,
+    collateral: CollateralToken,
+) -> ProgramResult {
+    msg!("Ix=HarvestCollateralRewardStakingPool {:?}", collateral);
+    utils::assert_permissions(&ctx, collateral)?;
+
+    let HarvestCollateralEffects { collateral_reward } =
+        staking_pool_operations::user_harvest_collateral(
+            &mut ctx.accounts.staking_pool_state,
+            &mut ctx.accounts.user_staking_state,
+            &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+            collateral,
+            ctx.accounts.clock.unix_timestamp as u64,
+        )?;
+
+    // Stakers collateral was left in the vaults as inactive collateral
+    ctx.accounts
+        .borrowing_market_state
+        .inactive_collateral
+        .sub_assign(&collateral_reward);
+
+    let amount = collateral_reward.token_amount(collateral);
+    msg!("Reward is {}", amount);
+    match collateral {
+        CollateralToken::SOL => soltoken::transfer_from_vault(
+            amount,
+            &ctx.accounts.collateral_from,
+            &ctx.accounts.owner,
+        ),
+        _ => spltoken::transfer_from_vault(
+            amount,
+            PDA::collateral_vault_from(&ctx.accounts.borrowing_market_state.initial_market_owner),
+            &ctx.accounts.collateral_to,
+            &ctx.accounts.collateral_from,
+            &ctx.accounts.collateral_from_authority,
+            ctx.accounts.borrowing_vaults.collateral_vaults_seed,
+            &ctx.accounts.token_program,
+            ctx.program_id,
+        ),
+    }?;
+
+    Ok(())
+}
+
+mod utils {
+
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context, Key,
+    };
+    use vipers::{assert_ata, assert_keys_eq};
+
+    use crate::CollateralToken;
+
+    pub fn assert_permissions(
+        ctx: &Context<crate::HarvestCollateralRewardStakingPool>,
+        collateral: CollateralToken,
+    ) -> ProgramResult {
+        let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+
+        let from_vault = ctx.accounts.collateral_from.key;
+        let from_authority = ctx.accounts.collateral_from_authority.key;
+
+        assert_keys_eq!(
+            borrowing_vaults.vault_address(collateral),
+            from_vault,
+            "From vault does not match borrowing market collateral vault"
+        );
+
+        if collateral != CollateralToken::SOL {
+            assert_keys_eq!(
+                borrowing_vaults.collateral_vaults_authority,
+                from_authority,
+                "From vault authority does not match borrowing market collateral vaults authority"
+            );
+            assert_ata!(
+                ctx.accounts.collateral_to,
+                ctx.accounts.owner,
+                borrowing_vaults.mint_address(collateral)
+            );
+        } else {
+            assert_keys_eq!(
+                ctx.accounts.owner.key,
+                ctx.accounts.collateral_to.key,
+                "To account should be the owner native account"
+            );
+        }
+
+        Ok(())
+    }
+}
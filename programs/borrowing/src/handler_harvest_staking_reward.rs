@@ -11,8 +11,12 @@ pub fn process(ctx: Context<crate::HarvestRewardStakingPool>) -> ProgramResult {
     let staking_pool_state = &mut ctx.accounts.staking_pool_state;
     let user_staking_state = &mut ctx.accounts.user_staking_state;
 
-    let HarvestEffects { reward } =
-        staking_pool_operations::user_harvest(staking_pool_state, user_staking_state)?;
+    let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+        staking_pool_state,
+        user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
 
     msg!("Reward is {}", reward);
     if reward > 0 {
@@ -41,13 +45,23 @@ mod utils {
     use crate::BorrowError;
 
     pub fn assert_permissions(ctx: &Context<crate::HarvestRewardStakingPool>) -> ProgramResult {
-        assert_amount_not_zero(ctx.accounts.user_staking_state.user_stake)?;
+        let user_staking_state = &ctx.accounts.user_staking_state;
 
-        assert_there_is_reward(
-            ctx.accounts.user_staking_state.rewards_tally,
-            ctx.accounts.user_staking_state.user_stake,
-            ctx.accounts.staking_pool_state.reward_per_token,
-        )?;
+        // Rewards settled from a withdrawn lock can still be harvested
+        if user_staking_state.pending_rewards == 0 {
+            // Auto-compounding stakes do not earn USDH
+            if user_staking_state.auto_compound {
+                return Err(BorrowError::NoRewardToWithdraw.into());
+            }
+
+            assert_amount_not_zero(user_staking_state.boosted_stake)?;
+
+            assert_there_is_reward(
+                user_staking_state.rewards_tally,
+                user_staking_state.boosted_stake,
+                ctx.accounts.staking_pool_state.reward_per_token,
+            )?;
+        }
 
         assert_ata!(
             ctx.accounts.user_stablecoin_rewards_ata,
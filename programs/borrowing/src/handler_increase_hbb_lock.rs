@@ -0,0 +1,28 @@
+use crate::handler_lock_hbb::utils;
+use crate::staking_pool::staking_pool_operations;
+use crate::token_operations::hbb;
+
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::LockHbbStakingPool>, amount: u64) -> ProgramResult {
+    msg!("ix=IncreaseHbbLock {}", amount);
+    utils::assert_permissions(&ctx)?;
+
+    staking_pool_operations::increase_lock_amount(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        amount,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    hbb::transfer(
+        amount,
+        &ctx.accounts.user_hbb_staking_ata,
+        &ctx.accounts.staking_vault,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+    )?;
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use crate::{
-    borrowing_market::borrowing_operations, key, pda, state::CollateralToken,
-    utils::consts::BOOTSTRAP_PERIOD,
+    borrowing_market::{borrowing_operations, sorted_troves},
+    key, pda,
+    state::CollateralToken,
+    utils::consts::{BOOTSTRAP_PERIOD, REDEMPTION_ORDER_EXPIRY_SECONDS, UNSTAKE_COOLDOWN_SECONDS},
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, SetAuthority};
@@ -33,11 +35,14 @@ pub fn process(ctx: Context<crate::InitializeBorrowingMarket>) -> ProgramResult
     global_config.initial_market_owner = key!(ctx, initial_market_owner);
     global_config.is_borrowing_allowed = true;
     global_config.borrow_limit_usdh = 1_000;
+    global_config.redemption_order_expiry_seconds = REDEMPTION_ORDER_EXPIRY_SECONDS;
+    global_config.unstake_cooldown_seconds = UNSTAKE_COOLDOWN_SECONDS;
 
     // 6. Initialize Global State
     let market = &mut ctx.accounts.borrowing_market_state;
     market.initial_market_owner = key!(ctx, initial_market_owner);
     market.redemptions_queue = key!(ctx, redemptions_queue);
+    market.sorted_troves = key!(ctx, sorted_troves);
 
     market.stablecoin_mint = key!(ctx, stablecoin_mint);
     market.stablecoin_mint_authority = pda_mint_stable.key;
@@ -78,6 +83,10 @@ pub fn process(ctx: Context<crate::InitializeBorrowingMarket>) -> ProgramResult
     // Update state
     let now = ctx.accounts.clock.unix_timestamp as u64;
     borrowing_operations::initialize_borrowing_market(market, now + BOOTSTRAP_PERIOD);
+    sorted_troves::initialize_sorted_troves(
+        &mut *ctx.accounts.sorted_troves.load_init()?,
+        key!(market),
+    );
 
     Ok(())
 }
@@ -4,6 +4,7 @@ use pda::PDA::*;
 use utils::*;
 
 use crate::{
+    borrowing_market::borrowing_operations,
     key,
     stability_pool::stability_pool_operations,
     state::{
@@ -13,13 +14,17 @@ use crate::{
     utils::pda,
 };
 
-pub fn process(ctx: Context<crate::InitializeStabilityPool>) -> ProgramResult {
+pub fn process(
+    ctx: Context<crate::InitializeStabilityPool>,
+    collateral_coverage: u8,
+) -> ProgramResult {
     msg!("Initializing stability pool!");
 
     let owner = key!(ctx, initial_market_owner);
 
     let pda_stability_pool = transfer_stability_pool_to_pda(&ctx, StabilityPool { owner });
     let pda_liq_rewards = pda::make_pda_pubkey(LiquidationsVault { owner }, ctx.program_id);
+    let pda_receipt_mint = transfer_receipt_mint_to_pda(&ctx, StabilityReceiptMint { owner });
 
     transfer_liquidations_vault_to_pda(&ctx, &pda_liq_rewards, CollateralToken::ETH);
     transfer_liquidations_vault_to_pda(&ctx, &pda_liq_rewards, CollateralToken::BTC);
@@ -41,6 +46,10 @@ pub fn process(ctx: Context<crate::InitializeStabilityPool>) -> ProgramResult {
     stability_vaults.stablecoin_stability_pool_vault_authority = pda_stability_pool.key;
     stability_vaults.stablecoin_stability_pool_vault_seed = pda_stability_pool.seed;
 
+    stability_vaults.stability_receipt_mint = key!(ctx, stability_receipt_mint);
+    stability_vaults.stability_receipt_mint_authority = pda_receipt_mint.key;
+    stability_vaults.stability_receipt_mint_seed = pda_receipt_mint.seed;
+
     stability_vaults.liquidation_rewards_vault_sol = key!(ctx, liquidation_rewards_vault_sol);
     stability_vaults.liquidation_rewards_vault_srm = key!(ctx, liquidation_rewards_vault_srm);
     stability_vaults.liquidation_rewards_vault_eth = key!(ctx, liquidation_rewards_vault_eth);
@@ -64,6 +73,16 @@ pub fn process(ctx: Context<crate::InitializeStabilityPool>) -> ProgramResult {
         ctx.accounts.clock.unix_timestamp as u64,
     );
 
+    // A market can have several pools, each absorbing the
+    // liquidations of a subset of the collateral types
+    stability_pool_operations::set_collateral_coverage(stability_pool_state, collateral_coverage)?;
+
+    let stability_pool_key = key!(stability_pool_state);
+    borrowing_operations::register_stability_pool(
+        &mut ctx.accounts.borrowing_market_state,
+        stability_pool_key,
+    )?;
+
     Ok(())
 }
 
@@ -91,6 +110,29 @@ mod utils {
 
         authority_pda
     }
+    pub fn transfer_receipt_mint_to_pda(
+        ctx: &Context<crate::InitializeStabilityPool>,
+        mode: pda::PDA,
+    ) -> PdaAddress {
+        let authority_pda = pda::make_pda_pubkey(mode, ctx.program_id);
+
+        token::set_authority(
+            ctx.accounts.to_set_authority_cpi_context_receipt_mint(),
+            spl_token::instruction::AuthorityType::MintTokens,
+            Some(authority_pda.key),
+        )
+        .unwrap();
+
+        // Receipt accounts are kept frozen, they only move through the program
+        token::set_authority(
+            ctx.accounts.to_set_authority_cpi_context_receipt_mint(),
+            spl_token::instruction::AuthorityType::FreezeAccount,
+            Some(authority_pda.key),
+        )
+        .unwrap();
+
+        authority_pda
+    }
     pub fn transfer_liquidations_vault_to_pda(
         ctx: &Context<crate::InitializeStabilityPool>,
         authority_pda: &PdaAddress,
@@ -142,3 +184,16 @@ impl<'a, 'b, 'c, 'info> crate::InitializeStabilityPool<'info> {
         CpiContext::new(cpi_program, cpi_accounts)
     }
 }
+
+impl<'a, 'b, 'c, 'info> crate::InitializeStabilityPool<'info> {
+    pub fn to_set_authority_cpi_context_receipt_mint(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, SetAuthority<'info>> {
+        let cpi_accounts = SetAuthority {
+            account_or_mint: self.stability_receipt_mint.to_account_info().clone(),
+            current_authority: self.initial_market_owner.clone(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
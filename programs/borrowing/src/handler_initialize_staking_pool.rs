@@ -9,6 +9,12 @@ pub fn process(
     treasury_fee_rate: u16,
 ) -> ProgramResult {
     let pda_staking_vault = utils::transfer_staking_vault_account_ownership_to_pda(&ctx);
+    let pda_treasury_vault = utils::transfer_treasury_vault_account_ownership_to_pda(&ctx);
+
+    ctx.accounts
+        .boost_checkpoints
+        .load_init()?
+        .staking_pool_state = key!(ctx, staking_pool_state);
 
     let staking_pool_state = &mut ctx.accounts.staking_pool_state;
 
@@ -18,8 +24,12 @@ pub fn process(
     staking_pool_state.staking_vault_authority = pda_staking_vault.key;
     staking_pool_state.staking_vault_seed = pda_staking_vault.seed;
 
+    staking_pool_state.boost_checkpoints = key!(ctx, boost_checkpoints);
+
     staking_pool_state.treasury_vault = key!(ctx, treasury_vault);
-    staking_pool_state.treasury_fee_rate = treasury_fee_rate;
+    staking_pool_state.treasury_vault_authority = pda_treasury_vault.key;
+    staking_pool_state.treasury_vault_seed = pda_treasury_vault.seed;
+    staking_pool_operations::update_treasury_fee_rate(staking_pool_state, treasury_fee_rate)?;
 
     staking_pool_operations::initialize_staking_pool(staking_pool_state);
 
@@ -38,6 +48,16 @@ impl<'a, 'b, 'c, 'info> crate::InitializeStakingPool<'info> {
         let cpi_program = self.token_program.to_account_info();
         CpiContext::new(cpi_program, cpi_accounts)
     }
+
+    pub fn to_treasury_cpi_context(&self) -> CpiContext<'a, 'b, 'c, 'info, SetAuthority<'info>> {
+        let cpi_accounts = SetAuthority {
+            account_or_mint: self.treasury_vault.clone(),
+            current_authority: self.initial_market_owner.clone(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
 }
 
 mod utils {
@@ -71,4 +91,30 @@ mod utils {
 
         staking_vault_authority_pda
     }
+
+    pub fn transfer_treasury_vault_account_ownership_to_pda(
+        ctx: &Context<crate::InitializeStakingPool>,
+    ) -> pda::PdaAddress {
+        let treasury_vault_authority_pda = pda::make_pda_pubkey(
+            pda::PDA::Treasury {
+                owner: key!(ctx, initial_market_owner),
+            },
+            ctx.program_id,
+        );
+
+        token::set_authority(
+            ctx.accounts.to_treasury_cpi_context(),
+            spl_token::instruction::AuthorityType::AccountOwner,
+            Some(treasury_vault_authority_pda.key),
+        )
+        .unwrap();
+
+        msg!(
+            "Set treasury vault {} to authority {}",
+            key!(ctx, treasury_vault),
+            treasury_vault_authority_pda.key
+        );
+
+        treasury_vault_authority_pda
+    }
 }
@@ -0,0 +1,216 @@
+use anchor_lang::prelude::{msg, AccountInfo};
+use anchor_lang::{prelude::ProgramResult, Context, ToAccountInfo};
+
+use crate::borrowing_market::borrowing_operations;
+use crate::borrowing_market::types::MintAccruedInterestEffects;
+use crate::handler_fill_redemption_order::utils::{
+    accounts_to_metadatas, deserialize_remaining_user_metadatas, serialize_user_metadatas,
+};
+use crate::key;
+use crate::redemption::redemption_operations;
+use crate::redemption::types::InstantRedemptionEffects;
+use crate::token_operations::{soltoken, spltoken, stablecoin};
+use crate::utils::oracle::get_prices;
+use crate::utils::pda;
+use crate::{CollateralToken, InstantRedeem};
+
+pub fn process(
+    ctx: Context<InstantRedeem>,
+    stablecoin_amount: u64,
+    max_fee_bps: u16,
+    min_collateral_value_usdh: Option<u64>,
+    preferred_collateral: Option<CollateralToken>,
+) -> ProgramResult {
+    utils::assert_permissions(&ctx)?;
+
+    let borrowing_market_state_pk = key!(ctx, borrowing_market_state);
+    let mut metadata_accounts =
+        deserialize_remaining_user_metadatas(&ctx, &borrowing_market_state_pk)?;
+    let mut borrowers = accounts_to_metadatas(&mut metadata_accounts);
+    let redeemer_metadata = &mut ctx.accounts.redeemer_metadata;
+    let borrowing_market_state = &mut ctx.accounts.borrowing_market_state;
+    let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+    let redemptions_queue = &ctx.accounts.redemptions_queue.load()?;
+    let sorted_troves = &mut ctx.accounts.sorted_troves.load_mut()?;
+    let timestamp = ctx.accounts.clock.unix_timestamp as u64;
+
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
+
+    msg!(
+        "User {:?} redeeming {} against {} troves",
+        redeemer_metadata.metadata_pk,
+        stablecoin_amount,
+        borrowers.len(),
+    );
+
+    let InstantRedemptionEffects {
+        redeemed_stablecoin,
+        redeemed_collateral: _redeemed_collateral,
+        collateral_to_transfer_to_redeemer,
+        stakers_fee_usdh,
+        treasury_fee_usdh,
+    } = redemption_operations::instant_redeem(
+        redeemer_metadata,
+        borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        redemptions_queue,
+        sorted_troves,
+        &mut borrowers,
+        &prices,
+        timestamp,
+        stablecoin_amount,
+        max_fee_bps,
+        min_collateral_value_usdh,
+        preferred_collateral,
+        ctx.accounts.global_config.redemption_stakers_fee_in_usdh,
+    )?;
+
+    // Only what was actually redeemed is taken from the redeemer
+    crate::stablecoin::transfer(
+        redeemed_stablecoin,
+        &ctx.accounts.redeemer_stablecoin_associated_account,
+        &ctx.accounts.burning_vault,
+        &ctx.accounts.redeemer,
+        &ctx.accounts.token_program,
+    )?;
+
+    crate::stablecoin::burn(
+        redeemed_stablecoin,
+        &ctx.accounts.burning_vault,
+        &ctx.accounts.stablecoin_mint,
+        &ctx.accounts.burning_vault_authority,
+        borrowing_vaults.burning_vault_seed,
+        pda::PDA::BurningPotAccount {
+            owner: borrowing_market_state.initial_market_owner,
+        },
+        ctx.program_id,
+        &ctx.accounts.token_program,
+    )?;
+
+    if stakers_fee_usdh > 0 {
+        crate::stablecoin::transfer(
+            stakers_fee_usdh,
+            &ctx.accounts.redeemer_stablecoin_associated_account,
+            &ctx.accounts.borrowing_fees_vault,
+            &ctx.accounts.redeemer,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    if treasury_fee_usdh > 0 {
+        crate::stablecoin::transfer(
+            treasury_fee_usdh,
+            &ctx.accounts.redeemer_stablecoin_associated_account,
+            &ctx.accounts.treasury_vault,
+            &ctx.accounts.redeemer,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    // Mint the interest charged on the troves to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        timestamp,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        borrowing_market_state.stablecoin_mint_seed,
+        borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    // The redeemer's share, as well as the filler and clearer
+    // shares, is paid out right away
+    let initial_market_owner = borrowing_market_state.initial_market_owner;
+    for token in (0..6).map(CollateralToken::from) {
+        let amount = collateral_to_transfer_to_redeemer.token_amount(token);
+        if amount == 0 {
+            continue;
+        }
+
+        match token {
+            CollateralToken::SOL => soltoken::transfer_from_vault(
+                amount,
+                &ctx.accounts.collateral_vault_sol,
+                &ctx.accounts.redeemer,
+            ),
+            _ => spltoken::transfer_from_vault(
+                amount,
+                pda::PDA::collateral_vault_from(&initial_market_owner),
+                ctx.accounts.redeemer_ata(token),
+                ctx.accounts.collateral_vault(token),
+                &ctx.accounts.collateral_vaults_authority,
+                borrowing_vaults.collateral_vaults_seed,
+                &ctx.accounts.token_program,
+                ctx.program_id,
+            ),
+        }?;
+    }
+
+    serialize_user_metadatas(&ctx, &mut metadata_accounts);
+    Ok(())
+}
+
+impl<'info> InstantRedeem<'info> {
+    fn collateral_vault(&self, token: CollateralToken) -> &AccountInfo<'info> {
+        match token {
+            CollateralToken::SOL => &self.collateral_vault_sol,
+            CollateralToken::SRM => &self.collateral_vault_srm,
+            CollateralToken::ETH => &self.collateral_vault_eth,
+            CollateralToken::BTC => &self.collateral_vault_btc,
+            CollateralToken::RAY => &self.collateral_vault_ray,
+            CollateralToken::FTT => &self.collateral_vault_ftt,
+        }
+    }
+
+    fn redeemer_ata(&self, token: CollateralToken) -> &AccountInfo<'info> {
+        match token {
+            CollateralToken::SRM => &self.redeemer_srm_ata,
+            CollateralToken::ETH => &self.redeemer_eth_ata,
+            CollateralToken::BTC => &self.redeemer_btc_ata,
+            CollateralToken::RAY => &self.redeemer_ray_ata,
+            CollateralToken::FTT => &self.redeemer_ftt_ata,
+            _ => unimplemented!(),
+        }
+    }
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context,
+    };
+    use vipers::assert_ata;
+
+    use crate::CollateralToken;
+
+    pub fn assert_permissions(ctx: &Context<crate::InstantRedeem>) -> ProgramResult {
+        let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+        for token in (1..6).map(CollateralToken::from) {
+            assert_ata!(
+                *ctx.accounts.redeemer_ata(token),
+                ctx.accounts.redeemer_metadata.owner,
+                borrowing_vaults.mint_address(token)
+            );
+        }
+
+        Ok(())
+    }
+}
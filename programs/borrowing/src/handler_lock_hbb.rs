@@ -0,0 +1,50 @@
+use crate::staking_pool::staking_pool_operations;
+use crate::token_operations::hbb;
+
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<crate::LockHbbStakingPool>,
+    amount: u64,
+    lock_expiry_ts: u64,
+) -> ProgramResult {
+    msg!("ix=LockHbbStakingPool {} until {}", amount, lock_expiry_ts);
+    utils::assert_permissions(&ctx)?;
+
+    staking_pool_operations::lock_hbb(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        amount,
+        lock_expiry_ts,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    hbb::transfer(
+        amount,
+        &ctx.accounts.user_hbb_staking_ata,
+        &ctx.accounts.staking_vault,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+    )?;
+
+    Ok(())
+}
+
+pub mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context,
+    };
+    use vipers::assert_ata;
+
+    pub fn assert_permissions(ctx: &Context<crate::LockHbbStakingPool>) -> ProgramResult {
+        assert_ata!(
+            ctx.accounts.user_hbb_staking_ata,
+            ctx.accounts.owner,
+            ctx.accounts.borrowing_market_state.hbb_mint,
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::staking_pool::staking_pool_operations;
+use crate::token_operations::stablecoin;
+use anchor_lang::prelude::*;
+use anchor_spl::token;
+
+pub fn process(ctx: Context<crate::ReconcileStakingRewards>) -> ProgramResult {
+    // Permissionless, the surplus can only go to the treasury vault
+
+    let borrowing_market_state = &ctx.accounts.borrowing_market_state;
+    let borrowing_vaults = &ctx.accounts.borrowing_vaults;
+    let staking_pool_state = &ctx.accounts.staking_pool_state;
+
+    let vault_balance = token::accessor::amount(&ctx.accounts.borrowing_fees_vault)?;
+
+    let surplus =
+        staking_pool_operations::reconcile_borrowing_fees_vault(staking_pool_state, vault_balance)?;
+
+    msg!(
+        "Fees vault holds {} USDH, owed {} to stakers and {} to compound",
+        vault_balance,
+        staking_pool_state.rewards_not_yet_claimed,
+        staking_pool_state.rewards_to_compound
+    );
+    msg!("Sweeping surplus {} USDH to the treasury", surplus);
+
+    if surplus > 0 {
+        stablecoin::transfer_from_borrowing_fees_vault(
+            surplus,
+            borrowing_market_state.initial_market_owner,
+            &ctx.accounts.treasury_vault,
+            &ctx.accounts.borrowing_fees_vault,
+            &ctx.accounts.borrowing_fees_vault_authority,
+            borrowing_vaults.borrowing_fees_vault_seed,
+            &ctx.accounts.token_program,
+            ctx.program_id,
+        )?;
+    }
+
+    Ok(())
+}
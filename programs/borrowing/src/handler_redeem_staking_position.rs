@@ -0,0 +1,33 @@
+use crate::{key, staking_pool::staking_pool_operations, token_operations::staking_position};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::RedeemStakingPosition>) -> ProgramResult {
+    msg!("ix=RedeemStakingPosition");
+
+    // The holder burns the position token and becomes the owner,
+    // the stake and everything it earned so far move along
+    let new_owner = ctx.accounts.holder.key();
+
+    staking_position::burn(
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.position_mint,
+        &ctx.accounts.holder,
+        &ctx.accounts.token_program,
+    )?;
+
+    staking_pool_operations::redeem_staking_position(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        new_owner,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    msg!(
+        "Staking position {} redeemed by {}",
+        key!(ctx, user_staking_state),
+        new_owner
+    );
+
+    Ok(())
+}
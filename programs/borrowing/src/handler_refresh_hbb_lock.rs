@@ -0,0 +1,23 @@
+use crate::staking_pool::staking_pool_operations;
+
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::RefreshHbbLockStakingPool>) -> ProgramResult {
+    msg!("ix=RefreshHbbLock");
+
+    // Permissionless, checkpoints the expired locks and settles
+    // what the stake earned so far, boost included
+    staking_pool_operations::refresh_boosted_stake(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+
+    msg!(
+        "Boosted stake is {}",
+        ctx.accounts.user_staking_state.boosted_stake
+    );
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::msg;
+use anchor_lang::{prelude::ProgramResult, Context, ToAccountInfo};
+
+use crate::borrowing_market::types::MintAccruedInterestEffects;
+use crate::borrowing_market::{borrowing_operations, sorted_troves};
+use crate::handler_fill_redemption_order::utils::{
+    accounts_to_metadatas, deserialize_remaining_user_metadatas, serialize_user_metadatas,
+};
+use crate::key;
+use crate::stablecoin;
+use crate::utils::oracle::get_prices;
+use crate::RefreshSortedTroves;
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshSortedTroves<'info>>,
+) -> ProgramResult {
+    // Anyone can reposition troves at current prices, troves whose
+    // collateral lost value since they were last touched would
+    // otherwise stay behind safer ones
+    let borrowing_market_state_pk = key!(ctx, borrowing_market_state);
+    let mut metadata_accounts =
+        deserialize_remaining_user_metadatas(&ctx, &borrowing_market_state_pk)?;
+    let mut troves = accounts_to_metadatas(&mut metadata_accounts);
+
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
+
+    msg!("Refreshing {} troves", troves.len());
+
+    let now = ctx.accounts.clock.unix_timestamp as u64;
+    let borrowing_market_state = &mut ctx.accounts.borrowing_market_state;
+    let sorted_troves = &mut ctx.accounts.sorted_troves.load_mut()?;
+    for trove in troves.iter_mut() {
+        sorted_troves::refresh_trove(borrowing_market_state, sorted_troves, trove, &prices, now)?;
+    }
+
+    // Mint the interest charged on the troves to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        now,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        borrowing_market_state.stablecoin_mint_seed,
+        borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    serialize_user_metadatas(&ctx, &mut metadata_accounts);
+    Ok(())
+}
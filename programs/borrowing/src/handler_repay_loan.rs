@@ -1,14 +1,28 @@
-use anchor_lang::{prelude::ProgramResult, Context};
+use anchor_lang::{prelude::ProgramResult, Context, ToAccountInfo};
 
 use crate::{
-    borrowing_market::{borrowing_operations, types::RepayLoanEffects},
-    pda, RepayLoan,
+    borrowing_market::{
+        borrowing_operations, sorted_troves,
+        types::{MintAccruedInterestEffects, RepayLoanEffects},
+    },
+    pda, stablecoin,
+    utils::oracle::get_prices,
+    RepayLoan,
 };
 
 pub fn process(ctx: Context<RepayLoan>, stablecoin_amount: u64) -> ProgramResult {
     let borrowing_market_state = &mut ctx.accounts.borrowing_market_state;
     let borrowing_vaults = &ctx.accounts.borrowing_vaults;
 
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
+
     let RepayLoanEffects {
         amount_to_burn,
         amount_to_transfer,
@@ -16,6 +30,35 @@ pub fn process(ctx: Context<RepayLoan>, stablecoin_amount: u64) -> ProgramResult
         borrowing_market_state,
         &mut ctx.accounts.user_metadata,
         stablecoin_amount,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
+    // Mint the interest charged on the trove to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        borrowing_market_state.stablecoin_mint_seed,
+        borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
     )?;
 
     // 1. Transfer the amount of debt from user associated account to burning pot
@@ -0,0 +1,29 @@
+use crate::staking_pool::{staking_pool_operations, types::RequestUnstakeEffects};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::RequestUnstakeHbbStakingPool>, amount: u64) -> ProgramResult {
+    msg!("ix=RequestUnstakeHbb {}", amount);
+
+    // Same as unstaking, except the HBB is only released
+    // once the cooldown has elapsed, see CompleteUnstakeHbb
+
+    let RequestUnstakeEffects {
+        amount_requested,
+        withdrawable_after_ts,
+    } = staking_pool_operations::request_unstake(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        amount,
+        ctx.accounts.global_config.unstake_cooldown_seconds,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    msg!(
+        "Requested unstake of {} HBB, withdrawable after {}",
+        amount_requested,
+        withdrawable_after_ts
+    );
+
+    Ok(())
+}
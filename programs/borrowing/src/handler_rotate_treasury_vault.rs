@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, SetAuthority};
+
+use crate::key;
+use crate::token_operations::spltoken;
+use crate::utils::pda;
+
+pub fn process(ctx: Context<crate::RotateTreasuryVault>) -> ProgramResult {
+    utils::assert_permissions(&ctx)?;
+
+    // Fees already in the previous vault move to the new one, once
+    // rotated the previous vault can no longer be spent from
+    let previous_vault_amount = token::accessor::amount(&ctx.accounts.treasury_vault)?;
+    if previous_vault_amount > 0 {
+        spltoken::transfer_from_vault(
+            previous_vault_amount,
+            pda::PDA::Treasury {
+                owner: key!(ctx, initial_market_owner),
+            },
+            &ctx.accounts.new_treasury_vault,
+            &ctx.accounts.treasury_vault,
+            &ctx.accounts.treasury_vault_authority,
+            ctx.accounts.staking_pool_state.treasury_vault_seed,
+            &ctx.accounts.token_program,
+            ctx.program_id,
+        )?;
+    }
+
+    let pda_treasury_vault = pda::make_pda_pubkey(
+        pda::PDA::Treasury {
+            owner: key!(ctx, initial_market_owner),
+        },
+        ctx.program_id,
+    );
+
+    anchor_spl::token::set_authority(
+        ctx.accounts.to_treasury_cpi_context(),
+        spl_token::instruction::AuthorityType::AccountOwner,
+        Some(pda_treasury_vault.key),
+    )?;
+
+    let staking_pool_state = &mut ctx.accounts.staking_pool_state;
+
+    msg!(
+        "Treasury vault rotated from {} to {}, {} USDH moved over",
+        staking_pool_state.treasury_vault,
+        key!(ctx, new_treasury_vault),
+        previous_vault_amount
+    );
+
+    staking_pool_state.treasury_vault = key!(ctx, new_treasury_vault);
+    staking_pool_state.treasury_vault_authority = pda_treasury_vault.key;
+    staking_pool_state.treasury_vault_seed = pda_treasury_vault.seed;
+
+    Ok(())
+}
+
+impl<'a, 'b, 'c, 'info> crate::RotateTreasuryVault<'info> {
+    pub fn to_treasury_cpi_context(&self) -> CpiContext<'a, 'b, 'c, 'info, SetAuthority<'info>> {
+        let cpi_accounts = SetAuthority {
+            account_or_mint: self.new_treasury_vault.clone(),
+            current_authority: self.initial_market_owner.clone(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context, Key,
+    };
+    use anchor_spl::token;
+    use vipers::{assert_keys_eq, assert_keys_neq};
+
+    pub fn assert_permissions(ctx: &Context<crate::RotateTreasuryVault>) -> ProgramResult {
+        // The treasury only holds USDH
+        assert_keys_eq!(
+            token::accessor::mint(&ctx.accounts.new_treasury_vault)?,
+            ctx.accounts.borrowing_market_state.stablecoin_mint,
+            "New treasury vault must be a stablecoin account"
+        );
+        assert_keys_neq!(
+            ctx.accounts.new_treasury_vault,
+            ctx.accounts.treasury_vault,
+            "New treasury vault must differ from the current one"
+        );
+
+        Ok(())
+    }
+}
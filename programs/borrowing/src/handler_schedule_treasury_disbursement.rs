@@ -0,0 +1,43 @@
+use crate::staking_pool::staking_pool_operations;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::ScheduleTreasuryDisbursement>, amount: u64) -> ProgramResult {
+    utils::assert_permissions(&ctx)?;
+
+    let recipient = ctx.accounts.recipient.key();
+    let executable_ts = staking_pool_operations::schedule_treasury_disbursement(
+        &mut ctx.accounts.staking_pool_state,
+        amount,
+        recipient,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    msg!(
+        "Treasury disbursement of {} USDH to {} scheduled, executable after {}",
+        amount,
+        recipient,
+        executable_ts
+    );
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context, Key,
+    };
+    use anchor_spl::token;
+    use vipers::assert_keys_eq;
+
+    pub fn assert_permissions(ctx: &Context<crate::ScheduleTreasuryDisbursement>) -> ProgramResult {
+        // The treasury only holds USDH
+        assert_keys_eq!(
+            token::accessor::mint(&ctx.accounts.recipient)?,
+            ctx.accounts.borrowing_market_state.stablecoin_mint,
+            "Recipient must be a stablecoin account"
+        );
+
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use std::num::NonZeroU64;
 
 use crate::borrowing_market::borrowing_operations;
-use crate::borrowing_market::types::WithdrawCollateralEffects;
+use crate::borrowing_market::types::{MintAccruedInterestEffects, WithdrawCollateralEffects};
 use crate::handler_serum_swap::utils::assert_swap_not_zero;
 use crate::log_compute_units;
+use crate::stablecoin;
 use crate::state::CollateralToken;
 use crate::token_operations::spltoken;
 use crate::utils::oracle::get_prices;
@@ -65,6 +66,29 @@ pub fn process(
         base_amount,
         collateral,
         &prices,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    // Mint the interest charged on the trove to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        ctx.accounts.borrowing_market_state.stablecoin_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.clone(),
     )?;
 
     let coll_vault_amount_before_transfer =
@@ -213,7 +237,7 @@ pub fn process(
     Ok(())
 }
 
-mod utils {
+pub mod utils {
     use std::cell::RefMut;
 
     use anchor_lang::{
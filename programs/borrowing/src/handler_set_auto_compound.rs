@@ -0,0 +1,21 @@
+use crate::staking_pool::staking_pool_operations;
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<crate::SetAutoCompoundStakingPool>,
+    auto_compound: bool,
+) -> ProgramResult {
+    msg!("ix=SetAutoCompound {}", auto_compound);
+
+    // Rewards earned so far stay pending, only the future
+    // USDH rewards are compounded into HBB
+    staking_pool_operations::set_auto_compound(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        auto_compound,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+
+    Ok(())
+}
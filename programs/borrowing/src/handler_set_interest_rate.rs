@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    borrowing_market::{borrowing_operations, sorted_troves, types::MintAccruedInterestEffects},
+    stablecoin,
+    utils::oracle::get_prices,
+};
+
+pub fn process(ctx: Context<crate::SetInterestRate>, interest_rate_bps: u16) -> ProgramResult {
+    msg!("Ix=SetInterestRate {}", interest_rate_bps);
+    let prices = get_prices(
+        &ctx.accounts.pyth_sol_price_info,
+        &ctx.accounts.pyth_eth_price_info,
+        &ctx.accounts.pyth_btc_price_info,
+        &ctx.accounts.pyth_srm_price_info,
+        &ctx.accounts.pyth_ray_price_info,
+        &ctx.accounts.pyth_ftt_price_info,
+    )?;
+
+    borrowing_operations::set_interest_rate(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.user_metadata,
+        interest_rate_bps,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
+    // Mint the interest charged on the trove, and the upfront fee
+    // of the new rate, to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        ctx.accounts.borrowing_market_state.stablecoin_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+
+use crate::redemption::redemption_operations;
+use crate::redemption::types::{RedemptionSimulation, SimulatedTroveRedemption};
+use crate::SimulateRedemption;
+
+pub fn process(ctx: Context<SimulateRedemption>) -> ProgramResult {
+    // Read only, meant to be run through transaction simulation,
+    // the results are only logged
+    let borrowing_market_state = &ctx.accounts.borrowing_market_state;
+    let troves = utils::deserialize_troves(&ctx, &borrowing_market_state.key())?;
+
+    let RedemptionSimulation {
+        redemption_fee_bps,
+        redeemed_stablecoin,
+        redeemed_collateral,
+        troves,
+    } = redemption_operations::simulate_redemption(
+        borrowing_market_state,
+        &*ctx.accounts.redemption_order.load()?,
+        &troves,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    msg!("Redemption fee {} bps", redemption_fee_bps);
+    for SimulatedTroveRedemption {
+        user_metadata,
+        debt_redeemed,
+        collateral_redeemed,
+    } in troves.iter()
+    {
+        msg!(
+            "Trove {} debt redeemed {} collateral redeemed {:?}",
+            user_metadata,
+            debt_redeemed,
+            collateral_redeemed
+        );
+    }
+    msg!("Redeemed stablecoin {}", redeemed_stablecoin);
+    msg!("Redeemer collateral {:?}", redeemed_collateral.redeemer);
+    msg!("Filler collateral {:?}", redeemed_collateral.filler);
+    msg!("Clearer collateral {:?}", redeemed_collateral.clearer);
+    msg!("Stakers collateral {:?}", redeemed_collateral.stakers);
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::__private::ErrorCode;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{Context, ProgramAccount};
+
+    use crate::{SimulateRedemption, UserMetadata};
+
+    pub fn deserialize_troves(
+        ctx: &Context<SimulateRedemption>,
+        borrowing_market_state: &Pubkey,
+    ) -> Result<Vec<UserMetadata>, ErrorCode> {
+        // Unlike filling and clearing nothing is written back,
+        // so the troves don't need to be writable
+        ctx.remaining_accounts
+            .iter()
+            .filter_map(|acc| ProgramAccount::<UserMetadata>::try_from(ctx.program_id, acc).ok())
+            .map(|user_metadata| {
+                if &user_metadata.borrowing_market_state != borrowing_market_state {
+                    return Err(ErrorCode::ConstraintHasOne);
+                }
+                Ok(user_metadata.into_inner())
+            })
+            .collect()
+    }
+}
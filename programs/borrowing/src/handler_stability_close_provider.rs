@@ -0,0 +1,17 @@
+use crate::stability_pool::stability_pool_operations;
+use anchor_lang::{prelude::*, AccountsClose};
+
+pub fn process(ctx: Context<crate::CloseStabilityProvider>) -> ProgramResult {
+    msg!("ix=CloseStabilityProvider");
+
+    stability_pool_operations::close_stability_provider(
+        &mut ctx.accounts.stability_pool_state,
+        &ctx.accounts.stability_provider_state,
+    )?;
+
+    ctx.accounts
+        .stability_provider_state
+        .close(ctx.accounts.owner.clone())?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::{
+    stability_pool::{stability_pool_operations, types::LockStabilityDepositEffects},
+    state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
+    LockupTier,
+};
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<crate::LockStabilityDeposit>,
+    lockup_tier: LockupTier,
+) -> ProgramResult {
+    msg!("ix=LockStabilityDeposit {:?}", lockup_tier);
+
+    utils::assert_permissions(&ctx)?;
+
+    let mut epoch_to_scale_to_sum =
+        EpochToScaleToSum::unpack_from_zero_copy_account(&ctx.accounts.epoch_to_scale_to_sum)?;
+
+    let LockStabilityDepositEffects { lockup_expiry_ts } =
+        stability_pool_operations::lock_stability_deposit(
+            &mut ctx.accounts.stability_pool_state,
+            &mut ctx.accounts.stability_provider_state,
+            &mut epoch_to_scale_to_sum,
+            lockup_tier,
+            ctx.accounts.clock.unix_timestamp as u64,
+        )?;
+
+    msg!("Stability deposit locked until {}", lockup_expiry_ts);
+
+    epoch_to_scale_to_sum
+        .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{prelude::ProgramResult, Context};
+
+    use crate::BorrowError;
+
+    pub fn assert_permissions(ctx: &Context<crate::LockStabilityDeposit>) -> ProgramResult {
+        assert_has_deposit(ctx.accounts.stability_provider_state.deposited_stablecoin)?;
+
+        Ok(())
+    }
+
+    fn assert_has_deposit(user_total_stablecoin_provided: u64) -> ProgramResult {
+        if user_total_stablecoin_provided == 0 {
+            Err(BorrowError::CannotLockEmptyStabilityDeposit.into())
+        } else {
+            Ok(())
+        }
+    }
+}
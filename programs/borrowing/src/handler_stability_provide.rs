@@ -1,7 +1,7 @@
 use crate::stability_pool::stability_pool_operations;
 use crate::stability_pool::types::ProvideStabilityEffects;
 use crate::state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode};
-use crate::token_operations::stablecoin;
+use crate::token_operations::{stability_receipt, stablecoin};
 use anchor_lang::prelude::*;
 
 pub fn process(ctx: Context<crate::ProvideStability>, amount: u64) -> ProgramResult {
@@ -10,6 +10,7 @@ pub fn process(ctx: Context<crate::ProvideStability>, amount: u64) -> ProgramRes
     // 1. Harvest
     // 2. Send USD to the stability pool
     // 3. Update deposit and snapshot
+    // 4. Mint the receipts
 
     utils::assert_permissions(&ctx, amount)?;
 
@@ -19,6 +20,7 @@ pub fn process(ctx: Context<crate::ProvideStability>, amount: u64) -> ProgramRes
     // Update state
     let ProvideStabilityEffects {
         usd_to_stability_pool_transfer,
+        receipts_to_mint,
     } = stability_pool_operations::provide_stability(
         &mut ctx.accounts.stability_pool_state,
         &mut ctx.accounts.stability_provider_state,
@@ -36,6 +38,17 @@ pub fn process(ctx: Context<crate::ProvideStability>, amount: u64) -> ProgramRes
         &ctx.accounts.token_program,
     )?;
 
+    stability_receipt::mint(
+        receipts_to_mint,
+        ctx.accounts.stability_vaults.stability_receipt_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        &ctx.accounts.stability_receipt_mint,
+        &ctx.accounts.stability_receipt_ata,
+        &ctx.accounts.stability_receipt_mint_authority,
+        &ctx.accounts.token_program,
+    )?;
+
     epoch_to_scale_to_sum
         .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
 
@@ -49,7 +62,7 @@ mod utils {
     };
     use vipers::assert_ata;
 
-    use crate::BorrowError;
+    use crate::{stability_pool::stability_pool_operations, BorrowError};
 
     pub fn assert_permissions(
         ctx: &Context<crate::ProvideStability>,
@@ -57,12 +70,24 @@ mod utils {
     ) -> ProgramResult {
         assert_amount_not_zero(amount)?;
 
+        // the receipts are minted in the receipt epoch the deposit starts, if any
+        assert_receipt_mint_is_current(
+            ctx.accounts.stability_vaults.stability_receipt_mint_epoch,
+            stability_pool_operations::next_receipt_epoch(&ctx.accounts.stability_pool_state),
+        )?;
+
         assert_ata!(
             ctx.accounts.stablecoin_ata,
             ctx.accounts.owner,
             ctx.accounts.borrowing_market_state.stablecoin_mint
         );
 
+        assert_ata!(
+            ctx.accounts.stability_receipt_ata,
+            ctx.accounts.owner,
+            ctx.accounts.stability_vaults.stability_receipt_mint
+        );
+
         Ok(())
     }
 
@@ -73,4 +98,15 @@ mod utils {
             Ok(())
         }
     }
+
+    fn assert_receipt_mint_is_current(
+        receipt_mint_epoch: u64,
+        receipt_epoch: u64,
+    ) -> ProgramResult {
+        if receipt_mint_epoch != receipt_epoch {
+            Err(BorrowError::StabilityReceiptMintStale.into())
+        } else {
+            Ok(())
+        }
+    }
 }
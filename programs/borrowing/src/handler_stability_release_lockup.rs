@@ -0,0 +1,25 @@
+use crate::{
+    stability_pool::stability_pool_operations,
+    state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
+};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::ReleaseStabilityLockup>) -> ProgramResult {
+    msg!("ix=ReleaseStabilityLockup");
+
+    // Permissionless, the operation only acts on expired lockups
+    let mut epoch_to_scale_to_sum =
+        EpochToScaleToSum::unpack_from_zero_copy_account(&ctx.accounts.epoch_to_scale_to_sum)?;
+
+    stability_pool_operations::release_stability_lockup(
+        &mut ctx.accounts.stability_pool_state,
+        &mut ctx.accounts.stability_provider_state,
+        &mut epoch_to_scale_to_sum,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    epoch_to_scale_to_sum
+        .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::{
+    stability_pool::{stability_pool_operations, types::RequestStabilityWithdrawalEffects},
+    state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
+    token_operations::stability_receipt,
+};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::RequestStabilityWithdrawal>, amount: u64) -> ProgramResult {
+    msg!("ix=RequestStabilityWithdrawal");
+
+    // Same as a withdrawal, except the stablecoin is only paid out
+    // once the liquidations queue is drained, see SettleStabilityWithdrawal
+
+    utils::assert_permissions(&ctx, amount)?;
+
+    let mut epoch_to_scale_to_sum =
+        EpochToScaleToSum::unpack_from_zero_copy_account(&ctx.accounts.epoch_to_scale_to_sum)?;
+
+    let RequestStabilityWithdrawalEffects {
+        usd_requested,
+        receipts_to_burn,
+    } = stability_pool_operations::request_stability_withdrawal(
+        &mut ctx.accounts.stability_pool_state,
+        &mut ctx.accounts.stability_provider_state,
+        &mut epoch_to_scale_to_sum,
+        amount,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    msg!("Requested withdrawal of {} stablecoin", usd_requested);
+
+    stability_receipt::burn(
+        receipts_to_burn,
+        ctx.accounts.stability_vaults.stability_receipt_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        &ctx.accounts.stability_receipt_ata,
+        &ctx.accounts.stability_receipt_mint,
+        &ctx.accounts.stability_receipt_mint_authority,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+    )?;
+
+    epoch_to_scale_to_sum
+        .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context,
+    };
+    use vipers::assert_ata;
+
+    use crate::BorrowError;
+
+    pub fn assert_permissions(
+        ctx: &Context<crate::RequestStabilityWithdrawal>,
+        amount: u64,
+    ) -> ProgramResult {
+        if amount == 0 || ctx.accounts.stability_provider_state.deposited_stablecoin == 0 {
+            return Err(BorrowError::NothingToUnstake.into());
+        }
+
+        assert_ata!(
+            ctx.accounts.stability_receipt_ata,
+            ctx.accounts.stability_provider_state.owner,
+            ctx.accounts.stability_vaults.stability_receipt_mint
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{key, stability_pool::stability_pool_operations};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::RotateStabilityReceiptMint>) -> ProgramResult {
+    msg!("ix=RotateStabilityReceiptMint");
+
+    // Permissionless, once the receipt rate starts over the receipts
+    // of the previous receipt epoch are left behind on the old mint
+    // and the new receipt epoch gets a mint of its own
+
+    utils::assert_permissions(&ctx)?;
+
+    stability_pool_operations::start_next_receipt_epoch(&mut ctx.accounts.stability_pool_state)?;
+
+    let receipt_epoch = ctx.accounts.stability_pool_state.receipt_epoch;
+    let stability_vaults = &mut ctx.accounts.stability_vaults;
+    stability_vaults.stability_receipt_mint = key!(ctx, new_stability_receipt_mint);
+    stability_vaults.stability_receipt_mint_epoch = receipt_epoch;
+
+    msg!(
+        "Receipts of receipt epoch {} are minted by {}",
+        receipt_epoch,
+        stability_vaults.stability_receipt_mint
+    );
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{AccountInfo, ProgramError, ProgramResult},
+        solana_program::program_option::COption,
+        AccountDeserialize, Context,
+    };
+    use anchor_spl::token::{self, Mint};
+
+    use crate::BorrowError;
+
+    pub fn assert_permissions(ctx: &Context<crate::RotateStabilityReceiptMint>) -> ProgramResult {
+        let current_mint = load_mint(&ctx.accounts.stability_receipt_mint)?;
+        let new_mint = load_mint(&ctx.accounts.new_stability_receipt_mint)?;
+
+        // The receipt authority keeps the receipt accounts frozen
+        let receipt_mint_authority = COption::Some(
+            ctx.accounts
+                .stability_vaults
+                .stability_receipt_mint_authority,
+        );
+        if new_mint.mint_authority != receipt_mint_authority
+            || new_mint.freeze_authority != receipt_mint_authority
+            || new_mint.supply != 0
+            || new_mint.decimals != current_mint.decimals
+        {
+            return Err(BorrowError::InvalidStabilityReceiptMint.into());
+        }
+
+        Ok(())
+    }
+
+    fn load_mint(account: &AccountInfo) -> Result<Mint, ProgramError> {
+        if *account.owner != token::ID {
+            return Err(BorrowError::InvalidStabilityReceiptMint.into());
+        }
+
+        Mint::try_deserialize(&mut &account.data.borrow()[..])
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    stability_pool::{stability_pool_operations, types::SettleStabilityWithdrawalEffects},
+    token_operations::stablecoin,
+};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::SettleStabilityWithdrawal>) -> ProgramResult {
+    msg!("ix=SettleStabilityWithdrawal");
+
+    // Permissionless, anyone can pay out a withdrawal request
+    // to the provider once the liquidations queue is drained
+
+    utils::assert_permissions(&ctx)?;
+
+    let SettleStabilityWithdrawalEffects { usd_to_withdraw } =
+        stability_pool_operations::settle_stability_withdrawal(
+            &mut ctx.accounts.stability_pool_state,
+            &mut ctx.accounts.stability_provider_state,
+            &mut ctx.accounts.liquidations_queue.load_mut()?,
+        )?;
+
+    stablecoin::transfer_from_stability_pool(
+        usd_to_withdraw,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        &ctx.accounts.stablecoin_ata,
+        &ctx.accounts.stablecoin_stability_pool_vault,
+        &ctx.accounts.stablecoin_stability_pool_vault_authority,
+        ctx.accounts
+            .stability_vaults
+            .stablecoin_stability_pool_vault_seed,
+        &ctx.accounts.token_program,
+        ctx.program_id,
+    )?;
+
+    Ok(())
+}
+
+pub mod utils {
+    use anchor_lang::{
+        prelude::{msg, AccountInfo, ProgramResult},
+        Context,
+    };
+    use vipers::assert_ata;
+
+    use crate::{BorrowingMarketState, StabilityProviderState};
+
+    pub fn assert_permissions(ctx: &Context<crate::SettleStabilityWithdrawal>) -> ProgramResult {
+        assert_provider_ata(
+            &ctx.accounts.stability_provider_state,
+            &ctx.accounts.borrowing_market_state,
+            &ctx.accounts.stablecoin_ata,
+        )
+    }
+
+    pub fn assert_provider_ata(
+        stability_provider_state: &StabilityProviderState,
+        borrowing_market_state: &BorrowingMarketState,
+        stablecoin_ata: &AccountInfo,
+    ) -> ProgramResult {
+        // the stablecoin can only go back to the provider
+        assert_ata!(
+            *stablecoin_ata.key,
+            stability_provider_state.owner,
+            borrowing_market_state.stablecoin_mint
+        );
+
+        Ok(())
+    }
+}
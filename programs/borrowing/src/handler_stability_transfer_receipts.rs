@@ -0,0 +1,116 @@
+use crate::{
+    stability_pool::{stability_pool_operations, types::TransferStabilityReceiptsEffects},
+    state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
+    token_operations::stability_receipt,
+};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::TransferStabilityReceipts>, amount: u64) -> ProgramResult {
+    msg!("ix=TransferStabilityReceipts {}", amount);
+
+    // Receipts are moved by the program so that the deposit they
+    // represent follows exactly the receipts being transferred,
+    // their accounts are frozen to any other transfer
+
+    utils::assert_permissions(&ctx)?;
+
+    let mut epoch_to_scale_to_sum =
+        EpochToScaleToSum::unpack_from_zero_copy_account(&ctx.accounts.epoch_to_scale_to_sum)?;
+
+    let TransferStabilityReceiptsEffects {
+        usd_moved,
+        gains_moved,
+        receipts_to_reissue,
+    } = stability_pool_operations::transfer_stability_receipts(
+        &mut ctx.accounts.stability_pool_state,
+        &mut ctx.accounts.from_stability_provider_state,
+        &mut ctx.accounts.to_stability_provider_state,
+        &mut epoch_to_scale_to_sum,
+        amount,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    stability_receipt::mint(
+        receipts_to_reissue,
+        ctx.accounts.stability_vaults.stability_receipt_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        &ctx.accounts.stability_receipt_mint,
+        &ctx.accounts.to_stability_receipt_ata,
+        &ctx.accounts.stability_receipt_mint_authority,
+        &ctx.accounts.token_program,
+    )?;
+
+    stability_receipt::transfer(
+        amount,
+        ctx.accounts.stability_vaults.stability_receipt_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        &ctx.accounts.from_stability_receipt_ata,
+        &ctx.accounts.to_stability_receipt_ata,
+        &ctx.accounts.stability_receipt_mint,
+        &ctx.accounts.stability_receipt_mint_authority,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+    )?;
+
+    msg!(
+        "Transferred {} receipts backing {} stablecoin and gains {:?}",
+        amount,
+        usd_moved,
+        gains_moved
+    );
+
+    epoch_to_scale_to_sum
+        .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{msg, ProgramResult},
+        Context, Key,
+    };
+    use vipers::assert_ata;
+
+    use crate::BorrowError;
+
+    pub fn assert_permissions(ctx: &Context<crate::TransferStabilityReceipts>) -> ProgramResult {
+        if ctx.accounts.from_stability_provider_state.key()
+            == ctx.accounts.to_stability_provider_state.key()
+        {
+            return Err(BorrowError::NoStabilityReceiptsToTransfer.into());
+        }
+
+        assert_receipt_mint_is_current(
+            ctx.accounts.stability_vaults.stability_receipt_mint_epoch,
+            ctx.accounts.stability_pool_state.receipt_epoch,
+        )?;
+
+        assert_ata!(
+            ctx.accounts.from_stability_receipt_ata,
+            ctx.accounts.from_stability_provider_state.owner,
+            ctx.accounts.stability_vaults.stability_receipt_mint
+        );
+
+        assert_ata!(
+            ctx.accounts.to_stability_receipt_ata,
+            ctx.accounts.to_stability_provider_state.owner,
+            ctx.accounts.stability_vaults.stability_receipt_mint
+        );
+
+        Ok(())
+    }
+
+    fn assert_receipt_mint_is_current(
+        receipt_mint_epoch: u64,
+        receipt_epoch: u64,
+    ) -> ProgramResult {
+        if receipt_mint_epoch != receipt_epoch {
+            Err(BorrowError::StabilityReceiptMintStale.into())
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     stability_pool::{stability_pool_operations, types::WithdrawStabilityEffects},
     state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
-    token_operations::stablecoin,
+    token_operations::{stability_receipt, stablecoin},
 };
 use anchor_lang::prelude::*;
 
@@ -15,6 +15,7 @@ pub fn process(ctx: Context<crate::WithdrawStability>, amount: u64) -> ProgramRe
 
     let WithdrawStabilityEffects {
         usd_remaining_to_withdraw,
+        receipts_to_burn,
     } = stability_pool_operations::withdraw_stability(
         &mut ctx.accounts.stability_pool_state,
         &mut ctx.accounts.stability_provider_state,
@@ -36,6 +37,18 @@ pub fn process(ctx: Context<crate::WithdrawStability>, amount: u64) -> ProgramRe
         ctx.program_id,
     )?;
 
+    stability_receipt::burn(
+        receipts_to_burn,
+        ctx.accounts.stability_vaults.stability_receipt_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        &ctx.accounts.stability_receipt_ata,
+        &ctx.accounts.stability_receipt_mint,
+        &ctx.accounts.stability_receipt_mint_authority,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+    )?;
+
     epoch_to_scale_to_sum
         .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
 
@@ -65,6 +78,12 @@ mod utils {
             ctx.accounts.borrowing_market_state.stablecoin_mint
         );
 
+        assert_ata!(
+            ctx.accounts.stability_receipt_ata,
+            ctx.accounts.stability_provider_state.owner,
+            ctx.accounts.stability_vaults.stability_receipt_mint
+        );
+
         Ok(())
     }
 
@@ -9,7 +9,9 @@ pub fn process(ctx: Context<crate::StakeHbbStakingPool>, amount: u64) -> Program
     staking_pool_operations::user_stake(
         &mut ctx.accounts.staking_pool_state,
         &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
         amount,
+        ctx.accounts.clock.unix_timestamp as u64,
     );
 
     hbb::transfer(
@@ -0,0 +1,66 @@
+use crate::{key, staking_pool::staking_pool_operations, token_operations::staking_position};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::TokenizeStakingPosition>) -> ProgramResult {
+    msg!("ix=TokenizeStakingPosition");
+
+    // The position is then moved by transferring its token,
+    // whoever holds it redeems the position as its owner
+
+    utils::assert_permissions(&ctx)?;
+
+    staking_pool_operations::tokenize_staking_position(
+        &mut ctx.accounts.user_staking_state,
+        key!(ctx, position_mint),
+    )?;
+
+    staking_position::mint(
+        ctx.accounts.staking_pool_state.staking_vault_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        &ctx.accounts.position_mint,
+        &ctx.accounts.position_token_account,
+        &ctx.accounts.staking_vault_authority,
+        &ctx.accounts.token_program,
+    )?;
+
+    msg!(
+        "Staking position {} of {} tokenized as {}",
+        key!(ctx, user_staking_state),
+        ctx.accounts.owner.key,
+        key!(ctx, position_mint)
+    );
+
+    Ok(())
+}
+
+mod utils {
+    use anchor_lang::{
+        prelude::{ProgramError, ProgramResult},
+        solana_program::program_option::COption,
+        AccountDeserialize, Context,
+    };
+    use anchor_spl::token::{self, Mint};
+
+    use crate::BorrowError;
+
+    pub fn assert_permissions(ctx: &Context<crate::TokenizeStakingPosition>) -> ProgramResult {
+        if *ctx.accounts.position_mint.owner != token::ID {
+            return Err(BorrowError::InvalidStakingPositionMint.into());
+        }
+        let position_mint: Mint =
+            Mint::try_deserialize(&mut &ctx.accounts.position_mint.data.borrow()[..])
+                .map_err(|_: ProgramError| BorrowError::InvalidStakingPositionMint)?;
+
+        if position_mint.mint_authority
+            != COption::Some(ctx.accounts.staking_pool_state.staking_vault_authority)
+            || position_mint.freeze_authority.is_some()
+            || position_mint.supply != 0
+            || position_mint.decimals != 0
+        {
+            return Err(BorrowError::InvalidStakingPositionMint.into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use crate::staking_pool::staking_pool_operations;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<crate::TransferStakingPosition>) -> ProgramResult {
+    msg!("ix=TransferStakingPosition");
+
+    let new_owner = ctx.accounts.new_owner.key();
+
+    // Stake, lock, pending unstake and unharvested rewards
+    // all move to the new owner
+    staking_pool_operations::transfer_staking_position(
+        &mut ctx.accounts.staking_pool_state,
+        &mut ctx.accounts.user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        new_owner,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    msg!(
+        "Staking position {} transferred from {} to {}",
+        ctx.accounts.user_staking_state.key(),
+        ctx.accounts.owner.key,
+        new_owner
+    );
+
+    Ok(())
+}
@@ -1,15 +1,23 @@
+use std::cell::RefMut;
+
 use crate::{
     borrowing_market::{
         borrowing_operations::{self},
-        types::LiquidationEffects,
+        sorted_troves,
+        types::{MintAccruedInterestEffects, TrancheLiquidationEffects},
     },
-    key, pda, stablecoin,
+    key, pda,
+    stability_pool::types::StabilityPoolTranche,
+    stablecoin,
     state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
     utils::oracle::get_prices,
+    LiquidationsQueue, StabilityPoolState,
 };
 use anchor_lang::prelude::*;
 
-pub fn process(ctx: Context<crate::TryLiquidate>) -> ProgramResult {
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, crate::TryLiquidate<'info>>,
+) -> ProgramResult {
     msg!("ix=TryLiquidate");
     // Due to lack of space in the accounts inputs,
     // we cannot transfer all the collateral at once, i.e. from sol, eth, btc
@@ -40,6 +48,24 @@ pub fn process(ctx: Context<crate::TryLiquidate>) -> ProgramResult {
     //  4. Liquidation gain is distributed among all stability providers
     //  5. The liquidator is paid a small fee
 
+    // Other stability pools of the market (tranches), each covering a subset
+    // of the collateral types, are passed as remaining accounts, see utils.
+    // All the pools registered on the market must be there, so that the
+    // liquidation is routed between them as configured
+    let mut tranche_accounts = utils::load_tranches(&ctx)?;
+    let mut tranche_pools: Vec<ProgramAccount<StabilityPoolState>> = Vec::new();
+    let mut tranche_epochs: Vec<EpochToScaleToSum> = Vec::new();
+    for tranche in tranche_accounts.iter() {
+        tranche_pools.push(tranche.stability_pool_state.clone());
+        tranche_epochs.push(EpochToScaleToSum::unpack_from_zero_copy_account(
+            &tranche.epoch_to_scale_to_sum,
+        )?);
+    }
+    let mut tranche_queues: Vec<RefMut<LiquidationsQueue>> = tranche_accounts
+        .iter()
+        .map(|tranche| tranche.liquidations_queue.load_mut())
+        .collect::<Result<_, _>>()?;
+
     let stability_pool_state = &mut ctx.accounts.stability_pool_state;
     let liquidations_queue = &mut ctx.accounts.liquidations_queue;
 
@@ -55,10 +81,26 @@ pub fn process(ctx: Context<crate::TryLiquidate>) -> ProgramResult {
         &ctx.accounts.pyth_ftt_price_info,
     )?;
 
-    let LiquidationEffects {
+    let mut tranches: Vec<StabilityPoolTranche> = tranche_pools
+        .iter_mut()
+        .zip(tranche_epochs.iter_mut())
+        .zip(tranche_queues.iter_mut())
+        .map(
+            |((stability_pool_state, epoch_to_scale_to_sum), liquidations_queue)| {
+                StabilityPoolTranche {
+                    stability_pool_state,
+                    epoch_to_scale_to_sum,
+                    liquidations_queue,
+                }
+            },
+        )
+        .collect();
+
+    let TrancheLiquidationEffects {
         liquidation_event,
         usd_to_burn_from_stability_pool,
-    } = borrowing_operations::try_liquidate(
+        usd_to_burn_from_tranches,
+    } = borrowing_operations::try_liquidate_with_tranches(
         key!(ctx, liquidator),
         &mut ctx.accounts.borrowing_market_state,
         &mut ctx.accounts.user_metadata,
@@ -66,9 +108,20 @@ pub fn process(ctx: Context<crate::TryLiquidate>) -> ProgramResult {
         &mut epoch_to_scale_to_sum,
         &prices,
         &mut liquidations_queue.load_mut()?,
+        &mut tranches,
         ctx.accounts.clock.unix_timestamp as u64,
     )?;
 
+    drop(tranches);
+    drop(tranche_queues);
+
+    // The liquidated trove has no debt left, this takes it out
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
     stablecoin::burn(
         usd_to_burn_from_stability_pool,
         &ctx.accounts.stablecoin_stability_pool_vault,
@@ -84,9 +137,57 @@ pub fn process(ctx: Context<crate::TryLiquidate>) -> ProgramResult {
         &ctx.accounts.token_program,
     )?;
 
+    // Mint the interest charged on the trove to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        ctx.accounts.borrowing_market_state.stablecoin_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    )?;
+
     epoch_to_scale_to_sum
         .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
 
+    for (((tranche, stability_pool_state), epoch_to_scale_to_sum), usd_to_burn) in tranche_accounts
+        .iter_mut()
+        .zip(tranche_pools.iter())
+        .zip(tranche_epochs.iter())
+        .zip(usd_to_burn_from_tranches)
+    {
+        stablecoin::burn(
+            usd_to_burn,
+            &tranche.stablecoin_stability_pool_vault,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.stablecoin_stability_pool_vault_authority,
+            tranche
+                .stability_vaults
+                .stablecoin_stability_pool_vault_seed,
+            pda::PDA::StabilityPool {
+                owner: ctx.accounts.borrowing_market_state.initial_market_owner,
+            },
+            ctx.program_id,
+            &ctx.accounts.token_program,
+        )?;
+
+        epoch_to_scale_to_sum
+            .pack_to_zero_copy_account(&mut tranche.epoch_to_scale_to_sum, LoadingMode::Mut)?;
+        anchor_lang::AccountsExit::exit(stability_pool_state, ctx.program_id)?;
+    }
+
     msg!(
         "Liquidation successful, liquidation event {:?}",
         liquidation_event
@@ -94,3 +195,87 @@ pub fn process(ctx: Context<crate::TryLiquidate>) -> ProgramResult {
 
     Ok(())
 }
+
+mod utils {
+    use anchor_lang::{__private::ErrorCode, prelude::*};
+    use vipers::assert_keys_eq;
+
+    use crate::{
+        key, BorrowError, EpochToScaleToSumAccount, LiquidationsQueue, StabilityPoolState,
+        StabilityVaults,
+    };
+
+    pub struct TrancheAccounts<'info> {
+        pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+        pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
+        pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
+        pub liquidations_queue: Loader<'info, LiquidationsQueue>,
+        pub stablecoin_stability_pool_vault: AccountInfo<'info>,
+    }
+
+    pub fn load_tranches<'info>(
+        ctx: &Context<'_, '_, '_, 'info, crate::TryLiquidate<'info>>,
+    ) -> Result<Vec<TrancheAccounts<'info>>, ProgramError> {
+        // Remaining accounts are groups of (stability_pool_state, stability_vaults,
+        // epoch_to_scale_to_sum, liquidations_queue, stablecoin_stability_pool_vault)
+        let remaining_accounts = ctx.remaining_accounts;
+        if remaining_accounts.len() % 5 != 0 {
+            return Err(ErrorCode::AccountNotEnoughKeys.into());
+        }
+
+        let registered_stability_pools = ctx
+            .accounts
+            .borrowing_market_state
+            .registered_stability_pools();
+        if remaining_accounts.len() / 5 + 1 != registered_stability_pools.len()
+            || !registered_stability_pools.contains(&key!(ctx, stability_pool_state))
+        {
+            return Err(BorrowError::StabilityPoolsMismatch.into());
+        }
+
+        let mut tranches: Vec<TrancheAccounts<'info>> = Vec::new();
+        for accounts in remaining_accounts.chunks(5) {
+            let tranche = TrancheAccounts {
+                stability_pool_state: ProgramAccount::try_from(ctx.program_id, &accounts[0])?,
+                stability_vaults: ProgramAccount::try_from(ctx.program_id, &accounts[1])?,
+                epoch_to_scale_to_sum: Loader::try_from(ctx.program_id, &accounts[2])?,
+                liquidations_queue: Loader::try_from(ctx.program_id, &accounts[3])?,
+                stablecoin_stability_pool_vault: accounts[4].clone(),
+            };
+
+            let stability_pool_key = *accounts[0].key;
+            if !registered_stability_pools.contains(&stability_pool_key) {
+                return Err(BorrowError::StabilityPoolsMismatch.into());
+            }
+            if stability_pool_key == key!(ctx, stability_pool_state)
+                || tranches
+                    .iter()
+                    .any(|other| other.stability_pool_state.key() == stability_pool_key)
+            {
+                return Err(ErrorCode::ConstraintRaw.into());
+            }
+
+            let stability_pool_state = &tranche.stability_pool_state;
+            let stability_vaults = &tranche.stability_vaults;
+            assert_keys_eq!(
+                stability_pool_state.borrowing_market_state,
+                key!(ctx, borrowing_market_state)
+            );
+            assert_keys_eq!(stability_pool_state.epoch_to_scale_to_sum, *accounts[2].key);
+            assert_keys_eq!(stability_pool_state.liquidations_queue, *accounts[3].key);
+            assert_keys_eq!(stability_vaults.stability_pool_state, stability_pool_key);
+            assert_keys_eq!(
+                stability_vaults.stablecoin_stability_pool_vault,
+                *accounts[4].key
+            );
+            assert_keys_eq!(
+                stability_vaults.stablecoin_stability_pool_vault_authority,
+                key!(ctx, stablecoin_stability_pool_vault_authority)
+            );
+
+            tranches.push(tranche);
+        }
+
+        Ok(tranches)
+    }
+}
@@ -13,7 +13,13 @@ pub fn process(ctx: Context<crate::UnstakeHbbStakingPool>, amount: u64) -> Progr
     let UnstakeEffects {
         reward,
         amount_to_withdraw,
-    } = staking_pool_operations::user_unstake(staking_pool_state, user_staking_state, amount)?;
+    } = staking_pool_operations::user_unstake(
+        staking_pool_state,
+        user_staking_state,
+        &mut *ctx.accounts.boost_checkpoints.load_mut()?,
+        amount,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
 
     if reward > 0 {
         stablecoin::transfer_from_borrowing_fees_vault(
@@ -55,6 +61,10 @@ mod utils {
         ctx: &Context<crate::UnstakeHbbStakingPool>,
         amount: u64,
     ) -> ProgramResult {
+        if ctx.accounts.global_config.unstake_cooldown_seconds > 0 {
+            return Err(BorrowError::UnstakeCooldownRequired.into());
+        }
+
         assert_amount_not_zero(amount)?;
 
         assert_ata!(
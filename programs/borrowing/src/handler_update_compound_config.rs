@@ -0,0 +1,32 @@
+use crate::{handler_serum_swap, staking_pool::staking_pool_operations};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use vipers::assert_keys_eq;
+
+/// Sets the HBB/USDH market compounding buys on and the
+/// max price, in USDH per HBB, it can buy HBB at
+pub fn process(ctx: Context<crate::UpdateCompoundConfig>, max_hbb_price: u64) -> ProgramResult {
+    msg!("Ix=UpdateCompoundConfig");
+    assert_keys_eq!(ctx.accounts.dex_program.key, dex::ID);
+
+    handler_serum_swap::utils::assert_dex_inputs(
+        &ctx.accounts.market,
+        ctx.accounts.dex_program.key,
+        &ctx.accounts.borrowing_market_state.hbb_mint,
+        &ctx.accounts.borrowing_market_state.stablecoin_mint,
+    )?;
+
+    staking_pool_operations::update_compound_config(
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.market.key(),
+        max_hbb_price,
+    )?;
+
+    msg!(
+        "Compounding on market {} up to {} USDH per HBB",
+        ctx.accounts.market.key(),
+        max_hbb_price
+    );
+
+    Ok(())
+}
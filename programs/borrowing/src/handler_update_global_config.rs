@@ -17,6 +17,18 @@ pub fn process(ctx: Context<UpdateGlobalConfig>, key: u16, value: u64) -> Progra
             global_config.borrow_limit_usdh = value;
             Ok(())
         }
+        Some(GlobalConfigOption::RedemptionOrderExpirySeconds) => {
+            global_config.redemption_order_expiry_seconds = value;
+            Ok(())
+        }
+        Some(GlobalConfigOption::RedemptionStakersFeeInUsdh) => {
+            global_config.redemption_stakers_fee_in_usdh = value > 0;
+            Ok(())
+        }
+        Some(GlobalConfigOption::UnstakeCooldownSeconds) => {
+            global_config.unstake_cooldown_seconds = value;
+            Ok(())
+        }
         None => Err(BorrowError::GlobalConfigKeyError.into()),
     }
 }
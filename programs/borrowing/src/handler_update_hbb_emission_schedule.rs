@@ -0,0 +1,34 @@
+use crate::{
+    stability_pool::stability_pool_operations,
+    state::epoch_to_scale_to_sum::{EpochToScaleToSum, LoadingMode},
+    HbbEmissionSchedule,
+};
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<crate::UpdateHbbEmissionSchedule>,
+    schedule: HbbEmissionSchedule,
+    stop_emissions: bool,
+) -> ProgramResult {
+    msg!(
+        "ix=UpdateHbbEmissionSchedule {:?} stop {}",
+        schedule,
+        stop_emissions
+    );
+
+    let mut epoch_to_scale_to_sum =
+        EpochToScaleToSum::unpack_from_zero_copy_account(&ctx.accounts.epoch_to_scale_to_sum)?;
+
+    stability_pool_operations::update_hbb_emission_schedule(
+        &mut ctx.accounts.stability_pool_state,
+        &mut epoch_to_scale_to_sum,
+        schedule,
+        stop_emissions,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    epoch_to_scale_to_sum
+        .pack_to_zero_copy_account(&mut ctx.accounts.epoch_to_scale_to_sum, LoadingMode::Mut)?;
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use crate::staking_pool::staking_pool_operations;
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<crate::UpdateTreasuryFeeRate>,
+    treasury_fee_rate: u16,
+) -> ProgramResult {
+    let staking_pool_state = &mut ctx.accounts.staking_pool_state;
+    let previous_fee_rate = staking_pool_state.treasury_fee_rate;
+
+    staking_pool_operations::update_treasury_fee_rate(staking_pool_state, treasury_fee_rate)?;
+
+    msg!(
+        "Treasury fee rate updated from {} to {} bps",
+        previous_fee_rate,
+        treasury_fee_rate
+    );
+
+    Ok(())
+}
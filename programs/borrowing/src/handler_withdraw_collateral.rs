@@ -1,8 +1,11 @@
 use anchor_lang::{prelude::*, AccountsClose};
 
 use crate::{
-    borrowing_market::{borrowing_operations, types::WithdrawCollateralEffects},
-    soltoken,
+    borrowing_market::{
+        borrowing_operations, sorted_troves,
+        types::{MintAccruedInterestEffects, WithdrawCollateralEffects},
+    },
+    soltoken, stablecoin,
     token_operations::spltoken,
     utils::{oracle::get_prices, pda::PDA},
     CollateralToken,
@@ -34,6 +37,35 @@ pub fn process(
         amount,
         collateral,
         &prices,
+        ctx.accounts.clock.unix_timestamp as u64,
+    )?;
+
+    sorted_troves::reposition_trove(
+        &mut *ctx.accounts.sorted_troves.load_mut()?,
+        &ctx.accounts.user_metadata,
+        &prices,
+    )?;
+
+    // Mint the interest charged on the trove to the stakers and the treasury
+    let MintAccruedInterestEffects {
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+    } = borrowing_operations::mint_accrued_interest(
+        &mut ctx.accounts.borrowing_market_state,
+        &mut ctx.accounts.staking_pool_state,
+        ctx.accounts.clock.unix_timestamp as u64,
+    );
+    stablecoin::mint_accrued_interest(
+        amount_mint_to_fees_vault,
+        amount_mint_to_treasury_vault,
+        ctx.accounts.borrowing_market_state.stablecoin_mint_seed,
+        ctx.accounts.borrowing_market_state.initial_market_owner,
+        ctx.program_id,
+        ctx.accounts.stablecoin_mint.clone(),
+        ctx.accounts.borrowing_fees_vault.clone(),
+        ctx.accounts.treasury_vault.clone(),
+        ctx.accounts.stablecoin_mint_authority.clone(),
+        ctx.accounts.token_program.to_account_info(),
     )?;
 
     match collateral {
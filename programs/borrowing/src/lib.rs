@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 use borsh::{BorshDeserialize, BorshSerialize};
 use decimal_wad::error::DecimalError;
+use std::convert::TryFrom;
 
 mod token_operations;
 use token_operations::{soltoken, stablecoin};
@@ -10,32 +11,72 @@ pub mod utils;
 use utils::{bn::U256, pda};
 
 mod borrowing_market;
+mod handler_add_redemption_candidates_page;
 mod handler_add_redemption_order;
+mod handler_adjust_trove;
 mod handler_approve_staking_pool;
 mod handler_approve_trove;
 mod handler_borrow_stablecoin;
+mod handler_cancel_redemption_order;
+mod handler_cancel_treasury_disbursement;
+mod handler_claim_redemption_surplus;
 mod handler_clear_liquidation_gains;
 mod handler_clear_redemption_order;
+mod handler_close_staking_account;
+mod handler_close_trove;
+mod handler_close_trove_account;
+mod handler_complete_unstake_hbb;
+mod handler_compound_init_open_orders;
+mod handler_compound_staking_rewards;
 mod handler_deposit_and_borrow;
 mod handler_deposit_collateral;
+mod handler_execute_treasury_disbursement;
+mod handler_extend_hbb_lock;
 mod handler_fill_redemption_order;
 mod handler_harvest_liquidation_gains;
+mod handler_harvest_staking_collateral_reward;
 mod handler_harvest_staking_reward;
+mod handler_increase_hbb_lock;
 mod handler_initialize_borrowing_market;
 mod handler_initialize_stability_pool;
 mod handler_initialize_staking_pool;
+mod handler_instant_redeem;
+mod handler_lock_hbb;
+mod handler_reconcile_staking_rewards;
+mod handler_redeem_staking_position;
+mod handler_refresh_hbb_lock;
+mod handler_refresh_sorted_troves;
 mod handler_repay_loan;
+mod handler_request_unstake_hbb;
+mod handler_rotate_treasury_vault;
+mod handler_schedule_treasury_disbursement;
 mod handler_serum_close_account;
 mod handler_serum_init_account;
 mod handler_serum_swap;
+mod handler_set_auto_compound;
+mod handler_set_interest_rate;
+mod handler_simulate_redemption;
 mod handler_stability_approve;
+mod handler_stability_close_provider;
+mod handler_stability_lock_deposit;
 mod handler_stability_provide;
+mod handler_stability_release_lockup;
+mod handler_stability_request_withdrawal;
+mod handler_stability_rotate_receipt_mint;
+mod handler_stability_settle_withdrawal;
+mod handler_stability_transfer_receipts;
 mod handler_stability_withdraw;
 mod handler_stake_hbb;
+mod handler_tokenize_staking_position;
+mod handler_transfer_staking_position;
 mod handler_try_liquidate;
 mod handler_unstake_hbb;
+mod handler_update_compound_config;
 mod handler_update_global_config;
+mod handler_update_hbb_emission_schedule;
+mod handler_update_treasury_fee_rate;
 mod handler_withdraw_collateral;
+mod handler_withdraw_locked_hbb;
 pub mod redemption;
 mod stability_pool;
 mod staking_pool;
@@ -118,9 +159,56 @@ pub mod borrowing {
         handler_withdraw_collateral::process(ctx, amount, CollateralToken::from(collateral))
     }
 
-    pub fn stability_initialize(ctx: Context<InitializeStabilityPool>) -> ProgramResult {
+    pub fn adjust_trove<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustTrove<'info>>,
+        collateral_deltas: Vec<CollateralDelta>,
+        debt_delta: i64,
+    ) -> ProgramResult {
+        handler_adjust_trove::process(
+            ctx,
+            collateral_deltas
+                .iter()
+                .map(|delta| (CollateralToken::from(delta.token), delta.amount))
+                .collect(),
+            debt_delta,
+        )
+    }
+
+    pub fn claim_redemption_surplus(
+        ctx: Context<ClaimRedemptionSurplus>,
+        collateral: u8,
+        close: bool,
+    ) -> ProgramResult {
+        handler_claim_redemption_surplus::process(ctx, CollateralToken::from(collateral), close)
+    }
+
+    pub fn close_trove<'info>(ctx: Context<'_, '_, '_, 'info, CloseTrove<'info>>) -> ProgramResult {
+        handler_close_trove::process(ctx)
+    }
+
+    pub fn close_trove_account(ctx: Context<CloseTroveAccount>) -> ProgramResult {
+        handler_close_trove_account::process(ctx)
+    }
+
+    pub fn set_interest_rate(
+        ctx: Context<SetInterestRate>,
+        interest_rate_bps: u16,
+    ) -> ProgramResult {
+        handler_set_interest_rate::process(ctx, interest_rate_bps)
+    }
+
+    pub fn refresh_sorted_troves<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshSortedTroves<'info>>,
+    ) -> ProgramResult {
+        handler_refresh_sorted_troves::process(ctx)
+    }
+
+    pub fn stability_initialize(
+        ctx: Context<InitializeStabilityPool>,
+        collateral_coverage: u8,
+    ) -> ProgramResult {
         // good to go
-        handler_initialize_stability_pool::process(ctx)
+        handler_initialize_stability_pool::process(ctx, collateral_coverage)
     }
 
     pub fn stability_approve(ctx: Context<ApproveProvideStability>) -> ProgramResult {
@@ -138,7 +226,70 @@ pub mod borrowing {
         handler_stability_withdraw::process(ctx, amount)
     }
 
-    pub fn try_liquidate(ctx: Context<TryLiquidate>) -> ProgramResult {
+    pub fn stability_request_withdrawal(
+        ctx: Context<RequestStabilityWithdrawal>,
+        amount: u64,
+    ) -> ProgramResult {
+        handler_stability_request_withdrawal::process(ctx, amount)
+    }
+
+    pub fn stability_settle_withdrawal(ctx: Context<SettleStabilityWithdrawal>) -> ProgramResult {
+        handler_stability_settle_withdrawal::process(ctx)
+    }
+
+    pub fn close_stability_provider(ctx: Context<CloseStabilityProvider>) -> ProgramResult {
+        handler_stability_close_provider::process(ctx)
+    }
+
+    pub fn stability_transfer_receipts(
+        ctx: Context<TransferStabilityReceipts>,
+        amount: u64,
+    ) -> ProgramResult {
+        handler_stability_transfer_receipts::process(ctx, amount)
+    }
+
+    pub fn stability_rotate_receipt_mint(
+        ctx: Context<RotateStabilityReceiptMint>,
+    ) -> ProgramResult {
+        handler_stability_rotate_receipt_mint::process(ctx)
+    }
+
+    pub fn stability_lock_deposit(
+        ctx: Context<LockStabilityDeposit>,
+        lockup_tier: u8,
+    ) -> ProgramResult {
+        handler_stability_lock_deposit::process(ctx, LockupTier::try_from(lockup_tier)?)
+    }
+
+    pub fn stability_release_lockup(ctx: Context<ReleaseStabilityLockup>) -> ProgramResult {
+        handler_stability_release_lockup::process(ctx)
+    }
+
+    pub fn stability_update_hbb_emission_schedule(
+        ctx: Context<UpdateHbbEmissionSchedule>,
+        total_allocation: u64,
+        decay_factor: u64,
+        cliff_seconds: u64,
+        linear_tail_start_seconds: u64,
+        linear_tail_duration_seconds: u64,
+        stop_emissions: bool,
+    ) -> ProgramResult {
+        handler_update_hbb_emission_schedule::process(
+            ctx,
+            HbbEmissionSchedule::new(
+                total_allocation,
+                decay_factor,
+                cliff_seconds,
+                linear_tail_start_seconds,
+                linear_tail_duration_seconds,
+            ),
+            stop_emissions,
+        )
+    }
+
+    pub fn try_liquidate<'info>(
+        ctx: Context<'_, '_, '_, 'info, TryLiquidate<'info>>,
+    ) -> ProgramResult {
         // good to go
         // might add seed generated addresses to remove the fixed size queue altogether
         handler_try_liquidate::process(ctx)
@@ -152,8 +303,8 @@ pub mod borrowing {
         handler_harvest_liquidation_gains::process(ctx, StabilityToken::from(token))
     }
 
-    pub fn clear_liquidation_gains(
-        ctx: Context<ClearLiquidationGains>,
+    pub fn clear_liquidation_gains<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClearLiquidationGains<'info>>,
         token: u8,
     ) -> ProgramResult {
         // good to go (might be subject to above change)
@@ -163,10 +314,27 @@ pub mod borrowing {
     pub fn add_redemption_order(
         ctx: Context<AddRedemptionOrder>,
         stablecoin_amount: u64,
+        max_fee_bps: u16,
+        min_collateral_value_usdh: Option<u64>,
+        preferred_collateral: Option<u8>,
     ) -> ProgramResult {
         // 95%
         // block redemptions when system is in Recovery mode && change the dynamic rate depeding on Recovery mode
-        handler_add_redemption_order::process(ctx, stablecoin_amount)
+        handler_add_redemption_order::process(
+            ctx,
+            stablecoin_amount,
+            max_fee_bps,
+            min_collateral_value_usdh,
+            preferred_collateral
+                .map(CollateralToken::try_from)
+                .transpose()?,
+        )
+    }
+
+    pub fn add_redemption_candidates_page(
+        ctx: Context<AddRedemptionCandidatesPage>,
+    ) -> ProgramResult {
+        handler_add_redemption_candidates_page::process(ctx)
     }
 
     pub fn fill_redemption_order(
@@ -177,14 +345,43 @@ pub mod borrowing {
         handler_fill_redemption_order::process(ctx, order_id)
     }
 
-    pub fn clear_redemption_order(
-        ctx: Context<ClearRedemptionOrder>,
+    pub fn clear_redemption_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClearRedemptionOrder<'info>>,
         order_id: u64,
     ) -> ProgramResult {
         // good to go
         handler_clear_redemption_order::process(ctx, order_id)
     }
 
+    pub fn cancel_redemption_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, CancelRedemptionOrder<'info>>,
+        order_id: u64,
+    ) -> ProgramResult {
+        handler_cancel_redemption_order::process(ctx, order_id)
+    }
+
+    pub fn instant_redeem(
+        ctx: Context<InstantRedeem>,
+        stablecoin_amount: u64,
+        max_fee_bps: u16,
+        min_collateral_value_usdh: Option<u64>,
+        preferred_collateral: Option<u8>,
+    ) -> ProgramResult {
+        handler_instant_redeem::process(
+            ctx,
+            stablecoin_amount,
+            max_fee_bps,
+            min_collateral_value_usdh,
+            preferred_collateral
+                .map(CollateralToken::try_from)
+                .transpose()?,
+        )
+    }
+
+    pub fn simulate_redemption(ctx: Context<SimulateRedemption>) -> ProgramResult {
+        handler_simulate_redemption::process(ctx)
+    }
+
     pub fn staking_initialize(
         ctx: Context<InitializeStakingPool>,
         treasury_fee_rate: u16,
@@ -193,6 +390,38 @@ pub mod borrowing {
         handler_initialize_staking_pool::process(ctx, treasury_fee_rate)
     }
 
+    pub fn treasury_update_fee_rate(
+        ctx: Context<UpdateTreasuryFeeRate>,
+        treasury_fee_rate: u16,
+    ) -> ProgramResult {
+        handler_update_treasury_fee_rate::process(ctx, treasury_fee_rate)
+    }
+
+    pub fn treasury_rotate_vault(ctx: Context<RotateTreasuryVault>) -> ProgramResult {
+        handler_rotate_treasury_vault::process(ctx)
+    }
+
+    pub fn treasury_schedule_disbursement(
+        ctx: Context<ScheduleTreasuryDisbursement>,
+        amount: u64,
+    ) -> ProgramResult {
+        handler_schedule_treasury_disbursement::process(ctx, amount)
+    }
+
+    pub fn treasury_cancel_disbursement(ctx: Context<CancelTreasuryDisbursement>) -> ProgramResult {
+        handler_cancel_treasury_disbursement::process(ctx)
+    }
+
+    pub fn treasury_execute_disbursement(
+        ctx: Context<ExecuteTreasuryDisbursement>,
+    ) -> ProgramResult {
+        handler_execute_treasury_disbursement::process(ctx)
+    }
+
+    pub fn staking_reconcile_rewards(ctx: Context<ReconcileStakingRewards>) -> ProgramResult {
+        handler_reconcile_staking_rewards::process(ctx)
+    }
+
     pub fn staking_approve(ctx: Context<ApproveStakingPool>) -> ProgramResult {
         // good to go
         handler_approve_staking_pool::process(ctx)
@@ -205,15 +434,105 @@ pub mod borrowing {
 
     pub fn staking_harvest_reward(ctx: Context<HarvestRewardStakingPool>) -> ProgramResult {
         // good to go
-        // might change something here - gain: u64 -> TokenMap
         handler_harvest_staking_reward::process(ctx)
     }
 
+    pub fn staking_harvest_collateral_reward(
+        ctx: Context<HarvestCollateralRewardStakingPool>,
+        collateral: u8,
+    ) -> ProgramResult {
+        handler_harvest_staking_collateral_reward::process(ctx, CollateralToken::from(collateral))
+    }
+
     pub fn unstake_hbb(ctx: Context<UnstakeHbbStakingPool>, amount: u64) -> ProgramResult {
         // good to go
         handler_unstake_hbb::process(ctx, amount)
     }
 
+    pub fn staking_request_unstake(
+        ctx: Context<RequestUnstakeHbbStakingPool>,
+        amount: u64,
+    ) -> ProgramResult {
+        handler_request_unstake_hbb::process(ctx, amount)
+    }
+
+    pub fn staking_complete_unstake(ctx: Context<CompleteUnstakeHbbStakingPool>) -> ProgramResult {
+        handler_complete_unstake_hbb::process(ctx)
+    }
+
+    pub fn staking_lock_hbb(
+        ctx: Context<LockHbbStakingPool>,
+        amount: u64,
+        lock_expiry_ts: u64,
+    ) -> ProgramResult {
+        handler_lock_hbb::process(ctx, amount, lock_expiry_ts)
+    }
+
+    pub fn staking_increase_lock_amount(
+        ctx: Context<LockHbbStakingPool>,
+        amount: u64,
+    ) -> ProgramResult {
+        handler_increase_hbb_lock::process(ctx, amount)
+    }
+
+    pub fn staking_extend_lock(
+        ctx: Context<ExtendHbbLockStakingPool>,
+        lock_expiry_ts: u64,
+    ) -> ProgramResult {
+        handler_extend_hbb_lock::process(ctx, lock_expiry_ts)
+    }
+
+    pub fn staking_withdraw_locked_hbb(
+        ctx: Context<WithdrawLockedHbbStakingPool>,
+    ) -> ProgramResult {
+        handler_withdraw_locked_hbb::process(ctx)
+    }
+
+    pub fn staking_refresh_lock_boost(ctx: Context<RefreshHbbLockStakingPool>) -> ProgramResult {
+        handler_refresh_hbb_lock::process(ctx)
+    }
+
+    pub fn staking_set_auto_compound(
+        ctx: Context<SetAutoCompoundStakingPool>,
+        auto_compound: bool,
+    ) -> ProgramResult {
+        handler_set_auto_compound::process(ctx, auto_compound)
+    }
+
+    pub fn staking_transfer_position(ctx: Context<TransferStakingPosition>) -> ProgramResult {
+        handler_transfer_staking_position::process(ctx)
+    }
+
+    pub fn staking_tokenize_position(ctx: Context<TokenizeStakingPosition>) -> ProgramResult {
+        handler_tokenize_staking_position::process(ctx)
+    }
+
+    pub fn staking_redeem_position(ctx: Context<RedeemStakingPosition>) -> ProgramResult {
+        handler_redeem_staking_position::process(ctx)
+    }
+
+    pub fn close_staking_account(ctx: Context<CloseStakingAccount>) -> ProgramResult {
+        handler_close_staking_account::process(ctx)
+    }
+
+    pub fn compound_staking_rewards(
+        ctx: Context<CompoundStakingRewards>,
+        limit_price: u64,
+    ) -> ProgramResult {
+        handler_compound_staking_rewards::process(ctx, limit_price)
+    }
+
+    pub fn compound_update_config(
+        ctx: Context<UpdateCompoundConfig>,
+        max_hbb_price: u64,
+    ) -> ProgramResult {
+        handler_update_compound_config::process(ctx, max_hbb_price)
+    }
+
+    pub fn compound_init_open_orders(ctx: Context<CompoundInitOpenOrders>) -> ProgramResult {
+        handler_compound_init_open_orders::process(ctx)
+    }
+
     pub fn serum_init_account(ctx: Context<SerumInitOpenOrders>) -> ProgramResult {
         handler_serum_init_account::process(ctx)
     }
@@ -282,6 +601,9 @@ pub struct InitializeBorrowingMarket<'info> {
     #[account(zero)]
     pub redemptions_queue: Loader<'info, RedemptionsQueue>,
 
+    #[account(zero)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
     // Where all the borrowing fees are directed
     #[account(mut)]
     pub borrowing_fees_vault: AccountInfo<'info>,
@@ -340,7 +662,7 @@ pub struct InitializeStabilityPool<'info> {
     #[account(signer)]
     pub initial_market_owner: AccountInfo<'info>,
 
-    #[account(has_one = initial_market_owner)]
+    #[account(mut, has_one = initial_market_owner)]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(init, payer = initial_market_owner)]
@@ -372,6 +694,9 @@ pub struct InitializeStabilityPool<'info> {
     #[account(mut)]
     pub stablecoin_stability_pool_vault: AccountInfo<'info>,
 
+    #[account(mut)]
+    pub stability_receipt_mint: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 
@@ -390,9 +715,13 @@ pub struct InitializeStakingPool<'info> {
     #[account(init, payer = initial_market_owner)]
     pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
+    #[account(zero)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
     #[account(mut)]
     pub staking_vault: AccountInfo<'info>,
 
+    #[account(mut)]
     pub treasury_vault: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
@@ -401,6 +730,123 @@ pub struct InitializeStakingPool<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateTreasuryFeeRate<'info> {
+    #[account(signer)]
+    pub initial_market_owner: AccountInfo<'info>,
+
+    #[account(has_one = initial_market_owner)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut, has_one = borrowing_market_state)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+}
+
+#[derive(Accounts)]
+pub struct RotateTreasuryVault<'info> {
+    #[account(signer)]
+    pub initial_market_owner: AccountInfo<'info>,
+
+    #[account(has_one = initial_market_owner)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+        has_one = treasury_vault_authority,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    // Its balance is swept into the new vault
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+    pub treasury_vault_authority: AccountInfo<'info>,
+
+    // Ownership is transferred to the treasury PDA
+    #[account(mut)]
+    pub new_treasury_vault: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleTreasuryDisbursement<'info> {
+    #[account(signer)]
+    pub initial_market_owner: AccountInfo<'info>,
+
+    #[account(has_one = initial_market_owner)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut, has_one = borrowing_market_state)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    // Stablecoin account the disbursement is paid to
+    pub recipient: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTreasuryDisbursement<'info> {
+    #[account(signer)]
+    pub initial_market_owner: AccountInfo<'info>,
+
+    #[account(has_one = initial_market_owner)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut, has_one = borrowing_market_state)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTreasuryDisbursement<'info> {
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+        has_one = treasury_vault_authority,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+    pub treasury_vault_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct ReconcileStakingRewards<'info> {
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+        has_one = borrowing_fees_vault_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct ApproveProvideStability<'info> {
     #[account(mut, signer)]
@@ -439,6 +885,8 @@ pub struct ProvideStability<'info> {
     #[account(mut,
         has_one = stability_pool_state,
         has_one = stablecoin_stability_pool_vault,
+        has_one = stability_receipt_mint,
+        has_one = stability_receipt_mint_authority,
     )]
     pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
 
@@ -452,6 +900,14 @@ pub struct ProvideStability<'info> {
     #[account(mut)]
     pub stablecoin_ata: AccountInfo<'info>,
 
+    #[account(mut)]
+    pub stability_receipt_mint: AccountInfo<'info>,
+    pub stability_receipt_mint_authority: AccountInfo<'info>,
+
+    // must be an owner ATA
+    #[account(mut)]
+    pub stability_receipt_ata: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
 
     pub clock: Sysvar<'info, Clock>,
@@ -480,6 +936,8 @@ pub struct WithdrawStability<'info> {
         has_one = stability_pool_state,
         has_one = stablecoin_stability_pool_vault,
         has_one = stablecoin_stability_pool_vault_authority,
+        has_one = stability_receipt_mint,
+        has_one = stability_receipt_mint_authority,
     )]
     pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
 
@@ -494,164 +952,1257 @@ pub struct WithdrawStability<'info> {
     #[account(mut)]
     pub stablecoin_ata: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
-
-    pub clock: Sysvar<'info, Clock>,
-}
-
-#[derive(Accounts)]
-pub struct ApproveStakingPool<'info> {
-    #[account(mut, signer)]
-    pub owner: AccountInfo<'info>,
+    // thaws the receipt ATA for the burn
+    #[account(mut)]
+    pub stability_receipt_mint: AccountInfo<'info>,
+    pub stability_receipt_mint_authority: AccountInfo<'info>,
 
-    #[account(init, payer = owner)]
-    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+    // must be owner ATA, receipts are burnt from it
     #[account(mut)]
-    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+    pub stability_receipt_ata: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
 
-    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct StakeHbbStakingPool<'info> {
+pub struct RequestStabilityWithdrawal<'info> {
     #[account(signer)]
     pub owner: AccountInfo<'info>,
 
     #[account(mut,
         has_one = owner,
-        has_one = staking_pool_state,
+        has_one = stability_pool_state,
     )]
-    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+    pub stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
 
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(mut,
         has_one = borrowing_market_state,
-        has_one = staking_vault,
+        has_one = epoch_to_scale_to_sum,
+    )]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(
+        has_one = stability_pool_state,
+        has_one = stability_receipt_mint,
+        has_one = stability_receipt_mint_authority,
+    )]
+    pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
+
+    #[account(mut)]
+    pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
+
+    // thaws the receipt ATA for the burn
+    #[account(mut)]
+    pub stability_receipt_mint: AccountInfo<'info>,
+    pub stability_receipt_mint_authority: AccountInfo<'info>,
+
+    // must be owner ATA, receipts are burnt from it
+    #[account(mut)]
+    pub stability_receipt_ata: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct TransferStabilityReceipts<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = stability_pool_state,
+    )]
+    pub from_stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
+
+    #[account(mut,
+        has_one = stability_pool_state,
+    )]
+    pub to_stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = epoch_to_scale_to_sum,
+    )]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(
+        has_one = stability_pool_state,
+        has_one = stability_receipt_mint,
+        has_one = stability_receipt_mint_authority,
+    )]
+    pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
+
+    #[account(mut)]
+    pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
+
+    // receipts owed to the receiving provider from a previous
+    // receipt epoch are reissued along with the transfer
+    #[account(mut)]
+    pub stability_receipt_mint: AccountInfo<'info>,
+    pub stability_receipt_mint_authority: AccountInfo<'info>,
+
+    // must be the ATAs of the providers' owners
+    #[account(mut)]
+    pub from_stability_receipt_ata: AccountInfo<'info>,
+    #[account(mut)]
+    pub to_stability_receipt_ata: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct RotateStabilityReceiptMint<'info> {
+    // Permissionless, only possible once the receipts start a new epoch
+    #[account(mut)]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(mut,
+        has_one = stability_pool_state,
+        has_one = stability_receipt_mint,
+        has_one = stability_receipt_mint_authority,
+    )]
+    pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
+
+    pub stability_receipt_mint: AccountInfo<'info>,
+    pub stability_receipt_mint_authority: AccountInfo<'info>,
+
+    // Created by the caller, with the receipt authority as its mint and freeze authority
+    pub new_stability_receipt_mint: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleStabilityWithdrawal<'info> {
+    // Permissionless, no signer required
+    #[account(mut,
+        has_one = stability_pool_state,
+    )]
+    pub stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = liquidations_queue,
+    )]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(
+        has_one = stability_pool_state,
+        has_one = stablecoin_stability_pool_vault,
+        has_one = stablecoin_stability_pool_vault_authority,
+    )]
+    pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
+
+    #[account(mut)]
+    pub liquidations_queue: Loader<'info, LiquidationsQueue>,
+
+    #[account(mut)]
+    pub stablecoin_stability_pool_vault: AccountInfo<'info>,
+    pub stablecoin_stability_pool_vault_authority: AccountInfo<'info>,
+
+    // must be the stability provider's ATA
+    #[account(mut)]
+    pub stablecoin_ata: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct LockStabilityDeposit<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = stability_pool_state,
+    )]
+    pub stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = epoch_to_scale_to_sum,
+    )]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(mut)]
+    pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseStabilityLockup<'info> {
+    #[account(mut,
+        has_one = stability_pool_state,
+    )]
+    pub stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
+
+    #[account(mut,
+        has_one = epoch_to_scale_to_sum,
+    )]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(mut)]
+    pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHbbEmissionSchedule<'info> {
+    #[account(signer)]
+    pub initial_market_owner: AccountInfo<'info>,
+
+    #[account(has_one = initial_market_owner)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = epoch_to_scale_to_sum,
+    )]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+
+    #[account(mut)]
+    pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStabilityProvider<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = stability_pool_state,
+    )]
+    pub stability_provider_state: ProgramAccount<'info, StabilityProviderState>,
+
+    #[account(mut)]
+    pub stability_pool_state: ProgramAccount<'info, StabilityPoolState>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveStakingPool<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(init, payer = owner)]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+    #[account(mut)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStakingAccount<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+}
+
+#[derive(Accounts)]
+pub struct StakeHbbStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = staking_vault,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    #[account(mut)]
+    pub staking_vault: AccountInfo<'info>,
+
+    // must be owner ATA
+    #[account(mut)]
+    pub user_hbb_staking_ata: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeHbbStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+        has_one = borrowing_fees_vault_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    // Instant unstaking is only allowed without a cooldown
+    #[account(
+        constraint = global_config.initial_market_owner == borrowing_market_state.initial_market_owner,
+    )]
+    pub global_config: ProgramAccount<'info, GlobalConfig>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = staking_vault,
+        has_one = staking_vault_authority,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    // Must be the user's ATA
+    #[account(mut)]
+    pub user_hbb_staking_ata: AccountInfo<'info>,
+    // Must be the user's ATA
+    #[account(mut)]
+    pub user_stablecoin_rewards_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub staking_vault: AccountInfo<'info>,
+    pub staking_vault_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstakeHbbStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        constraint = global_config.initial_market_owner == borrowing_market_state.initial_market_owner,
+    )]
+    pub global_config: ProgramAccount<'info, GlobalConfig>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteUnstakeHbbStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = staking_vault,
+        has_one = staking_vault_authority,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    // Must be the user's ATA
+    #[account(mut)]
+    pub user_hbb_staking_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub staking_vault: AccountInfo<'info>,
+    pub staking_vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestRewardStakingPool<'info> {
+    #[account(signer, mut)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+        has_one = borrowing_fees_vault_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    // Must be the user's ATA
+    #[account(mut)]
+    pub user_stablecoin_rewards_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestCollateralRewardStakingPool<'info> {
+    #[account(signer, mut)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    // Redemption fees are held as inactive collateral of the market
+    #[account(mut)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(has_one = borrowing_market_state)]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    // Vault where collateral is withdrawn from
+    #[account(mut)]
+    pub collateral_from: AccountInfo<'info>,
+    pub collateral_from_authority: AccountInfo<'info>,
+
+    // Where collateral is withdrawn to
+    // Must be the user's mint ATA or native account
+    #[account(mut)]
+    pub collateral_to: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct LockHbbStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = staking_vault,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    #[account(mut)]
+    pub staking_vault: AccountInfo<'info>,
+
+    // must be owner ATA
+    #[account(mut)]
+    pub user_hbb_staking_ata: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendHbbLockStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut, has_one = boost_checkpoints)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLockedHbbStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = staking_vault,
+        has_one = staking_vault_authority,
+        has_one = boost_checkpoints,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    // Must be the user's ATA
+    #[account(mut)]
+    pub user_hbb_staking_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub staking_vault: AccountInfo<'info>,
+    pub staking_vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoCompoundStakingPool<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut, has_one = boost_checkpoints)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct TransferStakingPosition<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    pub new_owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut, has_one = boost_checkpoints)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct TokenizeStakingPosition<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = staking_vault_authority,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+    pub staking_vault_authority: AccountInfo<'info>,
+
+    // Created by the caller, with the staking vault authority as its mint authority
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>,
+
+    // Receives the position token, any wallet or custody account
+    #[account(mut)]
+    pub position_token_account: AccountInfo<'info>,
+
+    pub token_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemStakingPosition<'info> {
+    // Holder of the position token, signs its burn
+    #[account(signer)]
+    pub holder: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub position_token_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = position_mint,
+        has_one = staking_pool_state,
+    )]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut, has_one = boost_checkpoints)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    pub token_program: AccountInfo<'info>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshHbbLockStakingPool<'info> {
+    // Anyone can checkpoint the expired locks
+    #[account(mut, has_one = staking_pool_state)]
+    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
+
+    #[account(mut, has_one = boost_checkpoints)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut)]
+    pub boost_checkpoints: Loader<'info, BoostCheckpoints>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveTrove<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(init, payer = owner)]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
+    #[account(mut)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    pub stablecoin_ata: AccountInfo<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTrove<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
+    )]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = burning_vault,
+        has_one = burning_vault_authority,
+        has_one = collateral_vaults_authority,
+        has_one = borrowing_fees_vault,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    // Credited with the interest charged on the trove
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+
+    // Where the debt is repaid from
+    #[account(mut,
+        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
+    )]
+    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
+
+    // Where the debt is repaid into (and burned)
+    #[account(mut)]
+    pub burning_vault: AccountInfo<'info>,
+    pub burning_vault_authority: AccountInfo<'info>,
+
+    // Where the interest charged on the trove is minted
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    // One authority for all collateral vaults, the vaults
+    // themselves are passed as remaining accounts
+    pub collateral_vaults_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTroveAccount<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state,
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
+    #[account(mut, has_one = sorted_troves)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(mut, signer)]
+    pub owner: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
+    )]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    // Credited with the interest charged on the trove
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
+    // Account where collateral is deposited from, either the owner account or an ATA
+    #[account(mut)]
+    pub collateral_from: AccountInfo<'info>,
+
+    // Vault where collateral is deposited to
+    #[account(mut)]
+    pub collateral_to: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
+    // Where the interest charged on the trove is minted
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    // Oracle accounts
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowStable<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    // Global state
+    #[account(mut,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves
+    )]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    //Staking pool state to update rewards data
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
+    // Stablecoin account from which we mint/burn stablecoin
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+
+    // Source of stablecoin mint PDA authority (authority to which minting rights have been transferred)
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+
+    // Where stablecoin will be minted (borrowed)
+    #[account(mut,
+        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
+    )]
+    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
+
+    // Where the borrowing 0.5% fees go
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+
+    // Where the treasury fees go
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    // Oracle accounts
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateralAndBorrowStable<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+
+    // Global state
+    #[account(mut,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves
+    )]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    //Staking pool state to update rewards data
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault
     )]
     pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
     #[account(mut)]
-    pub staking_vault: AccountInfo<'info>,
+    pub sorted_troves: Loader<'info, SortedTroves>,
 
-    // must be owner ATA
+    // Stablecoin account from which we mint/burn stablecoin
     #[account(mut)]
-    pub user_hbb_staking_ata: AccountInfo<'info>,
+    pub stablecoin_mint: AccountInfo<'info>,
+
+    // Source of stablecoin mint PDA authority (authority to which minting rights have been transferred)
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+
+    // Account where collateral is deposited from, either the owner account or an ATA
+    #[account(mut)]
+    pub collateral_from: AccountInfo<'info>,
+
+    // Vault where collateral is deposited to
+    #[account(mut)]
+    pub collateral_to: AccountInfo<'info>,
+
+    // Where stablecoin will be minted (borrowed)
+    #[account(mut,
+        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
+    )]
+    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
+
+    // Where the borrowing 0.5% fees go
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+
+    // Where the treasury fees go
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    // Oracle accounts
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct UnstakeHbbStakingPool<'info> {
+pub struct RepayLoan<'info> {
     #[account(signer)]
     pub owner: AccountInfo<'info>,
 
+    // Global state
     #[account(mut,
-        has_one = owner,
-        has_one = staking_pool_state,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
-    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
-
-    #[account(mut)]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
-
-    #[account(
+    #[account(mut,
         has_one = borrowing_market_state,
+        has_one = burning_vault,
+        has_one = burning_vault_authority,
         has_one = borrowing_fees_vault,
-        has_one = borrowing_fees_vault_authority,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
+    // Credited with the interest charged on the trove
     #[account(mut,
         has_one = borrowing_market_state,
-        has_one = staking_vault,
-        has_one = staking_vault_authority,
+        has_one = treasury_vault,
     )]
     pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
-    // Must be the user's ATA
-    #[account(mut)]
-    pub user_hbb_staking_ata: AccountInfo<'info>,
-    // Must be the user's ATA
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state,
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
+    // Stablecoin account from which we mint/burn stablecoin
     #[account(mut)]
-    pub user_stablecoin_rewards_ata: AccountInfo<'info>,
+    pub stablecoin_mint: AccountInfo<'info>,
+    // Source of stablecoin mint PDA authority (authority to which minting rights have been transferred)
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+
+    // Where stablecoin will be repaid from
+    #[account(mut,
+        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
+    )]
+    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
 
+    // Where the debt will be repaid into (and burned)
     #[account(mut)]
-    pub staking_vault: AccountInfo<'info>,
-    pub staking_vault_authority: AccountInfo<'info>,
+    pub burning_vault: AccountInfo<'info>,
+    pub burning_vault_authority: AccountInfo<'info>,
 
+    // Where the interest charged on the trove is minted
     #[account(mut)]
     pub borrowing_fees_vault: AccountInfo<'info>,
-    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
+    // Oracle accounts
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
 
+    // Source of stablecoin mint
     pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct HarvestRewardStakingPool<'info> {
-    #[account(signer, mut)]
+pub struct WithdrawCollateral<'info> {
+    #[account(mut, signer)]
     pub owner: AccountInfo<'info>,
 
     #[account(mut,
-        has_one = owner,
-        has_one = staking_pool_state,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
-    pub user_staking_state: ProgramAccount<'info, UserStakingState>,
-
-    #[account(mut)]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(
         has_one = borrowing_market_state,
         has_one = borrowing_fees_vault,
-        has_one = borrowing_fees_vault_authority,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
+    // Credited with the interest charged on the trove
     #[account(mut,
         has_one = borrowing_market_state,
+        has_one = treasury_vault,
     )]
     pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
-    // Must be the user's ATA
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state
+    )]
+    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+
     #[account(mut)]
-    pub user_stablecoin_rewards_ata: AccountInfo<'info>,
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
+    // Vault where collateral is withdrawn from
+    #[account(mut)]
+    pub collateral_from: AccountInfo<'info>,
+    pub collateral_from_authority: AccountInfo<'info>,
+
+    // Where collateral is withdrawn to
+    // Must be the user's mint ATA or native account
+    #[account(mut)]
+    pub collateral_to: AccountInfo<'info>,
 
+    // Where the interest charged on the trove is minted
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
     #[account(mut)]
     pub borrowing_fees_vault: AccountInfo<'info>,
-    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
 
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+
+    // Oracle accounts
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct ApproveTrove<'info> {
+pub struct AdjustTrove<'info> {
     #[account(mut, signer)]
     pub owner: AccountInfo<'info>,
 
-    #[account(init, payer = owner)]
+    // Global state
+    #[account(mut,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves
+    )]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+        has_one = burning_vault,
+        has_one = burning_vault_authority,
+        has_one = collateral_vaults_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    //Staking pool state to update rewards data
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    #[account(mut,
+        has_one = owner,
+        has_one = borrowing_market_state
+    )]
     pub user_metadata: ProgramAccount<'info, UserMetadata>,
 
     #[account(mut)]
-    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+    pub sorted_troves: Loader<'info, SortedTroves>,
 
-    pub stablecoin_ata: AccountInfo<'info>,
+    // Stablecoin account from which we mint/burn stablecoin
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
 
-    pub rent: Sysvar<'info, Rent>,
+    // Where stablecoin is minted to (borrowed) and repaid from
+    #[account(mut,
+        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
+    )]
+    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
+    // Where the debt is repaid into (and burned)
+    #[account(mut)]
+    pub burning_vault: AccountInfo<'info>,
+    pub burning_vault_authority: AccountInfo<'info>,
+
+    // One authority for all collateral vaults, the vaults
+    // themselves are passed as remaining accounts
+    pub collateral_vaults_authority: AccountInfo<'info>,
+
+    // Oracle accounts
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct DepositCollateral<'info> {
+pub struct ClaimRedemptionSurplus<'info> {
     #[account(mut, signer)]
     pub owner: AccountInfo<'info>,
 
-    #[account(mut)]
+    #[account(mut, has_one = sorted_troves)]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(has_one = borrowing_market_state)]
@@ -663,40 +2214,45 @@ pub struct DepositCollateral<'info> {
     )]
     pub user_metadata: ProgramAccount<'info, UserMetadata>,
 
-    // Account where collateral is deposited from, either the owner account or an ATA
+    // Vault where collateral is withdrawn from
     #[account(mut)]
     pub collateral_from: AccountInfo<'info>,
+    pub collateral_from_authority: AccountInfo<'info>,
 
-    // Vault where collateral is deposited to
+    // Where collateral is withdrawn to
+    // Must be the user's mint ATA or native account
     #[account(mut)]
     pub collateral_to: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
     pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct BorrowStable<'info> {
+pub struct SetInterestRate<'info> {
     #[account(signer)]
     pub owner: AccountInfo<'info>,
 
-    // Global state
     #[account(mut,
         has_one = stablecoin_mint,
-        has_one = stablecoin_mint_authority
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(
         has_one = borrowing_market_state,
-        has_one = borrowing_fees_vault
+        has_one = borrowing_fees_vault,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
-    //Staking pool state to update rewards data
+    // Credited with the interest charged on the trove
     #[account(mut,
         has_one = borrowing_market_state,
-        has_one = treasury_vault
+        has_one = treasury_vault,
     )]
     pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
@@ -706,24 +2262,15 @@ pub struct BorrowStable<'info> {
     )]
     pub user_metadata: ProgramAccount<'info, UserMetadata>,
 
-    // Stablecoin account from which we mint/burn stablecoin
     #[account(mut)]
-    pub stablecoin_mint: AccountInfo<'info>,
+    pub sorted_troves: Loader<'info, SortedTroves>,
 
-    // Source of stablecoin mint PDA authority (authority to which minting rights have been transferred)
+    // Where the interest charged on the trove is minted
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
     pub stablecoin_mint_authority: AccountInfo<'info>,
-
-    // Where stablecoin will be minted (borrowed)
-    #[account(mut,
-        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
-    )]
-    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
-
-    // Where the borrowing 0.5% fees go
     #[account(mut)]
     pub borrowing_fees_vault: AccountInfo<'info>,
-
-    // Where the treasury fees go
     #[account(mut)]
     pub treasury_vault: AccountInfo<'info>,
 
@@ -740,62 +2287,36 @@ pub struct BorrowStable<'info> {
 }
 
 #[derive(Accounts)]
-pub struct DepositCollateralAndBorrowStable<'info> {
-    #[account(signer)]
-    pub owner: AccountInfo<'info>,
-
-    // Global state
+pub struct RefreshSortedTroves<'info> {
     #[account(mut,
         has_one = stablecoin_mint,
-        has_one = stablecoin_mint_authority
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(
         has_one = borrowing_market_state,
-        has_one = borrowing_fees_vault
+        has_one = borrowing_fees_vault,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
-    //Staking pool state to update rewards data
+    // Credited with the interest charged on the troves
     #[account(mut,
         has_one = borrowing_market_state,
-        has_one = treasury_vault
+        has_one = treasury_vault,
     )]
     pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
-    #[account(mut,
-        has_one = owner,
-        has_one = borrowing_market_state
-    )]
-    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
 
-    // Stablecoin account from which we mint/burn stablecoin
+    // Where the interest charged on the troves is minted
     #[account(mut)]
     pub stablecoin_mint: AccountInfo<'info>,
-
-    // Source of stablecoin mint PDA authority (authority to which minting rights have been transferred)
     pub stablecoin_mint_authority: AccountInfo<'info>,
-
-    // Account where collateral is deposited from, either the owner account or an ATA
-    #[account(mut)]
-    pub collateral_from: AccountInfo<'info>,
-
-    // Vault where collateral is deposited to
-    #[account(mut)]
-    pub collateral_to: AccountInfo<'info>,
-
-    // Where stablecoin will be minted (borrowed)
-    #[account(mut,
-        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
-    )]
-    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
-
-    // Where the borrowing 0.5% fees go
     #[account(mut)]
     pub borrowing_fees_vault: AccountInfo<'info>,
-
-    // Where the treasury fees go
     #[account(mut)]
     pub treasury_vault: AccountInfo<'info>,
 
@@ -808,106 +2329,181 @@ pub struct DepositCollateralAndBorrowStable<'info> {
     pub pyth_ftt_price_info: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
     pub clock: Sysvar<'info, Clock>,
+    // remaining accounts (troves to reposition)
 }
 
 #[derive(Accounts)]
-pub struct RepayLoan<'info> {
-    #[account(signer)]
-    pub owner: AccountInfo<'info>,
+pub struct AddRedemptionOrder<'info> {
+    #[account(mut, signer)]
+    pub redeemer: AccountInfo<'info>,
 
-    // Global state
     #[account(mut,
-        has_one = stablecoin_mint,
-        has_one = stablecoin_mint_authority,
+        constraint = redeemer.key == &redeemer_metadata.owner,
+        has_one = borrowing_market_state,
     )]
-    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+    pub redeemer_metadata: ProgramAccount<'info, UserMetadata>,
+
+    // Must be redeemer ATA
     #[account(mut,
+        constraint = redeemer_stablecoin_associated_account.key == &redeemer_metadata.stablecoin_ata,
+    )]
+    pub redeemer_stablecoin_associated_account: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = redemptions_queue,
+    )]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
         has_one = borrowing_market_state,
         has_one = burning_vault,
-        has_one = burning_vault_authority,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
-    #[account(mut,
-        has_one = owner,
-        has_one = borrowing_market_state,
+    #[account(
+        constraint = global_config.initial_market_owner == borrowing_market_state.initial_market_owner,
     )]
-    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+    pub global_config: ProgramAccount<'info, GlobalConfig>,
 
-    // Stablecoin account from which we mint/burn stablecoin
     #[account(mut)]
-    pub stablecoin_mint: AccountInfo<'info>,
-    // Source of stablecoin mint PDA authority (authority to which minting rights have been transferred)
-    pub stablecoin_mint_authority: AccountInfo<'info>,
+    pub redemptions_queue: Loader<'info, RedemptionsQueue>,
 
-    // Where stablecoin will be repaid from
-    #[account(mut,
-        constraint = stablecoin_borrowing_associated_account.key == &user_metadata.stablecoin_ata
+    #[account(init,
+        seeds = [
+            redemptions_queue.key().as_ref(),
+            pda::REDEMPTION_ORDER_TAG.as_bytes(),
+            &redemptions_queue.load()?.next_index.to_le_bytes(),
+        ],
+        bump,
+        payer = redeemer,
     )]
-    pub stablecoin_borrowing_associated_account: AccountInfo<'info>,
+    pub redemption_order: Loader<'info, RedemptionOrder>,
+
+    #[account(init,
+        seeds = [
+            redemption_order.key().as_ref(),
+            pda::REDEMPTION_CANDIDATES_PAGE_TAG.as_bytes(),
+            &0_u64.to_le_bytes(),
+        ],
+        bump,
+        payer = redeemer,
+    )]
+    pub candidates_page: Loader<'info, RedemptionCandidatesPage>,
 
-    // Where the debt will be repaid into (and burned)
+    // Also holds the stakers fee paid in USDH until the order is cleared
     #[account(mut)]
     pub burning_vault: AccountInfo<'info>,
-    pub burning_vault_authority: AccountInfo<'info>,
 
-    // Source of stablecoin mint
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawCollateral<'info> {
-    #[account(mut, signer)]
-    pub owner: AccountInfo<'info>,
+pub struct FillRedemptionOrder<'info> {
+    #[account(signer)]
+    pub filler: AccountInfo<'info>,
 
-    #[account(mut)]
+    #[account(mut,
+        constraint = filler.key == &filler_metadata.owner,
+        has_one = borrowing_market_state,
+    )]
+    pub filler_metadata: ProgramAccount<'info, UserMetadata>,
+
+    #[account(mut,
+        has_one = redemptions_queue,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+    )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+    pub redemptions_queue: Loader<'info, RedemptionsQueue>,
 
-    #[account(has_one = borrowing_market_state)]
+    #[account(mut, has_one = redemptions_queue)]
+    pub redemption_order: Loader<'info, RedemptionOrder>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+    )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
+    // Credited with the interest charged on the troves
     #[account(mut,
-        has_one = owner,
-        has_one = borrowing_market_state
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
     )]
-    pub user_metadata: ProgramAccount<'info, UserMetadata>,
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
 
-    // Vault where collateral is withdrawn from
+    // Where the interest charged on the troves is minted
     #[account(mut)]
-    pub collateral_from: AccountInfo<'info>,
-    pub collateral_from_authority: AccountInfo<'info>,
-
-    // Where collateral is withdrawn to
-    // Must be the user's mint ATA or native account
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
     #[account(mut)]
-    pub collateral_to: AccountInfo<'info>,
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
 
-    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
-
-    // Oracle accounts
-    pub pyth_sol_price_info: AccountInfo<'info>,
-    pub pyth_eth_price_info: AccountInfo<'info>,
-    pub pyth_btc_price_info: AccountInfo<'info>,
-    pub pyth_srm_price_info: AccountInfo<'info>,
-    pub pyth_ray_price_info: AccountInfo<'info>,
-    pub pyth_ftt_price_info: AccountInfo<'info>,
+    pub clock: Sysvar<'info, Clock>,
+    // remaining accounts (candidate pages, user metadata, candidate user)
 }
 
 #[derive(Accounts)]
-pub struct AddRedemptionOrder<'info> {
+pub struct AddRedemptionCandidatesPage<'info> {
     #[account(mut, signer)]
-    pub redeemer: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub redemption_order: Loader<'info, RedemptionOrder>,
+
+    #[account(init,
+        seeds = [
+            redemption_order.key().as_ref(),
+            pda::REDEMPTION_CANDIDATES_PAGE_TAG.as_bytes(),
+            &redemption_order.load()?.candidate_pages.to_le_bytes(),
+        ],
+        bump,
+        payer = payer,
+    )]
+    pub candidates_page: Loader<'info, RedemptionCandidatesPage>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClearRedemptionOrder<'info> {
+    #[account(signer)]
+    pub clearer: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = clearer.key == &clearer_metadata.owner,
+        has_one = borrowing_market_state,
+    )]
+    pub clearer_metadata: ProgramAccount<'info, UserMetadata>,
 
     #[account(mut,
-        constraint = redeemer.key == &redeemer_metadata.owner,
         has_one = borrowing_market_state,
     )]
     pub redeemer_metadata: ProgramAccount<'info, UserMetadata>,
 
-    // Must be redeemer ATA
+    // Receives the rent of the order and its candidate pages once completed
+    #[account(mut,
+        constraint = redeemer.key == &redeemer_metadata.owner,
+    )]
+    pub redeemer: AccountInfo<'info>,
+
+    // Refunded when the collateral falls short of the redeemer's minimum
     #[account(mut,
         constraint = redeemer_stablecoin_associated_account.key == &redeemer_metadata.stablecoin_ata,
     )]
@@ -915,21 +2511,49 @@ pub struct AddRedemptionOrder<'info> {
 
     #[account(mut,
         has_one = redemptions_queue,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
     #[account(
         has_one = borrowing_market_state,
         has_one = burning_vault,
+        has_one = burning_vault_authority,
+        has_one = borrowing_fees_vault,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
+    // Credited with the stakers share of the redeemed collateral,
+    // or of the escrowed USDH fee, and with the interest charged
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
     #[account(mut)]
     pub redemptions_queue: Loader<'info, RedemptionsQueue>,
 
+    #[account(mut, has_one = redemptions_queue)]
+    pub redemption_order: Loader<'info, RedemptionOrder>,
+
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
     #[account(mut)]
     pub burning_vault: AccountInfo<'info>,
+    pub burning_vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
 
+    // Oracle accounts
     pub pyth_sol_price_info: AccountInfo<'info>,
     pub pyth_eth_price_info: AccountInfo<'info>,
     pub pyth_btc_price_info: AccountInfo<'info>,
@@ -937,54 +2561,96 @@ pub struct AddRedemptionOrder<'info> {
     pub pyth_ray_price_info: AccountInfo<'info>,
     pub pyth_ftt_price_info: AccountInfo<'info>,
 
-    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub clock: Sysvar<'info, Clock>,
+    // remaining accounts (candidate pages, user metadata, borrowers and fillers,
+    // closed candidate troves)
+}
+
+#[derive(Accounts)]
+pub struct SimulateRedemption<'info> {
+    #[account(has_one = redemptions_queue)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+    pub redemptions_queue: Loader<'info, RedemptionsQueue>,
+
+    #[account(has_one = redemptions_queue)]
+    pub redemption_order: Loader<'info, RedemptionOrder>,
 
     pub clock: Sysvar<'info, Clock>,
-    pub rent: Sysvar<'info, Rent>,
+    // remaining accounts (candidate troves, read only)
 }
 
 #[derive(Accounts)]
-pub struct FillRedemptionOrder<'info> {
-    #[account(signer)]
-    pub filler: AccountInfo<'info>,
+pub struct CancelRedemptionOrder<'info> {
+    // Receives the rent of the order and its candidate pages once refunded in full
+    #[account(signer, mut)]
+    pub redeemer: AccountInfo<'info>,
 
-    #[account(mut,
-        constraint = filler.key == &filler_metadata.owner,
+    #[account(
+        constraint = redeemer.key == &redeemer_metadata.owner,
         has_one = borrowing_market_state,
     )]
-    pub filler_metadata: ProgramAccount<'info, UserMetadata>,
+    pub redeemer_metadata: ProgramAccount<'info, UserMetadata>,
 
+    // Must be redeemer ATA
     #[account(mut,
-        has_one = redemptions_queue,
+        constraint = redeemer_stablecoin_associated_account.key == &redeemer_metadata.stablecoin_ata,
     )]
+    pub redeemer_stablecoin_associated_account: AccountInfo<'info>,
+
+    #[account(has_one = redemptions_queue)]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = burning_vault,
+        has_one = burning_vault_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    #[account(
+        constraint = global_config.initial_market_owner == borrowing_market_state.initial_market_owner,
+    )]
+    pub global_config: ProgramAccount<'info, GlobalConfig>,
+
     #[account(mut)]
     pub redemptions_queue: Loader<'info, RedemptionsQueue>,
 
+    #[account(mut, has_one = redemptions_queue)]
+    pub redemption_order: Loader<'info, RedemptionOrder>,
+
+    #[account(mut)]
+    pub burning_vault: AccountInfo<'info>,
+    pub burning_vault_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
     pub clock: Sysvar<'info, Clock>,
-    // remaining accounts (user metadata, candidate user)
+    // remaining accounts (candidate pages)
 }
 
 #[derive(Accounts)]
-pub struct ClearRedemptionOrder<'info> {
-    #[account(signer)]
-    pub clearer: AccountInfo<'info>,
+pub struct InstantRedeem<'info> {
+    // Receives the redeemed SOL
+    #[account(mut, signer)]
+    pub redeemer: AccountInfo<'info>,
 
     #[account(mut,
-        constraint = clearer.key == &clearer_metadata.owner,
+        constraint = redeemer.key == &redeemer_metadata.owner,
         has_one = borrowing_market_state,
     )]
-    pub clearer_metadata: ProgramAccount<'info, UserMetadata>,
+    pub redeemer_metadata: ProgramAccount<'info, UserMetadata>,
 
+    // Must be redeemer ATA
     #[account(mut,
-        has_one = borrowing_market_state,
+        constraint = redeemer_stablecoin_associated_account.key == &redeemer_metadata.stablecoin_ata,
     )]
-    pub redeemer_metadata: ProgramAccount<'info, UserMetadata>,
+    pub redeemer_stablecoin_associated_account: AccountInfo<'info>,
 
     #[account(mut,
         has_one = redemptions_queue,
         has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
 
@@ -992,21 +2658,84 @@ pub struct ClearRedemptionOrder<'info> {
         has_one = borrowing_market_state,
         has_one = burning_vault,
         has_one = burning_vault_authority,
+        has_one = borrowing_fees_vault,
+        has_one = collateral_vault_sol,
+        has_one = collateral_vault_srm,
+        has_one = collateral_vault_eth,
+        has_one = collateral_vault_btc,
+        has_one = collateral_vault_ray,
+        has_one = collateral_vault_ftt,
+        has_one = collateral_vaults_authority,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
+    #[account(
+        constraint = global_config.initial_market_owner == borrowing_market_state.initial_market_owner,
+    )]
+    pub global_config: ProgramAccount<'info, GlobalConfig>,
+
+    // Vaults the redeemed collateral is paid out from
+    #[account(mut)]
+    pub collateral_vault_sol: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault_srm: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault_eth: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault_btc: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault_ray: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault_ftt: AccountInfo<'info>,
+    pub collateral_vaults_authority: AccountInfo<'info>,
+
+    // Where the redeemed collateral is paid to
+    // Must be the redeemer's mint ATAs
+    #[account(mut)]
+    pub redeemer_srm_ata: AccountInfo<'info>,
+    #[account(mut)]
+    pub redeemer_eth_ata: AccountInfo<'info>,
+    #[account(mut)]
+    pub redeemer_btc_ata: AccountInfo<'info>,
+    #[account(mut)]
+    pub redeemer_ray_ata: AccountInfo<'info>,
     #[account(mut)]
+    pub redeemer_ftt_ata: AccountInfo<'info>,
+
+    // Credited with the stakers share when it is paid in USDH,
+    // and with the interest charged on the troves
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
     pub redemptions_queue: Loader<'info, RedemptionsQueue>,
 
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
+
     #[account(mut)]
     pub burning_vault: AccountInfo<'info>,
     pub burning_vault_authority: AccountInfo<'info>,
     #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+    #[account(mut)]
     pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+
+    pub pyth_sol_price_info: AccountInfo<'info>,
+    pub pyth_eth_price_info: AccountInfo<'info>,
+    pub pyth_btc_price_info: AccountInfo<'info>,
+    pub pyth_srm_price_info: AccountInfo<'info>,
+    pub pyth_ray_price_info: AccountInfo<'info>,
+    pub pyth_ftt_price_info: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
     pub clock: Sysvar<'info, Clock>,
-    // remaining accounts (user metadata, borrowers and fillers)
+    // remaining accounts (borrowers, in the order of the sorted troves)
 }
 
 #[derive(Accounts)]
@@ -1017,6 +2746,7 @@ pub struct TryLiquidate<'info> {
     #[account(mut,
         has_one = stablecoin_mint,
         has_one = stablecoin_mint_authority,
+        has_one = sorted_troves,
     )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
     #[account(mut,
@@ -1029,6 +2759,8 @@ pub struct TryLiquidate<'info> {
         has_one = borrowing_market_state,
     )]
     pub user_metadata: ProgramAccount<'info, UserMetadata>,
+    #[account(mut)]
+    pub sorted_troves: Loader<'info, SortedTroves>,
 
     #[account(mut)]
     pub epoch_to_scale_to_sum: Loader<'info, EpochToScaleToSumAccount>,
@@ -1040,16 +2772,30 @@ pub struct TryLiquidate<'info> {
     pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
     #[account(
         has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
     )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
     #[account(mut)]
     pub liquidations_queue: Loader<'info, LiquidationsQueue>,
 
+    // Credited with the interest charged on the trove
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
     // Stablecoin account from which we mint/burn stablecoin
     #[account(mut)]
     pub stablecoin_mint: AccountInfo<'info>,
     pub stablecoin_mint_authority: AccountInfo<'info>,
 
+    // Where the interest charged on the trove is minted
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
     #[account(mut)]
     pub stablecoin_stability_pool_vault: AccountInfo<'info>,
     pub stablecoin_stability_pool_vault_authority: AccountInfo<'info>,
@@ -1157,6 +2903,8 @@ pub struct ClearLiquidationGains<'info> {
 
     #[account(
         has_one = stability_pool_state,
+        has_one = stablecoin_stability_pool_vault,
+        has_one = stablecoin_stability_pool_vault_authority,
     )]
     pub stability_vaults: ProgramAccount<'info, StabilityVaults>,
 
@@ -1172,6 +2920,12 @@ pub struct ClearLiquidationGains<'info> {
     #[account(mut)]
     pub liquidation_rewards_vault: AccountInfo<'info>,
 
+    // Stability pool vault, to pay out the withdrawal requests
+    // submitted as remaining accounts once the queue is drained
+    #[account(mut)]
+    pub stablecoin_stability_pool_vault: AccountInfo<'info>,
+    pub stablecoin_stability_pool_vault_authority: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
     pub clock: Sysvar<'info, Clock>,
 }
@@ -1282,11 +3036,24 @@ pub struct SerumSwapToUsdc<'info> {
     #[account(mut)]
     dex_swap_account: AccountInfo<'info>,
 
-    #[account(mut)]
+    #[account(mut,
+        has_one = stablecoin_mint,
+        has_one = stablecoin_mint_authority,
+    )]
     pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
-    #[account(has_one = borrowing_market_state)]
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+    )]
     pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
 
+    // Credited with the interest charged on the trove
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = treasury_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
     #[account(mut,
         has_one = owner,
         has_one = borrowing_market_state
@@ -1307,6 +3074,15 @@ pub struct SerumSwapToUsdc<'info> {
     // Pass usdc_mint for security (verify that the user that receives USDC is the user_metadata owner)
     pub usdc_mint: AccountInfo<'info>,
 
+    // Where the interest charged on the trove is minted
+    #[account(mut)]
+    pub stablecoin_mint: AccountInfo<'info>,
+    pub stablecoin_mint_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury_vault: AccountInfo<'info>,
+
     // Oracle accounts
     pub pyth_sol_price_info: AccountInfo<'info>,
     pub pyth_eth_price_info: AccountInfo<'info>,
@@ -1317,6 +3093,120 @@ pub struct SerumSwapToUsdc<'info> {
 
     pub token_program: AccountInfo<'info>,
     pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundStakingRewards<'info> {
+    /// The DEX program
+    pub dex_program: AccountInfo<'info>,
+
+    // The market pair address (HBB/USDH)
+    #[account(mut)]
+    market: AccountInfo<'info>,
+
+    // The open orders of the borrowing fees vault authority
+    #[account(mut)]
+    open_orders: AccountInfo<'info>,
+
+    // The queue with the events that are being processeds
+    #[account(mut)]
+    request_queue: AccountInfo<'info>,
+
+    // The queue with the consumed events on the market
+    #[account(mut)]
+    event_queue: AccountInfo<'info>,
+
+    // The address of bids on the orderbook
+    #[account(mut)]
+    bids: AccountInfo<'info>,
+
+    // The address of asks on the orderbook
+    #[account(mut)]
+    asks: AccountInfo<'info>,
+
+    /// The DEX vault for the "base" currency (HBB)
+    #[account(mut)]
+    coin_vault: AccountInfo<'info>,
+
+    /// The DEX vault for the "quote" currency (USDH)
+    #[account(mut)]
+    pc_vault: AccountInfo<'info>,
+
+    /// DEX owner
+    vault_signer: AccountInfo<'info>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault,
+        has_one = borrowing_fees_vault_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    #[account(mut,
+        has_one = borrowing_market_state,
+        has_one = staking_vault,
+    )]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    // Receives the bought HBB
+    #[account(mut)]
+    pub staking_vault: AccountInfo<'info>,
+
+    // Pays for the HBB, and receives back the unmatched USDH
+    #[account(mut)]
+    pub borrowing_fees_vault: AccountInfo<'info>,
+    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+
+    pub token_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCompoundConfig<'info> {
+    #[account(signer)]
+    pub initial_market_owner: AccountInfo<'info>,
+
+    #[account(has_one = initial_market_owner)]
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(mut, has_one = borrowing_market_state)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    pub dex_program: AccountInfo<'info>,
+
+    // The market pair address (HBB/USDH) compounding buys on
+    pub market: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundInitOpenOrders<'info> {
+    pub dex_program: AccountInfo<'info>,
+
+    // A new account, created before the call, owned by the dex
+    #[account(mut)]
+    pub open_orders: AccountInfo<'info>,
+
+    pub market: AccountInfo<'info>,
+
+    pub borrowing_market_state: ProgramAccount<'info, BorrowingMarketState>,
+
+    #[account(
+        has_one = borrowing_market_state,
+        has_one = borrowing_fees_vault_authority,
+    )]
+    pub borrowing_vaults: ProgramAccount<'info, BorrowingVaults>,
+
+    #[account(has_one = borrowing_market_state)]
+    pub staking_pool_state: ProgramAccount<'info, StakingPoolState>,
+
+    // Owns the open orders, signs the compounding orders
+    pub borrowing_fees_vault_authority: AccountInfo<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[error]
@@ -1498,6 +3388,165 @@ pub enum BorrowError {
 
     #[msg("Key is not present in global config")]
     GlobalConfigKeyError,
+
+    #[msg("Stability deposit is locked, cannot withdraw before the lockup expires")]
+    StabilityDepositLocked,
+
+    #[msg("Invalid lockup tier")]
+    InvalidLockupTier,
+
+    #[msg("Cannot shorten an existing lockup")]
+    LockupCannotBeShortened,
+
+    #[msg("Cannot lock an empty stability deposit")]
+    CannotLockEmptyStabilityDeposit,
+
+    #[msg("Invalid HBB emission schedule")]
+    InvalidHbbEmissionSchedule,
+
+    #[msg("Cannot settle withdrawals until liquidation gains are cleared")]
+    CannotSettleUntilLiquidationGainsCleared,
+
+    #[msg("No pending stability withdrawal")]
+    NoPendingStabilityWithdrawal,
+
+    #[msg("Not enough stability pool receipts to transfer")]
+    NoStabilityReceiptsToTransfer,
+
+    #[msg("Invalid stability pool collateral coverage")]
+    InvalidCollateralCoverage,
+
+    #[msg("Redemption order has reached the maximum number of candidate pages")]
+    TooManyRedemptionCandidatePages,
+
+    #[msg("Redemption candidate pages are missing or out of order")]
+    InvalidRedemptionCandidatesPage,
+
+    #[msg("Instant redemption troves must be redeemable and sorted by collateral ratio")]
+    InvalidInstantRedemptionTroves,
+
+    #[msg("Troves are not the lowest interest rate troves in the sorted troves")]
+    TrovesNotLowestInIndex,
+
+    #[msg("Redemption order has not expired yet")]
+    RedemptionOrderNotExpired,
+
+    #[msg("Cannot cancel redemption order while in clearing mode")]
+    CannotCancelRedemptionOrderWhileClearing,
+
+    #[msg("Redemption order has no unfilled amount to refund")]
+    NoUnfilledRedemptionAmount,
+
+    #[msg("Redemption fee is above the maximum accepted by the redeemer")]
+    RedemptionFeeAboveMaximum,
+
+    #[msg("Redeemed collateral value is below the minimum accepted by the redeemer")]
+    RedemptionCollateralBelowMinimum,
+
+    #[msg("Trove has not been fully redeemed")]
+    TroveNotRedeemed,
+
+    #[msg("Interest rate is above the maximum")]
+    InterestRateAboveMaximum,
+
+    #[msg("HBB is already locked, increase the amount or extend the lock instead")]
+    HbbAlreadyLocked,
+
+    #[msg("No active HBB lock")]
+    NoActiveHbbLock,
+
+    #[msg(
+        "HBB lock expiry must be later than the current one and within the maximum lock duration"
+    )]
+    InvalidHbbLockExpiry,
+
+    #[msg("HBB lock has not expired yet")]
+    HbbLockNotExpired,
+
+    #[msg("Unstake cooldown has not elapsed yet")]
+    UnstakeCooldownNotElapsed,
+
+    #[msg("Unstaking is subject to a cooldown, request the unstake instead")]
+    UnstakeCooldownRequired,
+
+    #[msg("No staking rewards to compound")]
+    NothingToCompound,
+
+    #[msg("Treasury fee rate is above the maximum")]
+    TreasuryFeeRateAboveMaximum,
+
+    #[msg("Treasury disbursement amount cannot be zero")]
+    InvalidTreasuryDisbursement,
+
+    #[msg("A treasury disbursement is already scheduled")]
+    TreasuryDisbursementAlreadyScheduled,
+
+    #[msg("No treasury disbursement scheduled")]
+    NoTreasuryDisbursementScheduled,
+
+    #[msg("Treasury disbursement is still timelocked")]
+    TreasuryDisbursementTimelocked,
+
+    #[msg("Staking rewards accounting does not match the borrowing fees vault balance")]
+    StakingRewardsAccountingMismatch,
+
+    #[msg("Staking position is already owned by this account")]
+    InvalidStakingPositionOwner,
+
+    #[msg("Cannot close a staking account with stake or unclaimed rewards")]
+    StakingAccountNotEmpty,
+
+    #[msg("Cannot close a stability provider with a deposit or pending gains")]
+    StabilityProviderNotEmpty,
+
+    #[msg("Cannot close a trove with debt or collateral")]
+    TroveAccountNotEmpty,
+
+    #[msg("Trove adjustment must change the position, with at most one delta per collateral")]
+    InvalidTroveAdjustment,
+
+    #[msg("Lockup has not expired, there is nothing to release")]
+    LockupNotExpired,
+
+    #[msg(
+        "Stability receipts started a new receipt epoch, the receipt mint must be rotated first"
+    )]
+    StabilityReceiptMintStale,
+
+    #[msg("Stability receipt mint already belongs to the current receipt epoch")]
+    StabilityReceiptMintNotStale,
+
+    #[msg("New stability receipt mint must be empty, with the receipt authority as mint and freeze authority")]
+    InvalidStabilityReceiptMint,
+
+    #[msg("Market already has the maximum number of stability pools")]
+    TooManyStabilityPools,
+
+    #[msg("Every stability pool of the market must take part in the liquidation")]
+    StabilityPoolsMismatch,
+
+    #[msg("Compounding max HBB price cannot be zero")]
+    InvalidCompoundMaxPrice,
+
+    #[msg("Compounding bought HBB above the max price")]
+    CompoundPriceAboveMaximum,
+
+    #[msg("Staking position is already tokenized")]
+    StakingPositionAlreadyTokenized,
+
+    #[msg("Staking position is not tokenized")]
+    StakingPositionNotTokenized,
+
+    #[msg(
+        "Staking position mint must be empty, without decimals and minted by the staking pool only"
+    )]
+    InvalidStakingPositionMint,
+
+    #[msg("Sorted troves are full, no new trove can be added")]
+    SortedTrovesFull,
+
+    #[msg("Invalid collateral token")]
+    InvalidCollateralToken,
 }
 
 impl From<DecimalError> for BorrowError {
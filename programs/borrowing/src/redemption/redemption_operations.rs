@@ -1,25 +1,34 @@
 use crate::{
-    borrowing_market::borrowing_rate::{self, FeeEvent},
+    borrowing_market::{
+        borrowing_operations::utils::accrue_interest,
+        borrowing_rate::{self, FeeEvent},
+        sorted_troves,
+    },
+    staking_pool::staking_pool_operations,
     state::{
         redemptions_queue::{RedemptionCandidateStatus, RedemptionOrderStatus},
-        BorrowingMarketState, CandidateRedemptionUser, RedemptionOrder, RedemptionsQueue,
+        BorrowingMarketState, CandidateRedemptionUser, CollateralToken, RedemptionOrder,
+        RedemptionsQueue, SortedTroves, StakingPoolState, UserStatus,
     },
-    utils::consts::NORMAL_MCR,
-    utils::{coretypes::CheckedAssign, finance::CollateralInfo},
+    utils::consts::{MAX_REDEMPTION_CANDIDATE_PAGES, NORMAL_MCR},
+    utils::finance::CollateralInfo,
 };
 
 use crate::redemption::types::RedemptionCollateralSplit;
-use anchor_lang::prelude::msg;
+use anchor_lang::prelude::{msg, Pubkey};
 #[cfg(not(test))]
 use anchor_lang::solana_program::log::sol_log_compute_units;
 
 // Adding an order
 // When a user asks to redeem x amount of USDH, we take the following actions:
-// 1. We place an order on the RedemptionsQueue.
+// 1. We open an order account, keyed by the RedemptionsQueue index,
+//    together with its first page of candidates.
 // 2. The Redemptions queue invites bots (fillers) to submit users with lowest CR
 // 3. We give 5 seconds for fillers to submitted users
 // 4. After that we start clearing them off the queue
-// 5. Once clearing is over, we pop the redemption order off the queue.
+// 5. Once clearing is over, we close the redemption order.
+// Orders are independent from each other, the queue only keeps track of
+// the next index and the amount still outstanding across all orders.
 
 // Filling an order
 // Once the RedemptionOrder is added, its status is set to Filling and
@@ -43,42 +52,33 @@ use crate::{
     BorrowError::*, TokenPrices, UserMetadata,
 };
 
-use crate::redemption::types::AddRedemptionOrderEffects;
-use std::cell::RefMut;
+use crate::redemption::types::{
+    AddRedemptionOrderEffects, CancelRedemptionOrderEffects, InstantRedemptionEffects,
+    RedemptionSimulation, SimulatedTroveRedemption,
+};
 
 use super::types::RedemptionFillingResults;
 
+#[allow(clippy::too_many_arguments)]
 pub fn add_redemption_order(
     redeemer: &mut UserMetadata,
-    queue: &mut RefMut<RedemptionsQueue>,
+    queue: &mut RedemptionsQueue,
+    order: &mut RedemptionOrder,
     market: &mut BorrowingMarketState,
     prices: &TokenPrices,
     now_timestamp: u64,
     redemption_amount: u64,
+    max_fee_bps: u16,
+    min_collateral_value_usdh: Option<u64>,
+    preferred_collateral: Option<CollateralToken>,
+    stakers_fee_in_usdh: bool,
 ) -> Result<AddRedemptionOrderEffects, crate::BorrowError> {
     if redemption_amount < MIN_REDEMPTIONS_AMOUNT_USDH {
         return Err(RedemptionsAmountTooSmall);
     }
 
-    let outstanding_redemptions = queue::calculate_outstanding_redemption_amount(queue);
-    let remaining_supply = market.stablecoin_borrowed - outstanding_redemptions;
-    if redemption_amount > remaining_supply {
-        return Err(CannotRedeemMoreThanMinted);
-    }
-
-    if now_timestamp < market.bootstrap_period_timestamp {
-        return Err(CannotRedeemDuringBootstrapPeriod);
-    }
-
-    let tcr = CollateralInfo::calc_coll_ratio(
-        market.stablecoin_borrowed,
-        &market.deposited_collateral,
-        prices,
-    );
-
-    if tcr.to_percent()? < NORMAL_MCR as u128 {
-        return Err(CannotRedeemWhenUndercollateralized);
-    }
+    let remaining_supply =
+        queue::assert_can_redeem(market, queue, prices, now_timestamp, redemption_amount)?;
 
     borrowing_rate::refresh_base_rate(
         market,
@@ -89,25 +89,68 @@ pub fn add_redemption_order(
         now_timestamp,
     )?;
 
-    let order = queue::add_redemption_order(
+    // What the redeemer gets back depends on the prices at clear
+    // time, so the minimum is only stored and checked then
+    calcs::assert_redemption_fee(market.base_rate_bps, max_fee_bps)?;
+
+    queue::add_redemption_order(
         redemption_amount,
         queue,
+        order,
         redeemer,
         prices,
         now_timestamp,
         market.base_rate_bps,
     )?;
+    order.set_preferred_collateral(preferred_collateral);
+    order.stakers_fee_in_usdh = stakers_fee_in_usdh as u8;
+    order.min_collateral_value_usdh = min_collateral_value_usdh.unwrap_or(0);
+
+    // The stakers share is held with the order, on top of the
+    // redeemed amount, and only charged for what gets cleared
+    order.escrowed_fee_usdh = if stakers_fee_in_usdh {
+        calcs::calc_stakers_fee_usdh(redemption_amount, market.base_rate_bps)
+    } else {
+        0
+    };
 
     Ok(AddRedemptionOrderEffects {
         redemption_order_id: order.id,
         transfer_stablecoin_amount: order.requested_amount,
+        escrowed_fee_usdh: order.escrowed_fee_usdh,
     })
 }
 
+pub fn add_redemption_candidates_page(
+    order: &mut RedemptionOrder,
+) -> Result<u64, crate::BorrowError> {
+    // Pages can only be added while candidates are still being submitted
+    let status: RedemptionOrderStatus = order.status.into();
+    match status {
+        RedemptionOrderStatus::Open | RedemptionOrderStatus::Filling => {}
+        RedemptionOrderStatus::Claiming => {
+            return Err(CannotFillRedemptionOrderWhileInClearingMode);
+        }
+        RedemptionOrderStatus::Inactive => {
+            return Err(InvalidRedemptionOrder);
+        }
+    }
+
+    if order.candidate_pages >= MAX_REDEMPTION_CANDIDATE_PAGES {
+        return Err(TooManyRedemptionCandidatePages);
+    }
+
+    let page = order.candidate_pages;
+    order.candidate_pages += 1;
+
+    Ok(page)
+}
+
 pub fn fill_redemption_order(
     order_id: u64,
     market: &mut BorrowingMarketState,
-    queue: &mut RefMut<RedemptionsQueue>,
+    order: &mut RedemptionOrder,
+    candidate_users: &mut [CandidateRedemptionUser],
     user_metadatas: &mut [&mut UserMetadata],
     filler_metadata: &UserMetadata,
     now_timestamp: u64,
@@ -116,42 +159,124 @@ pub fn fill_redemption_order(
         return Ok(());
     }
 
-    let order = queue::next_fill_order(queue, order_id, now_timestamp)?;
+    queue::next_fill_order(order, order_id, now_timestamp)?;
 
     log_compute_units!("Before process users.");
-    let candidates =
-        sort::extract_transform_sort_candidates(market, order, user_metadatas, filler_metadata)?;
+    let candidates = sort::extract_transform_sort_candidates(
+        market,
+        order,
+        user_metadatas,
+        filler_metadata,
+        now_timestamp,
+    )?;
 
     // Merge new with existing, prioritizing existing if smaller or equal
     log_compute_units!("Before merge users.");
-    sort::merge(candidates, &mut order.candidate_users);
+    sort::merge(candidates, candidate_users);
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn clear_redemption_order<'a, 'b>(
     order_id: u64,
     redeemer: &'a mut UserMetadata,
     clearer: &'a mut UserMetadata,
     market: &'a mut BorrowingMarketState,
-    redemptions_queue: &'a mut RefMut<RedemptionsQueue>,
+    staking_pool_state: &'a mut StakingPoolState,
+    redemptions_queue: &'a mut RedemptionsQueue,
+    order: &'a mut RedemptionOrder,
+    candidate_users: &'a mut [CandidateRedemptionUser],
     fillers_and_borrowers: &'a mut [&'b mut UserMetadata],
+    closed_troves: &[Pubkey],
+    sorted_troves: &mut SortedTroves,
+    prices: &TokenPrices,
     now_timestamp: u64,
 ) -> Result<ClearRedemptionOrderEffects, crate::BorrowError> {
     sort::assert_unique(fillers_and_borrowers)?;
 
-    let order = queue::next_clear_order(
-        redemptions_queue,
-        order_id,
-        &redeemer.metadata_pk,
-        now_timestamp,
+    queue::next_clear_order(order, order_id, &redeemer.metadata_pk, now_timestamp)?;
+
+    // Interest is charged up to now, on what is redeemed too
+    for user in fillers_and_borrowers.iter_mut() {
+        if user.status == (UserStatus::Active as u8) {
+            accrue_interest(market, user, now_timestamp)?;
+        }
+    }
+
+    // The troves are redeemed at the prices of the time the order was
+    // added, but the redeemer gets the collateral at today's prices.
+    // If that falls short of the minimum they asked for, pro rata of
+    // what this clear redeems, nothing is redeemed and the order is
+    // refunded instead, so the clear is first done on copies.
+    let market_snapshot = market.clone();
+    let order_snapshot = *order;
+    let candidates_snapshot = candidate_users.to_vec();
+    let users_snapshot: Vec<UserMetadata> = fillers_and_borrowers
+        .iter()
+        .map(|user| (**user).clone())
+        .collect();
+
+    let results = queue::collect_collateral_and_pay_debt(
+        market,
+        order,
+        candidate_users,
+        fillers_and_borrowers,
+        closed_troves,
     )?;
 
+    let min_collateral_value_usdh = calcs::calc_min_collateral_value_usdh(
+        order_snapshot.min_collateral_value_usdh,
+        results.debt_redeemed,
+        order_snapshot.requested_amount,
+    );
+    let redeemer_collateral_value_usdh =
+        CollateralInfo::calc_market_value_usdh(prices, &results.collateral_redeemed.redeemer);
+    if calcs::assert_min_collateral_value(redeemer_collateral_value_usdh, min_collateral_value_usdh)
+        .is_err()
+    {
+        msg!(
+            "Redeemer would get {} USDH worth of collateral, below {}. Refunding order.",
+            redeemer_collateral_value_usdh,
+            min_collateral_value_usdh.unwrap_or(0)
+        );
+        *market = market_snapshot;
+        *order = order_snapshot;
+        candidate_users.copy_from_slice(&candidates_snapshot);
+        for (user, snapshot) in fillers_and_borrowers.iter_mut().zip(users_snapshot) {
+            **user = snapshot;
+        }
+
+        let remaining_amount = order.remaining_amount;
+        redemptions_queue.outstanding_amount = redemptions_queue
+            .outstanding_amount
+            .checked_sub(remaining_amount)
+            .ok_or(MathOverflow)?;
+        let refunded_fee_usdh = fees::release_escrowed_fee(order, remaining_amount);
+        queue::close_redemption_order(order, candidate_users);
+
+        return Ok(ClearRedemptionOrderEffects {
+            redeemed_stablecoin: 0,
+            redeemed_collateral: RedemptionCollateralSplit::default(),
+            stakers_fee_usdh: 0,
+            treasury_fee_usdh: 0,
+            refunded_stablecoin: remaining_amount + refunded_fee_usdh,
+        });
+    }
+
+    queue::settle_redeemed_debt(market, &results);
+
+    // Redeemed troves move in the index, fully redeemed
+    // ones are marked Redeemed and leave it
+    for user in fillers_and_borrowers.iter() {
+        sorted_troves::reposition_trove(sorted_troves, user, prices)?;
+    }
+
     let RedemptionFillingResults {
         collateral_redeemed,
-        collateral_made_inactive,
         debt_redeemed,
-    } = queue::collect_collateral_and_pay_debt(market, order, fillers_and_borrowers)?;
+        ..
+    } = results;
 
     // Reward redeemer and cleared
     redeemer
@@ -162,30 +287,295 @@ pub fn clear_redemption_order<'a, 'b>(
         .inactive_collateral
         .add_assign(&collateral_redeemed.clearer);
 
-    // Turn the global collateral to inactive
-    let collateral_made_inactive = collateral_made_inactive.add(&collateral_redeemed.total);
-    market
-        .deposited_collateral
-        .sub_assign(&collateral_made_inactive);
-
-    market
-        .inactive_collateral
-        .add_assign(&collateral_made_inactive);
+    staking_pool_operations::distribute_collateral_fees(
+        staking_pool_state,
+        &collateral_redeemed.stakers,
+        now_timestamp,
+    );
 
-    // Remove debt
-    market
-        .stablecoin_borrowed
-        .checked_sub_assign(debt_redeemed)
-        .unwrap();
+    redemptions_queue.outstanding_amount = redemptions_queue
+        .outstanding_amount
+        .checked_sub(debt_redeemed)
+        .ok_or(MathOverflow)?;
+
+    // The escrowed fee is charged for what was redeemed, whatever
+    // is left of it goes back once the order is complete
+    let (stakers_fee_usdh, treasury_fee_usdh) =
+        fees::charge_escrowed_fee(order, staking_pool_state, debt_redeemed, now_timestamp);
+    let refunded_stablecoin = if order.remaining_amount == 0 {
+        fees::release_escrowed_fee(order, 0)
+    } else {
+        0
+    };
 
-    queue::flush_order(order);
+    queue::flush_order(order, candidate_users);
 
     Ok(ClearRedemptionOrderEffects {
         redeemed_stablecoin: debt_redeemed,
         redeemed_collateral: collateral_redeemed,
+        stakers_fee_usdh,
+        treasury_fee_usdh,
+        refunded_stablecoin,
+    })
+}
+
+pub fn cancel_redemption_order(
+    order_id: u64,
+    redeemer: &UserMetadata,
+    redemptions_queue: &mut RedemptionsQueue,
+    order: &mut RedemptionOrder,
+    candidate_users: &mut [CandidateRedemptionUser],
+    expiry_seconds: u64,
+    now_timestamp: u64,
+) -> Result<CancelRedemptionOrderEffects, crate::BorrowError> {
+    // Nobody is forced to fill or clear an order, past its expiry the
+    // redeemer takes back whatever is not cleared yet, whether the
+    // order is open, filling or clearing. Candidates may no longer
+    // match their troves, so they don't hold any of it back.
+    if order.id != order_id || order.status == RedemptionOrderStatus::Inactive as u8 {
+        return Err(InvalidRedemptionOrder);
+    }
+    if order.redeemer_user_metadata != redeemer.metadata_pk {
+        return Err(InvalidRedeemer);
+    }
+    if order.created_at.saturating_add(expiry_seconds) > now_timestamp {
+        return Err(RedemptionOrderNotExpired);
+    }
+
+    let refunded_stablecoin = order.remaining_amount;
+    if refunded_stablecoin == 0 {
+        return Err(NoUnfilledRedemptionAmount);
+    }
+
+    order.remaining_amount = 0;
+    redemptions_queue.outstanding_amount = redemptions_queue
+        .outstanding_amount
+        .checked_sub(refunded_stablecoin)
+        .ok_or(MathOverflow)?;
+    let refunded_fee_usdh = fees::release_escrowed_fee(order, refunded_stablecoin);
+
+    msg!("Redemption order cancelled, removing order.");
+    queue::close_redemption_order(order, candidate_users);
+
+    Ok(CancelRedemptionOrderEffects {
+        refunded_stablecoin: refunded_stablecoin + refunded_fee_usdh,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn instant_redeem(
+    redeemer: &mut UserMetadata,
+    market: &mut BorrowingMarketState,
+    staking_pool_state: &mut StakingPoolState,
+    redemptions_queue: &RedemptionsQueue,
+    sorted_troves: &mut SortedTroves,
+    borrowers: &mut [&mut UserMetadata],
+    prices: &TokenPrices,
+    now_timestamp: u64,
+    redemption_amount: u64,
+    max_fee_bps: u16,
+    min_collateral_value_usdh: Option<u64>,
+    preferred_collateral: Option<CollateralToken>,
+    stakers_fee_in_usdh: bool,
+) -> Result<InstantRedemptionEffects, crate::BorrowError> {
+    // Fills and clears an order in one go, the redeemer submits
+    // the troves first in line for redemptions themselves and
+    // takes the filler and clearer share for doing so
+    if redemption_amount < MIN_REDEMPTIONS_AMOUNT_USDH {
+        return Err(RedemptionsAmountTooSmall);
+    }
+
+    let remaining_supply = queue::assert_can_redeem(
+        market,
+        redemptions_queue,
+        prices,
+        now_timestamp,
+        redemption_amount,
+    )?;
+
+    borrowing_rate::refresh_base_rate(
+        market,
+        FeeEvent::Redemption {
+            redeeming: redemption_amount,
+            supply: remaining_supply,
+        },
+        now_timestamp,
+    )?;
+
+    let mut order = RedemptionOrder {
+        status: RedemptionOrderStatus::Claiming.into(),
+        base_rate: market.base_rate_bps,
+        last_reset: now_timestamp,
+        redeemer_user_metadata: redeemer.metadata_pk,
+        redeemer: redeemer.owner,
+        requested_amount: redemption_amount,
+        remaining_amount: redemption_amount,
+        redemption_prices: *prices,
+        stakers_fee_in_usdh: stakers_fee_in_usdh as u8,
+        ..Default::default()
+    };
+    order.set_preferred_collateral(preferred_collateral);
+
+    let mut candidate_users =
+        sort::extract_sorted_candidates(market, &order, borrowers, redeemer, now_timestamp)?;
+
+    // Checked once the troves are brought up to date, at current prices
+    let submitted = borrowers
+        .iter()
+        .map(|borrower| sorted_troves::current_trove(borrower, prices))
+        .collect::<Result<Vec<_>, _>>()?;
+    sorted_troves::assert_lowest_troves(sorted_troves, &submitted)?;
+
+    let mut fillers_and_borrowers: Vec<&mut UserMetadata> = Vec::with_capacity(borrowers.len() + 1);
+    fillers_and_borrowers.push(redeemer);
+    fillers_and_borrowers.extend(borrowers.iter_mut().map(|borrower| &mut **borrower));
+    sort::assert_unique(&mut fillers_and_borrowers)?;
+
+    let results = queue::collect_collateral_and_pay_debt(
+        market,
+        &mut order,
+        &mut candidate_users,
+        &mut fillers_and_borrowers,
+        &[],
+    )?;
+    queue::settle_redeemed_debt(market, &results);
+
+    let RedemptionFillingResults {
+        collateral_redeemed,
+        debt_redeemed,
+        ..
+    } = results;
+
+    if debt_redeemed == 0 {
+        return Err(InvalidInstantRedemptionTroves);
+    }
+
+    // Fewer troves may have been redeemed than requested,
+    // the minimum applies to what the redeemer actually gets
+    let received = collateral_redeemed
+        .redeemer
+        .add(&collateral_redeemed.filler)
+        .add(&collateral_redeemed.clearer);
+    calcs::assert_redemption_slippage(
+        order.base_rate,
+        max_fee_bps,
+        CollateralInfo::calc_market_value_usdh(prices, &received),
+        min_collateral_value_usdh,
+    )?;
+
+    for borrower in fillers_and_borrowers.iter().skip(1) {
+        sorted_troves::reposition_trove(sorted_troves, borrower, prices)?;
+    }
+
+    let (stakers_fee_usdh, treasury_fee_usdh) = if stakers_fee_in_usdh {
+        fees::distribute_usdh_fee(
+            staking_pool_state,
+            calcs::calc_stakers_fee_usdh(debt_redeemed, order.base_rate),
+            now_timestamp,
+        )
+    } else {
+        (0, 0)
+    };
+    staking_pool_operations::distribute_collateral_fees(
+        staking_pool_state,
+        &collateral_redeemed.stakers,
+        now_timestamp,
+    );
+
+    // The redeemer is paid out in the same instruction, the filler
+    // share credited while filling is taken back out along with
+    // the market's inactive collateral
+    fillers_and_borrowers[0]
+        .inactive_collateral
+        .sub_assign(&collateral_redeemed.filler);
+    market.inactive_collateral.sub_assign(&received);
+
+    Ok(InstantRedemptionEffects {
+        redeemed_stablecoin: debt_redeemed,
+        redeemed_collateral: collateral_redeemed,
+        collateral_to_transfer_to_redeemer: received,
+        stakers_fee_usdh,
+        treasury_fee_usdh,
     })
 }
 
+pub fn simulate_redemption(
+    market: &BorrowingMarketState,
+    order: &RedemptionOrder,
+    troves: &[UserMetadata],
+    now_timestamp: u64,
+) -> Result<RedemptionSimulation, crate::BorrowError> {
+    // Fills and clears the order against copies of the market and
+    // the troves, so that bots can tell ahead of time what filling
+    // or clearing it pays. Nothing is written back.
+    let mut market = market.clone();
+    let mut order = *order;
+    let mut troves: Vec<UserMetadata> = troves.to_vec();
+
+    // Stands in for whoever fills the order
+    let mut filler = UserMetadata {
+        user_id: u64::MAX,
+        ..Default::default()
+    };
+
+    let mut candidate_users = {
+        let mut candidates: Vec<&mut UserMetadata> = troves.iter_mut().collect();
+        sort::extract_transform_sort_candidates(
+            &mut market,
+            &order,
+            &mut candidates,
+            &filler,
+            now_timestamp,
+        )?
+    };
+
+    let mut fillers_and_borrowers: Vec<&mut UserMetadata> = Vec::with_capacity(troves.len() + 1);
+    fillers_and_borrowers.push(&mut filler);
+    fillers_and_borrowers.extend(troves.iter_mut());
+
+    let mut simulation = RedemptionSimulation {
+        redemption_fee_bps: borrowing_rate::calc_redemption_fee(order.base_rate),
+        redeemed_stablecoin: 0,
+        redeemed_collateral: RedemptionCollateralSplit::default(),
+        troves: Vec::with_capacity(candidate_users.len()),
+    };
+
+    // One candidate at a time to tell each trove's share apart
+    for i in 0..candidate_users.len() {
+        if order.remaining_amount == 0 {
+            break;
+        }
+
+        let RedemptionFillingResults {
+            collateral_redeemed,
+            debt_redeemed,
+            ..
+        } = queue::collect_collateral_and_pay_debt(
+            &mut market,
+            &mut order,
+            &mut candidate_users[i..i + 1],
+            &mut fillers_and_borrowers,
+            &[],
+        )?;
+
+        if debt_redeemed == 0 {
+            continue;
+        }
+
+        simulation.redeemed_stablecoin += debt_redeemed;
+        simulation
+            .redeemed_collateral
+            .checked_add_assign(&collateral_redeemed);
+        simulation.troves.push(SimulatedTroveRedemption {
+            user_metadata: candidate_users[i].user_metadata,
+            debt_redeemed,
+            collateral_redeemed: collateral_redeemed.total,
+        });
+    }
+
+    Ok(simulation)
+}
+
 mod sort {
     use crate::{
         log_compute_units,
@@ -202,6 +592,7 @@ mod sort {
         redemption_order: &RedemptionOrder,
         candidates: &mut [&mut UserMetadata],
         filler_metadata: &UserMetadata,
+        now_timestamp: u64,
     ) -> Result<Vec<CandidateRedemptionUser>, BorrowError> {
         // Clean up, sort, dedup, get MV for submitted users
         // We don't trust any of the data coming from off-chain
@@ -216,25 +607,61 @@ mod sort {
                 market,
                 user_metadata,
                 &redemption_order.redemption_prices,
+                redemption_order.preferred_collateral(),
                 filler_metadata.metadata_pk,
+                now_timestamp,
             ) {
                 cleaned_candidates.push(res);
             }
         }
 
         log_compute_units!("Before sort candidates.");
-        cleaned_candidates.sort_by(|left, right| {
-            left.collateral_ratio
-                .partial_cmp(&right.collateral_ratio)
-                .unwrap()
-        });
+        cleaned_candidates.sort_by_key(calcs::redemption_priority);
 
         Ok(cleaned_candidates)
     }
 
+    pub fn extract_sorted_candidates(
+        market: &mut BorrowingMarketState,
+        redemption_order: &RedemptionOrder,
+        candidates: &mut [&mut UserMetadata],
+        filler_metadata: &UserMetadata,
+        now_timestamp: u64,
+    ) -> Result<Vec<CandidateRedemptionUser>, BorrowError> {
+        // Unlike fillers, who only submit candidates to be sorted,
+        // the troves redeemed against right away must all be valid
+        // and come lowest interest rate, then collateral ratio, first
+        let mut sorted_candidates = Vec::with_capacity(candidates.len());
+
+        for user_metadata in candidates.iter_mut() {
+            let candidate = calcs::calculate_candidate(
+                market,
+                user_metadata,
+                &redemption_order.redemption_prices,
+                redemption_order.preferred_collateral(),
+                filler_metadata.metadata_pk,
+                now_timestamp,
+            )?
+            .ok_or(BorrowError::InvalidInstantRedemptionTroves)?;
+
+            if let Some(previous) = sorted_candidates.last() {
+                let previous: &CandidateRedemptionUser = previous;
+                if (previous.interest_rate_bps, previous.collateral_ratio)
+                    > (candidate.interest_rate_bps, candidate.collateral_ratio)
+                {
+                    return Err(BorrowError::InvalidInstantRedemptionTroves);
+                }
+            }
+
+            sorted_candidates.push(candidate);
+        }
+
+        Ok(sorted_candidates)
+    }
+
     pub fn merge(
         new_candidates: Vec<CandidateRedemptionUser>,
-        current_candidates: &mut [CandidateRedemptionUser; 32],
+        current_candidates: &mut [CandidateRedemptionUser],
     ) {
         let (mut i, mut j) = (0, 0);
         while i < new_candidates.len() && j < current_candidates.len() {
@@ -256,7 +683,9 @@ mod sort {
                 j += 1;
                 continue;
             }
-            if existing_position.collateral_ratio <= candidate_position.collateral_ratio {
+            if calcs::redemption_priority(&existing_position)
+                <= calcs::redemption_priority(&candidate_position)
+            {
                 j += 1;
                 continue;
             } else {
@@ -288,6 +717,54 @@ mod sort {
     }
 }
 
+mod fees {
+    use crate::{
+        redemption::redemption_operations::calcs, staking_pool::staking_pool_operations,
+        RedemptionOrder, StakingPoolState,
+    };
+
+    pub fn distribute_usdh_fee(
+        staking_pool_state: &mut StakingPoolState,
+        fee: u64,
+        now_timestamp: u64,
+    ) -> (u64, u64) {
+        // Split with the treasury like the borrowing fees
+        let (staking_fee, treasury_fee) =
+            staking_pool_operations::split_fees(fee, staking_pool_state.treasury_fee_rate);
+        staking_pool_operations::distribute_fees(staking_pool_state, staking_fee, now_timestamp);
+        (staking_fee, treasury_fee)
+    }
+
+    pub fn charge_escrowed_fee(
+        order: &mut RedemptionOrder,
+        staking_pool_state: &mut StakingPoolState,
+        debt_redeemed: u64,
+        now_timestamp: u64,
+    ) -> (u64, u64) {
+        let fee = u64::min(
+            calcs::calc_stakers_fee_usdh(debt_redeemed, order.base_rate),
+            order.escrowed_fee_usdh,
+        );
+        order.escrowed_fee_usdh -= fee;
+        distribute_usdh_fee(staking_pool_state, fee, now_timestamp)
+    }
+
+    pub fn release_escrowed_fee(order: &mut RedemptionOrder, refunded_amount: u64) -> u64 {
+        // The fee of the refunded amount, or all that is left
+        // of it once nothing remains to be redeemed
+        let fee = if order.remaining_amount == 0 || refunded_amount >= order.remaining_amount {
+            order.escrowed_fee_usdh
+        } else {
+            u64::min(
+                calcs::calc_stakers_fee_usdh(refunded_amount, order.base_rate),
+                order.escrowed_fee_usdh,
+            )
+        };
+        order.escrowed_fee_usdh -= fee;
+        fee
+    }
+}
+
 pub mod calcs {
 
     use super::RedemptionCollateralSplit;
@@ -299,8 +776,8 @@ pub mod calcs {
     use anchor_lang::prelude::{msg, Pubkey};
 
     use crate::{
-        borrowing_market::borrowing_operations::apply_pending_rewards,
-        state::{CandidateRedemptionUser, UserStatus},
+        borrowing_market::borrowing_operations::{apply_pending_rewards, utils::accrue_interest},
+        state::{CandidateRedemptionUser, CollateralToken, UserStatus},
         utils::finance::CollateralInfo,
         BorrowingMarketState, RedemptionOrder, TokenPrices, UserMetadata,
     };
@@ -308,6 +785,7 @@ pub mod calcs {
     pub fn split_redemption_collateral(
         total: &CollateralAmounts,
         base_rate_bps: u16,
+        stakers_fee_in_usdh: bool,
     ) -> RedemptionCollateralSplit {
         let one = 10_000; // bps
         let redemption_fee = borrowing_rate::calc_redemption_fee(base_rate_bps);
@@ -331,8 +809,12 @@ pub mod calcs {
 
         // rest goes to stakers
         // 40 bps normally, but could be higher
-        let stakers_bps = redemption_fee - REDEMPTION_FILLER - REDEMPTION_CLEARER;
-        let stakers = total.mul_bps(stakers_bps);
+        // unless they were already paid in USDH
+        let stakers = if stakers_fee_in_usdh {
+            CollateralAmounts::default()
+        } else {
+            total.mul_bps(calc_stakers_fee_bps(base_rate_bps))
+        };
 
         // println!("Redeeming from {:?}", total);
         // println!("Redeeming redeemer {:?}", redeemer);
@@ -356,14 +838,76 @@ pub mod calcs {
             total: *total,
         }
     }
+
+    pub fn assert_redemption_slippage(
+        base_rate_bps: u16,
+        max_fee_bps: u16,
+        redeemer_collateral_value_usdh: u64,
+        min_collateral_value_usdh: Option<u64>,
+    ) -> Result<(), BorrowError> {
+        // The base rate can go up between signing and execution when
+        // others redeem at the same time, the redeemer caps the fee
+        // and what they are willing to get back for their stablecoin
+        assert_redemption_fee(base_rate_bps, max_fee_bps)?;
+        assert_min_collateral_value(redeemer_collateral_value_usdh, min_collateral_value_usdh)
+    }
+
+    pub fn assert_redemption_fee(base_rate_bps: u16, max_fee_bps: u16) -> Result<(), BorrowError> {
+        if borrowing_rate::calc_redemption_fee(base_rate_bps) > max_fee_bps {
+            return Err(BorrowError::RedemptionFeeAboveMaximum);
+        }
+        Ok(())
+    }
+
+    pub fn assert_min_collateral_value(
+        redeemer_collateral_value_usdh: u64,
+        min_collateral_value_usdh: Option<u64>,
+    ) -> Result<(), BorrowError> {
+        match min_collateral_value_usdh {
+            Some(min) if redeemer_collateral_value_usdh < min => {
+                Err(BorrowError::RedemptionCollateralBelowMinimum)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn calc_min_collateral_value_usdh(
+        order_min_collateral_value_usdh: u64,
+        debt_redeemed: u64,
+        requested_amount: u64,
+    ) -> Option<u64> {
+        // A queued order can be cleared in several goes,
+        // each of them has to meet its share of the minimum
+        if order_min_collateral_value_usdh == 0 || debt_redeemed == 0 || requested_amount == 0 {
+            return None;
+        }
+        Some(
+            (order_min_collateral_value_usdh as u128 * debt_redeemed as u128
+                / requested_amount as u128) as u64,
+        )
+    }
+
+    pub fn calc_stakers_fee_bps(base_rate_bps: u16) -> u16 {
+        borrowing_rate::calc_redemption_fee(base_rate_bps) - REDEMPTION_FILLER - REDEMPTION_CLEARER
+    }
+
+    pub fn calc_stakers_fee_usdh(redemption_amount: u64, base_rate_bps: u16) -> u64 {
+        let one = 10_000; // bps
+        (redemption_amount as u128 * calc_stakers_fee_bps(base_rate_bps) as u128 / one as u128)
+            as u64
+    }
+
     pub fn calculate_candidate(
         market: &mut BorrowingMarketState,
         user_metadata: &mut UserMetadata,
         prices: &TokenPrices,
+        preferred_collateral: Option<CollateralToken>,
         filler_metadata: Pubkey,
+        now_timestamp: u64,
     ) -> Result<Option<CandidateRedemptionUser>, BorrowError> {
         if user_metadata.status == (UserStatus::Active as u8) {
             apply_pending_rewards(market, user_metadata)?;
+            accrue_interest(market, user_metadata, now_timestamp)?;
             update_user_stake_and_total_stakes(market, user_metadata);
             if user_metadata.borrowed_stablecoin == 0 {
                 return Ok(None);
@@ -383,15 +927,63 @@ pub mod calcs {
                 collateral_ratio: collateral_ratio.try_floor_u64().unwrap(),
                 filler_metadata,
                 user_metadata: user_metadata.metadata_pk,
+                holds_preferred_collateral: preferred_collateral.map_or(0, |token| {
+                    !user_metadata.deposited_collateral.is_zero_token(token) as u8
+                }),
+                interest_rate_bps: user_metadata.interest_rate_bps,
             }));
         }
 
         Ok(None)
     }
 
+    pub fn redemption_priority(candidate: &CandidateRedemptionUser) -> (u16, u64, u8) {
+        // Lowest interest rate first, then lowest collateral ratio,
+        // among troves with the same rate and ratio those holding
+        // the collateral the redeemer prefers.
+        // The candidate ratio is rounded down to a whole number, so
+        // troves share it across a band of 100 points, 110% to 199%,
+        // 200% to 299% and so on, and the preference applies within
+        // that band. It never moves a trove ahead of one in a lower
+        // band, the riskiest troves are still redeemed first.
+        (
+            candidate.interest_rate_bps,
+            candidate.collateral_ratio,
+            1 - candidate.holds_preferred_collateral,
+        )
+    }
+
+    pub fn calc_collateral_to_redeem(
+        deposited: &CollateralAmounts,
+        prices: &TokenPrices,
+        amount_to_redeem: u64,
+        collateral_value: u64,
+        preferred_collateral: Option<CollateralToken>,
+    ) -> CollateralAmounts {
+        // Draws from the preferred collateral first, whatever it
+        // can't cover is taken from the rest of the basket pro rata
+        let token = match preferred_collateral {
+            Some(token) => token,
+            None => return deposited.mul_fraction(amount_to_redeem, collateral_value),
+        };
+
+        let preferred = CollateralAmounts::of_token(deposited.token_amount(token), token);
+        let preferred_value = CollateralInfo::calc_market_value_usdh(prices, &preferred);
+        if preferred_value >= amount_to_redeem && preferred_value > 0 {
+            return preferred.mul_fraction(amount_to_redeem, preferred_value);
+        }
+
+        let rest = deposited.sub(&preferred);
+        preferred.add(&rest.mul_fraction(
+            amount_to_redeem - preferred_value,
+            collateral_value - preferred_value,
+        ))
+    }
+
     pub fn calc_redemption_amounts(
         fillers_and_borrowers: &mut [&mut UserMetadata],
         redemption_order: &RedemptionOrder,
+        candidate_users: &[CandidateRedemptionUser],
         user_to_redeem_ix: usize,
         candidate_user_ix: usize,
         remaining_amount: u64,
@@ -404,10 +996,12 @@ pub mod calcs {
         );
 
         if collateral_info.collateral_ratio.try_floor_u64().unwrap()
-            != redemption_order.candidate_users[candidate_user_ix].collateral_ratio
+            != candidate_users[candidate_user_ix].collateral_ratio
+            || fillers_and_borrowers[user_to_redeem_ix].interest_rate_bps
+                != candidate_users[candidate_user_ix].interest_rate_bps
         {
-            // The user has changed their CR since the 'fill' event,
-            // skip it
+            // The user has changed their CR or interest rate
+            // since the 'fill' event, skip it
             msg!(
                 "User {} has changed since fill, skipping.",
                 candidate_user_ix
@@ -418,9 +1012,13 @@ pub mod calcs {
 
         // ratios is how much of the collateral is the redemed amount worth
         let mv = collateral_info.collateral_value;
-        let collateral_to_redeem = fillers_and_borrowers[user_to_redeem_ix]
-            .deposited_collateral
-            .mul_fraction(amount_to_redeem, mv);
+        let collateral_to_redeem = calc_collateral_to_redeem(
+            &fillers_and_borrowers[user_to_redeem_ix].deposited_collateral,
+            &redemption_order.redemption_prices,
+            amount_to_redeem,
+            mv,
+            redemption_order.preferred_collateral(),
+        );
         // println!(
         //     "User has debt USDH={}, collateral USDH={}, CR={:?}, having collaterals {:?}, redeeming {:?}",
         //     fillers_and_borrowers[user_to_redeem_ix].borrowed_stablecoin,
@@ -439,125 +1037,91 @@ mod queue {
 
     use crate::{
         borrowing_market::borrowing_operations::redistribution,
-        fail, some_or_continue,
+        fail,
+        state::UserStatus,
         utils::{consts::REDEMPTIONS_SECONDS_TO_FILL_ORDER, coretypes::CheckedAssign},
         BorrowError, CollateralAmounts, TokenPrices, UserMetadata,
     };
 
     use super::*;
-    use std::cell::RefMut;
 
-    pub fn next_fill_order<'a>(
-        redemptions_queue: &'a mut RefMut<RedemptionsQueue>,
+    pub fn next_fill_order(
+        order: &mut RedemptionOrder,
         order_id: u64,
         now: u64,
-    ) -> Result<&'a mut RedemptionOrder, BorrowError> {
-        match first_outstanding(redemptions_queue) {
-            None => {
-                fail!(BorrowError::RedemptionsQueueIsEmpty);
+    ) -> Result<(), BorrowError> {
+        if order.id != order_id {
+            fail!(BorrowError::InvalidRedemptionOrder);
+        }
+        let status = RedemptionOrderStatus::from(order.status);
+        match status {
+            RedemptionOrderStatus::Open => {
+                order.status = RedemptionOrderStatus::Filling.into();
+                order.last_reset = now;
             }
-            Some((index, id)) => {
-                if id != order_id {
-                    // this is not really important tbh
-                    // we should always just fill the first next available
-                    // fail!(BorrowError::InvalidRedemptionOrder);
-                }
-                let order = &mut redemptions_queue.orders[index];
-                let status = RedemptionOrderStatus::from(order.status);
-                match status {
-                    RedemptionOrderStatus::Open => {
-                        order.status = RedemptionOrderStatus::Filling.into();
-                        order.last_reset = now;
-                    }
-                    RedemptionOrderStatus::Filling => {}
-                    RedemptionOrderStatus::Claiming => {
-                        fail!(BorrowError::CannotFillRedemptionOrderWhileInClearingMode);
-                    }
-                    RedemptionOrderStatus::Inactive => {
-                        fail!(BorrowError::InvalidRedemptionOrder);
-                    }
-                }
-                Ok(order)
+            RedemptionOrderStatus::Filling => {}
+            RedemptionOrderStatus::Claiming => {
+                fail!(BorrowError::CannotFillRedemptionOrderWhileInClearingMode);
+            }
+            RedemptionOrderStatus::Inactive => {
+                fail!(BorrowError::InvalidRedemptionOrder);
             }
         }
+        Ok(())
     }
 
-    pub fn next_clear_order<'a>(
-        redemptions_queue: &'a mut RefMut<RedemptionsQueue>,
+    pub fn next_clear_order(
+        order: &mut RedemptionOrder,
         order_id: u64,
         redeemer_metadata: &Pubkey,
         now: u64,
-    ) -> Result<&'a mut RedemptionOrder, BorrowError> {
-        match first_outstanding(redemptions_queue) {
-            None => {
-                fail!(BorrowError::RedemptionsQueueIsEmpty);
+    ) -> Result<(), BorrowError> {
+        if order.id != order_id {
+            fail!(BorrowError::InvalidRedemptionOrder);
+        }
+        if &order.redeemer_user_metadata != redeemer_metadata {
+            fail!(BorrowError::InvalidRedeemer);
+        }
+        let status: RedemptionOrderStatus = order.status.into();
+
+        match status {
+            RedemptionOrderStatus::Open => {
+                fail!(BorrowError::CannotClearRedemptionOrderWhileInFillingMode);
             }
-            Some((index, id)) => {
-                if id != order_id {
-                    // this is not really important tbh
-                    // we should always just clear the first next available
-                    // fail!(BorrowError::InvalidRedemptionOrder);
-                }
-                let order = &mut redemptions_queue.orders[index];
-                if &order.redeemer_user_metadata != redeemer_metadata {
-                    fail!(BorrowError::InvalidRedeemer);
+            RedemptionOrderStatus::Filling => {
+                if order.last_reset + REDEMPTIONS_SECONDS_TO_FILL_ORDER > now {
+                    fail!(BorrowError::CannotClearRedemptionOrderWhileInFillingMode);
+                } else {
+                    order.status = RedemptionOrderStatus::Claiming.into();
                 }
-                let status: RedemptionOrderStatus = order.status.into();
-
-                match status {
-                    RedemptionOrderStatus::Open => {
-                        fail!(BorrowError::CannotClearRedemptionOrderWhileInFillingMode);
-                    }
-                    RedemptionOrderStatus::Filling => {
-                        if order.last_reset + REDEMPTIONS_SECONDS_TO_FILL_ORDER > now {
-                            fail!(BorrowError::CannotClearRedemptionOrderWhileInFillingMode);
-                        } else {
-                            order.status = RedemptionOrderStatus::Claiming.into();
-                        }
-                    }
-                    RedemptionOrderStatus::Claiming => {}
-                    RedemptionOrderStatus::Inactive => {
-                        fail!(BorrowError::InvalidRedemptionOrder);
-                    }
-                };
-                Ok(order)
             }
-        }
-    }
-
-    pub fn first_outstanding(
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
-    ) -> Option<(usize, u64)> {
-        redemptions_queue
-            .orders
-            .iter()
-            .enumerate()
-            .filter(|(_index, o)| o.status != RedemptionOrderStatus::Inactive as u8)
-            .min_by_key(|(_index, o)| o.id)
-            .map(|(index, o)| (index, o.id))
+            RedemptionOrderStatus::Claiming => {}
+            RedemptionOrderStatus::Inactive => {
+                fail!(BorrowError::InvalidRedemptionOrder);
+            }
+        };
+        Ok(())
     }
 
-    pub fn add_redemption_order<'a, 'b>(
+    pub fn add_redemption_order(
         amount: u64,
-        queue: &'a mut RefMut<RedemptionsQueue>,
-        redeemer: &'b UserMetadata,
-        prices: &'b TokenPrices,
+        queue: &mut RedemptionsQueue,
+        order: &mut RedemptionOrder,
+        redeemer: &UserMetadata,
+        prices: &TokenPrices,
         now: u64,
         base_rate: u16,
-    ) -> Result<&'a RedemptionOrder, BorrowError> {
-        // Zeroes out the next available order on the queue and updates it with
-        // relevant data such as requested amount, requester, etc
-
-        let index = queue
-            .orders
-            .iter()
-            .position(|o| o.status == RedemptionOrderStatus::Inactive as u8)
-            .ok_or(BorrowError::RedemptionsQueueIsFull)?;
+    ) -> Result<(), BorrowError> {
+        // Takes the next index of the queue and fills in the freshly
+        // created order with the requested amount, requester, etc
 
         let next_index = queue.next_index;
         queue.next_index += 1;
+        queue.outstanding_amount = queue
+            .outstanding_amount
+            .checked_add(amount)
+            .ok_or(BorrowError::MathOverflow)?;
 
-        let mut order: &mut RedemptionOrder = &mut queue.orders[index];
         order.id = next_index;
         order.status = RedemptionOrderStatus::Open as u8;
         order.base_rate = base_rate;
@@ -565,35 +1129,86 @@ mod queue {
         order.requested_amount = amount;
         order.remaining_amount = amount;
         order.last_reset = now;
+        order.created_at = now;
         order.redeemer_user_metadata = redeemer.metadata_pk;
         order.redemption_prices = *prices;
+        order.candidate_pages = 1;
+
+        Ok(())
+    }
+
+    pub fn assert_can_redeem(
+        market: &BorrowingMarketState,
+        queue: &RedemptionsQueue,
+        prices: &TokenPrices,
+        now: u64,
+        redemption_amount: u64,
+    ) -> Result<u64, BorrowError> {
+        // Returns the supply not yet claimed by outstanding orders
+        let remaining_supply = market.stablecoin_borrowed - queue.outstanding_amount;
+        if redemption_amount > remaining_supply {
+            fail!(BorrowError::CannotRedeemMoreThanMinted);
+        }
+
+        if now < market.bootstrap_period_timestamp {
+            fail!(BorrowError::CannotRedeemDuringBootstrapPeriod);
+        }
+
+        let tcr = CollateralInfo::calc_coll_ratio(
+            market.stablecoin_borrowed,
+            &market.deposited_collateral,
+            prices,
+        );
 
-        for i in 0..order.candidate_users.len() {
-            order.candidate_users[i].status = 0;
+        if tcr.to_percent()? < NORMAL_MCR as u128 {
+            fail!(BorrowError::CannotRedeemWhenUndercollateralized);
         }
 
-        // queue.orders[index] = order;
+        Ok(remaining_supply)
+    }
+
+    pub fn settle_redeemed_debt(
+        market: &mut BorrowingMarketState,
+        results: &RedemptionFillingResults,
+    ) {
+        // Turn the global collateral to inactive
+        let collateral_made_inactive = results
+            .collateral_made_inactive
+            .add(&results.collateral_redeemed.total);
+        market
+            .deposited_collateral
+            .sub_assign(&collateral_made_inactive);
+
+        market
+            .inactive_collateral
+            .add_assign(&collateral_made_inactive);
 
-        Ok(order)
+        // Remove debt
+        market
+            .stablecoin_borrowed
+            .checked_sub_assign(results.debt_redeemed)
+            .unwrap();
     }
 
     pub fn collect_collateral_and_pay_debt(
         market: &mut BorrowingMarketState,
         order: &mut RedemptionOrder,
+        candidate_users: &mut [CandidateRedemptionUser],
         fillers_and_borrowers: &mut [&mut UserMetadata],
+        closed_troves: &[Pubkey],
     ) -> Result<RedemptionFillingResults, crate::BorrowError> {
         let mut total_collateral_gains = RedemptionCollateralSplit::default();
         let mut total_collateral_made_inactive = CollateralAmounts::default();
         let mut remaining_amount = order.remaining_amount;
         let mut claimed_amount = 0;
 
-        'candidates_loop: for i in 0..order.candidate_users.len() {
+        'candidates_loop: for i in 0..candidate_users.len() {
             log_compute_units!("Looping through user {}", i);
             if remaining_amount == 0 {
                 break 'candidates_loop;
             }
 
-            if order.candidate_users[i].status != RedemptionCandidateStatus::Active as u8 {
+            if candidate_users[i].status != RedemptionCandidateStatus::Active as u8 {
                 // This is an invalid entry (or end of list)
                 // nothing to do here, we should stop
                 break 'candidates_loop;
@@ -601,7 +1216,7 @@ mod queue {
 
             let (user, filler) = match queue::map_accounts_to_candidate_user(
                 &i,
-                &order.candidate_users,
+                candidate_users,
                 fillers_and_borrowers,
             ) {
                 Ok(v) => v,
@@ -609,13 +1224,24 @@ mod queue {
                     return Err(RedemptionFillerNotFound);
                 }
                 Err(crate::BorrowError::RedemptionUserNotFound) => {
+                    // A trove whose account was closed can never be
+                    // submitted again, it is dropped from the order
+                    if closed_troves.contains(&candidate_users[i].user_metadata) {
+                        msg!(
+                            "Candidate {:?} was closed. Dropping it.",
+                            &candidate_users[i].user_metadata
+                        );
+                        candidate_users[i].status = RedemptionCandidateStatus::Inactive.into();
+                        continue 'candidates_loop;
+                    }
+
                     // ensure that the user_to_redeem and filler are both found
                     // else error, we should disallow clearing in any other order
                     // other than the currently sorted one
                     // if we have not found the next correct user, we break out of the loop
                     msg!(
                         "Could not find user for candidate {:?} {:?}. Stopping early.",
-                        &order.candidate_users[i].user_metadata,
+                        &candidate_users[i].user_metadata,
                         RedemptionUserNotFound
                     );
                     break 'candidates_loop;
@@ -623,16 +1249,28 @@ mod queue {
                 _ => return Err(WrongRedemptionUser),
             };
 
-            let (redeemed_amount, redeemed_collateral) = some_or_continue!(calcs::calc_redemption_amounts(
-                    fillers_and_borrowers,
-                    order,
-                    user,
-                    i,
-                    remaining_amount
-                ), 'candidates_loop);
+            let (redeemed_amount, redeemed_collateral) = match calcs::calc_redemption_amounts(
+                fillers_and_borrowers,
+                order,
+                candidate_users,
+                user,
+                i,
+                remaining_amount,
+            ) {
+                Some(amounts) => amounts,
+                None => {
+                    // Changed since it was filled, it would be skipped
+                    // by every clear, so it leaves the order
+                    candidate_users[i].status = RedemptionCandidateStatus::Inactive.into();
+                    continue 'candidates_loop;
+                }
+            };
 
-            let collateral_split =
-                calcs::split_redemption_collateral(&redeemed_collateral, order.base_rate);
+            let collateral_split = calcs::split_redemption_collateral(
+                &redeemed_collateral,
+                order.base_rate,
+                order.stakers_fee_in_usdh > 0,
+            );
 
             claimed_amount += redeemed_amount;
             remaining_amount = remaining_amount.checked_sub(redeemed_amount).unwrap();
@@ -654,7 +1292,7 @@ mod queue {
 
             if fillers_and_borrowers[user].borrowed_stablecoin == 0 {
                 // Mark for cleaning, no longer useful for this order
-                order.candidate_users[i].status = RedemptionOrderStatus::Inactive.into();
+                candidate_users[i].status = RedemptionOrderStatus::Inactive.into();
 
                 // Full redemption, no more debt, no need for the collateral to be active
                 // Also, mark the collateral as inactive since it's no longer backing and USDH debt
@@ -668,6 +1306,12 @@ mod queue {
 
                 fillers_and_borrowers[user].deposited_collateral = CollateralAmounts::default();
 
+                // The trove is closed, what's left of its collateral is
+                // surplus for the owner to claim, it no longer takes part
+                // in redistributions
+                fillers_and_borrowers[user].status = UserStatus::Redeemed as u8;
+                redistribution::remove_stake(market, fillers_and_borrowers[user]);
+
                 market.num_active_users -= 1;
             }
         }
@@ -681,7 +1325,10 @@ mod queue {
         })
     }
 
-    pub fn flush_order(redemption_order: &mut RedemptionOrder) {
+    pub fn flush_order(
+        redemption_order: &mut RedemptionOrder,
+        candidate_users: &mut [CandidateRedemptionUser],
+    ) {
         // Update the queue:
         // - if the redemption has been fully completed,
         // there is nothing left to be redeemed,
@@ -690,25 +1337,35 @@ mod queue {
         // we need to leave the queue in a clean spot
         if redemption_order.remaining_amount == 0 {
             msg!("Redemption order filled, removing order.");
-            queue::close_redemption_order(redemption_order);
+            queue::close_redemption_order(redemption_order, candidate_users);
         } else {
             msg!("Redemption order partially filled, popping redeemed users.");
-            queue::refresh_unfulfilled_order(redemption_order);
+            queue::refresh_unfulfilled_order(redemption_order, candidate_users);
         }
     }
 
-    pub fn close_redemption_order(order: &mut RedemptionOrder) {
+    pub fn close_redemption_order(
+        order: &mut RedemptionOrder,
+        candidate_users: &mut [CandidateRedemptionUser],
+    ) {
+        // Inactive orders are closed by the handler, along with
+        // their candidate pages, the rent going back to the redeemer
         order.id = 0;
         order.status = RedemptionOrderStatus::Inactive as u8;
         order.redeemer = Pubkey::default();
         order.requested_amount = 0;
         order.remaining_amount = 0;
         order.last_reset = 0;
+        order.created_at = 0;
         order.redeemer_user_metadata = Pubkey::default();
         order.redemption_prices = TokenPrices::default();
+        order.preferred_collateral = 0;
+        order.stakers_fee_in_usdh = 0;
+        order.min_collateral_value_usdh = 0;
+        order.escrowed_fee_usdh = 0;
 
-        for i in 0..order.candidate_users.len() {
-            order.candidate_users[i].status = 0;
+        for candidate in candidate_users.iter_mut() {
+            candidate.status = 0;
         }
     }
 
@@ -735,9 +1392,11 @@ mod queue {
         Ok((user_to_redeem_idx, filler_to_reward_idx))
     }
 
-    pub fn refresh_unfulfilled_order(redemption_order: &mut RedemptionOrder) {
-        let valid_users: Vec<CandidateRedemptionUser> = redemption_order
-            .candidate_users
+    pub fn refresh_unfulfilled_order(
+        redemption_order: &mut RedemptionOrder,
+        candidate_users: &mut [CandidateRedemptionUser],
+    ) {
+        let valid_users: Vec<CandidateRedemptionUser> = candidate_users
             .iter()
             .filter(|user| user.status == RedemptionCandidateStatus::Active as u8)
             .copied()
@@ -748,8 +1407,7 @@ mod queue {
             // therefore we set the order back to Open mode
             redemption_order.status = RedemptionOrderStatus::Open.into();
         } else {
-            redemption_order
-                .candidate_users
+            candidate_users
                 .iter_mut()
                 .enumerate()
                 .for_each(|(i, user)| {
@@ -761,18 +1419,4 @@ mod queue {
                 });
         }
     }
-
-    pub fn calculate_outstanding_redemption_amount(queue: &mut RefMut<RedemptionsQueue>) -> u64 {
-        queue
-            .orders
-            .iter()
-            .map(|o| {
-                if o.status != RedemptionOrderStatus::Inactive as u8 {
-                    o.remaining_amount
-                } else {
-                    0
-                }
-            })
-            .sum()
-    }
 }
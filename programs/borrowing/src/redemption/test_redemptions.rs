@@ -3,7 +3,8 @@
 mod tests {
     use crate::borrowing_market::borrowing_operations;
     use crate::borrowing_market::borrowing_operations::redistribution::compute_new_stake;
-    use crate::borrowing_market::borrowing_rate::BorrowSplit;
+    use crate::borrowing_market::borrowing_rate::{self, BorrowSplit};
+    use crate::borrowing_market::sorted_troves;
     use crate::borrowing_market::tests_utils::utils::{
         new_borrower, new_borrowing_users_with_amounts_and_price,
     };
@@ -13,20 +14,29 @@ mod tests {
         new_borrowing_users_with_sol_collateral, new_redemption_orders,
         set_up_filled_redemption_order, setup_redemption_borrowing_program,
         setup_redemption_borrowing_program_with_prices, BorrowersFilter, FilledOrderSetUp,
+        TestRedemptionsQueue,
     };
     use crate::state::epoch_to_scale_to_sum::EpochToScaleToSum;
     use std::cell::RefCell;
     use std::convert::TryInto;
 
     use crate::{
-        assert_fuzzy_eq, BorrowingMarketState, LiquidationsQueue, RedemptionsQueue,
-        StabilityPoolState, StakingPoolState,
+        assert_fuzzy_eq, BorrowingMarketState, LiquidationsQueue, StabilityPoolState,
+        StakingPoolState,
     };
 
-    use crate::redemption::types::ClearRedemptionOrderEffects;
-    use crate::state::redemptions_queue::RedemptionOrderStatus;
+    use crate::redemption::types::{
+        AddRedemptionOrderEffects, ClearRedemptionOrderEffects, InstantRedemptionEffects,
+    };
+    use crate::state::redemptions_queue::{RedemptionCandidateStatus, RedemptionOrderStatus};
     use crate::state::CollateralToken;
-    use crate::utils::consts::{BOOTSTRAP_PERIOD, REDEMPTIONS_SECONDS_TO_FILL_ORDER};
+    use crate::state::RedemptionOrder;
+    use crate::state::UserStatus;
+    use crate::utils::consts::{
+        BOOTSTRAP_PERIOD, MAX_REDEMPTION_CANDIDATE_PAGES, MAX_REDEMPTION_FEE,
+        MIN_REDEMPTIONS_AMOUNT_USDH, REDEMPTIONS_SECONDS_TO_FILL_ORDER, REDEMPTION_FEE_FLOOR,
+        REDEMPTION_ORDER_EXPIRY_SECONDS,
+    };
     use crate::utils::coretypes::{SOL, USDH};
     use crate::utils::finance::CollateralInfo;
     use crate::{state::CandidateRedemptionUser, UserMetadata};
@@ -144,7 +154,7 @@ mod tests {
 
         // try to clear second redemption order first
         let mut clearer = utils::new_approved_user(&mut market);
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             order_2.order_id,
             &mut order_2.redeemer,
             &mut clearer,
@@ -196,7 +206,7 @@ mod tests {
 
         // clear first order - wrong redeemer
         let mut clearer = utils::new_approved_user(&mut market);
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             order_1.order_id,
             &mut order_2.redeemer, // wrong redeemer
             &mut clearer,
@@ -568,7 +578,7 @@ mod tests {
 
         // duplicates is error
         let fill_bot = new_approved_user(&mut market);
-        let res = redemption_operations::fill_redemption_order(
+        let res = utils::fill_order(
             order_1.order_id,
             &mut market,
             &mut redemptions_queue.borrow_mut(),
@@ -1006,7 +1016,7 @@ mod tests {
         submitted_users_chunks
             .iter_mut()
             .for_each(|submitted_users_chunk| {
-                redemption_operations::fill_redemption_order(
+                utils::fill_order(
                     order_1.order_id,
                     &mut market,
                     &mut redemptions_queue.borrow_mut(),
@@ -1026,7 +1036,7 @@ mod tests {
             .for_each(|user| fillers_and_borrowers.push(user));
 
         // Try to fill it once, but 5 seconds have not passed by
-        let err = redemption_operations::clear_redemption_order(
+        let err = utils::clear_order(
             order_1.order_id,
             &mut order_1.redeemer,
             &mut clearer,
@@ -1046,7 +1056,8 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             redeemed_collateral,
-        } = redemption_operations::clear_redemption_order(
+            ..
+        } = utils::clear_order(
             order_1.order_id,
             &mut order_1.redeemer,
             &mut clearer,
@@ -1205,7 +1216,8 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             redeemed_collateral,
-        } = redemption_operations::clear_redemption_order(
+            ..
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1318,7 +1330,8 @@ mod tests {
             let ClearRedemptionOrderEffects {
                 redeemed_stablecoin,
                 redeemed_collateral,
-            } = redemption_operations::clear_redemption_order(
+                ..
+            } = utils::clear_order(
                 order.order_id,
                 &mut order.redeemer,
                 &mut clearer,
@@ -1466,6 +1479,7 @@ mod tests {
             &mut borrowers[0],
             1 * LAMPORTS_PER_SOL,
             CollateralToken::SOL,
+            0,
         )
         .unwrap();
 
@@ -1474,7 +1488,7 @@ mod tests {
             .iter_mut()
             .for_each(|user| fillers_and_borrowers.push(user));
 
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1491,6 +1505,102 @@ mod tests {
         assert_eq!(borrowed_after_first, borrowed_before_first);
         assert_eq!(borrowed_before_second, borrowed_before_first);
         assert_eq!(borrowed_after_second, 0);
+
+        // The changed trove is dropped rather than skipped on every clear,
+        // nothing is left to clear and the order is open again
+        let redemptions_queue = redemptions_queue.borrow();
+        assert!(redemptions_queue.orders[0]
+            .candidate_users
+            .iter()
+            .all(|candidate| candidate.status == RedemptionCandidateStatus::Inactive as u8));
+        assert_eq!(
+            redemptions_queue.orders[0].order.status,
+            RedemptionOrderStatus::Open as u8
+        );
+    }
+
+    #[test]
+    fn test_redemption_clear_drops_closed_candidates() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            10,
+            (0..10).map(|i| ((i + 1) as f64) * 100.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            1000.0,
+            now_timestamp,
+        );
+        let borrowers_snapshot = borrowers.clone();
+
+        let mut order = utils::set_up_redemption_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            USDH::from(2500.0),
+            now_timestamp,
+        );
+        let mut filler = utils::fill_redemption_order(
+            &order,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            BorrowersFilter::Some(vec![0, 1]),
+            now_timestamp,
+        )
+        .unwrap();
+
+        // The first trove's account is gone, a clear without it
+        // stops early unless it is shown to be closed
+        let closed_trove = borrowers[0].metadata_pk;
+        let mut clearer = new_approved_user(&mut market);
+        let mut clear = |fillers_and_borrowers: &mut [&mut UserMetadata],
+                         closed_troves: &[Pubkey]| {
+            let queue = &mut *redemptions_queue.borrow_mut();
+            let utils::TestRedemptionOrder {
+                order: redemption_order,
+                candidate_users,
+            } = &mut queue.orders[0];
+            redemption_operations::clear_redemption_order(
+                order.order_id,
+                &mut order.redeemer,
+                &mut clearer,
+                &mut market,
+                &mut staking_pool_state,
+                &mut queue.queue,
+                redemption_order,
+                candidate_users,
+                fillers_and_borrowers,
+                closed_troves,
+                &mut utils::new_sorted_troves(&[], &prices).borrow_mut(),
+                &prices,
+                now_timestamp + REDEMPTIONS_SECONDS_TO_FILL_ORDER + 1,
+            )
+            .unwrap()
+        };
+
+        let (_, rest) = borrowers.split_at_mut(1);
+        let effects = clear(&mut [&mut filler, &mut rest[0]], &[]);
+        assert_eq!(effects.redeemed_stablecoin, 0);
+
+        let effects = clear(&mut [&mut filler, &mut rest[0]], &[closed_trove]);
+        assert_eq!(
+            effects.redeemed_stablecoin,
+            borrowers_snapshot[1].borrowed_stablecoin
+        );
+        assert_eq!(rest[0].borrowed_stablecoin, 0);
+
+        let redemptions_queue = redemptions_queue.borrow();
+        assert!(redemptions_queue.orders[0]
+            .candidate_users
+            .iter()
+            .all(|candidate| candidate.status == RedemptionCandidateStatus::Inactive as u8));
+        assert_eq!(
+            redemptions_queue.orders[0].order.status,
+            RedemptionOrderStatus::Open as u8
+        );
     }
 
     #[test]
@@ -1543,7 +1653,7 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             ..
-        } = redemption_operations::clear_redemption_order(
+        } = utils::clear_order(
             order_1.order_id,
             &mut order_1.redeemer,
             &mut clearer,
@@ -1579,7 +1689,7 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             ..
-        } = redemption_operations::clear_redemption_order(
+        } = utils::clear_order(
             order_2.order_id,
             &mut order_2.redeemer,
             &mut clearer,
@@ -1651,7 +1761,7 @@ mod tests {
             .for_each(|user| fillers_and_borrowers.push(user));
 
         // Clearing while filling in the time window is an error
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1671,7 +1781,7 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             ..
-        } = redemption_operations::clear_redemption_order(
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1688,7 +1798,7 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             ..
-        } = redemption_operations::clear_redemption_order(
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1739,7 +1849,7 @@ mod tests {
             .drain(1..3)
             .for_each(|x| second_third_borrowers_and_filler.push(x));
 
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1785,7 +1895,7 @@ mod tests {
             .for_each(|x| second_to_ninth_borrower_and_filler.push(x));
 
         // Should be an error, need to fill after seconds have passed
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1804,7 +1914,7 @@ mod tests {
             .for_each(|x| second_to_ninth_borrower_and_filler.push(x));
 
         let red_q = redemptions_queue.clone();
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1924,7 +2034,8 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             redeemed_collateral,
-        } = redemption_operations::clear_redemption_order(
+            ..
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1944,7 +2055,8 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             redeemed_collateral,
-        } = redemption_operations::clear_redemption_order(
+            ..
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1969,7 +2081,7 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             ..
-        } = redemption_operations::clear_redemption_order(
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -1996,7 +2108,7 @@ mod tests {
         let mut borrowers_mut: Vec<&mut UserMetadata> = borrowers.iter_mut().map(|x| x).collect();
 
         // trying to fill with empty candidates is noop
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -2030,7 +2142,7 @@ mod tests {
         );
 
         let mut redeemer = new_approved_user(&mut market);
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2041,7 +2153,7 @@ mod tests {
 
         assert_eq!(res.err().unwrap(), BorrowError::CannotRedeemMoreThanMinted);
 
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2104,7 +2216,7 @@ mod tests {
         // Try to clear order_two first, should err
         let mut borrowers_mut: Vec<&mut UserMetadata> = borrowers.iter_mut().map(|x| x).collect();
 
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             orders[1].order_id,
             &mut orders[1].redeemer,
             &mut clearer,
@@ -2135,7 +2247,7 @@ mod tests {
             .iter_mut()
             .for_each(|user| fillers_and_borrowers.push(user));
 
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             orders[0].order_id,
             &mut orders[0].redeemer,
             &mut clearer,
@@ -2182,7 +2294,7 @@ mod tests {
                 .iter_mut()
                 .for_each(|user| filler_one_wrong_and_borrowers.push(user));
 
-            let err = redemption_operations::clear_redemption_order(
+            let err = utils::clear_order(
                 orders[*order_id as usize].order_id,
                 &mut orders[*order_id as usize].redeemer,
                 &mut clearer,
@@ -2202,7 +2314,7 @@ mod tests {
                 .iter_mut()
                 .for_each(|user| filler_one_correct_and_borrowers.push(user));
 
-            let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+            let ClearRedemptionOrderEffects { .. } = utils::clear_order(
                 orders[*order_id as usize].order_id,
                 &mut orders[*order_id as usize].redeemer,
                 &mut clearer,
@@ -2298,7 +2410,7 @@ mod tests {
         utils::print_candidate_users(redemptions_queue.clone(), 0);
 
         println!("c");
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -2353,7 +2465,7 @@ mod tests {
             .iter_mut()
             .for_each(|user| filler_two_and_borrowers.push(user));
 
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -2441,7 +2553,7 @@ mod tests {
             .iter_mut()
             .for_each(|user| wrong_fillers_and_borrowers.push(user));
 
-        let res = redemption_operations::clear_redemption_order(
+        let res = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -2467,7 +2579,8 @@ mod tests {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             redeemed_collateral,
-        } = redemption_operations::clear_redemption_order(
+            ..
+        } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -2491,7 +2604,7 @@ mod tests {
             .iter_mut()
             .for_each(|user| good_fillers_and_good_borrowers.push(user));
 
-        let ClearRedemptionOrderEffects { .. } = redemption_operations::clear_redemption_order(
+        let ClearRedemptionOrderEffects { .. } = utils::clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -2642,7 +2755,7 @@ mod tests {
         );
 
         let mut redeemer = new_approved_user(&mut market);
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2673,7 +2786,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
-        let redemptions_queue = RefCell::new(RedemptionsQueue::default());
+        let redemptions_queue = RefCell::new(TestRedemptionsQueue::default());
         let prices = TokenPrices::new(prices_at_beginning);
         borrowing_operations::initialize_borrowing_market(
             &mut market,
@@ -2693,7 +2806,7 @@ mod tests {
         );
 
         let mut redeemer = new_approved_user(&mut market);
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2715,7 +2828,7 @@ mod tests {
         let redeem_amt = USDH::from(2000.0);
 
         let mut market = BorrowingMarketState::new();
-        let redemptions_queue = RefCell::new(RedemptionsQueue::default());
+        let redemptions_queue = RefCell::new(TestRedemptionsQueue::default());
         let prices = TokenPrices::new(prices_at_beginning);
         borrowing_operations::initialize_borrowing_market(
             &mut market,
@@ -2723,7 +2836,7 @@ mod tests {
         );
 
         let mut redeemer = new_approved_user(&mut market);
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2764,7 +2877,7 @@ mod tests {
         let mut redeemer = new_approved_user(&mut market);
 
         // Redeeming once works, 500 is left
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2776,7 +2889,7 @@ mod tests {
         assert!(res.is_ok());
 
         // This is trying to redeem 1000.0, shouldn't be allowed
-        let res = redemption_operations::add_redemption_order(
+        let res = utils::add_order(
             &mut redeemer,
             &mut redemptions_queue.borrow_mut(),
             &mut market,
@@ -2791,163 +2904,1516 @@ mod tests {
     }
 
     #[test]
-    fn test_redemption_assert_user_stake_and_total_stakes_updated() {
-        // - [x] redemption: _updateStakeAndTotalStakes
-        // Everyone deposits 1000 SOL
-        // Everyone has a debt of 1000.0 USDH
-        // SOL/USD price is 1.09
-        // Such that everyone is at 109% CR
-        // But there is a whale that keeps the system well collateralized
-        // We redeem 2000 from the first two users
-        // one with 1005 USDH and one with 1005 USDH
-        // the first one remains with 0, the second one remains with 1005 - (2000 - 1005) = 10
-        // expecting total system stake to reduce by 2000
-        // and user stake to reduce by 1005 and 995
-
-        let prices = 2.0;
-
-        let (mut market, mut spool, redemptions_queue, _prices) =
-            setup_redemption_borrowing_program_with_prices(prices);
-
+    fn test_redemption_many_orders_outstanding() {
+        // Orders no longer share one account, so there is no
+        // limit on how many can be outstanding at once
         let now_timestamp = 0;
-        let whale_deposit = SOL::from(1000.0 * 100.0);
-        let borrow_amt = USDH::from(1000.0);
-        let num = 100;
-        let collaterals: Vec<CollateralAmounts> = (0..num)
-            .map(|i| {
-                CollateralAmounts::of_token(
-                    SOL::from(((i + 1) as f64) * 1000.0),
-                    CollateralToken::SOL,
-                )
-            })
-            .collect();
         let redeem_amt = USDH::from(2000.0);
-        let borrow_split = BorrowSplit::from_amount(borrow_amt, market.base_rate_bps);
-        let borrow_splits = vec![borrow_amt; num];
+        let num_orders = 20;
 
-        let (_whale, _) = new_borrower(
+        let (mut market, mut spool, redemptions_queue, prices) =
+            setup_redemption_borrowing_program_with_prices(2.0);
+
+        let _whale = new_borrower(
             &mut market,
             &mut spool,
-            whale_deposit,
-            borrow_amt,
-            &TokenPrices::new(prices),
+            SOL::from(1_000_000.0),
+            USDH::from(100_000.0),
+            &prices,
             now_timestamp,
         );
 
-        assert_eq!(_whale.user_stake, borrow_split.amount_to_borrow);
+        let orders = new_redemption_orders(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            vec![redeem_amt; num_orders],
+            now_timestamp,
+        );
 
-        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+        let queue = redemptions_queue.borrow();
+        for (i, order) in orders.iter().enumerate() {
+            assert_eq!(order.order_id, i as u64);
+            assert_eq!(queue.orders[i].status, RedemptionOrderStatus::Open as u8);
+            assert_eq!(queue.orders[i].candidate_pages, 1);
+        }
+        assert_eq!(queue.queue.next_index, num_orders as u64);
+        assert_eq!(
+            queue.queue.outstanding_amount,
+            redeem_amt * num_orders as u64
+        );
+    }
+
+    #[test]
+    fn test_redemption_outstanding_amount_released_on_clear() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let count = 10;
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            count,
+            (0..count).map(|i| ((i + 1) as f64) * 100.0).collect(),
             &mut market,
-            &mut spool,
-            num,
-            &borrow_splits,
-            &collaterals,
-            prices,
+            &mut staking_pool_state,
+            1000.0,
             now_timestamp,
         );
-        borrowers
-            .iter()
-            .for_each(|u| assert_eq!(u.user_stake, borrow_split.amount_to_borrow));
 
-        let _ = add_fill_and_clear_order(
+        let redeem_amt = USDH::from(2500.0);
+        let (_, _, _, redeemed_stablecoin, _) = add_fill_and_clear_order(
             &mut market,
             &mut redemptions_queue.borrow_mut(),
             &mut borrowers,
-            &TokenPrices::new(prices),
+            &prices,
             redeem_amt,
             now_timestamp,
         )
         .unwrap();
 
-        assert_eq!(borrowers[0].user_stake, 0);
-        assert_eq!(borrowers[1].user_stake, USDH::from(10.0));
-        assert_eq!(
-            market.total_stake,
-            borrow_split.amount_to_borrow * ((num + 1) as u64) - redeem_amt // including whale
-        );
+        assert_eq!(redeemed_stablecoin, redeem_amt);
+        assert_eq!(redemptions_queue.borrow().queue.outstanding_amount, 0);
+        utils::assert_order_cleared(redemptions_queue.clone(), 0);
     }
 
     #[test]
-    fn test_redemption_assert_pending_rewards_applied() {
-        // There is a liquidation event, users get redistributed an amount
-        // then there is a redemption event
-        // and users get redeemed including the redistributed amount
-
-        // 1 borrower whale with 1000 usdh & 100000000 sol
-        // 3 borrowers with 1000 usdh & 1000, 2000, 3000 sol
-        // stability pool is empty
-        // borrower[0] gets liquidated
-        // the other two guys should receive
-
-        let mut market = BorrowingMarketState::new();
-        let mut stability_pool_state = StabilityPoolState::default();
-        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
-        let liquidations = RefCell::new(LiquidationsQueue::default());
-        let mut staking_pool_state = StakingPoolState {
-            treasury_fee_rate: 1_500,
-            ..Default::default()
-        };
-        let redemptions_queue = RefCell::new(RedemptionsQueue::default());
+    fn test_redemption_candidates_span_pages() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
 
-        let prices_at_beginning = 2.0;
-        let prices_at_liquidation = 1.09;
-        let prices_at_redemption = 1.0;
-        let whale_deposit = SOL::from(100000.0);
-        let redeem_amt = USDH::from(2000.0);
+        // More candidates than fit on a single page
+        let count = 40;
         let now_timestamp = 0;
-        let borrow_amt = USDH::from(1000.0);
-        let num = 3;
-        let collaterals: Vec<CollateralAmounts> = [1000.0, 2000.0, 3000.0]
-            .iter()
-            .map(|amt| CollateralAmounts::of_token(SOL::from(*amt), CollateralToken::SOL))
-            .collect();
-        let borrow_split = BorrowSplit::from_amount(borrow_amt, market.base_rate_bps);
-        let borrow_amounts = vec![borrow_amt; num];
-
-        let (mut whale, _) = new_borrower(
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            count,
+            (0..count).rev().map(|i| ((i + 1) as f64) * 100.0).collect(),
             &mut market,
             &mut staking_pool_state,
-            whale_deposit,
-            borrow_amt,
-            &TokenPrices::new(prices_at_beginning),
+            1000.0,
             now_timestamp,
         );
 
-        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+        let order = utils::set_up_redemption_order(
             &mut market,
-            &mut staking_pool_state,
-            num,
-            &borrow_amounts,
-            &collaterals,
-            prices_at_beginning,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            USDH::from(2000.0),
             now_timestamp,
         );
+        let mut redemption_order = redemptions_queue.borrow().orders[0].order;
 
-        let liquidator = Pubkey::new_unique();
-        borrowing_operations::try_liquidate(
-            liquidator,
+        // Pages can be added up to the limit
+        for page in 1..MAX_REDEMPTION_CANDIDATE_PAGES {
+            let res = redemption_operations::add_redemption_candidates_page(&mut redemption_order);
+            assert_eq!(res, Ok(page));
+        }
+        let res = redemption_operations::add_redemption_candidates_page(&mut redemption_order);
+        assert_eq!(res, Err(BorrowError::TooManyRedemptionCandidatePages));
+
+        let mut candidate_users =
+            vec![CandidateRedemptionUser::default(); 32 * MAX_REDEMPTION_CANDIDATE_PAGES as usize];
+        let filler = new_approved_user(&mut market);
+        let mut submitted_users: Vec<&mut UserMetadata> = borrowers.iter_mut().collect();
+        submitted_users.shuffle(&mut thread_rng());
+        for users in submitted_users.chunks_mut(8) {
+            redemption_operations::fill_redemption_order(
+                order.order_id,
+                &mut market,
+                &mut redemption_order,
+                &mut candidate_users,
+                users,
+                &filler,
+                now_timestamp,
+            )
+            .unwrap();
+        }
+
+        let active_candidates: Vec<CandidateRedemptionUser> = candidate_users
+            .iter()
+            .filter(|candidate| candidate.status != 0)
+            .copied()
+            .collect();
+        assert_eq!(active_candidates.len(), count);
+        assert!(active_candidates
+            .windows(2)
+            .all(|pair| pair[0].collateral_ratio <= pair[1].collateral_ratio));
+
+        // Once clearing started no more pages can be added
+        let mut clearer = new_approved_user(&mut market);
+        let mut redeemer = order.redeemer;
+        redemption_operations::clear_redemption_order(
+            order.order_id,
+            &mut redeemer,
+            &mut clearer,
             &mut market,
-            &mut borrowers[0],
-            &mut stability_pool_state,
-            &mut epoch_to_scale_to_sum,
-            &TokenPrices::new(prices_at_liquidation),
-            &mut liquidations.borrow_mut(),
-            now_timestamp,
+            &mut StakingPoolState::default(),
+            &mut redemptions_queue.borrow_mut().queue,
+            &mut redemption_order,
+            &mut candidate_users,
+            &mut [],
+            &[],
+            &mut utils::new_sorted_troves(&[], &prices).borrow_mut(),
+            &prices,
+            now_timestamp + REDEMPTIONS_SECONDS_TO_FILL_ORDER + 1,
         )
         .unwrap();
+        let res = redemption_operations::add_redemption_candidates_page(&mut redemption_order);
+        assert_eq!(
+            res,
+            Err(BorrowError::CannotFillRedemptionOrderWhileInClearingMode)
+        );
+    }
 
-        borrowing_operations::refresh_positions(&mut market, &mut borrowers[0]).unwrap();
-        borrowing_operations::refresh_positions(&mut market, &mut borrowers[1]).unwrap();
-        borrowing_operations::refresh_positions(&mut market, &mut borrowers[2]).unwrap();
-        borrowing_operations::refresh_positions(&mut market, &mut whale).unwrap();
+    #[test]
+    fn test_cancel_open_redemption_order_after_expiry() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
 
-        // TODO: think about inactive collateral & redsitributeion & total stake
+        let now_timestamp = 0;
+        new_borrowing_users_with_sol_collateral(
+            10,
+            vec![100.0; 10],
+            &mut market,
+            &mut staking_pool_state,
+            1000.0,
+            now_timestamp,
+        );
 
-        // Liquidating 1005 USDH and 1000 SOL ->
-        // 1005 / 3  = 335
-        // 1000 - 0.005% (for liquidator) = 995 / 3 = 331.6666666666667
+        let redeem_amt = USDH::from(2500.0);
+        let order = utils::set_up_redemption_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            redeem_amt,
+            now_timestamp,
+        );
 
-        // borrowers have 1005 + 335 = 1340 (1339.999)
+        let res = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS - 1,
+        );
+        assert_eq!(res.err().unwrap(), BorrowError::RedemptionOrderNotExpired);
+
+        let someone_else = new_approved_user(&mut market);
+        let res = utils::cancel_order(
+            order.order_id,
+            &someone_else,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS,
+        );
+        assert_eq!(res.err().unwrap(), BorrowError::InvalidRedeemer);
+
+        let effects = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS,
+        )
+        .unwrap();
+
+        assert_eq!(effects.refunded_stablecoin, redeem_amt);
+        assert_eq!(redemptions_queue.borrow().queue.outstanding_amount, 0);
+        utils::assert_order_cleared(redemptions_queue.clone(), 0);
+
+        // Nothing left to cancel
+        let res = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS,
+        );
+        assert_eq!(res.err().unwrap(), BorrowError::InvalidRedemptionOrder);
+    }
+
+    #[test]
+    fn test_cancel_filling_redemption_order_refunds_remaining_amount() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            10,
+            (0..10).map(|i| ((i + 1) as f64) * 100.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            1000.0,
+            now_timestamp,
+        );
+
+        // Two troves are submitted, then one of them repays its debt,
+        // the candidates don't hold back any of the refund
+        let redeem_amt = USDH::from(2500.0);
+        let order = utils::set_up_redemption_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            redeem_amt,
+            now_timestamp,
+        );
+        utils::fill_redemption_order(
+            &order,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            BorrowersFilter::Some(vec![0, 1]),
+            now_timestamp,
+        )
+        .unwrap();
+        borrowing_operations::repay_loan(
+            &mut market,
+            &mut borrowers[0],
+            USDH::from(500.0),
+            now_timestamp,
+        )
+        .unwrap();
+
+        let effects = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS,
+        )
+        .unwrap();
+        assert_eq!(effects.refunded_stablecoin, redeem_amt);
+        assert_eq!(redemptions_queue.borrow().queue.outstanding_amount, 0);
+        utils::assert_order_cleared(redemptions_queue.clone(), 0);
+    }
+
+    #[test]
+    fn test_cancel_clearing_redemption_order_after_expiry() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            10,
+            (0..10).map(|i| ((i + 1) as f64) * 100.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            1000.0,
+            now_timestamp,
+        );
+
+        let redeem_amt = USDH::from(2500.0);
+        let mut order = utils::set_up_redemption_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            redeem_amt,
+            now_timestamp,
+        );
+        utils::fill_redemption_order(
+            &order,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            BorrowersFilter::Some(vec![0]),
+            now_timestamp,
+        )
+        .unwrap();
+
+        // Clearing with none of the accounts moves the order to clearing
+        let mut clearer = new_approved_user(&mut market);
+        utils::clear_order(
+            order.order_id,
+            &mut order.redeemer,
+            &mut clearer,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut [],
+            now_timestamp + REDEMPTIONS_SECONDS_TO_FILL_ORDER,
+        )
+        .unwrap();
+        assert_eq!(
+            redemptions_queue.borrow().orders[0].status,
+            RedemptionOrderStatus::Claiming as u8
+        );
+
+        let res = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS - 1,
+        );
+        assert_eq!(res.err().unwrap(), BorrowError::RedemptionOrderNotExpired);
+
+        // Nobody cleared it in time, the whole order goes back
+        let effects = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS,
+        )
+        .unwrap();
+        assert_eq!(effects.refunded_stablecoin, redeem_amt);
+        assert_eq!(redemptions_queue.borrow().queue.outstanding_amount, 0);
+        utils::assert_order_cleared(redemptions_queue.clone(), 0);
+    }
+
+    #[test]
+    fn test_instant_redeem_lowest_troves() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        // 0 has the lowest CR, 99 has the highest CR
+        let count = 99;
+        let borrow_per_user = 200.0;
+        let requested_redeemption_amount = USDH::from(2500.0);
+        let now_timestamp = 0;
+
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            count,
+            (0..count).map(|i| ((i + 1) as f64) * 10.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            borrow_per_user,
+            now_timestamp,
+        );
+
+        // for test assertions
+        let borrowers_snapshot = borrowers.clone();
+        let sorted_troves = utils::new_sorted_troves(&borrowers, &prices);
+
+        let mut redeemer = new_approved_user(&mut market);
+        let mut lowest_troves: Vec<&mut UserMetadata> = borrowers.iter_mut().take(20).collect();
+
+        let inactive_collateral_before = market.inactive_collateral;
+        let InstantRedemptionEffects {
+            redeemed_stablecoin,
+            redeemed_collateral,
+            collateral_to_transfer_to_redeemer,
+            stakers_fee_usdh,
+            ..
+        } = redemption_operations::instant_redeem(
+            &mut redeemer,
+            &mut market,
+            &mut staking_pool_state,
+            &redemptions_queue.borrow().queue,
+            &mut sorted_troves.borrow_mut(),
+            &mut lowest_troves,
+            &prices,
+            now_timestamp,
+            requested_redeemption_amount,
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+
+        // Stakers take their share in collateral by default
+        assert_eq!(stakers_fee_usdh, 0);
+
+        // The redeemer does the work of the filler and the clearer,
+        // and is paid out right away rather than credited
+        let simulated = utils::simulate_redemption_results(
+            requested_redeemption_amount,
+            &prices,
+            borrowers_snapshot.clone(),
+            market.base_rate_bps,
+        );
+        assert!(redeemer.inactive_collateral.is_zero());
+        assert_eq!(
+            collateral_to_transfer_to_redeemer,
+            simulated
+                .redeemer
+                .add(&simulated.fill_bot)
+                .add(&simulated.clear_bot)
+        );
+        assert_eq!(
+            redeemed_collateral.total,
+            simulated
+                .redeemer
+                .add(&simulated.fill_bot)
+                .add(&simulated.clear_bot)
+                .add(&simulated.stakers)
+        );
+        // Only the stakers share and the redeemed troves surplus stay inactive
+        let surplus = borrowers
+            .iter()
+            .fold(CollateralAmounts::default(), |total, borrower| {
+                total.add(&borrower.inactive_collateral)
+            });
+        assert_eq!(
+            market.inactive_collateral,
+            inactive_collateral_before
+                .add(&redeemed_collateral.stakers)
+                .add(&surplus)
+        );
+        borrowers
+            .iter()
+            .zip(simulated.updated_borrowers.iter())
+            .for_each(|(actual, expected)| {
+                assert_eq!(actual.borrowed_stablecoin, expected.borrowed_stablecoin);
+                assert_eq!(actual.deposited_collateral, expected.deposited_collateral);
+            });
+        utils::assert_debt_burned(
+            requested_redeemption_amount,
+            redeemed_stablecoin,
+            &borrowers_snapshot,
+            &borrowers,
+        );
+
+        // Fully redeemed troves leave the sorted troves, the partially
+        // redeemed one is safer now and moves past the next trove
+        let fully_redeemed = borrowers
+            .iter()
+            .filter(|borrower| borrower.borrowed_stablecoin == 0)
+            .count();
+        let sorted_troves = sorted_troves.borrow();
+        assert_eq!(sorted_troves::len(&sorted_troves), count - fully_redeemed);
+        assert_eq!(
+            sorted_troves.troves[0].user_id,
+            borrowers[fully_redeemed + 1].user_id
+        );
+    }
+
+    #[test]
+    fn test_instant_redeem_below_minimum_amount() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let count = 10;
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            count,
+            (0..count).map(|i| ((i + 1) as f64) * 10.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            200.0,
+            now_timestamp,
+        );
+        let sorted_troves = utils::new_sorted_troves(&borrowers, &prices);
+        let debt = borrowers[0].borrowed_stablecoin;
+
+        // Same minimum as the queued orders
+        let mut redeemer = new_approved_user(&mut market);
+        let mut lowest_troves: Vec<&mut UserMetadata> = borrowers.iter_mut().take(1).collect();
+        let res = redemption_operations::instant_redeem(
+            &mut redeemer,
+            &mut market,
+            &mut staking_pool_state,
+            &redemptions_queue.borrow().queue,
+            &mut sorted_troves.borrow_mut(),
+            &mut lowest_troves,
+            &prices,
+            now_timestamp,
+            MIN_REDEMPTIONS_AMOUNT_USDH - 1,
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(res.err(), Some(BorrowError::RedemptionsAmountTooSmall));
+        assert_eq!(lowest_troves[0].borrowed_stablecoin, debt);
+    }
+
+    #[test]
+    fn test_instant_redeem_troves_must_be_sorted() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let count = 10;
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            count,
+            (0..count).map(|i| ((i + 1) as f64) * 100.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            1500.0,
+            now_timestamp,
+        );
+        let borrowers_snapshot = borrowers.clone();
+        let sorted_troves = utils::new_sorted_troves(&borrowers, &prices);
+
+        let mut redeemer = new_approved_user(&mut market);
+        let (lowest, rest) = borrowers.split_at_mut(1);
+        let res = redemption_operations::instant_redeem(
+            &mut redeemer,
+            &mut market,
+            &mut staking_pool_state,
+            &redemptions_queue.borrow().queue,
+            &mut sorted_troves.borrow_mut(),
+            &mut [&mut rest[0], &mut lowest[0]],
+            &prices,
+            now_timestamp,
+            USDH::from(2500.0),
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(
+            res.err().unwrap(),
+            BorrowError::InvalidInstantRedemptionTroves
+        );
+
+        // Sorted among themselves, but the lowest trove is skipped
+        let res = redemption_operations::instant_redeem(
+            &mut redeemer,
+            &mut market,
+            &mut staking_pool_state,
+            &redemptions_queue.borrow().queue,
+            &mut sorted_troves.borrow_mut(),
+            &mut rest.iter_mut().take(2).collect::<Vec<_>>(),
+            &prices,
+            now_timestamp,
+            USDH::from(2500.0),
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(res.err().unwrap(), BorrowError::TrovesNotLowestInIndex);
+
+        let res = redemption_operations::instant_redeem(
+            &mut redeemer,
+            &mut market,
+            &mut staking_pool_state,
+            &redemptions_queue.borrow().queue,
+            &mut sorted_troves.borrow_mut(),
+            &mut [],
+            &prices,
+            now_timestamp,
+            USDH::from(2500.0),
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(
+            res.err().unwrap(),
+            BorrowError::InvalidInstantRedemptionTroves
+        );
+
+        assert!(redeemer.inactive_collateral.is_zero());
+        for (after, before) in borrowers.iter().zip(borrowers_snapshot.iter()) {
+            assert_eq!(after.borrowed_stablecoin, before.borrowed_stablecoin);
+        }
+    }
+
+    #[test]
+    fn test_add_redemption_order_slippage() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let now_timestamp = 0;
+        let _ = new_borrowing_users_with_sol_collateral(
+            10,
+            (0..10).map(|i| ((i + 1) as f64) * 100.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            1000.0,
+            now_timestamp,
+        );
+
+        let redemption_amount = USDH::from(2500.0);
+        let mut redeemer = new_approved_user(&mut market);
+        let mut add_order = |market: &mut BorrowingMarketState,
+                             max_fee_bps: u16,
+                             min_collateral_value_usdh: Option<u64>| {
+            let mut order = RedemptionOrder::default();
+            redemption_operations::add_redemption_order(
+                &mut redeemer,
+                &mut redemptions_queue.borrow_mut().queue,
+                &mut order,
+                market,
+                &prices,
+                now_timestamp,
+                redemption_amount,
+                max_fee_bps,
+                min_collateral_value_usdh,
+                None,
+                false,
+            )
+        };
+
+        // The order itself pushes the fee above the floor
+        let res = add_order(&mut market, REDEMPTION_FEE_FLOOR, None);
+        assert_eq!(res.err().unwrap(), BorrowError::RedemptionFeeAboveMaximum);
+
+        // A failed transaction leaves the base rate untouched
+        let base_rate_bps = market.base_rate_bps;
+        let redemption_fee = borrowing_rate::calc_redemption_fee(base_rate_bps);
+        assert!(redemption_fee > REDEMPTION_FEE_FLOOR);
+        market.base_rate_bps = 0;
+
+        // The minimum collateral value is only known at clear time,
+        // even one above the redeemed amount is accepted and stored
+        add_order(&mut market, redemption_fee, Some(redemption_amount + 1)).unwrap();
+        assert_eq!(
+            redemptions_queue.borrow().queue.outstanding_amount,
+            redemption_amount
+        );
+    }
+
+    #[test]
+    fn test_clear_redemption_order_refunds_below_min_collateral_value() {
+        for (price_drop, refunded) in [(1.0, false), (0.9, true)] {
+            let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+                setup_redemption_borrowing_program();
+
+            let count = 10;
+            let now_timestamp = 0;
+            let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+                count,
+                (0..count).map(|i| ((i + 1) as f64) * 100.0).collect(),
+                &mut market,
+                &mut staking_pool_state,
+                1000.0,
+                now_timestamp,
+            );
+            let borrowers_snapshot = borrowers.clone();
+
+            let redeem_amt = USDH::from(2500.0);
+            let FilledOrderSetUp {
+                mut order,
+                mut fill_bot,
+            } = set_up_filled_redemption_order(
+                &mut market,
+                &mut redemptions_queue.borrow_mut(),
+                &mut borrowers,
+                BorrowersFilter::All,
+                &prices,
+                redeem_amt,
+                now_timestamp,
+            )
+            .unwrap();
+
+            // The redeemer asks for what the order is worth at the time
+            // it was added, less the redemption fee and a 1% tolerance
+            let min_collateral_value_usdh = {
+                let queue = &mut *redemptions_queue.borrow_mut();
+                let redemption_order = &mut queue.orders[0].order;
+                let redemption_fee =
+                    borrowing_rate::calc_redemption_fee(redemption_order.base_rate);
+                let min = redeem_amt * (10_000 - redemption_fee as u64 - 100) / 10_000;
+                redemption_order.min_collateral_value_usdh = min;
+                min
+            };
+
+            let mut clear_prices = prices;
+            clear_prices.sol.value = (prices.sol.value as f64 * price_drop) as u64;
+
+            let mut clearer = new_approved_user(&mut market);
+            let mut fillers_and_borrowers: Vec<&mut UserMetadata> = vec![&mut fill_bot];
+            fillers_and_borrowers.extend(borrowers.iter_mut());
+
+            let effects = {
+                let queue = &mut *redemptions_queue.borrow_mut();
+                let utils::TestRedemptionOrder {
+                    order: redemption_order,
+                    candidate_users,
+                } = &mut queue.orders[0];
+                redemption_operations::clear_redemption_order(
+                    order.order_id,
+                    &mut order.redeemer,
+                    &mut clearer,
+                    &mut market,
+                    &mut StakingPoolState::default(),
+                    &mut queue.queue,
+                    redemption_order,
+                    candidate_users,
+                    &mut fillers_and_borrowers,
+                    &[],
+                    &mut utils::new_sorted_troves(&[], &prices).borrow_mut(),
+                    &clear_prices,
+                    now_timestamp + REDEMPTIONS_SECONDS_TO_FILL_ORDER + 1,
+                )
+                .unwrap()
+            };
+
+            // Either way the order is done with
+            utils::assert_order_cleared(redemptions_queue.clone(), 0);
+            assert_eq!(redemptions_queue.borrow().queue.outstanding_amount, 0);
+
+            if refunded {
+                assert_eq!(effects.refunded_stablecoin, redeem_amt);
+                assert_eq!(effects.redeemed_stablecoin, 0);
+                assert!(order.redeemer.inactive_collateral.is_zero());
+                for (after, before) in borrowers.iter().zip(borrowers_snapshot.iter()) {
+                    assert_eq!(after.borrowed_stablecoin, before.borrowed_stablecoin);
+                    assert_eq!(after.deposited_collateral, before.deposited_collateral);
+                }
+            } else {
+                assert_eq!(effects.refunded_stablecoin, 0);
+                assert_eq!(effects.redeemed_stablecoin, redeem_amt);
+                assert!(
+                    CollateralInfo::calc_market_value_usdh(
+                        &clear_prices,
+                        &order.redeemer.inactive_collateral
+                    ) >= min_collateral_value_usdh
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_instant_redeem_slippage() {
+        // The redemption fee goes above the floor, so nothing
+        // comes back at full value
+        for (max_fee_bps, min_collateral_value_usdh, expected) in [
+            (
+                REDEMPTION_FEE_FLOOR,
+                None,
+                BorrowError::RedemptionFeeAboveMaximum,
+            ),
+            (
+                MAX_REDEMPTION_FEE,
+                Some(USDH::from(2500.0)),
+                BorrowError::RedemptionCollateralBelowMinimum,
+            ),
+        ] {
+            let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+                setup_redemption_borrowing_program();
+
+            let count = 10;
+            let now_timestamp = 0;
+            let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+                count,
+                (0..count).map(|i| ((i + 1) as f64) * 100.0).collect(),
+                &mut market,
+                &mut staking_pool_state,
+                1500.0,
+                now_timestamp,
+            );
+            let sorted_troves = utils::new_sorted_troves(&borrowers, &prices);
+            let mut redeemer = new_approved_user(&mut market);
+
+            let res = redemption_operations::instant_redeem(
+                &mut redeemer,
+                &mut market,
+                &mut staking_pool_state,
+                &redemptions_queue.borrow().queue,
+                &mut sorted_troves.borrow_mut(),
+                &mut borrowers.iter_mut().take(2).collect::<Vec<_>>(),
+                &prices,
+                now_timestamp,
+                USDH::from(2500.0),
+                max_fee_bps,
+                min_collateral_value_usdh,
+                None,
+                false,
+            );
+            assert_eq!(res.err().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_calc_collateral_to_redeem_prefers_collateral() {
+        let prices = TokenPrices::new_all(40.0);
+        let deposited = CollateralAmounts::of_token_f64(10.0, CollateralToken::SOL)
+            .add(&CollateralAmounts::of_token_f64(10.0, CollateralToken::ETH));
+        let collateral_value = CollateralInfo::calc_market_value_usdh(&prices, &deposited);
+        let to_redeem = |amount: f64, preferred: Option<CollateralToken>| {
+            redemption_operations::calcs::calc_collateral_to_redeem(
+                &deposited,
+                &prices,
+                USDH::from(amount),
+                collateral_value,
+                preferred,
+            )
+        };
+
+        // Pro rata without a preference
+        assert_eq!(
+            to_redeem(200.0, None),
+            CollateralAmounts::of_token_f64(2.5, CollateralToken::SOL)
+                .add(&CollateralAmounts::of_token_f64(2.5, CollateralToken::ETH))
+        );
+
+        // Enough ETH to cover it all
+        assert_eq!(
+            to_redeem(200.0, Some(CollateralToken::ETH)),
+            CollateralAmounts::of_token_f64(5.0, CollateralToken::ETH)
+        );
+
+        // All of the ETH, the rest from the basket
+        assert_eq!(
+            to_redeem(600.0, Some(CollateralToken::ETH)),
+            CollateralAmounts::of_token_f64(5.0, CollateralToken::SOL)
+                .add(&CollateralAmounts::of_token_f64(10.0, CollateralToken::ETH))
+        );
+
+        // None of it held, falls back to the basket
+        assert_eq!(
+            to_redeem(200.0, Some(CollateralToken::BTC)),
+            to_redeem(200.0, None)
+        );
+    }
+
+    #[test]
+    fn test_redemption_prefers_collateral() {
+        let (mut market, mut staking_pool_state, redemptions_queue, _) =
+            setup_redemption_borrowing_program();
+        let prices = TokenPrices::new_all(40.0);
+
+        // The first two troves round to the same collateral ratio,
+        // only the second one holds ETH
+        let now_timestamp = 0;
+        let sol = |amount| CollateralAmounts::of_token_f64(amount, CollateralToken::SOL);
+        let eth = |amount| CollateralAmounts::of_token_f64(amount, CollateralToken::ETH);
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut staking_pool_state,
+            3,
+            &[USDH::from(1000.0); 3],
+            &[sol(55.0), sol(30.0).add(&eth(30.0)), sol(200.0)],
+            40.0,
+            now_timestamp,
+        );
+
+        let mut order = utils::set_up_redemption_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &prices,
+            USDH::from(2000.0),
+            now_timestamp,
+        );
+        redemptions_queue.borrow_mut().orders[0]
+            .order
+            .set_preferred_collateral(Some(CollateralToken::ETH));
+
+        let mut filler = utils::fill_redemption_order(
+            &order,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            BorrowersFilter::All,
+            now_timestamp,
+        )
+        .unwrap();
+
+        let candidate_ids: Vec<u64> = redemptions_queue.borrow().orders[0].candidate_users[..2]
+            .iter()
+            .map(|candidate| candidate.user_id)
+            .collect();
+        assert_eq!(
+            candidate_ids,
+            vec![borrowers[1].user_id, borrowers[0].user_id]
+        );
+
+        let mut clearer = new_approved_user(&mut market);
+        let mut fillers_and_borrowers: Vec<&mut UserMetadata> = borrowers.iter_mut().collect();
+        fillers_and_borrowers.push(&mut filler);
+        utils::clear_order(
+            order.order_id,
+            &mut order.redeemer,
+            &mut clearer,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut fillers_and_borrowers,
+            now_timestamp + REDEMPTIONS_SECONDS_TO_FILL_ORDER,
+        )
+        .unwrap();
+
+        // The ETH trove is paid off in ETH only, the
+        // SOL trove covers the rest of the order
+        assert_eq!(borrowers[1].borrowed_stablecoin, 0);
+        assert_eq!(borrowers[1].inactive_collateral.sol, sol(30.0).sol);
+        assert!(borrowers[1].inactive_collateral.eth > 0);
+        assert!(order.redeemer.inactive_collateral.eth > 0);
+        assert!(order.redeemer.inactive_collateral.sol > 0);
+        assert!(borrowers[0].borrowed_stablecoin > 0);
+    }
+
+    #[test]
+    fn test_redemption_priority_prefers_collateral_within_band() {
+        // A trove of 100 USDH debt and the given collateral value
+        let candidate = |collateral_value: f64, holds_preferred_collateral: u8| {
+            let collateral_ratio =
+                CollateralInfo::coll_ratio(USDH::from(100.0), USDH::from(collateral_value));
+            CandidateRedemptionUser {
+                status: 1,
+                collateral_ratio: collateral_ratio.try_floor_u64().unwrap(),
+                holds_preferred_collateral,
+                ..Default::default()
+            }
+        };
+        let priority = redemption_operations::calcs::redemption_priority;
+
+        // 190% holding the preferred collateral goes before 120% without it
+        assert!(priority(&candidate(190.0, 1)) < priority(&candidate(120.0, 0)));
+
+        // but not before a trove in a lower band
+        assert!(priority(&candidate(120.0, 0)) < priority(&candidate(210.0, 1)));
+    }
+
+    #[test]
+    fn test_redemption_stakers_fee_in_usdh() {
+        let (mut market, mut staking_pool_state, redemptions_queue, prices) =
+            setup_redemption_borrowing_program();
+
+        let now_timestamp = 0;
+        let (mut borrowers, _) = new_borrowing_users_with_sol_collateral(
+            10,
+            (0..10).map(|i| ((i + 1) as f64) * 100.0).collect(),
+            &mut market,
+            &mut staking_pool_state,
+            1000.0,
+            now_timestamp,
+        );
+        let borrowers_snapshot = borrowers.clone();
+        staking_pool_state.treasury_fee_rate = 1_500;
+        let distributed_before = staking_pool_state.total_distributed_rewards;
+
+        let redemption_amount = USDH::from(2500.0);
+        let mut redeemer = new_approved_user(&mut market);
+        let mut order = RedemptionOrder::default();
+        let AddRedemptionOrderEffects {
+            transfer_stablecoin_amount,
+            escrowed_fee_usdh,
+            ..
+        } = redemption_operations::add_redemption_order(
+            &mut redeemer,
+            &mut redemptions_queue.borrow_mut().queue,
+            &mut order,
+            &mut market,
+            &prices,
+            now_timestamp,
+            redemption_amount,
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            true,
+        )
+        .unwrap();
+
+        // The stakers share is held on top, nothing is paid out yet
+        let base_rate_bps = order.base_rate;
+        assert_eq!(transfer_stablecoin_amount, redemption_amount);
+        assert_eq!(
+            escrowed_fee_usdh,
+            redemption_operations::calcs::calc_stakers_fee_usdh(redemption_amount, base_rate_bps)
+        );
+        assert!(escrowed_fee_usdh > 0);
+        assert_eq!(
+            staking_pool_state.total_distributed_rewards,
+            distributed_before
+        );
+
+        redemptions_queue
+            .borrow_mut()
+            .orders
+            .push(utils::TestRedemptionOrder {
+                order,
+                candidate_users: [CandidateRedemptionUser::default(); 32],
+            });
+        let order = RedemptionOrderInfo {
+            redeemer,
+            order_id: 0,
+        };
+
+        // Two troves cover part of the order, the rest is cancelled
+        let mut filler = utils::fill_redemption_order(
+            &order,
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            BorrowersFilter::Some(vec![0, 1]),
+            now_timestamp,
+        )
+        .unwrap();
+        let filled_amount =
+            borrowers_snapshot[0].borrowed_stablecoin + borrowers_snapshot[1].borrowed_stablecoin;
+
+        let mut redeemer = order.redeemer.clone();
+        let mut clearer = new_approved_user(&mut market);
+        let mut fillers_and_borrowers: Vec<&mut UserMetadata> =
+            borrowers.iter_mut().take(2).collect();
+        fillers_and_borrowers.push(&mut filler);
+        let ClearRedemptionOrderEffects {
+            redeemed_stablecoin,
+            redeemed_collateral,
+            stakers_fee_usdh,
+            treasury_fee_usdh,
+            refunded_stablecoin,
+        } = {
+            let queue = &mut *redemptions_queue.borrow_mut();
+            let utils::TestRedemptionOrder {
+                order: redemption_order,
+                candidate_users,
+            } = &mut queue.orders[0];
+            redemption_operations::clear_redemption_order(
+                order.order_id,
+                &mut redeemer,
+                &mut clearer,
+                &mut market,
+                &mut staking_pool_state,
+                &mut queue.queue,
+                redemption_order,
+                candidate_users,
+                &mut fillers_and_borrowers,
+                &[],
+                &mut utils::new_sorted_troves(&[], &prices).borrow_mut(),
+                &prices,
+                now_timestamp + REDEMPTIONS_SECONDS_TO_FILL_ORDER + 1,
+            )
+            .unwrap()
+        };
+
+        // The fee is charged for the cleared amount only, split with the
+        // treasury, nothing is refunded while the order is still open
+        assert_eq!(redeemed_stablecoin, filled_amount);
+        assert_eq!(refunded_stablecoin, 0);
+        let charged_fee_usdh =
+            redemption_operations::calcs::calc_stakers_fee_usdh(filled_amount, base_rate_bps);
+        assert_eq!(stakers_fee_usdh + treasury_fee_usdh, charged_fee_usdh);
+        assert_eq!(treasury_fee_usdh, charged_fee_usdh * 1_500 / 10_000);
+        assert_eq!(
+            staking_pool_state.total_distributed_rewards - distributed_before,
+            stakers_fee_usdh as u128
+        );
+
+        // The rest of the escrow, rounding included, goes back on cancel
+        let cancelled = utils::cancel_order(
+            order.order_id,
+            &order.redeemer,
+            &mut redemptions_queue.borrow_mut(),
+            now_timestamp + REDEMPTION_ORDER_EXPIRY_SECONDS,
+        )
+        .unwrap();
+        assert_eq!(
+            cancelled.refunded_stablecoin,
+            redemption_amount - filled_amount + escrowed_fee_usdh - charged_fee_usdh
+        );
+        utils::assert_order_cleared(redemptions_queue.clone(), 0);
+
+        // No collateral is left for the stakers
+        assert!(redeemed_collateral.stakers.is_zero());
+        assert_eq!(
+            redeemed_collateral.redeemer,
+            redeemed_collateral
+                .total
+                .sub(&redeemed_collateral.filler)
+                .sub(&redeemed_collateral.clearer)
+        );
+    }
+
+    #[test]
+    fn test_simulate_redemption_matches_clearing() {
+        // Three troves of 2000 USDH at 199%, 298% and 398%, redeeming
+        // 3000 USDH fully redeems the first and part of the second one
+
+        let prices = 2.0;
+
+        let (mut market, mut spool, redemptions_queue, _prices) =
+            setup_redemption_borrowing_program_with_prices(prices);
+
+        let now_timestamp = 0;
+        let redeem_amt = USDH::from(3000.0);
+        let collaterals: Vec<CollateralAmounts> = (0..3)
+            .map(|i| {
+                CollateralAmounts::of_token(
+                    SOL::from(((i + 2) as f64) * 1000.0),
+                    CollateralToken::SOL,
+                )
+            })
+            .collect();
+
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut spool,
+            3,
+            &[USDH::from(2000.0); 3],
+            &collaterals,
+            prices,
+            now_timestamp,
+        );
+        let borrowers_before = borrowers.clone();
+        let market_before = market.clone();
+
+        let (_, _, _, redeemed_stablecoin, redeemed_collateral) = add_fill_and_clear_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            &TokenPrices::new(prices),
+            redeem_amt,
+            now_timestamp,
+        )
+        .unwrap();
+
+        // The order as it was before clearing
+        let order = RedemptionOrder {
+            status: RedemptionOrderStatus::Filling.into(),
+            base_rate: market.base_rate_bps,
+            requested_amount: redeem_amt,
+            remaining_amount: redeem_amt,
+            redemption_prices: TokenPrices::new(prices),
+            ..Default::default()
+        };
+        let simulation = redemption_operations::simulate_redemption(
+            &market_before,
+            &order,
+            &borrowers_before,
+            now_timestamp,
+        )
+        .unwrap();
+
+        assert_eq!(simulation.redeemed_stablecoin, redeemed_stablecoin);
+        assert_eq!(
+            simulation.redeemed_collateral.redeemer,
+            redeemed_collateral.redeemer
+        );
+        assert_eq!(
+            simulation.redeemed_collateral.filler,
+            redeemed_collateral.filler
+        );
+        assert_eq!(
+            simulation.redeemed_collateral.clearer,
+            redeemed_collateral.clearer
+        );
+        assert_eq!(
+            simulation.redeemed_collateral.stakers,
+            redeemed_collateral.stakers
+        );
+
+        assert_eq!(simulation.troves.len(), 2);
+        for (trove, i) in simulation.troves.iter().zip([0, 1]) {
+            assert_eq!(trove.user_metadata, borrowers[i].metadata_pk);
+            assert_eq!(
+                trove.debt_redeemed,
+                borrowers_before[i].borrowed_stablecoin - borrowers[i].borrowed_stablecoin
+            );
+        }
+        assert_eq!(
+            simulation.troves[1].collateral_redeemed,
+            borrowers_before[1]
+                .deposited_collateral
+                .sub(&borrowers[1].deposited_collateral)
+        );
+
+        // Nothing was written back
+        assert_eq!(market_before.stablecoin_borrowed, USDH::from(2010.0) * 3);
+    }
+
+    #[test]
+    fn test_redemption_assert_user_stake_and_total_stakes_updated() {
+        // - [x] redemption: _updateStakeAndTotalStakes
+        // Everyone deposits 1000 SOL
+        // Everyone has a debt of 1000.0 USDH
+        // SOL/USD price is 1.09
+        // Such that everyone is at 109% CR
+        // But there is a whale that keeps the system well collateralized
+        // We redeem 2000 from the first two users
+        // one with 1005 USDH and one with 1005 USDH
+        // the first one remains with 0, the second one remains with 1005 - (2000 - 1005) = 10
+        // expecting total system stake to reduce by 2000
+        // and user stake to reduce by 1005 and 995
+
+        let prices = 2.0;
+
+        let (mut market, mut spool, redemptions_queue, _prices) =
+            setup_redemption_borrowing_program_with_prices(prices);
+
+        let now_timestamp = 0;
+        let whale_deposit = SOL::from(1000.0 * 100.0);
+        let borrow_amt = USDH::from(1000.0);
+        let num = 100;
+        let collaterals: Vec<CollateralAmounts> = (0..num)
+            .map(|i| {
+                CollateralAmounts::of_token(
+                    SOL::from(((i + 1) as f64) * 1000.0),
+                    CollateralToken::SOL,
+                )
+            })
+            .collect();
+        let redeem_amt = USDH::from(2000.0);
+        let borrow_split = BorrowSplit::from_amount(borrow_amt, market.base_rate_bps);
+        let borrow_splits = vec![borrow_amt; num];
+
+        let (_whale, _) = new_borrower(
+            &mut market,
+            &mut spool,
+            whale_deposit,
+            borrow_amt,
+            &TokenPrices::new(prices),
+            now_timestamp,
+        );
+
+        assert_eq!(_whale.user_stake, borrow_split.amount_to_borrow);
+
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut spool,
+            num,
+            &borrow_splits,
+            &collaterals,
+            prices,
+            now_timestamp,
+        );
+        borrowers
+            .iter()
+            .for_each(|u| assert_eq!(u.user_stake, borrow_split.amount_to_borrow));
+
+        let _ = add_fill_and_clear_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            &TokenPrices::new(prices),
+            redeem_amt,
+            now_timestamp,
+        )
+        .unwrap();
+
+        assert_eq!(borrowers[0].user_stake, 0);
+        assert_eq!(borrowers[1].user_stake, USDH::from(10.0));
+        assert_eq!(
+            market.total_stake,
+            borrow_split.amount_to_borrow * ((num + 1) as u64) - redeem_amt // including whale
+        );
+    }
+
+    #[test]
+    fn test_redemption_fully_redeemed_trove_claims_surplus() {
+        // Two users borrow 2000 USDH against 2000 and 4000 SOL
+        // We redeem 2010 USDH which pays off the first trove entirely
+        // The first trove becomes Redeemed, without stake, and
+        // can claim its leftover SOL and close its account
+
+        let prices = 2.0;
+
+        let (mut market, mut spool, redemptions_queue, _prices) =
+            setup_redemption_borrowing_program_with_prices(prices);
+
+        let now_timestamp = 0;
+        let borrow_amt = USDH::from(2000.0);
+        let num = 2;
+        let collaterals: Vec<CollateralAmounts> = (0..num)
+            .map(|i| {
+                CollateralAmounts::of_token(
+                    SOL::from(((i + 1) as f64) * 2000.0),
+                    CollateralToken::SOL,
+                )
+            })
+            .collect();
+        let borrow_split = BorrowSplit::from_amount(borrow_amt, market.base_rate_bps);
+        let borrow_splits = vec![borrow_amt; num];
+
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut spool,
+            num,
+            &borrow_splits,
+            &collaterals,
+            prices,
+            now_timestamp,
+        );
+
+        let num_active_users_before = market.num_active_users;
+        for borrower in borrowers.iter() {
+            sorted_troves::reposition_trove(
+                &mut redemptions_queue.borrow_mut().sorted_troves,
+                borrower,
+                &TokenPrices::new(prices),
+            )
+            .unwrap();
+        }
+
+        let _ = add_fill_and_clear_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            &TokenPrices::new(prices),
+            borrow_split.amount_to_borrow,
+            now_timestamp,
+        )
+        .unwrap();
+
+        let surplus = borrowers[0].inactive_collateral.sol;
+        assert_eq!(borrowers[0].status, UserStatus::Redeemed as u8);
+        assert_eq!(borrowers[0].user_stake, 0);
+        assert_eq!(borrowers[0].borrowed_stablecoin, 0);
+        assert!(borrowers[0].deposited_collateral.is_zero());
+        assert!(surplus > 0);
+        assert_eq!(borrowers[1].status, UserStatus::Active as u8);
+        assert_eq!(market.num_active_users, num_active_users_before - 1);
+        assert_eq!(market.total_stake, borrow_split.amount_to_borrow);
+
+        // The redeemed trove left the index, the other one is still in it
+        let sorted_user_ids: Vec<u64> = {
+            let queue = redemptions_queue.borrow();
+            queue.sorted_troves.troves[..sorted_troves::len(&queue.sorted_troves)]
+                .iter()
+                .map(|trove| trove.user_id)
+                .collect()
+        };
+        assert_eq!(sorted_user_ids, vec![borrowers[1].user_id]);
+
+        // Only fully redeemed troves have a surplus to claim
+        let res = borrowing_operations::claim_redemption_surplus(
+            &mut market,
+            &mut borrowers[1],
+            CollateralToken::SOL,
+            true,
+        );
+        assert_eq!(res.err(), Some(BorrowError::TroveNotRedeemed));
+
+        // Nothing to claim in other tokens, the account stays open
+        let market_inactive_before = market.inactive_collateral.sol;
+        let effects = borrowing_operations::claim_redemption_surplus(
+            &mut market,
+            &mut borrowers[0],
+            CollateralToken::ETH,
+            true,
+        )
+        .unwrap();
+        assert!(effects.collateral_to_transfer_to_user.is_zero());
+        assert!(!effects.close_user_metadata);
+        assert_eq!(borrowers[0].status, UserStatus::Redeemed as u8);
+
+        let effects = borrowing_operations::claim_redemption_surplus(
+            &mut market,
+            &mut borrowers[0],
+            CollateralToken::SOL,
+            true,
+        )
+        .unwrap();
+        assert_eq!(effects.collateral_to_transfer_to_user.sol, surplus);
+        assert!(effects.close_user_metadata);
+        assert!(borrowers[0].inactive_collateral.is_zero());
+        assert_eq!(borrowers[0].status, UserStatus::Inactive as u8);
+        assert_eq!(
+            market.inactive_collateral.sol,
+            market_inactive_before - surplus
+        );
+    }
+
+    #[test]
+    fn test_redemption_lowest_interest_rate_first() {
+        // Two users borrow 2000 USDH against 2000 and 4000 SOL
+        // The riskier one pays 5% interest, the safer one nothing
+        // so the safer one is redeemed against first
+
+        let prices = 2.0;
+
+        let (mut market, mut spool, redemptions_queue, _prices) =
+            setup_redemption_borrowing_program_with_prices(prices);
+
+        let now_timestamp = 0;
+        let borrow_amt = USDH::from(2000.0);
+        let collaterals = vec![
+            CollateralAmounts::of_token(SOL::from(2000.0), CollateralToken::SOL),
+            CollateralAmounts::of_token(SOL::from(4000.0), CollateralToken::SOL),
+        ];
+
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut spool,
+            2,
+            &[borrow_amt; 2],
+            &collaterals,
+            prices,
+            now_timestamp,
+        );
+        borrowing_operations::set_interest_rate(&mut market, &mut borrowers[0], 500, now_timestamp)
+            .unwrap();
+        let debts: Vec<u64> = borrowers.iter().map(|b| b.borrowed_stablecoin).collect();
+
+        let _ = add_fill_and_clear_order(
+            &mut market,
+            &mut redemptions_queue.borrow_mut(),
+            &mut borrowers,
+            &TokenPrices::new(prices),
+            borrow_amt,
+            now_timestamp,
+        )
+        .unwrap();
+
+        // Only interest was added to the riskier trove
+        let interest = borrowing_rate::calc_accrued_interest(
+            debts[0],
+            500,
+            now_timestamp,
+            borrowers[0].last_interest_accrual,
+        );
+        assert_eq!(borrowers[0].borrowed_stablecoin, debts[0] + interest);
+        assert_eq!(borrowers[1].borrowed_stablecoin, debts[1] - borrow_amt);
+    }
+
+    #[test]
+    fn test_redemption_assert_pending_rewards_applied() {
+        // There is a liquidation event, users get redistributed an amount
+        // then there is a redemption event
+        // and users get redeemed including the redistributed amount
+
+        // 1 borrower whale with 1000 usdh & 100000000 sol
+        // 3 borrowers with 1000 usdh & 1000, 2000, 3000 sol
+        // stability pool is empty
+        // borrower[0] gets liquidated
+        // the other two guys should receive
+
+        let mut market = BorrowingMarketState::new();
+        let mut stability_pool_state = StabilityPoolState::default();
+        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
+        let liquidations = RefCell::new(LiquidationsQueue::default());
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let redemptions_queue = RefCell::new(TestRedemptionsQueue::default());
+
+        let prices_at_beginning = 2.0;
+        let prices_at_liquidation = 1.09;
+        let prices_at_redemption = 1.0;
+        let whale_deposit = SOL::from(100000.0);
+        let redeem_amt = USDH::from(2000.0);
+        let now_timestamp = 0;
+        let borrow_amt = USDH::from(1000.0);
+        let num = 3;
+        let collaterals: Vec<CollateralAmounts> = [1000.0, 2000.0, 3000.0]
+            .iter()
+            .map(|amt| CollateralAmounts::of_token(SOL::from(*amt), CollateralToken::SOL))
+            .collect();
+        let borrow_split = BorrowSplit::from_amount(borrow_amt, market.base_rate_bps);
+        let borrow_amounts = vec![borrow_amt; num];
+
+        let (mut whale, _) = new_borrower(
+            &mut market,
+            &mut staking_pool_state,
+            whale_deposit,
+            borrow_amt,
+            &TokenPrices::new(prices_at_beginning),
+            now_timestamp,
+        );
+
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut staking_pool_state,
+            num,
+            &borrow_amounts,
+            &collaterals,
+            prices_at_beginning,
+            now_timestamp,
+        );
+
+        let liquidator = Pubkey::new_unique();
+        borrowing_operations::try_liquidate(
+            liquidator,
+            &mut market,
+            &mut borrowers[0],
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            &TokenPrices::new(prices_at_liquidation),
+            &mut liquidations.borrow_mut(),
+            now_timestamp,
+        )
+        .unwrap();
+
+        borrowing_operations::refresh_positions(&mut market, &mut borrowers[0]).unwrap();
+        borrowing_operations::refresh_positions(&mut market, &mut borrowers[1]).unwrap();
+        borrowing_operations::refresh_positions(&mut market, &mut borrowers[2]).unwrap();
+        borrowing_operations::refresh_positions(&mut market, &mut whale).unwrap();
+
+        // TODO: think about inactive collateral & redsitributeion & total stake
+
+        // Liquidating 1005 USDH and 1000 SOL ->
+        // 1005 / 3  = 335
+        // 1000 - 0.005% (for liquidator) = 995 / 3 = 331.6666666666667
+
+        // borrowers have 1005 + 335 = 1340 (1339.999)
 
         // borrower 0 has 0 debt 0 coll
         // borrower 1 has 1339999999 debt 2331666666666 coll
@@ -3023,7 +4489,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
-        let redemptions_queue = RefCell::new(RedemptionsQueue::default());
+        let redemptions_queue = RefCell::new(TestRedemptionsQueue::default());
 
         let prices_at_beginning = 2.0;
         let prices_at_liquidation = 1.09;
@@ -3300,21 +4766,28 @@ mod property_tests {
 pub(crate) mod utils {
 
     use std::convert::TryInto;
-    use std::ops::SubAssign;
+    use std::ops::{Deref, SubAssign};
     use std::{borrow::BorrowMut, cell::RefMut};
 
     use crate::borrowing_market::borrowing_operations::utils::set_addresses;
     use crate::borrowing_market::borrowing_rate::BorrowSplit;
     use crate::redemption::redemption_operations;
     use crate::redemption::redemption_operations::calcs::split_redemption_collateral;
-    use crate::redemption::types::{ClearRedemptionOrderEffects, RedemptionCollateralSplit};
+    use crate::redemption::types::{
+        AddRedemptionOrderEffects, CancelRedemptionOrderEffects, ClearRedemptionOrderEffects,
+        RedemptionCollateralSplit,
+    };
     use crate::state::redemptions_queue::{RedemptionCandidateStatus, RedemptionOrderStatus};
-    use crate::utils::consts::REDEMPTIONS_SECONDS_TO_FILL_ORDER;
+    use crate::utils::consts::{
+        MAX_REDEMPTION_FEE, REDEMPTIONS_SECONDS_TO_FILL_ORDER, REDEMPTION_ORDER_EXPIRY_SECONDS,
+    };
     use crate::utils::coretypes::USDH;
     use crate::utils::finance::CollateralInfo;
     use crate::{
+        borrowing_market::sorted_troves,
         borrowing_market::tests_utils::utils::new_borrowing_users_with_amounts,
-        state::CollateralToken, BorrowingMarketState, CollateralAmounts, RedemptionsQueue,
+        state::{CandidateRedemptionUser, CollateralToken, RedemptionOrder},
+        BorrowError, BorrowingMarketState, CollateralAmounts, RedemptionsQueue, SortedTroves,
         StakingPoolState, TokenPrices, UserMetadata,
     };
 
@@ -3328,10 +4801,152 @@ pub(crate) mod utils {
 
     use super::tests::RedemptionOrderInfo;
 
+    // The queue account together with the order accounts it opened,
+    // each with a single page of candidates, indexed by order id,
+    // and the index of sorted troves clearing repositions
+    #[derive(Clone, Default)]
+    pub struct TestRedemptionsQueue {
+        pub queue: RedemptionsQueue,
+        pub orders: Vec<TestRedemptionOrder>,
+        pub sorted_troves: Box<SortedTroves>,
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct TestRedemptionOrder {
+        pub order: RedemptionOrder,
+        pub candidate_users: [CandidateRedemptionUser; 32],
+    }
+
+    impl Deref for TestRedemptionOrder {
+        type Target = RedemptionOrder;
+
+        fn deref(&self) -> &RedemptionOrder {
+            &self.order
+        }
+    }
+
+    pub fn add_order(
+        redeemer: &mut UserMetadata,
+        redemptions_queue: &mut TestRedemptionsQueue,
+        market: &mut BorrowingMarketState,
+        prices: &TokenPrices,
+        now_timestamp: u64,
+        redemption_amount: u64,
+    ) -> Result<AddRedemptionOrderEffects, BorrowError> {
+        let mut order = RedemptionOrder::default();
+        let effects = redemption_operations::add_redemption_order(
+            redeemer,
+            &mut redemptions_queue.queue,
+            &mut order,
+            market,
+            prices,
+            now_timestamp,
+            redemption_amount,
+            MAX_REDEMPTION_FEE,
+            None,
+            None,
+            false,
+        )?;
+
+        redemptions_queue.orders.push(TestRedemptionOrder {
+            order,
+            candidate_users: [CandidateRedemptionUser::default(); 32],
+        });
+
+        Ok(effects)
+    }
+
+    pub fn fill_order(
+        order_id: u64,
+        market: &mut BorrowingMarketState,
+        redemptions_queue: &mut TestRedemptionsQueue,
+        user_metadatas: &mut [&mut UserMetadata],
+        filler_metadata: &UserMetadata,
+        now_timestamp: u64,
+    ) -> Result<(), BorrowError> {
+        let TestRedemptionOrder {
+            order,
+            candidate_users,
+        } = redemptions_queue
+            .orders
+            .get_mut(order_id as usize)
+            .ok_or(BorrowError::InvalidRedemptionOrder)?;
+
+        redemption_operations::fill_redemption_order(
+            order_id,
+            market,
+            order,
+            candidate_users,
+            user_metadatas,
+            filler_metadata,
+            now_timestamp,
+        )
+    }
+
+    pub fn clear_order(
+        order_id: u64,
+        redeemer: &mut UserMetadata,
+        clearer: &mut UserMetadata,
+        market: &mut BorrowingMarketState,
+        redemptions_queue: &mut TestRedemptionsQueue,
+        fillers_and_borrowers: &mut [&mut UserMetadata],
+        now_timestamp: u64,
+    ) -> Result<ClearRedemptionOrderEffects, BorrowError> {
+        let TestRedemptionOrder {
+            order,
+            candidate_users,
+        } = redemptions_queue
+            .orders
+            .get_mut(order_id as usize)
+            .ok_or(BorrowError::InvalidRedemptionOrder)?;
+        let prices = order.redemption_prices;
+
+        redemption_operations::clear_redemption_order(
+            order_id,
+            redeemer,
+            clearer,
+            market,
+            &mut StakingPoolState::default(),
+            &mut redemptions_queue.queue,
+            order,
+            candidate_users,
+            fillers_and_borrowers,
+            &[],
+            &mut redemptions_queue.sorted_troves,
+            &prices,
+            now_timestamp,
+        )
+    }
+
+    pub fn cancel_order(
+        order_id: u64,
+        redeemer: &UserMetadata,
+        redemptions_queue: &mut TestRedemptionsQueue,
+        now_timestamp: u64,
+    ) -> Result<CancelRedemptionOrderEffects, BorrowError> {
+        let TestRedemptionOrder {
+            order,
+            candidate_users,
+        } = redemptions_queue
+            .orders
+            .get_mut(order_id as usize)
+            .ok_or(BorrowError::InvalidRedemptionOrder)?;
+
+        redemption_operations::cancel_redemption_order(
+            order_id,
+            redeemer,
+            &mut redemptions_queue.queue,
+            order,
+            candidate_users,
+            REDEMPTION_ORDER_EXPIRY_SECONDS,
+            now_timestamp,
+        )
+    }
+
     pub fn setup_redemption_borrowing_program() -> (
         BorrowingMarketState,
         StakingPoolState,
-        RefCell<RedemptionsQueue>,
+        RefCell<TestRedemptionsQueue>,
         TokenPrices,
     ) {
         setup_redemption_borrowing_program_with_prices(40.0)
@@ -3342,7 +4957,7 @@ pub(crate) mod utils {
     ) -> (
         BorrowingMarketState,
         StakingPoolState,
-        RefCell<RedemptionsQueue>,
+        RefCell<TestRedemptionsQueue>,
         TokenPrices,
     ) {
         let mut market = BorrowingMarketState::new();
@@ -3351,7 +4966,7 @@ pub(crate) mod utils {
             ..Default::default()
         };
 
-        let redemptions_queue = RefCell::new(RedemptionsQueue::default());
+        let redemptions_queue = RefCell::new(TestRedemptionsQueue::default());
         let prices = TokenPrices::new(price);
 
         borrowing_operations::initialize_borrowing_market(&mut market, 0);
@@ -3393,13 +5008,13 @@ pub(crate) mod utils {
 
     pub fn new_redemption_order(
         redeemer: &mut UserMetadata,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         prices: &TokenPrices,
         market: &mut BorrowingMarketState,
         amount: u64,
         now_timestamp: u64,
     ) -> u64 {
-        redemption_operations::add_redemption_order(
+        add_order(
             redeemer,
             &mut redemptions_queue.borrow_mut(),
             market,
@@ -3413,7 +5028,7 @@ pub(crate) mod utils {
 
     pub fn new_redemption_orders(
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         prices: &TokenPrices,
         amounts: Vec<u64>,
         now_timestamp: u64,
@@ -3437,14 +5052,14 @@ pub(crate) mod utils {
 
     pub fn fill_redemption_order_new_bot(
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         order_id: u64,
         user_metadatas: &mut Vec<&mut UserMetadata>,
         now: u64,
     ) -> UserMetadata {
         let fill_bot = new_approved_user(market);
 
-        let res = redemption_operations::fill_redemption_order(
+        let res = fill_order(
             order_id,
             market,
             &mut redemptions_queue.borrow_mut(),
@@ -3462,7 +5077,7 @@ pub(crate) mod utils {
 
     pub fn fill_redemption_order_new_fillers(
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         order_id: u64,
         mut user_metadatas: Vec<Vec<&mut UserMetadata>>,
         now_timestamp: u64,
@@ -3494,7 +5109,7 @@ pub(crate) mod utils {
 
     pub fn set_up_redemption_order(
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         prices: &TokenPrices,
         redeem_amount: u64,
         now_timestamp: u64,
@@ -3515,7 +5130,7 @@ pub(crate) mod utils {
     pub fn fill_redemption_order(
         order: &RedemptionOrderInfo,
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         borrowers: &mut Vec<UserMetadata>,
         borrowers_filter: BorrowersFilter,
         now_timestamp: u64,
@@ -3532,7 +5147,7 @@ pub(crate) mod utils {
 
         let fill_bot = new_approved_user(market);
         for users in submitted_users.chunks_mut(5) {
-            redemption_operations::fill_redemption_order(
+            fill_order(
                 order.order_id,
                 market,
                 redemptions_queue,
@@ -3547,7 +5162,7 @@ pub(crate) mod utils {
 
     pub fn set_up_filled_redemption_order(
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         borrowers: &mut Vec<UserMetadata>,
         borrowers_filter: BorrowersFilter,
         prices: &TokenPrices,
@@ -3589,7 +5204,7 @@ pub(crate) mod utils {
 
     pub fn add_fill_and_clear_order(
         market: &mut BorrowingMarketState,
-        redemptions_queue: &mut RefMut<RedemptionsQueue>,
+        redemptions_queue: &mut RefMut<TestRedemptionsQueue>,
         borrowers: &mut Vec<UserMetadata>,
         prices: &TokenPrices,
         redeem_amount: u64,
@@ -3626,7 +5241,8 @@ pub(crate) mod utils {
         let ClearRedemptionOrderEffects {
             redeemed_stablecoin,
             redeemed_collateral,
-        } = redemption_operations::clear_redemption_order(
+            ..
+        } = clear_order(
             order.order_id,
             &mut order.redeemer,
             &mut clearer,
@@ -3721,7 +5337,7 @@ pub(crate) mod utils {
                         stakers,
                         redeemer,
                         ..
-                    } = split_redemption_collateral(&redeemed_collateral, base_rate);
+                    } = split_redemption_collateral(&redeemed_collateral, base_rate, false);
 
                     updated_borrowers.push(borrower);
 
@@ -3739,6 +5355,18 @@ pub(crate) mod utils {
         )
     }
 
+    pub fn new_sorted_troves(
+        borrowers: &[UserMetadata],
+        prices: &TokenPrices,
+    ) -> RefCell<SortedTroves> {
+        let sorted_troves = RefCell::new(SortedTroves::default());
+        for borrower in borrowers.iter() {
+            sorted_troves::reposition_trove(&mut sorted_troves.borrow_mut(), borrower, prices)
+                .unwrap();
+        }
+        sorted_troves
+    }
+
     pub fn new_approved_user(market: &mut BorrowingMarketState) -> UserMetadata {
         let mut user = UserMetadata::default();
         let user_pubkey = Pubkey::new_unique();
@@ -3793,16 +5421,16 @@ pub(crate) mod utils {
     }
 
     pub fn assert_order_status(
-        redemptions_queue: RefCell<RedemptionsQueue>,
+        redemptions_queue: RefCell<TestRedemptionsQueue>,
         idx: usize,
         mode: RedemptionOrderStatus,
     ) {
         assert_eq!(redemptions_queue.borrow().orders[idx].status, mode as u8);
     }
-    pub fn assert_order_cleared(redemptions_queue: RefCell<RedemptionsQueue>, idx: usize) {
+    pub fn assert_order_cleared(redemptions_queue: RefCell<TestRedemptionsQueue>, idx: usize) {
         assert_order_status(redemptions_queue, idx, RedemptionOrderStatus::Inactive);
     }
-    pub fn assert_order_open(redemptions_queue: RefCell<RedemptionsQueue>, idx: usize) {
+    pub fn assert_order_open(redemptions_queue: RefCell<TestRedemptionsQueue>, idx: usize) {
         assert_order_status(redemptions_queue, idx, RedemptionOrderStatus::Open);
     }
 
@@ -3906,7 +5534,7 @@ pub(crate) mod utils {
         }
     }
 
-    pub fn assert_queue_is_empty(redemptions_queue: RefCell<RedemptionsQueue>) {
+    pub fn assert_queue_is_empty(redemptions_queue: RefCell<TestRedemptionsQueue>) {
         // ensure queue is empty
         for order in redemptions_queue.borrow().orders.iter() {
             assert_eq!(order.status, RedemptionOrderStatus::Inactive as u8);
@@ -3914,7 +5542,7 @@ pub(crate) mod utils {
     }
 
     pub fn assert_pending_active_users(
-        redemptions_queue: RefCell<RedemptionsQueue>,
+        redemptions_queue: RefCell<TestRedemptionsQueue>,
         ix: usize,
         num: usize,
     ) {
@@ -3946,7 +5574,10 @@ pub(crate) mod utils {
         assert_eq!(expected_redeemed_amount, debt_before - debt_after);
     }
 
-    pub fn print_candidate_users(redemptions_queue: RefCell<RedemptionsQueue>, order_id: usize) {
+    pub fn print_candidate_users(
+        redemptions_queue: RefCell<TestRedemptionsQueue>,
+        order_id: usize,
+    ) {
         for (i, candidate) in redemptions_queue.borrow().orders[order_id]
             .candidate_users
             .iter()
@@ -3962,13 +5593,13 @@ pub(crate) mod utils {
         }
     }
 
-    pub fn print_order(prefix: &str, redemptions_queue: RefCell<RedemptionsQueue>, idx: usize) {
+    pub fn print_order(prefix: &str, redemptions_queue: RefCell<TestRedemptionsQueue>, idx: usize) {
         let order = redemptions_queue.borrow().orders[idx];
         println!("{} {}", prefix, order.to_state_string());
     }
 
     pub fn assert_num_active_candidates(
-        redemptions_queue: RefCell<RedemptionsQueue>,
+        redemptions_queue: RefCell<TestRedemptionsQueue>,
         idx: usize,
         num: usize,
     ) {
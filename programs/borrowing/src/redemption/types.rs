@@ -1,3 +1,5 @@
+use anchor_lang::prelude::Pubkey;
+
 use crate::CollateralAmounts;
 
 #[derive(Debug, Default, Clone)]
@@ -30,10 +32,43 @@ pub struct RedemptionFillingResults {
 pub struct AddRedemptionOrderEffects {
     pub redemption_order_id: u64,
     pub transfer_stablecoin_amount: u64,
+    pub escrowed_fee_usdh: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct ClearRedemptionOrderEffects {
     pub redeemed_stablecoin: u64,
     pub redeemed_collateral: RedemptionCollateralSplit,
+    pub stakers_fee_usdh: u64,
+    pub treasury_fee_usdh: u64,
+    pub refunded_stablecoin: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CancelRedemptionOrderEffects {
+    pub refunded_stablecoin: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedTroveRedemption {
+    pub user_metadata: Pubkey,
+    pub debt_redeemed: u64,
+    pub collateral_redeemed: CollateralAmounts,
+}
+
+#[derive(Debug, Clone)]
+pub struct RedemptionSimulation {
+    pub redemption_fee_bps: u16,
+    pub redeemed_stablecoin: u64,
+    pub redeemed_collateral: RedemptionCollateralSplit,
+    pub troves: Vec<SimulatedTroveRedemption>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstantRedemptionEffects {
+    pub redeemed_stablecoin: u64,
+    pub redeemed_collateral: RedemptionCollateralSplit,
+    pub collateral_to_transfer_to_redeemer: CollateralAmounts,
+    pub stakers_fee_usdh: u64,
+    pub treasury_fee_usdh: u64,
 }
@@ -2,6 +2,9 @@ pub mod liquidations_queue;
 pub mod stability_pool_operations;
 pub mod tests_hbb_issuance;
 pub mod tests_liquidations_queue;
+pub mod tests_lockup;
+pub mod tests_receipts;
 pub mod tests_stability_pool;
+pub mod tests_tranches;
 pub mod tests_utils;
 pub mod types;
@@ -1,21 +1,23 @@
-use std::cell::RefMut;
+use std::{cell::RefMut, convert::TryFrom};
 
 use super::liquidations_queue;
 use super::types::{
-    HarvestLiquidationGainsEffects, ProvideStabilityEffects, WithdrawStabilityEffects,
+    HarvestLiquidationGainsEffects, LiquidationRouting, LockStabilityDepositEffects,
+    ProvideStabilityEffects, RequestStabilityWithdrawalEffects, SettleStabilityWithdrawalEffects,
+    TransferStabilityReceiptsEffects, WithdrawStabilityEffects,
 };
 use crate::stability_pool::types::RewardDistributionCalculation;
 use crate::state::epoch_to_scale_to_sum::EpochToScaleToSum;
-use crate::utils::consts::{DECIMAL_PRECISION, HBB_FACTOR, ONE};
+use crate::utils::consts::{ALL_COLLATERAL_COVERAGE, DECIMAL_PRECISION, HBB_FACTOR, ONE};
 use crate::StabilityTokenMap;
 use crate::U256;
 
 #[allow(unused_imports)]
 use crate::msg;
-use crate::state::StabilityToken;
+use crate::state::{CollateralToken, LockupTier, StabilityToken};
 use crate::{
-    fail, BorrowError, CollateralAmounts, LiquidationsQueue, StabilityCollateralAmounts,
-    StabilityPoolState, StabilityProviderState,
+    fail, BorrowError, CollateralAmounts, HbbEmissionSchedule, LiquidationsQueue,
+    StabilityCollateralAmounts, StabilityPoolState, StabilityProviderState, TokenPrices,
 };
 use anchor_lang::prelude::ProgramError;
 
@@ -27,6 +29,9 @@ pub fn initialize_stability_pool(
     stability_pool_state.version = 0;
     stability_pool_state.num_users = 0;
     stability_pool_state.stablecoin_deposited = 0;
+    stability_pool_state.boosted_stablecoin_deposited = 0;
+    stability_pool_state.stablecoin_pending_withdrawal = 0;
+    stability_pool_state.receipt_supply = 0;
     stability_pool_state.cumulative_gains_total = StabilityTokenMap::default();
     stability_pool_state.pending_collateral_gains = StabilityTokenMap::default();
     stability_pool_state.current_epoch = 0;
@@ -37,10 +42,24 @@ pub fn initialize_stability_pool(
     stability_pool_state.last_coll_loss_error_offset = StabilityCollateralAmounts::default();
 
     stability_pool_state.hbb_emissions_start_ts = hbb_emissions_start_time;
+    stability_pool_state.hbb_emission_schedule = HbbEmissionSchedule::default();
 
     liquidations_queue::initialize_queue(liquidations_queue);
 }
 
+pub fn set_collateral_coverage(
+    stability_pool_state: &mut StabilityPoolState,
+    collateral_coverage: u8,
+) -> Result<(), crate::BorrowError> {
+    if collateral_coverage & !ALL_COLLATERAL_COVERAGE != 0 {
+        fail!(BorrowError::InvalidCollateralCoverage);
+    }
+
+    stability_pool_state.collateral_coverage = collateral_coverage;
+
+    Ok(())
+}
+
 pub fn approve_new_user(
     stability_pool_state: &mut StabilityPoolState,
     stability_provider_state: &mut StabilityProviderState,
@@ -66,12 +85,29 @@ pub fn provide_stability(
 
     // 2. Recalculate pending state
     liquidations_logic::update_pending_gains(stability_provider_state, epoch_to_scale_to_sum)?;
+    liquidations_logic::release_expired_lockup(
+        stability_pool_state,
+        stability_provider_state,
+        compounded_usd_deposit,
+        now_timestamp,
+    );
 
-    // 3. Transfer usd to stability pool
+    // 3. Mint receipts at the current exchange rate
+    let receipts_to_mint =
+        liquidations_logic::mint_receipts(stability_pool_state, stability_provider_state, amount)?;
+
+    // 4. Transfer usd to stability pool
     liquidations_logic::send_usd_to_stability_pool(stability_pool_state, amount)?;
 
-    // 4. Update user deposit and snapshot
+    // 5. Update user deposit and snapshot
     let new_user_usd_deposits = compounded_usd_deposit.checked_add(amount).unwrap();
+    liquidations_logic::update_boosted_deposits(
+        stability_pool_state,
+        stability_provider_state.lockup_tier,
+        compounded_usd_deposit,
+        stability_provider_state.lockup_tier,
+        new_user_usd_deposits,
+    );
     stability_provider_state.deposited_stablecoin = new_user_usd_deposits;
     stability_provider_state.user_deposit_snapshot = liquidations_logic::get_new_user_snapshot(
         stability_pool_state,
@@ -85,6 +121,7 @@ pub fn provide_stability(
 
     Ok(ProvideStabilityEffects {
         usd_to_stability_pool_transfer: amount,
+        receipts_to_mint,
     })
 }
 
@@ -95,6 +132,12 @@ pub fn withdraw_stability(
     amount: u64,
     now_timestamp: u64,
 ) -> Result<WithdrawStabilityEffects, ProgramError> {
+    if stability_provider_state.lockup_expiry_ts > now_timestamp {
+        // locked deposits keep absorbing liquidations
+        // but cannot leave the pool before the lockup expires
+        fail!(BorrowError::StabilityDepositLocked);
+    }
+
     trigger_hbb_issuance(stability_pool_state, epoch_to_scale_to_sum, now_timestamp)?;
 
     // 1. Calculate compounded usd deposit
@@ -105,13 +148,35 @@ pub fn withdraw_stability(
 
     // 2. Recalculate pending state
     liquidations_logic::update_pending_gains(stability_provider_state, epoch_to_scale_to_sum)?;
+    liquidations_logic::release_expired_lockup(
+        stability_pool_state,
+        stability_provider_state,
+        compounded_usd_deposit,
+        now_timestamp,
+    );
 
-    // 3. Send usd back to depositor
+    // 3. Burn the receipts backing the withdrawn amount, liquidations
+    // may have left less than asked for
     let usd_to_withdraw = u64::min(compounded_usd_deposit, amount);
+    let new_user_usd_deposits = compounded_usd_deposit.checked_sub(usd_to_withdraw).unwrap();
+    let receipts_to_burn = liquidations_logic::burn_receipts(
+        stability_pool_state,
+        stability_provider_state,
+        compounded_usd_deposit,
+        new_user_usd_deposits,
+    );
+
+    // 4. Send usd back to depositor
     liquidations_logic::send_usd_to_depositor(stability_pool_state, usd_to_withdraw)?;
 
-    // 4. Update user deposit and snapshot
-    let new_user_usd_deposits = compounded_usd_deposit.checked_sub(amount).unwrap();
+    // 5. Update user deposit and snapshot
+    liquidations_logic::update_boosted_deposits(
+        stability_pool_state,
+        stability_provider_state.lockup_tier,
+        compounded_usd_deposit,
+        stability_provider_state.lockup_tier,
+        new_user_usd_deposits,
+    );
     stability_provider_state.deposited_stablecoin = new_user_usd_deposits;
     stability_provider_state.user_deposit_snapshot = liquidations_logic::get_new_user_snapshot(
         stability_pool_state,
@@ -125,9 +190,230 @@ pub fn withdraw_stability(
 
     Ok(WithdrawStabilityEffects {
         usd_remaining_to_withdraw: usd_to_withdraw as u64,
+        receipts_to_burn,
     })
 }
 
+pub fn request_stability_withdrawal(
+    stability_pool_state: &mut StabilityPoolState,
+    stability_provider_state: &mut StabilityProviderState,
+    epoch_to_scale_to_sum: &mut EpochToScaleToSum,
+    amount: u64,
+    now_timestamp: u64,
+) -> Result<RequestStabilityWithdrawalEffects, ProgramError> {
+    // The deposit leaves the pool right away, at its compounded value,
+    // therefore it doesn't absorb any further liquidations.
+    // The stablecoin stays in the vault until the liquidations
+    // queue is drained and the request is settled.
+    let WithdrawStabilityEffects {
+        usd_remaining_to_withdraw,
+        receipts_to_burn,
+    } = withdraw_stability(
+        stability_pool_state,
+        stability_provider_state,
+        epoch_to_scale_to_sum,
+        amount,
+        now_timestamp,
+    )?;
+
+    stability_provider_state.pending_withdrawal_stablecoin = stability_provider_state
+        .pending_withdrawal_stablecoin
+        .checked_add(usd_remaining_to_withdraw)
+        .unwrap();
+    stability_provider_state.pending_withdrawal_ts = now_timestamp;
+    stability_pool_state.stablecoin_pending_withdrawal = stability_pool_state
+        .stablecoin_pending_withdrawal
+        .checked_add(usd_remaining_to_withdraw)
+        .unwrap();
+
+    Ok(RequestStabilityWithdrawalEffects {
+        usd_requested: usd_remaining_to_withdraw,
+        receipts_to_burn,
+    })
+}
+
+pub fn settle_stability_withdrawal(
+    stability_pool_state: &mut StabilityPoolState,
+    stability_provider_state: &mut StabilityProviderState,
+    liquidations_queue: &mut RefMut<LiquidationsQueue>,
+) -> Result<SettleStabilityWithdrawalEffects, ProgramError> {
+    if liquidations_queue::has_pending_liquidation_events(liquidations_queue) {
+        fail!(BorrowError::CannotSettleUntilLiquidationGainsCleared);
+    }
+
+    let usd_to_withdraw = stability_provider_state.pending_withdrawal_stablecoin;
+    if usd_to_withdraw == 0 {
+        fail!(BorrowError::NoPendingStabilityWithdrawal);
+    }
+
+    stability_pool_state.stablecoin_pending_withdrawal = stability_pool_state
+        .stablecoin_pending_withdrawal
+        .checked_sub(usd_to_withdraw)
+        .unwrap();
+    stability_provider_state.pending_withdrawal_stablecoin = 0;
+    stability_provider_state.pending_withdrawal_ts = 0;
+
+    Ok(SettleStabilityWithdrawalEffects { usd_to_withdraw })
+}
+
+pub fn close_stability_provider(
+    stability_pool_state: &mut StabilityPoolState,
+    stability_provider_state: &StabilityProviderState,
+) -> Result<(), BorrowError> {
+    // A withdrawn deposit has a zero snapshot and doesn't
+    // accrue any further gains, only the pending ones are owed
+    if stability_provider_state.deposited_stablecoin > 0
+        || stability_provider_state.pending_withdrawal_stablecoin > 0
+        || liquidations_logic::receipt_shares(stability_pool_state, stability_provider_state) > 0
+        || !stability_provider_state.pending_gains_per_user.is_zero()
+    {
+        return Err(BorrowError::StabilityProviderNotEmpty);
+    }
+
+    stability_pool_state.num_closed_users = stability_pool_state
+        .num_closed_users
+        .checked_add(1)
+        .unwrap();
+
+    Ok(())
+}
+
+pub fn transfer_stability_receipts(
+    stability_pool_state: &mut StabilityPoolState,
+    from_stability_provider_state: &mut StabilityProviderState,
+    to_stability_provider_state: &mut StabilityProviderState,
+    epoch_to_scale_to_sum: &mut EpochToScaleToSum,
+    receipts_to_transfer: u64,
+    now_timestamp: u64,
+) -> Result<TransferStabilityReceiptsEffects, ProgramError> {
+    // The deposit backed by the receipts, and the share of the gains
+    // it has accrued so far, move with the receipts to the provider
+    // whose owner receives them
+    let from_receipt_shares =
+        liquidations_logic::receipt_shares(stability_pool_state, from_stability_provider_state);
+    if receipts_to_transfer == 0 || receipts_to_transfer > from_receipt_shares {
+        fail!(BorrowError::NoStabilityReceiptsToTransfer);
+    }
+
+    if from_stability_provider_state.lockup_expiry_ts > now_timestamp {
+        fail!(BorrowError::StabilityDepositLocked);
+    }
+
+    trigger_hbb_issuance(stability_pool_state, epoch_to_scale_to_sum, now_timestamp)?;
+
+    // 1. Calculate compounded usd deposits
+    let from_compounded_usd_deposit = liquidations_logic::get_compounded_usd_deposit(
+        stability_pool_state,
+        from_stability_provider_state,
+    );
+    let to_compounded_usd_deposit = liquidations_logic::get_compounded_usd_deposit(
+        stability_pool_state,
+        to_stability_provider_state,
+    );
+
+    // 2. Recalculate pending state
+    for (stability_provider_state, compounded_usd_deposit) in [
+        (
+            &mut *from_stability_provider_state,
+            from_compounded_usd_deposit,
+        ),
+        (&mut *to_stability_provider_state, to_compounded_usd_deposit),
+    ] {
+        liquidations_logic::update_pending_gains(stability_provider_state, epoch_to_scale_to_sum)?;
+        liquidations_logic::release_expired_lockup(
+            stability_pool_state,
+            stability_provider_state,
+            compounded_usd_deposit,
+            now_timestamp,
+        );
+    }
+
+    // 3. Move the share of the deposit and of the gains backed by the receipts
+    let usd_moved = liquidations_logic::pro_rata(
+        receipts_to_transfer,
+        from_receipt_shares,
+        from_compounded_usd_deposit,
+    );
+    let gains_moved = if receipts_to_transfer == from_receipt_shares {
+        from_stability_provider_state.pending_gains_per_user
+    } else {
+        from_stability_provider_state
+            .pending_gains_per_user
+            .mul_fraction(receipts_to_transfer, from_receipt_shares)
+    };
+
+    from_stability_provider_state
+        .pending_gains_per_user
+        .sub_assign(&gains_moved);
+    to_stability_provider_state
+        .pending_gains_per_user
+        .add_assign(&gains_moved);
+
+    let receipts_to_reissue = liquidations_logic::refresh_receipt_shares(
+        stability_pool_state,
+        to_stability_provider_state,
+    );
+    from_stability_provider_state.receipt_shares -= receipts_to_transfer;
+    to_stability_provider_state.receipt_shares = to_stability_provider_state
+        .receipt_shares
+        .checked_add(receipts_to_transfer)
+        .unwrap();
+
+    // 4. Update user deposits and snapshots, the pool total is unchanged
+    for (stability_provider_state, compounded_usd_deposit, new_user_usd_deposits) in [
+        (
+            &mut *from_stability_provider_state,
+            from_compounded_usd_deposit,
+            from_compounded_usd_deposit - usd_moved,
+        ),
+        (
+            &mut *to_stability_provider_state,
+            to_compounded_usd_deposit,
+            to_compounded_usd_deposit.checked_add(usd_moved).unwrap(),
+        ),
+    ] {
+        liquidations_logic::update_boosted_deposits(
+            stability_pool_state,
+            stability_provider_state.lockup_tier,
+            compounded_usd_deposit,
+            stability_provider_state.lockup_tier,
+            new_user_usd_deposits,
+        );
+        stability_provider_state.deposited_stablecoin = new_user_usd_deposits;
+        stability_provider_state.user_deposit_snapshot = liquidations_logic::get_new_user_snapshot(
+            stability_pool_state,
+            epoch_to_scale_to_sum,
+            new_user_usd_deposits,
+        );
+
+        if compounded_usd_deposit == 0 && new_user_usd_deposits > 0 {
+            stability_pool_state.total_users_providing_stability += 1;
+        } else if compounded_usd_deposit > 0 && new_user_usd_deposits == 0 {
+            stability_pool_state.total_users_providing_stability -= 1;
+        }
+    }
+
+    Ok(TransferStabilityReceiptsEffects {
+        usd_moved,
+        gains_moved,
+        receipts_to_reissue,
+    })
+}
+
+pub fn next_receipt_epoch(stability_pool_state: &StabilityPoolState) -> u64 {
+    liquidations_logic::next_receipt_epoch(stability_pool_state)
+}
+
+pub fn start_next_receipt_epoch(
+    stability_pool_state: &mut StabilityPoolState,
+) -> Result<(), BorrowError> {
+    if !liquidations_logic::start_next_receipt_epoch(stability_pool_state) {
+        fail!(BorrowError::StabilityReceiptMintNotStale);
+    }
+
+    Ok(())
+}
+
 pub fn update_pending_gains(
     stability_pool_state: &mut StabilityPoolState,
     stability_provider_state: &mut StabilityProviderState,
@@ -181,6 +467,14 @@ pub fn harvest_liquidation_gains(
         epoch_to_scale_to_sum,
     )?;
 
+    let compounded_usd_deposit = stability_provider_state.deposited_stablecoin;
+    liquidations_logic::release_expired_lockup(
+        stability_pool_state,
+        stability_provider_state,
+        compounded_usd_deposit,
+        now_timestamp,
+    );
+
     // Update state
     harvest_pending_gains(
         stability_pool_state,
@@ -220,6 +514,142 @@ pub fn harvest_pending_gains(
     Ok(())
 }
 
+pub fn lock_stability_deposit(
+    stability_pool_state: &mut StabilityPoolState,
+    stability_provider_state: &mut StabilityProviderState,
+    epoch_to_scale_to_sum: &mut EpochToScaleToSum,
+    lockup_tier: LockupTier,
+    now_timestamp: u64,
+) -> Result<LockStabilityDepositEffects, ProgramError> {
+    if lockup_tier == LockupTier::None {
+        fail!(BorrowError::InvalidLockupTier);
+    }
+
+    let lockup_expiry_ts = now_timestamp
+        .checked_add(lockup_tier.duration_seconds())
+        .unwrap();
+    if lockup_expiry_ts < stability_provider_state.lockup_expiry_ts {
+        fail!(BorrowError::LockupCannotBeShortened);
+    }
+
+    trigger_hbb_issuance(stability_pool_state, epoch_to_scale_to_sum, now_timestamp)?;
+
+    // 1. Calculate compounded usd deposit
+    let compounded_usd_deposit = liquidations_logic::get_compounded_usd_deposit(
+        stability_pool_state,
+        stability_provider_state,
+    );
+
+    if compounded_usd_deposit == 0 {
+        fail!(BorrowError::CannotLockEmptyStabilityDeposit);
+    }
+
+    // 2. Settle pending gains with the previous boost
+    liquidations_logic::update_pending_gains(stability_provider_state, epoch_to_scale_to_sum)?;
+
+    // 3. Re-weight the deposit with the new boost
+    liquidations_logic::update_boosted_deposits(
+        stability_pool_state,
+        stability_provider_state.lockup_tier,
+        compounded_usd_deposit,
+        lockup_tier as u8,
+        compounded_usd_deposit,
+    );
+    stability_provider_state.lockup_tier = lockup_tier as u8;
+    stability_provider_state.lockup_expiry_ts = lockup_expiry_ts;
+
+    // 4. Update user deposit and snapshot
+    stability_provider_state.deposited_stablecoin = compounded_usd_deposit;
+    stability_provider_state.user_deposit_snapshot = liquidations_logic::get_new_user_snapshot(
+        stability_pool_state,
+        epoch_to_scale_to_sum,
+        compounded_usd_deposit,
+    );
+
+    Ok(LockStabilityDepositEffects { lockup_expiry_ts })
+}
+
+pub fn release_stability_lockup(
+    stability_pool_state: &mut StabilityPoolState,
+    stability_provider_state: &mut StabilityProviderState,
+    epoch_to_scale_to_sum: &mut EpochToScaleToSum,
+    now_timestamp: u64,
+) -> Result<(), ProgramError> {
+    // Anyone can strip the boost from an expired lockup
+    // so that it stops diluting the other providers' HBB
+    if stability_provider_state.lockup_tier == LockupTier::None as u8
+        || stability_provider_state.lockup_expiry_ts > now_timestamp
+    {
+        fail!(BorrowError::LockupNotExpired);
+    }
+
+    trigger_hbb_issuance(stability_pool_state, epoch_to_scale_to_sum, now_timestamp)?;
+
+    // 1. Calculate compounded usd deposit
+    let compounded_usd_deposit = liquidations_logic::get_compounded_usd_deposit(
+        stability_pool_state,
+        stability_provider_state,
+    );
+
+    // 2. Settle pending gains with the boost, then drop it
+    liquidations_logic::update_pending_gains(stability_provider_state, epoch_to_scale_to_sum)?;
+    liquidations_logic::release_expired_lockup(
+        stability_pool_state,
+        stability_provider_state,
+        compounded_usd_deposit,
+        now_timestamp,
+    );
+
+    // 3. Update user deposit and snapshot
+    stability_provider_state.deposited_stablecoin = compounded_usd_deposit;
+    stability_provider_state.user_deposit_snapshot = liquidations_logic::get_new_user_snapshot(
+        stability_pool_state,
+        epoch_to_scale_to_sum,
+        compounded_usd_deposit,
+    );
+
+    Ok(())
+}
+
+pub fn update_hbb_emission_schedule(
+    stability_pool_state: &mut StabilityPoolState,
+    epoch_to_scale_to_sum: &mut EpochToScaleToSum,
+    mut schedule: HbbEmissionSchedule,
+    stop_emissions: bool,
+    now_timestamp: u64,
+) -> Result<(), ProgramError> {
+    if !schedule.is_valid() {
+        fail!(BorrowError::InvalidHbbEmissionSchedule);
+    }
+
+    // Issue what is owed under the current schedule first
+    trigger_hbb_issuance(stability_pool_state, epoch_to_scale_to_sum, now_timestamp)?;
+
+    let current_schedule = &stability_pool_state.hbb_emission_schedule;
+    schedule.stopped_at_ts = match (stop_emissions, current_schedule.is_stopped()) {
+        (false, _) => 0,
+        (true, true) => current_schedule.stopped_at_ts,
+        (true, false) => now_timestamp,
+    };
+
+    // The curve resumes where it was stopped
+    if !stop_emissions && current_schedule.is_stopped() {
+        let stopped_since = u64::max(
+            current_schedule.stopped_at_ts,
+            stability_pool_state.hbb_emissions_start_ts,
+        );
+        let stopped_seconds = now_timestamp.saturating_sub(stopped_since);
+        stability_pool_state.hbb_emissions_start_ts = stability_pool_state
+            .hbb_emissions_start_ts
+            .checked_add(stopped_seconds)
+            .unwrap();
+    }
+
+    stability_pool_state.hbb_emission_schedule = schedule;
+
+    Ok(())
+}
+
 pub fn liquidate(
     stability_pool_state: &mut StabilityPoolState,
     epoch_to_scale_to_sum: &mut EpochToScaleToSum,
@@ -236,6 +666,7 @@ pub fn liquidate(
     }
 
     let hbb_emission = issuance_logic::compute_new_hbb_issuance(
+        &stability_pool_state.hbb_emission_schedule,
         stability_pool_state.cumulative_gains_total.hbb as u64,
         stability_pool_state.hbb_emissions_start_ts,
         now_timestamp,
@@ -261,6 +692,142 @@ pub fn liquidate(
     Ok(())
 }
 
+pub fn stability_pools_capacity(
+    stability_pools: &[&StabilityPoolState],
+    collateral: &CollateralAmounts,
+    prices: &TokenPrices,
+) -> u64 {
+    // The largest debt, backed by this collateral, that can be offset
+    // without any pool absorbing more than it holds
+    let (weights, total_weight, _) =
+        routing_logic::pool_weights(stability_pools, collateral, prices);
+
+    let total_deposits: u64 = stability_pools
+        .iter()
+        .map(|pool| pool.stablecoin_deposited)
+        .sum();
+
+    stability_pools
+        .iter()
+        .zip(weights.iter())
+        .filter(|(_, weight)| !weight.is_zero())
+        .map(|(pool, weight)| {
+            U256::from(pool.stablecoin_deposited)
+                .checked_mul(total_weight)
+                .unwrap()
+                .checked_div(*weight)
+                .unwrap()
+        })
+        .min()
+        .map_or(0, |capacity| {
+            u64::min(capacity.min(U256::from(u64::MAX)).as_u64(), total_deposits)
+        })
+}
+
+pub fn route_liquidation(
+    stability_pools: &[&StabilityPoolState],
+    usd_debt: u64,
+    collateral: &CollateralAmounts,
+    prices: &TokenPrices,
+) -> LiquidationRouting {
+    // Each collateral token is split between the pools covering it,
+    // pro rata to their deposits, the debt follows the value each
+    // pool receives. Whatever is not covered by any pool
+    // is left to be redistributed.
+    let (weights, total_weight, uncovered_weight) =
+        routing_logic::pool_weights(stability_pools, collateral, prices);
+
+    let mut usd_debt_not_covered = if total_weight.is_zero() {
+        usd_debt
+    } else {
+        U256::from(usd_debt)
+            .checked_mul(uncovered_weight)
+            .unwrap()
+            .checked_div(total_weight)
+            .unwrap()
+            .as_u64()
+    };
+
+    // The last covering pool takes the rounding remainder
+    let usd_debt_covered = usd_debt - usd_debt_not_covered;
+    let last_covering_pool = weights.iter().rposition(|weight| !weight.is_zero());
+    let mut usd_debt_to_stability_pools = vec![0; stability_pools.len()];
+    let mut usd_debt_routed = 0;
+    for (i, weight) in weights.iter().enumerate() {
+        if Some(i) == last_covering_pool {
+            usd_debt_to_stability_pools[i] = usd_debt_covered - usd_debt_routed;
+        } else if !weight.is_zero() {
+            usd_debt_to_stability_pools[i] = U256::from(usd_debt)
+                .checked_mul(*weight)
+                .unwrap()
+                .checked_div(total_weight)
+                .unwrap()
+                .as_u64();
+            usd_debt_routed += usd_debt_to_stability_pools[i];
+        }
+    }
+
+    // Rounding must never take a pool beyond its deposits
+    for (usd_debt_to_pool, pool) in usd_debt_to_stability_pools
+        .iter_mut()
+        .zip(stability_pools.iter())
+    {
+        if *usd_debt_to_pool > pool.stablecoin_deposited {
+            usd_debt_not_covered += *usd_debt_to_pool - pool.stablecoin_deposited;
+            *usd_debt_to_pool = pool.stablecoin_deposited;
+        }
+    }
+
+    // Only the pools absorbing some of the debt receive collateral, a
+    // pool whose share of the debt rounds to zero leaves its collateral
+    // to the other pools covering the token, or to redistribution
+    let is_receiving = |i: usize, token: CollateralToken| {
+        usd_debt_to_stability_pools[i] > 0 && routing_logic::is_covering(stability_pools[i], token)
+    };
+
+    let mut coll_to_stability_pools = vec![CollateralAmounts::default(); stability_pools.len()];
+    let mut coll_not_covered = CollateralAmounts::default();
+    for token in (0..6).map(CollateralToken::from) {
+        let amount = collateral.token_amount(token);
+        let receiving_deposits: u64 = stability_pools
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| is_receiving(*i, token))
+            .map(|(_, pool)| pool.stablecoin_deposited)
+            .sum();
+        if receiving_deposits == 0 {
+            coll_not_covered.add_assign(&CollateralAmounts::of_token(amount, token));
+            continue;
+        }
+
+        let last_receiving_pool = (0..stability_pools.len()).rposition(|i| is_receiving(i, token));
+        let mut amount_routed = 0;
+        for (i, pool) in stability_pools.iter().enumerate() {
+            let amount_to_pool = if Some(i) == last_receiving_pool {
+                amount - amount_routed
+            } else if is_receiving(i, token) {
+                (amount as u128)
+                    .checked_mul(pool.stablecoin_deposited as u128)
+                    .unwrap()
+                    .checked_div(receiving_deposits as u128)
+                    .unwrap() as u64
+            } else {
+                0
+            };
+            amount_routed += amount_to_pool;
+            coll_to_stability_pools[i]
+                .add_assign(&CollateralAmounts::of_token(amount_to_pool, token));
+        }
+    }
+
+    LiquidationRouting {
+        usd_debt_to_stability_pools,
+        coll_to_stability_pools,
+        usd_debt_not_covered,
+        coll_not_covered,
+    }
+}
+
 fn add_rewards_and_loss(
     stability_pool_state: &mut StabilityPoolState,
     epoch_to_scale_to_sum: &mut EpochToScaleToSum,
@@ -280,6 +847,7 @@ fn add_rewards_and_loss(
         gains,
         usd_loss,
         stability_pool_state.stablecoin_deposited,
+        stability_pool_state.boosted_stablecoin_deposited,
     );
 
     liquidations_logic::update_reward_sum_and_product(
@@ -298,6 +866,7 @@ fn add_rewards_and_loss(
 
     stability_pool_state.last_stablecoin_loss_error_offset = last_usd_error;
     stability_pool_state.last_coll_loss_error_offset = last_coll_error;
+    let stablecoin_deposited_before_loss = stability_pool_state.stablecoin_deposited;
     stability_pool_state.stablecoin_deposited = stability_pool_state
         .stablecoin_deposited
         .checked_sub(
@@ -308,6 +877,17 @@ fn add_rewards_and_loss(
         )
         .unwrap();
 
+    // Every deposit shrinks by the same factor,
+    // therefore so does their boosted sum
+    if usd_loss > 0 {
+        stability_pool_state.boosted_stablecoin_deposited =
+            liquidations_logic::scale_boosted_deposits(
+                stability_pool_state.boosted_stablecoin_deposited,
+                stability_pool_state.stablecoin_deposited,
+                stablecoin_deposited_before_loss,
+            );
+    }
+
     Ok(())
 }
 
@@ -321,6 +901,7 @@ fn trigger_hbb_issuance(
     }
 
     let hbb_emission = issuance_logic::compute_new_hbb_issuance(
+        &stability_pool_state.hbb_emission_schedule,
         stability_pool_state.cumulative_gains_total.hbb as u64,
         stability_pool_state.hbb_emissions_start_ts,
         now_timestamp,
@@ -339,11 +920,78 @@ fn trigger_hbb_issuance(
     Ok(())
 }
 
+mod routing_logic {
+    use crate::utils::finance::CollateralInfo;
+
+    use super::*;
+
+    pub fn is_covering(stability_pool_state: &StabilityPoolState, token: CollateralToken) -> bool {
+        stability_pool_state.stablecoin_deposited > 0 && stability_pool_state.covers(token)
+    }
+
+    pub fn covering_deposits(
+        stability_pools: &[&StabilityPoolState],
+        token: CollateralToken,
+    ) -> u64 {
+        stability_pools
+            .iter()
+            .filter(|pool| is_covering(pool, token))
+            .map(|pool| pool.stablecoin_deposited)
+            .sum()
+    }
+
+    pub fn pool_weights(
+        stability_pools: &[&StabilityPoolState],
+        collateral: &CollateralAmounts,
+        prices: &TokenPrices,
+    ) -> (Vec<U256>, U256, U256) {
+        // Share of the collateral value going to each pool, as
+        // weights over a common denominator, along with the weight
+        // of the collateral that no pool covers
+        let mut weights = vec![U256::zero(); stability_pools.len()];
+        let mut total_weight = U256::zero();
+        let mut uncovered_weight = U256::zero();
+
+        for token in (0..6).map(CollateralToken::from) {
+            let value = CollateralInfo::calc_market_value_usdh(
+                prices,
+                &CollateralAmounts::of_token(collateral.token_amount(token), token),
+            );
+            let token_weight = U256::from(value)
+                .checked_mul(U256::from(DECIMAL_PRECISION))
+                .unwrap();
+            total_weight = total_weight.checked_add(token_weight).unwrap();
+
+            let covering_deposits = covering_deposits(stability_pools, token);
+            if covering_deposits == 0 {
+                uncovered_weight = uncovered_weight.checked_add(token_weight).unwrap();
+                continue;
+            }
+
+            for (weight, pool) in weights.iter_mut().zip(stability_pools.iter()) {
+                if is_covering(pool, token) {
+                    *weight = weight
+                        .checked_add(
+                            token_weight
+                                .checked_mul(U256::from(pool.stablecoin_deposited))
+                                .unwrap()
+                                .checked_div(U256::from(covering_deposits))
+                                .unwrap(),
+                        )
+                        .unwrap();
+                }
+            }
+        }
+
+        (weights, total_weight, uncovered_weight)
+    }
+}
+
 mod liquidations_logic {
 
     use crate::{
         stability_pool::types::RewardDistributionCalculation,
-        utils::consts::{DECIMAL_PRECISION, ONE, SCALE_FACTOR},
+        utils::consts::{DECIMAL_PRECISION, MAX_STABILITY_RECEIPT_RATE, ONE, SCALE_FACTOR},
         BorrowError, DepositSnapshot,
     };
 
@@ -444,11 +1092,250 @@ mod liquidations_logic {
         }
     }
 
+    pub fn mint_receipts(
+        stability_pool_state: &mut StabilityPoolState,
+        stability_provider_state: &mut StabilityProviderState,
+        amount: u64,
+    ) -> Result<u64, crate::BorrowError> {
+        // Every deposit compounds by the same P, so the pool wide
+        // ratio of deposits to receipts is the value of a receipt
+        start_next_receipt_epoch(stability_pool_state);
+        let receipts_to_reissue =
+            refresh_receipt_shares(stability_pool_state, stability_provider_state);
+
+        let receipts_to_mint = if stability_pool_state.receipt_supply == 0
+            || stability_pool_state.stablecoin_deposited == 0
+        {
+            amount
+        } else {
+            u64::try_from(
+                (amount as u128)
+                    .checked_mul(stability_pool_state.receipt_supply as u128)
+                    .unwrap()
+                    .checked_div(stability_pool_state.stablecoin_deposited as u128)
+                    .unwrap(),
+            )
+            .map_err(|_| BorrowError::MathOverflow)?
+        };
+
+        stability_pool_state.receipt_supply = stability_pool_state
+            .receipt_supply
+            .checked_add(receipts_to_mint)
+            .ok_or(BorrowError::MathOverflow)?;
+        stability_provider_state.receipt_shares = stability_provider_state
+            .receipt_shares
+            .checked_add(receipts_to_mint)
+            .unwrap();
+
+        Ok(receipts_to_mint + receipts_to_reissue)
+    }
+
+    pub fn burn_receipts(
+        stability_pool_state: &mut StabilityPoolState,
+        stability_provider_state: &mut StabilityProviderState,
+        old_deposit: u64,
+        new_deposit: u64,
+    ) -> u64 {
+        let reissued = stability_provider_state.receipt_epoch == stability_pool_state.receipt_epoch;
+        let receipt_shares = if reissued {
+            receipt_shares(stability_pool_state, stability_provider_state)
+        } else {
+            virtual_receipt_shares(stability_pool_state, old_deposit)
+        };
+
+        let receipts_to_burn = if new_deposit == 0 {
+            receipt_shares
+        } else {
+            // Rounding down what is kept, the remaining receipts
+            // must not be worth more than the remaining deposit
+            let receipts_to_keep = pro_rata(new_deposit, old_deposit, receipt_shares);
+            receipt_shares - receipts_to_keep
+        };
+
+        stability_pool_state.receipt_supply = stability_pool_state
+            .receipt_supply
+            .saturating_sub(receipts_to_burn);
+
+        if reissued {
+            stability_provider_state.receipt_shares = receipt_shares - receipts_to_burn;
+            receipts_to_burn
+        } else {
+            // Receipts not reissued yet only exist in the supply
+            0
+        }
+    }
+
+    pub fn receipt_shares(
+        stability_pool_state: &StabilityPoolState,
+        stability_provider_state: &StabilityProviderState,
+    ) -> u64 {
+        // Shares of a previous receipt epoch or of a deposit
+        // wiped out by liquidations no longer back anything
+        if stability_provider_state.receipt_epoch == stability_pool_state.receipt_epoch
+            && get_compounded_usd_deposit(stability_pool_state, stability_provider_state) > 0
+        {
+            stability_provider_state.receipt_shares
+        } else {
+            0
+        }
+    }
+
+    pub fn refresh_receipt_shares(
+        stability_pool_state: &StabilityPoolState,
+        stability_provider_state: &mut StabilityProviderState,
+    ) -> u64 {
+        if stability_provider_state.receipt_epoch == stability_pool_state.receipt_epoch {
+            stability_provider_state.receipt_shares =
+                receipt_shares(stability_pool_state, stability_provider_state);
+            return 0;
+        }
+
+        // A deposit from before the receipt epoch started over
+        // gets its receipts reissued at the current rate
+        let compounded_usd_deposit =
+            get_compounded_usd_deposit(stability_pool_state, stability_provider_state);
+        let receipts_to_reissue =
+            virtual_receipt_shares(stability_pool_state, compounded_usd_deposit);
+        stability_provider_state.receipt_shares = receipts_to_reissue;
+        stability_provider_state.receipt_epoch = stability_pool_state.receipt_epoch;
+
+        receipts_to_reissue
+    }
+
+    pub fn next_receipt_epoch(stability_pool_state: &StabilityPoolState) -> u64 {
+        // The rate starts over once the pool is emptied, the receipts
+        // left are worthless, and once a receipt is worth so little
+        // that minting new ones could overflow the supply
+        let receipt_supply = stability_pool_state.receipt_supply as u128;
+        let stablecoin_deposited = stability_pool_state.stablecoin_deposited as u128;
+        if receipt_supply > 0
+            && (stablecoin_deposited == 0
+                || receipt_supply > stablecoin_deposited * MAX_STABILITY_RECEIPT_RATE as u128)
+        {
+            stability_pool_state.receipt_epoch + 1
+        } else {
+            stability_pool_state.receipt_epoch
+        }
+    }
+
+    pub fn start_next_receipt_epoch(stability_pool_state: &mut StabilityPoolState) -> bool {
+        let next_receipt_epoch = next_receipt_epoch(stability_pool_state);
+        if next_receipt_epoch == stability_pool_state.receipt_epoch {
+            return false;
+        }
+
+        // The deposits still in the pool are owed receipts of the new
+        // epoch 1:1, they are counted now and reissued to each provider
+        // the next time it is given receipts
+        stability_pool_state.receipt_epoch = next_receipt_epoch;
+        stability_pool_state.receipt_supply = stability_pool_state.stablecoin_deposited;
+
+        true
+    }
+
+    fn virtual_receipt_shares(
+        stability_pool_state: &StabilityPoolState,
+        compounded_usd_deposit: u64,
+    ) -> u64 {
+        if stability_pool_state.stablecoin_deposited == 0 {
+            0
+        } else {
+            pro_rata(
+                compounded_usd_deposit,
+                stability_pool_state.stablecoin_deposited,
+                stability_pool_state.receipt_supply,
+            )
+        }
+    }
+
+    pub fn pro_rata(part: u64, whole: u64, amount: u64) -> u64 {
+        if part == whole {
+            return amount;
+        }
+
+        (part as u128)
+            .checked_mul(amount as u128)
+            .unwrap()
+            .checked_div(whole as u128)
+            .unwrap() as u64
+    }
+
+    pub fn boosted_deposit(amount: u64, lockup_tier: u8) -> u64 {
+        // tiers are validated before they are stored
+        let boost_bps = LockupTier::try_from(lockup_tier).unwrap().hbb_boost_bps();
+        (amount as u128)
+            .checked_mul(boost_bps as u128)
+            .unwrap()
+            .checked_div(10_000)
+            .unwrap() as u64
+    }
+
+    pub fn update_boosted_deposits(
+        stability_pool_state: &mut StabilityPoolState,
+        old_lockup_tier: u8,
+        old_deposit: u64,
+        new_lockup_tier: u8,
+        new_deposit: u64,
+    ) {
+        // saturating as the compounded deposits are rounded down
+        // and their sum may drift slightly below the pool total
+        stability_pool_state.boosted_stablecoin_deposited = stability_pool_state
+            .boosted_stablecoin_deposited
+            .saturating_sub(boosted_deposit(old_deposit, old_lockup_tier))
+            .checked_add(boosted_deposit(new_deposit, new_lockup_tier))
+            .unwrap();
+    }
+
+    pub fn scale_boosted_deposits(
+        boosted_deposits: u64,
+        usd_deposits_after_loss: u64,
+        usd_deposits_before_loss: u64,
+    ) -> u64 {
+        if usd_deposits_after_loss == 0 {
+            return 0;
+        }
+
+        // Round up, a slightly larger denominator favors the stability pool
+        (boosted_deposits as u128)
+            .checked_mul(usd_deposits_after_loss as u128)
+            .unwrap()
+            .checked_add(usd_deposits_before_loss as u128 - 1)
+            .unwrap()
+            .checked_div(usd_deposits_before_loss as u128)
+            .unwrap() as u64
+    }
+
+    pub fn release_expired_lockup(
+        stability_pool_state: &mut StabilityPoolState,
+        stability_provider_state: &mut StabilityProviderState,
+        compounded_usd_deposit: u64,
+        now_timestamp: u64,
+    ) {
+        // The boost is kept until the provider next touches the deposit,
+        // gains must be settled before calling this
+        if stability_provider_state.lockup_tier == LockupTier::None as u8
+            || stability_provider_state.lockup_expiry_ts > now_timestamp
+        {
+            return;
+        }
+
+        update_boosted_deposits(
+            stability_pool_state,
+            stability_provider_state.lockup_tier,
+            compounded_usd_deposit,
+            LockupTier::None as u8,
+            compounded_usd_deposit,
+        );
+        stability_provider_state.lockup_tier = LockupTier::None as u8;
+        stability_provider_state.lockup_expiry_ts = 0;
+    }
+
     pub fn compute_rewards_per_unit_staked(
         stability_pool_state: &mut StabilityPoolState,
         coll_to_add: StabilityCollateralAmounts,
         debt_to_offset: u64,
         total_usd_deposits: u64,
+        total_boosted_usd_deposits: u64,
     ) -> RewardDistributionCalculation {
         let (usd_loss_per_unit_staked, last_usd_error) = match debt_to_offset {
             // if full depletion
@@ -491,10 +1378,28 @@ mod liquidations_logic {
                     .to_token_map(),
             );
 
-        let coll_gained_per_unit_staked =
+        // HBB is shared by the lockup-boosted deposits,
+        // collateral gains by the actual deposits
+        let total_boosted_usd_deposits = if total_boosted_usd_deposits == 0 {
+            total_usd_deposits
+        } else {
+            total_boosted_usd_deposits
+        };
+
+        let mut coll_gained_per_unit_staked =
             coll_gain_numerator.div_scalar(total_usd_deposits as u128);
+        coll_gained_per_unit_staked.hbb = coll_gain_numerator
+            .hbb
+            .checked_div(total_boosted_usd_deposits as u128)
+            .unwrap();
+
+        let mut distributed = coll_gained_per_unit_staked.mul_scalar(total_usd_deposits as u128);
+        distributed.hbb = coll_gained_per_unit_staked
+            .hbb
+            .checked_mul(total_boosted_usd_deposits as u128)
+            .unwrap();
         let last_coll_error = coll_gain_numerator
-            .sub(&coll_gained_per_unit_staked.mul_scalar(total_usd_deposits as u128))
+            .sub(&distributed)
             .to_collateral_amounts();
 
         let actual_issuance = coll_gain_numerator
@@ -620,13 +1525,21 @@ mod liquidations_logic {
         if initial_deposit == 0 {
             StabilityCollateralAmounts::default()
         } else {
+            let boosted_initial_deposit =
+                boosted_deposit(initial_deposit, stability_provider_state.lockup_tier);
             let deposit_snapshot = &stability_provider_state.user_deposit_snapshot;
-            get_pending_gain_from_snapshot(initial_deposit, deposit_snapshot, epoch_to_scale_to_sum)
+            get_pending_gain_from_snapshot(
+                initial_deposit,
+                boosted_initial_deposit,
+                deposit_snapshot,
+                epoch_to_scale_to_sum,
+            )
         }
     }
 
     fn get_pending_gain_from_snapshot(
         initial_deposit: u64,
+        boosted_initial_deposit: u64,
         deposit_snapshot: &DepositSnapshot,
         epoch_to_scale_to_sum: &EpochToScaleToSum,
     ) -> StabilityCollateralAmounts {
@@ -701,7 +1614,7 @@ mod liquidations_logic {
             .checked_div(U256::from(DECIMAL_PRECISION))
             .unwrap();
         let hbb = U256::from(hbb)
-            .checked_mul(U256::from(initial_deposit))
+            .checked_mul(U256::from(boosted_initial_deposit))
             .unwrap()
             .checked_div(U256::from(p_snapshot))
             .unwrap()
@@ -765,9 +1678,8 @@ mod liquidations_logic {
 pub mod issuance_logic {
 
     use super::HBB_FACTOR;
-    use crate::utils::consts::{
-        HBB_ISSUANCE_FACTOR, SECONDS_PER_MINUTE, TOTAL_HBB_TO_STABILITY_POOL,
-    };
+    use crate::utils::consts::SECONDS_PER_MINUTE;
+    use crate::HbbEmissionSchedule;
 
     #[cfg(not(test))]
     use anchor_lang::prelude::msg;
@@ -777,16 +1689,17 @@ pub mod issuance_logic {
     };
 
     pub fn compute_new_hbb_issuance(
+        schedule: &HbbEmissionSchedule,
         total_issued_so_far: u64,
         start_issuance_timestamp: u64,
         now_timestamp: u64,
     ) -> u64 {
         let expected_issued_so_far: u64 =
-            expected_issuance_since_start(start_issuance_timestamp, now_timestamp);
+            expected_issuance_since_start(schedule, start_issuance_timestamp, now_timestamp);
 
-        let remaining_issuance = expected_issued_so_far
-            .checked_sub(total_issued_so_far)
-            .unwrap();
+        // Saturating as the schedule may have been changed to a curve
+        // that has issued less so far, it then resumes once it catches up
+        let remaining_issuance = expected_issued_so_far.saturating_sub(total_issued_so_far);
 
         #[cfg(not(test))]
         msg!(
@@ -798,7 +1711,58 @@ pub mod issuance_logic {
         remaining_issuance
     }
 
-    pub fn expected_issuance_since_start(start: u64, now: u64) -> u64 {
+    pub fn expected_issuance_since_start(
+        schedule: &HbbEmissionSchedule,
+        start: u64,
+        now: u64,
+    ) -> u64 {
+        // Validate the input before applying the schedule
+        now.checked_sub(start).unwrap();
+
+        let now = if schedule.is_stopped() {
+            u64::min(now, schedule.stopped_at_ts)
+        } else {
+            now
+        };
+
+        let emissions_start = start.checked_add(schedule.cliff_seconds).unwrap();
+        if now <= emissions_start {
+            return 0;
+        }
+        let seconds_diff = now - emissions_start;
+
+        let total_hbb = schedule
+            .total_allocation
+            .checked_mul(HBB_FACTOR as u64)
+            .unwrap();
+
+        if schedule.has_linear_tail() && seconds_diff >= schedule.linear_tail_start_seconds {
+            // Whatever the decaying curve has not issued
+            // by the start of the tail is issued linearly
+            let decayed = decaying_issuance(
+                total_hbb,
+                schedule.decay_factor,
+                schedule.linear_tail_start_seconds,
+            );
+            let tail_total = total_hbb.checked_sub(decayed).unwrap();
+            let tail_seconds = u64::min(
+                seconds_diff - schedule.linear_tail_start_seconds,
+                schedule.linear_tail_duration_seconds,
+            );
+            let tail = (tail_total as u128)
+                .checked_mul(tail_seconds as u128)
+                .unwrap()
+                .checked_div(schedule.linear_tail_duration_seconds as u128)
+                .unwrap() as u64;
+
+            decayed.checked_add(tail).unwrap()
+        } else {
+            decaying_issuance(total_hbb, schedule.decay_factor, seconds_diff)
+        }
+    }
+
+    fn decaying_issuance(total_hbb: u64, decay_factor: u64, seconds_diff: u64) -> u64 {
+        // With the default schedule:
         // 32,000,000 * (1–0.5^year)
         // halving yearly
 
@@ -817,20 +1781,15 @@ pub mod issuance_logic {
         // F = 0.999998681227695000
         //      1000000000000000000 -> decimal precision
 
-        let seconds_diff = now.checked_sub(start).unwrap();
         let minutes_diff = seconds_diff.checked_div(SECONDS_PER_MINUTE).unwrap();
 
         let one = Rate::one();
-        let factor = Rate::from_scaled_val(HBB_ISSUANCE_FACTOR);
+        let factor = Rate::from_scaled_val(decay_factor);
 
         let rate = factor.try_pow(minutes_diff).unwrap();
         let fraction = one.try_sub(rate).unwrap();
 
-        let total_hbb = Rate::from_scaled_val(
-            TOTAL_HBB_TO_STABILITY_POOL
-                .checked_mul(HBB_FACTOR as u64)
-                .unwrap(),
-        );
+        let total_hbb = Rate::from_scaled_val(total_hbb);
         let issuance = total_hbb.try_mul(fraction).unwrap();
 
         issuance.to_scaled_val() as u64
@@ -2,6 +2,7 @@
 mod tests {
     use std::cell::RefCell;
 
+    use decimal_wad::common::WAD;
     use solana_sdk::{clock::SECONDS_PER_DAY, native_token::sol_to_lamports};
 
     use crate::{
@@ -11,15 +12,17 @@ mod tests {
         },
         state::epoch_to_scale_to_sum::EpochToScaleToSum,
         utils::consts::{
-            DECIMAL_PRECISION, HBB_FACTOR, SECONDS_PER_YEAR, TOTAL_HBB_TO_STABILITY_POOL,
+            DECIMAL_PRECISION, HBB_FACTOR, HBB_ISSUANCE_FACTOR, SECONDS_PER_YEAR,
+            TOTAL_HBB_TO_STABILITY_POOL,
         },
-        LiquidationsQueue, StabilityPoolState, StabilityProviderState,
+        HbbEmissionSchedule, LiquidationsQueue, StabilityPoolState, StabilityProviderState,
     };
 
     const HALF: f64 = 0.5;
 
     #[test]
     fn test_hbb_issuance_fraction() {
+        let schedule = HbbEmissionSchedule::default();
         let total_to_be_issued = 31_000_000.0;
 
         let one_day = 1.0 / 365.0;
@@ -37,7 +40,11 @@ mod tests {
             let pct_of_total = issue_so_far / total_to_be_issued * 100.0;
 
             // actual
-            let act = expected_issuance_since_start(0, (years * (SECONDS_PER_YEAR as f64)) as u64);
+            let act = expected_issuance_since_start(
+                &schedule,
+                0,
+                (years * (SECONDS_PER_YEAR as f64)) as u64,
+            );
             println!(
                 "Issuing after years {:.2} - {:.2} {:.2} HBB Pct {:.2}%",
                 years, issue_so_far, act, pct_of_total
@@ -49,24 +56,27 @@ mod tests {
 
     #[quickcheck]
     fn test_hbb_issuance_prop(start: u64, now: u64) -> bool {
+        let schedule = HbbEmissionSchedule::default();
         if start > now {
             true
         } else {
-            let res = expected_issuance_since_start(start, now);
+            let res = expected_issuance_since_start(&schedule, start, now);
             res <= TOTAL_HBB_TO_STABILITY_POOL * (HBB_FACTOR as u64)
         }
     }
 
     #[test]
     fn test_hbb_issuance_noop() {
-        let res = expected_issuance_since_start(0, 0);
+        let schedule = HbbEmissionSchedule::default();
+        let res = expected_issuance_since_start(&schedule, 0, 0);
         assert_eq!(res, 0);
     }
 
     #[test]
     #[should_panic]
     fn test_hbb_issuance_bad_input() {
-        expected_issuance_since_start(100, 0);
+        let schedule = HbbEmissionSchedule::default();
+        expected_issuance_since_start(&schedule, 100, 0);
     }
 
     #[test]
@@ -123,6 +133,7 @@ mod tests {
 
     #[test]
     fn test_hbb_issuance_day_years() {
+        let schedule = HbbEmissionSchedule::default();
         // 1st jan 2021
         let start_time = 1609459200;
         let one_day = start_time + SECONDS_PER_DAY * 1;
@@ -132,12 +143,12 @@ mod tests {
         let one_year = start_time + SECONDS_PER_YEAR as u64;
         let two_years = start_time + (SECONDS_PER_YEAR * 2) as u64;
 
-        let after_one_day = expected_issuance_since_start(start_time, one_day);
-        let after_two_days = expected_issuance_since_start(start_time, two_days);
-        let after_three_days = expected_issuance_since_start(start_time, three_days);
+        let after_one_day = expected_issuance_since_start(&schedule, start_time, one_day);
+        let after_two_days = expected_issuance_since_start(&schedule, start_time, two_days);
+        let after_three_days = expected_issuance_since_start(&schedule, start_time, three_days);
 
-        let after_one_year = expected_issuance_since_start(start_time, one_year);
-        let after_two_years = expected_issuance_since_start(start_time, two_years);
+        let after_one_year = expected_issuance_since_start(&schedule, start_time, one_year);
+        let after_two_years = expected_issuance_since_start(&schedule, start_time, two_years);
 
         assert_eq!(after_one_day as f64, 58814171800.0);
         assert_eq!(after_two_days as f64, 117516759510.0);
@@ -508,4 +519,221 @@ mod tests {
             assert_fuzzy_eq!(total_hbb_issuance, expected_issuance, 200);
         }
     }
+
+    // EMISSION SCHEDULE TESTS
+
+    #[test]
+    fn test_hbb_issuance_schedule_custom_allocation() {
+        let schedule = HbbEmissionSchedule::new(1_000_000, HBB_ISSUANCE_FACTOR, 0, 0, 0);
+
+        let after_one_year = expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR);
+        let after_two_years = expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR * 2);
+
+        // same halving, smaller allocation
+        assert_fuzzy_eq!(after_one_year, 1_000_000 * HBB_FACTOR / 2, 200);
+        assert_fuzzy_eq!(after_two_years, 1_000_000 * HBB_FACTOR * 3 / 4, 200);
+    }
+
+    #[test]
+    fn test_hbb_issuance_schedule_cliff() {
+        let cliff = SECONDS_PER_DAY * 30;
+        let schedule = HbbEmissionSchedule::new(
+            TOTAL_HBB_TO_STABILITY_POOL,
+            HBB_ISSUANCE_FACTOR,
+            cliff,
+            0,
+            0,
+        );
+
+        assert_eq!(expected_issuance_since_start(&schedule, 0, cliff - 1), 0);
+        assert_eq!(expected_issuance_since_start(&schedule, 0, cliff), 0);
+
+        // the curve starts at the end of the cliff
+        let after_one_day = expected_issuance_since_start(&schedule, 0, cliff + SECONDS_PER_DAY);
+        assert_eq!(after_one_day, 58814171800);
+    }
+
+    #[test]
+    fn test_hbb_issuance_schedule_linear() {
+        // no decay, everything is issued linearly over a year
+        let schedule =
+            HbbEmissionSchedule::new(TOTAL_HBB_TO_STABILITY_POOL, WAD, 0, 0, SECONDS_PER_YEAR);
+        let total = TOTAL_HBB_TO_STABILITY_POOL * HBB_FACTOR;
+
+        assert_eq!(expected_issuance_since_start(&schedule, 0, 0), 0);
+        assert_eq!(
+            expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR / 2),
+            total / 2
+        );
+        assert_eq!(
+            expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR),
+            total
+        );
+        assert_eq!(
+            expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR * 3),
+            total
+        );
+    }
+
+    #[test]
+    fn test_hbb_issuance_schedule_decay_with_linear_tail() {
+        // halving for one year, then the remaining half linearly over one year
+        let schedule = HbbEmissionSchedule::new(
+            TOTAL_HBB_TO_STABILITY_POOL,
+            HBB_ISSUANCE_FACTOR,
+            0,
+            SECONDS_PER_YEAR,
+            SECONDS_PER_YEAR,
+        );
+        let total = TOTAL_HBB_TO_STABILITY_POOL * HBB_FACTOR;
+        let default_schedule = HbbEmissionSchedule::default();
+
+        // before the tail it follows the decaying curve
+        assert_eq!(
+            expected_issuance_since_start(&schedule, 0, SECONDS_PER_DAY),
+            expected_issuance_since_start(&default_schedule, 0, SECONDS_PER_DAY)
+        );
+
+        let after_one_year = expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR);
+        let after_one_and_half_years =
+            expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR * 3 / 2);
+        let after_two_years = expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR * 2);
+        let after_three_years = expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR * 3);
+
+        assert_fuzzy_eq!(after_one_year, total / 2, 200);
+        assert_fuzzy_eq!(after_one_and_half_years, total * 3 / 4, 200);
+
+        // unlike the decaying curve, the tail issues the full allocation
+        assert_eq!(after_two_years, total);
+        assert_eq!(after_three_years, total);
+    }
+
+    #[test]
+    fn test_hbb_issuance_schedule_stopped() {
+        let mut schedule = HbbEmissionSchedule::default();
+        schedule.stopped_at_ts = SECONDS_PER_DAY;
+
+        let after_one_day = expected_issuance_since_start(&schedule, 0, SECONDS_PER_DAY);
+        let after_one_year = expected_issuance_since_start(&schedule, 0, SECONDS_PER_YEAR);
+
+        assert_eq!(after_one_day, 58814171800);
+        assert_eq!(after_one_year, after_one_day);
+    }
+
+    #[test]
+    fn test_hbb_issuance_update_schedule_stop_and_resume() {
+        let mut stability_pool_state = StabilityPoolState::default();
+        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
+        let liquidations = RefCell::new(LiquidationsQueue::default());
+
+        stability_pool_operations::initialize_stability_pool(
+            &mut stability_pool_state,
+            &mut liquidations.borrow_mut(),
+            0,
+        );
+
+        let mut user_one = StabilityProviderState::default();
+        stability_pool_operations::approve_new_user(&mut stability_pool_state, &mut user_one);
+        stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut user_one,
+            &mut epoch_to_scale_to_sum,
+            sol_to_lamports(100.0),
+            0,
+        )
+        .unwrap();
+
+        // Stopping issues what is owed until then
+        stability_pool_operations::update_hbb_emission_schedule(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            HbbEmissionSchedule::default(),
+            true,
+            SECONDS_PER_DAY,
+        )
+        .unwrap();
+        assert_eq!(stability_pool_state.cumulative_gains_total.hbb, 58814171800);
+        assert_eq!(
+            stability_pool_state.hbb_emission_schedule.stopped_at_ts,
+            SECONDS_PER_DAY
+        );
+
+        // Nothing is issued while stopped
+        stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut user_one,
+            &mut epoch_to_scale_to_sum,
+            sol_to_lamports(100.0),
+            SECONDS_PER_DAY * 2,
+        )
+        .unwrap();
+        assert_eq!(stability_pool_state.cumulative_gains_total.hbb, 58814171800);
+
+        // Resuming picks the curve up where it was stopped
+        stability_pool_operations::update_hbb_emission_schedule(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            HbbEmissionSchedule::default(),
+            false,
+            SECONDS_PER_DAY * 3,
+        )
+        .unwrap();
+        stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut user_one,
+            &mut epoch_to_scale_to_sum,
+            sol_to_lamports(100.0),
+            SECONDS_PER_DAY * 3,
+        )
+        .unwrap();
+        assert_eq!(stability_pool_state.cumulative_gains_total.hbb, 58814171800);
+        assert_eq!(
+            stability_pool_state.hbb_emissions_start_ts,
+            SECONDS_PER_DAY * 2
+        );
+
+        // The stopped days are never issued
+        stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut user_one,
+            &mut epoch_to_scale_to_sum,
+            sol_to_lamports(100.0),
+            SECONDS_PER_DAY * 4,
+        )
+        .unwrap();
+        assert_fuzzy_eq!(
+            stability_pool_state.cumulative_gains_total.hbb as u64,
+            expected_issuance_since_start(&HbbEmissionSchedule::default(), 0, SECONDS_PER_DAY * 2),
+            10
+        );
+
+        let res = stability_pool_operations::update_hbb_emission_schedule(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            HbbEmissionSchedule::new(TOTAL_HBB_TO_STABILITY_POOL, WAD + 1, 0, 0, 0),
+            false,
+            SECONDS_PER_DAY * 3,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_hbb_issuance_schedule_validity() {
+        let total = TOTAL_HBB_TO_STABILITY_POOL;
+
+        assert!(HbbEmissionSchedule::default().is_valid());
+        assert!(HbbEmissionSchedule::new(total, WAD, 0, 0, SECONDS_PER_YEAR).is_valid());
+        assert!(
+            HbbEmissionSchedule::new(total, HBB_ISSUANCE_FACTOR, 0, SECONDS_PER_YEAR, 1).is_valid()
+        );
+
+        // Nothing issued, ever
+        assert!(!HbbEmissionSchedule::new(total, 0, 0, 0, 0).is_valid());
+        assert!(!HbbEmissionSchedule::new(total, WAD, 0, 0, 0).is_valid());
+        // Tail starting but lasting no time
+        assert!(
+            !HbbEmissionSchedule::new(total, HBB_ISSUANCE_FACTOR, 0, SECONDS_PER_YEAR, 0)
+                .is_valid()
+        );
+    }
 }
@@ -1115,6 +1115,137 @@ mod tests {
         assert_eq!(liquiation_event.collateral_gain_to_liquidator.sol, 0);
         assert_eq!(liquiation_event.status, 0);
     }
+
+    #[test]
+    fn test_liquidations_queue_withdrawal_request_settled_once_drained() {
+        let (
+            _market,
+            mut stability_pool_state,
+            _staking_pool_state,
+            mut epoch_to_scale_to_sum,
+            liquidations,
+            _hbb_emissions_start_ts,
+            now_timestamp,
+            mut sp_providers,
+        ) = set_up_market(vec![150.0, 150.0]);
+
+        let clearing_agent = Pubkey::new_unique();
+        liquidations_queue::add_liquidation_event(
+            LiquidationEvent::new(
+                clearing_agent,
+                CollateralAmounts::default(),
+                CollateralAmounts::default(),
+                CollateralAmounts::of_token(sol_to_lamports(1.0), CollateralToken::SOL),
+                now_timestamp,
+            ),
+            &mut liquidations.borrow_mut(),
+        )
+        .unwrap();
+
+        // Requesting does not depend on the queue
+        stability_pool_operations::request_stability_withdrawal(
+            &mut stability_pool_state,
+            &mut sp_providers[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50.0),
+            now_timestamp,
+        )
+        .unwrap();
+
+        assert_eq!(sp_providers[0].deposited_stablecoin, USDH::from(100.0));
+        assert_eq!(
+            sp_providers[0].pending_withdrawal_stablecoin,
+            USDH::from(50.0)
+        );
+        assert_eq!(stability_pool_state.stablecoin_deposited, USDH::from(250.0));
+        assert_eq!(
+            stability_pool_state.stablecoin_pending_withdrawal,
+            USDH::from(50.0)
+        );
+
+        // Settling has to wait for the queue to be drained
+        let res = stability_pool_operations::settle_stability_withdrawal(
+            &mut stability_pool_state,
+            &mut sp_providers[0],
+            &mut liquidations.borrow_mut(),
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            BorrowError::CannotSettleUntilLiquidationGainsCleared.into()
+        );
+
+        liquidations_queue::clear_liquidation_gains(
+            &mut liquidations.borrow_mut(),
+            CollateralToken::SOL,
+            clearing_agent,
+            now_timestamp,
+        );
+
+        let settled = stability_pool_operations::settle_stability_withdrawal(
+            &mut stability_pool_state,
+            &mut sp_providers[0],
+            &mut liquidations.borrow_mut(),
+        )
+        .unwrap();
+
+        assert_eq!(settled.usd_to_withdraw, USDH::from(50.0));
+        assert_eq!(sp_providers[0].pending_withdrawal_stablecoin, 0);
+        assert_eq!(stability_pool_state.stablecoin_pending_withdrawal, 0);
+
+        // Cannot be settled twice
+        let res = stability_pool_operations::settle_stability_withdrawal(
+            &mut stability_pool_state,
+            &mut sp_providers[0],
+            &mut liquidations.borrow_mut(),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_liquidations_queue_withdrawal_request_above_compounded_deposit() {
+        let (
+            _market,
+            mut stability_pool_state,
+            _staking_pool_state,
+            mut epoch_to_scale_to_sum,
+            _liquidations,
+            _hbb_emissions_start_ts,
+            now_timestamp,
+            mut sp_providers,
+        ) = set_up_market(vec![150.0, 150.0]);
+
+        // A liquidation shrinks both deposits to 120
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(1.0), CollateralToken::SOL),
+            USDH::from(60.0),
+            now_timestamp,
+        )
+        .unwrap();
+
+        // Asking for the original deposit gets what is left of it
+        let effects = stability_pool_operations::request_stability_withdrawal(
+            &mut stability_pool_state,
+            &mut sp_providers[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(150.0),
+            now_timestamp,
+        )
+        .unwrap();
+
+        assert_fuzzy_eq!(effects.usd_requested, USDH::from(120.0), 2);
+        assert_eq!(sp_providers[0].deposited_stablecoin, 0);
+        assert_eq!(
+            sp_providers[0].pending_withdrawal_stablecoin,
+            effects.usd_requested
+        );
+        assert_fuzzy_eq!(
+            stability_pool_state.stablecoin_deposited,
+            USDH::from(120.0),
+            2
+        );
+    }
 }
 
 #[cfg(test)]
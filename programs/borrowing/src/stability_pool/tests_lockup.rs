@@ -0,0 +1,323 @@
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, convert::TryFrom};
+
+    use solana_sdk::{clock::SECONDS_PER_DAY, native_token::sol_to_lamports};
+
+    use crate::{
+        assert_fuzzy_eq,
+        stability_pool::stability_pool_operations,
+        state::epoch_to_scale_to_sum::EpochToScaleToSum,
+        utils::{consts::LOCKUP_SECONDS_PER_MONTH, coretypes::USDH},
+        BorrowError, CollateralAmounts, CollateralToken, LiquidationsQueue, LockupTier,
+        StabilityPoolState, StabilityProviderState, StabilityToken,
+    };
+
+    fn setup_pool(
+        num_users: usize,
+        amount: f64,
+    ) -> (
+        StabilityPoolState,
+        EpochToScaleToSum,
+        RefCell<LiquidationsQueue>,
+        Vec<StabilityProviderState>,
+    ) {
+        let mut stability_pool_state = StabilityPoolState::default();
+        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
+        let liquidations = RefCell::new(LiquidationsQueue::default());
+
+        stability_pool_operations::initialize_stability_pool(
+            &mut stability_pool_state,
+            &mut liquidations.borrow_mut(),
+            0,
+        );
+
+        let users = (0..num_users)
+            .map(|_| {
+                let mut user = StabilityProviderState::default();
+                stability_pool_operations::approve_new_user(&mut stability_pool_state, &mut user);
+                stability_pool_operations::provide_stability(
+                    &mut stability_pool_state,
+                    &mut user,
+                    &mut epoch_to_scale_to_sum,
+                    USDH::from(amount),
+                    0,
+                )
+                .unwrap();
+                user
+            })
+            .collect();
+
+        (
+            stability_pool_state,
+            epoch_to_scale_to_sum,
+            liquidations,
+            users,
+        )
+    }
+
+    #[test]
+    fn test_lockup_cannot_withdraw_before_expiry() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, _, mut users) =
+            setup_pool(1, 100.0);
+
+        stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::OneMonth,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(users[0].lockup_tier, LockupTier::OneMonth as u8);
+        assert_eq!(users[0].lockup_expiry_ts, LOCKUP_SECONDS_PER_MONTH);
+
+        let res = stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(10.0),
+            LOCKUP_SECONDS_PER_MONTH - 1,
+        );
+        assert_eq!(res.unwrap_err(), BorrowError::StabilityDepositLocked.into());
+
+        stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(10.0),
+            LOCKUP_SECONDS_PER_MONTH,
+        )
+        .unwrap();
+
+        // the lockup is released once expired
+        assert_eq!(users[0].lockup_tier, LockupTier::None as u8);
+        assert_eq!(users[0].lockup_expiry_ts, 0);
+        assert_eq!(users[0].deposited_stablecoin, USDH::from(90.0));
+        assert_eq!(
+            stability_pool_state.boosted_stablecoin_deposited,
+            USDH::from(90.0)
+        );
+    }
+
+    #[test]
+    fn test_lockup_cannot_be_shortened() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, _, mut users) =
+            setup_pool(1, 100.0);
+
+        stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::SixMonths,
+            0,
+        )
+        .unwrap();
+
+        let res = stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::OneMonth,
+            SECONDS_PER_DAY,
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            BorrowError::LockupCannotBeShortened.into()
+        );
+
+        let res = stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::None,
+            SECONDS_PER_DAY,
+        );
+        assert_eq!(res.unwrap_err(), BorrowError::InvalidLockupTier.into());
+    }
+
+    #[test]
+    fn test_lockup_boosts_hbb_share() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, liquidations, mut users) =
+            setup_pool(2, 100.0);
+
+        // 2x boost, user one is now weighted as 200 USDH for HBB
+        stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::SixMonths,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stability_pool_state.boosted_stablecoin_deposited,
+            USDH::from(300.0)
+        );
+
+        for user in users.iter_mut() {
+            stability_pool_operations::harvest_liquidation_gains(
+                &mut stability_pool_state,
+                user,
+                &mut epoch_to_scale_to_sum,
+                &mut liquidations.borrow_mut(),
+                SECONDS_PER_DAY,
+                StabilityToken::HBB,
+            )
+            .unwrap();
+        }
+
+        // issuance after one day is 58814171800
+        let total_hbb_issuance = stability_pool_state.cumulative_gains_total.hbb;
+        assert_fuzzy_eq!(total_hbb_issuance, 58814171800u64, 10);
+        assert_fuzzy_eq!(
+            users[0].cumulative_gains_per_user.hbb,
+            total_hbb_issuance * 2 / 3,
+            10
+        );
+        assert_fuzzy_eq!(
+            users[1].cumulative_gains_per_user.hbb,
+            total_hbb_issuance / 3,
+            10
+        );
+        assert!(
+            users[0].cumulative_gains_per_user.hbb + users[1].cumulative_gains_per_user.hbb
+                <= total_hbb_issuance
+        );
+    }
+
+    #[test]
+    fn test_lockup_absorbs_liquidations() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, liquidations, mut users) =
+            setup_pool(2, 100.0);
+
+        stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::ThreeMonths,
+            0,
+        )
+        .unwrap();
+
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(10.0), CollateralToken::SOL),
+            USDH::from(50.0),
+            0,
+        )
+        .unwrap();
+
+        // boosted deposits shrink with the pool: (150 + 100) * 150 / 200
+        assert_fuzzy_eq!(
+            stability_pool_state.boosted_stablecoin_deposited,
+            USDH::from(187.5),
+            10
+        );
+
+        for user in users.iter_mut() {
+            stability_pool_operations::harvest_liquidation_gains(
+                &mut stability_pool_state,
+                user,
+                &mut epoch_to_scale_to_sum,
+                &mut liquidations.borrow_mut(),
+                0,
+                StabilityToken::SOL,
+            )
+            .unwrap();
+        }
+
+        // collateral gains and losses are not boosted
+        for user in users.iter() {
+            assert_fuzzy_eq!(user.deposited_stablecoin, USDH::from(75.0), 10);
+            assert_fuzzy_eq!(user.cumulative_gains_per_user.sol, sol_to_lamports(5.0), 10);
+        }
+
+        let res = stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50.0),
+            0,
+        );
+        assert!(res.is_err());
+
+        stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[1],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50.0),
+            0,
+        )
+        .unwrap();
+    }
+    #[test]
+    fn test_lockup_release_expired() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, liquidations, mut users) =
+            setup_pool(2, 100.0);
+
+        stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::OneMonth,
+            0,
+        )
+        .unwrap();
+
+        let res = stability_pool_operations::release_stability_lockup(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LOCKUP_SECONDS_PER_MONTH - 1,
+        );
+        assert_eq!(res.unwrap_err(), BorrowError::LockupNotExpired.into());
+
+        let res = stability_pool_operations::release_stability_lockup(
+            &mut stability_pool_state,
+            &mut users[1],
+            &mut epoch_to_scale_to_sum,
+            LOCKUP_SECONDS_PER_MONTH,
+        );
+        assert_eq!(res.unwrap_err(), BorrowError::LockupNotExpired.into());
+
+        // Anyone releases the expired lockup, the boost stops at once
+        stability_pool_operations::release_stability_lockup(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LOCKUP_SECONDS_PER_MONTH,
+        )
+        .unwrap();
+
+        assert_eq!(users[0].lockup_tier, LockupTier::None as u8);
+        assert_eq!(users[0].deposited_stablecoin, USDH::from(100.0));
+        assert_eq!(
+            stability_pool_state.boosted_stablecoin_deposited,
+            USDH::from(200.0)
+        );
+
+        // HBB accrued while boosted is kept
+        for user in users.iter_mut() {
+            stability_pool_operations::harvest_liquidation_gains(
+                &mut stability_pool_state,
+                user,
+                &mut epoch_to_scale_to_sum,
+                &mut liquidations.borrow_mut(),
+                LOCKUP_SECONDS_PER_MONTH,
+                StabilityToken::HBB,
+            )
+            .unwrap();
+        }
+        assert!(users[0].cumulative_gains_per_user.hbb > users[1].cumulative_gains_per_user.hbb);
+    }
+
+    #[test]
+    fn test_lockup_tier_from_invalid_input() {
+        assert_eq!(LockupTier::try_from(3), Ok(LockupTier::SixMonths));
+        assert_eq!(LockupTier::try_from(4), Err(BorrowError::InvalidLockupTier));
+    }
+}
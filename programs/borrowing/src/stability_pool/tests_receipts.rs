@@ -0,0 +1,401 @@
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use solana_sdk::native_token::sol_to_lamports;
+
+    use crate::{
+        assert_fuzzy_eq, stability_pool::stability_pool_operations,
+        state::epoch_to_scale_to_sum::EpochToScaleToSum, utils::coretypes::USDH, BorrowError,
+        CollateralAmounts, CollateralToken, LiquidationsQueue, LockupTier, StabilityPoolState,
+        StabilityProviderState, StabilityToken,
+    };
+
+    fn setup_pool(
+        num_users: usize,
+        amount: f64,
+    ) -> (
+        StabilityPoolState,
+        EpochToScaleToSum,
+        RefCell<LiquidationsQueue>,
+        Vec<StabilityProviderState>,
+    ) {
+        let mut stability_pool_state = StabilityPoolState::default();
+        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
+        let liquidations = RefCell::new(LiquidationsQueue::default());
+
+        stability_pool_operations::initialize_stability_pool(
+            &mut stability_pool_state,
+            &mut liquidations.borrow_mut(),
+            0,
+        );
+
+        let users = (0..num_users)
+            .map(|_| {
+                let mut user = StabilityProviderState::default();
+                stability_pool_operations::approve_new_user(&mut stability_pool_state, &mut user);
+                stability_pool_operations::provide_stability(
+                    &mut stability_pool_state,
+                    &mut user,
+                    &mut epoch_to_scale_to_sum,
+                    USDH::from(amount),
+                    0,
+                )
+                .unwrap();
+                user
+            })
+            .collect();
+
+        (
+            stability_pool_state,
+            epoch_to_scale_to_sum,
+            liquidations,
+            users,
+        )
+    }
+
+    #[test]
+    fn test_receipts_follow_exchange_rate() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, _, mut users) =
+            setup_pool(2, 100.0);
+
+        // first deposits are minted 1:1
+        assert_eq!(users[0].receipt_shares, USDH::from(100.0));
+        assert_eq!(users[1].receipt_shares, USDH::from(100.0));
+        assert_eq!(stability_pool_state.receipt_supply, USDH::from(200.0));
+
+        // the pool halves, so does the value of a receipt
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(10.0), CollateralToken::SOL),
+            USDH::from(100.0),
+            0,
+        )
+        .unwrap();
+
+        let mut new_user = StabilityProviderState::default();
+        stability_pool_operations::approve_new_user(&mut stability_pool_state, &mut new_user);
+        let effects = stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut new_user,
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50.0),
+            0,
+        )
+        .unwrap();
+
+        assert_fuzzy_eq!(effects.receipts_to_mint, USDH::from(100.0), 10);
+        assert_eq!(new_user.receipt_shares, effects.receipts_to_mint);
+
+        // withdrawing half of the deposit burns half of the receipts
+        let effects = stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(25.0),
+            0,
+        )
+        .unwrap();
+
+        assert_fuzzy_eq!(effects.receipts_to_burn, USDH::from(50.0), 10);
+        assert_fuzzy_eq!(users[0].receipt_shares, USDH::from(50.0), 10);
+
+        // a full withdrawal burns them all
+        stability_pool_operations::update_pending_gains(
+            &mut stability_pool_state,
+            &mut users[1],
+            &epoch_to_scale_to_sum,
+        )
+        .unwrap();
+        let deposited_stablecoin = users[1].deposited_stablecoin;
+        let effects = stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[1],
+            &mut epoch_to_scale_to_sum,
+            deposited_stablecoin,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(effects.receipts_to_burn, USDH::from(100.0));
+        assert_eq!(users[1].receipt_shares, 0);
+    }
+
+    #[test]
+    fn test_receipts_transfer_moves_deposit_and_gains() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, liquidations, mut users) =
+            setup_pool(2, 100.0);
+
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(10.0), CollateralToken::SOL),
+            0,
+            0,
+        )
+        .unwrap();
+
+        // user one transfers half of their receipts to user two,
+        // half of their pending gains go along with them
+        let (from, to) = users.split_at_mut(1);
+        let effects = stability_pool_operations::transfer_stability_receipts(
+            &mut stability_pool_state,
+            &mut from[0],
+            &mut to[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50.0),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(effects.usd_moved, USDH::from(50.0));
+        assert_fuzzy_eq!(effects.gains_moved.sol, sol_to_lamports(2.5), 10);
+        assert_eq!(users[0].deposited_stablecoin, USDH::from(50.0));
+        assert_eq!(users[1].deposited_stablecoin, USDH::from(150.0));
+        assert_fuzzy_eq!(
+            users[0].pending_gains_per_user.sol,
+            sol_to_lamports(2.5),
+            10
+        );
+        assert_fuzzy_eq!(
+            users[1].pending_gains_per_user.sol,
+            sol_to_lamports(7.5),
+            10
+        );
+        assert_eq!(users[0].receipt_shares, USDH::from(50.0));
+        assert_eq!(users[1].receipt_shares, USDH::from(150.0));
+        assert_eq!(stability_pool_state.stablecoin_deposited, USDH::from(200.0));
+
+        // cannot transfer more than what backs the deposit
+        let (from, to) = users.split_at_mut(1);
+        let res = stability_pool_operations::transfer_stability_receipts(
+            &mut stability_pool_state,
+            &mut from[0],
+            &mut to[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50.0) + 1,
+            0,
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            BorrowError::NoStabilityReceiptsToTransfer.into()
+        );
+
+        // later gains follow the new deposits
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(10.0), CollateralToken::SOL),
+            0,
+            0,
+        )
+        .unwrap();
+
+        for user in users.iter_mut() {
+            stability_pool_operations::harvest_liquidation_gains(
+                &mut stability_pool_state,
+                user,
+                &mut epoch_to_scale_to_sum,
+                &mut liquidations.borrow_mut(),
+                0,
+                StabilityToken::SOL,
+            )
+            .unwrap();
+        }
+
+        assert_fuzzy_eq!(
+            users[0].cumulative_gains_per_user.sol,
+            sol_to_lamports(5.0),
+            10
+        );
+        assert_fuzzy_eq!(
+            users[1].cumulative_gains_per_user.sol,
+            sol_to_lamports(15.0),
+            10
+        );
+    }
+
+    #[test]
+    fn test_receipts_of_locked_deposit_cannot_be_transferred() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, _, mut users) =
+            setup_pool(2, 100.0);
+
+        stability_pool_operations::lock_stability_deposit(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            LockupTier::OneMonth,
+            0,
+        )
+        .unwrap();
+
+        let (from, to) = users.split_at_mut(1);
+        let res = stability_pool_operations::transfer_stability_receipts(
+            &mut stability_pool_state,
+            &mut from[0],
+            &mut to[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(100.0),
+            0,
+        );
+        assert_eq!(res.unwrap_err(), BorrowError::StabilityDepositLocked.into());
+        assert_eq!(users[0].deposited_stablecoin, USDH::from(100.0));
+        assert_eq!(users[1].deposited_stablecoin, USDH::from(100.0));
+    }
+
+    #[test]
+    fn test_receipts_bound_to_their_epoch() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, _, mut users) =
+            setup_pool(2, 100.0);
+
+        // the pool is emptied, it moves to a new epoch
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(10.0), CollateralToken::SOL),
+            USDH::from(200.0),
+            0,
+        )
+        .unwrap();
+        assert_eq!(stability_pool_state.current_epoch, 1);
+
+        // receipts of the previous epoch back nothing
+        let (from, to) = users.split_at_mut(1);
+        let res = stability_pool_operations::transfer_stability_receipts(
+            &mut stability_pool_state,
+            &mut from[0],
+            &mut to[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(100.0),
+            0,
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            BorrowError::NoStabilityReceiptsToTransfer.into()
+        );
+
+        // and the rate starts over
+        assert_eq!(
+            stability_pool_operations::next_receipt_epoch(&stability_pool_state),
+            1
+        );
+        let effects = stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(100.0),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(effects.receipts_to_mint, USDH::from(100.0));
+        assert_eq!(users[0].receipt_shares, USDH::from(100.0));
+        assert_eq!(users[0].receipt_epoch, 1);
+        assert_eq!(stability_pool_state.receipt_supply, USDH::from(100.0));
+        assert_eq!(stability_pool_state.receipt_epoch, 1);
+
+        // the old receipts of user two don't keep the account open
+        stability_pool_operations::harvest_liquidation_gains(
+            &mut stability_pool_state,
+            &mut users[1],
+            &mut epoch_to_scale_to_sum,
+            &mut RefCell::new(LiquidationsQueue::default()).borrow_mut(),
+            0,
+            StabilityToken::SOL,
+        )
+        .unwrap();
+        stability_pool_operations::close_stability_provider(&mut stability_pool_state, &users[1])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_receipts_rate_starts_over_once_a_receipt_is_worth_too_little() {
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum, _, mut users) =
+            setup_pool(1, 1_000_000.0);
+
+        // a thousand stablecoin units are left for the whole receipt supply
+        stability_pool_operations::liquidate(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            CollateralAmounts::of_token(sol_to_lamports(10.0), CollateralToken::SOL),
+            USDH::from(1_000_000.0) - 1_000,
+            0,
+        )
+        .unwrap();
+        assert_eq!(stability_pool_state.current_epoch, 0);
+        assert_eq!(
+            stability_pool_operations::next_receipt_epoch(&stability_pool_state),
+            1
+        );
+
+        // the deposit left is owed receipts 1:1 in the new receipt epoch
+        let mut user = StabilityProviderState::default();
+        stability_pool_operations::approve_new_user(&mut stability_pool_state, &mut user);
+        let effects = stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut user,
+            &mut epoch_to_scale_to_sum,
+            USDH::from(100_000.0),
+            0,
+        )
+        .unwrap();
+        assert_eq!(effects.receipts_to_mint, USDH::from(100_000.0));
+        assert_eq!(stability_pool_state.receipt_epoch, 1);
+        assert_fuzzy_eq!(
+            stability_pool_state.receipt_supply,
+            USDH::from(100_000.0) + 1_000,
+            10
+        );
+
+        // the old receipts are stale until reissued to their provider
+        let res = stability_pool_operations::transfer_stability_receipts(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut user,
+            &mut epoch_to_scale_to_sum,
+            1_000,
+            0,
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            BorrowError::NoStabilityReceiptsToTransfer.into()
+        );
+
+        let effects = stability_pool_operations::transfer_stability_receipts(
+            &mut stability_pool_state,
+            &mut user,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            USDH::from(50_000.0),
+            0,
+        )
+        .unwrap();
+        assert_fuzzy_eq!(effects.receipts_to_reissue, 1_000, 10);
+        assert_fuzzy_eq!(effects.usd_moved, USDH::from(50_000.0), 10);
+        assert_eq!(
+            users[0].receipt_shares,
+            USDH::from(50_000.0) + effects.receipts_to_reissue
+        );
+        assert_eq!(users[0].receipt_epoch, 1);
+
+        // withdrawing everything burns every receipt that was issued
+        let withdrawn = users[0].deposited_stablecoin;
+        let receipt_shares = users[0].receipt_shares;
+        let effects = stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut users[0],
+            &mut epoch_to_scale_to_sum,
+            withdrawn,
+            0,
+        )
+        .unwrap();
+        assert_eq!(effects.receipts_to_burn, receipt_shares);
+        assert_eq!(users[0].receipt_shares, 0);
+        assert_fuzzy_eq!(
+            stability_pool_state.receipt_supply,
+            USDH::from(50_000.0),
+            10
+        );
+    }
+}
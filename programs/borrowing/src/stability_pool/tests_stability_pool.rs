@@ -14,6 +14,7 @@ mod tests {
         },
         state::epoch_to_scale_to_sum::EpochToScaleToSum,
         utils::{coretypes::USDH, math::coll_to_lamports},
+        BorrowError,
     };
     use anchor_lang::solana_program::native_token::sol_to_lamports;
 
@@ -1132,6 +1133,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_stability_close_provider() {
+        let mut stability_pool_state = StabilityPoolState::default();
+        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
+        let liquidations = RefCell::new(LiquidationsQueue::default());
+        let now_timestamp = 0;
+
+        stability_pool_operations::initialize_stability_pool(
+            &mut stability_pool_state,
+            &mut liquidations.borrow_mut(),
+            0,
+        );
+
+        let mut user_one = StabilityProviderState::default();
+        stability_pool_operations::approve_new_user(&mut stability_pool_state, &mut user_one);
+
+        stability_pool_operations::provide_stability(
+            &mut stability_pool_state,
+            &mut user_one,
+            &mut epoch_to_scale_to_sum,
+            USDH::from(100.0),
+            now_timestamp,
+        )
+        .unwrap();
+
+        let res = stability_pool_operations::close_stability_provider(
+            &mut stability_pool_state,
+            &user_one,
+        );
+        assert_eq!(res.err(), Some(BorrowError::StabilityProviderNotEmpty));
+
+        stability_pool_operations::withdraw_stability(
+            &mut stability_pool_state,
+            &mut user_one,
+            &mut epoch_to_scale_to_sum,
+            USDH::from(100.0),
+            now_timestamp,
+        )
+        .unwrap();
+
+        // Gains not yet harvested keep the account open
+        user_one.pending_gains_per_user.sol = 1;
+        let res = stability_pool_operations::close_stability_provider(
+            &mut stability_pool_state,
+            &user_one,
+        );
+        assert_eq!(res.err(), Some(BorrowError::StabilityProviderNotEmpty));
+        user_one.pending_gains_per_user.sol = 0;
+
+        stability_pool_operations::close_stability_provider(&mut stability_pool_state, &user_one)
+            .unwrap();
+        assert_eq!(stability_pool_state.num_closed_users, 1);
+        assert_eq!(stability_pool_state.num_users, 1);
+    }
+
     #[test]
     fn test_stability_single_user_big_numbers() {
         // This test ensures that big number operations don't panic
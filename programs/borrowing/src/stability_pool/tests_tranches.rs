@@ -0,0 +1,311 @@
+#![allow(unaligned_references)]
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use anchor_lang::prelude::Pubkey;
+
+    use crate::borrowing_market::borrowing_operations;
+    use crate::borrowing_market::tests_utils::utils::{
+        new_borrowing_users_with_amounts_and_price, set_up_market,
+    };
+    use crate::borrowing_market::types::TrancheLiquidationEffects;
+    use crate::stability_pool::liquidations_queue;
+    use crate::stability_pool::stability_pool_operations;
+    use crate::stability_pool::tests_utils::utils::new_stability_users;
+    use crate::stability_pool::types::{LiquidationRouting, StabilityPoolTranche};
+    use crate::state::epoch_to_scale_to_sum::EpochToScaleToSum;
+    use crate::state::*;
+    use crate::utils::consts::MAX_STABILITY_POOLS;
+    use crate::utils::coretypes::USDH;
+    use crate::utils::math::coll_to_lamports;
+    use crate::{assert_fuzzy_eq, BorrowError};
+
+    const SOL_ONLY: u8 = 0b0000_0001;
+    const ETH_AND_BTC: u8 = 0b0000_0110;
+
+    fn new_pool(collateral_coverage: u8, deposit: f64) -> (StabilityPoolState, EpochToScaleToSum) {
+        let mut stability_pool_state = StabilityPoolState::default();
+        let mut epoch_to_scale_to_sum = EpochToScaleToSum::default();
+        stability_pool_operations::initialize_stability_pool(
+            &mut stability_pool_state,
+            &mut RefCell::new(LiquidationsQueue::default()).borrow_mut(),
+            0,
+        );
+        stability_pool_operations::set_collateral_coverage(
+            &mut stability_pool_state,
+            collateral_coverage,
+        )
+        .unwrap();
+        new_stability_users(
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            1,
+            deposit,
+        );
+        (stability_pool_state, epoch_to_scale_to_sum)
+    }
+
+    #[test]
+    fn test_tranches_coverage_rejects_unknown_tokens() {
+        let mut stability_pool_state = StabilityPoolState::default();
+        let res = stability_pool_operations::set_collateral_coverage(
+            &mut stability_pool_state,
+            0b0100_0000,
+        );
+        assert_eq!(res.unwrap_err(), BorrowError::InvalidCollateralCoverage);
+
+        // no coverage set means the pool covers everything
+        for token in (0..6).map(CollateralToken::from) {
+            assert!(stability_pool_state.covers(token));
+        }
+    }
+
+    #[test]
+    fn test_tranches_route_splits_tokens_between_covering_pools() {
+        // Main pool covers everything, the tranche only ETH and BTC,
+        // both hold 1000 USDH. The SOL goes to the main pool only,
+        // the ETH is split in half.
+        let (main_pool, _) = new_pool(0, 1000.0);
+        let (tranche_pool, _) = new_pool(ETH_AND_BTC, 1000.0);
+
+        let collateral = CollateralAmounts {
+            sol: coll_to_lamports(10.0, CollateralToken::SOL),
+            eth: coll_to_lamports(10.0, CollateralToken::ETH),
+            ..Default::default()
+        };
+
+        let LiquidationRouting {
+            usd_debt_to_stability_pools,
+            coll_to_stability_pools,
+            usd_debt_not_covered,
+            coll_not_covered,
+        } = stability_pool_operations::route_liquidation(
+            &[&main_pool, &tranche_pool],
+            USDH::from(180.0),
+            &collateral,
+            &TokenPrices::new_all(10.0),
+        );
+
+        assert_eq!(usd_debt_not_covered, 0);
+        assert!(coll_not_covered.is_zero());
+        assert_fuzzy_eq!(usd_debt_to_stability_pools[0], USDH::from(135.0), 1);
+        assert_fuzzy_eq!(usd_debt_to_stability_pools[1], USDH::from(45.0), 1);
+        assert_eq!(
+            usd_debt_to_stability_pools[0] + usd_debt_to_stability_pools[1],
+            USDH::from(180.0)
+        );
+
+        assert_eq!(
+            coll_to_stability_pools[0],
+            CollateralAmounts {
+                sol: coll_to_lamports(10.0, CollateralToken::SOL),
+                eth: coll_to_lamports(5.0, CollateralToken::ETH),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            coll_to_stability_pools[1],
+            CollateralAmounts::of_token(
+                coll_to_lamports(5.0, CollateralToken::ETH),
+                CollateralToken::ETH
+            )
+        );
+    }
+
+    #[test]
+    fn test_tranches_route_skips_pools_taking_no_debt() {
+        // The main pool's share of a small liquidation rounds to no
+        // debt, the collateral it would have received goes to the tranche
+        let (main_pool, _) = new_pool(0, 0.001);
+        let (tranche_pool, _) = new_pool(0, 1000.0);
+
+        let collateral = CollateralAmounts::of_token(
+            coll_to_lamports(10.0, CollateralToken::SOL),
+            CollateralToken::SOL,
+        );
+
+        let routing = stability_pool_operations::route_liquidation(
+            &[&main_pool, &tranche_pool],
+            100,
+            &collateral,
+            &TokenPrices::new_all(10.0),
+        );
+
+        assert_eq!(routing.usd_debt_to_stability_pools, vec![0, 100]);
+        assert!(routing.coll_to_stability_pools[0].is_zero());
+        assert_eq!(routing.coll_to_stability_pools[1], collateral);
+        assert!(routing.coll_not_covered.is_zero());
+    }
+
+    #[test]
+    fn test_tranches_route_leaves_uncovered_collateral() {
+        // Only a SOL pool, the ETH half of the liquidation is left over
+        let (sol_pool, _) = new_pool(SOL_ONLY, 1000.0);
+
+        let collateral = CollateralAmounts {
+            sol: coll_to_lamports(10.0, CollateralToken::SOL),
+            eth: coll_to_lamports(10.0, CollateralToken::ETH),
+            ..Default::default()
+        };
+
+        let routing = stability_pool_operations::route_liquidation(
+            &[&sol_pool],
+            USDH::from(180.0),
+            &collateral,
+            &TokenPrices::new_all(10.0),
+        );
+
+        assert_eq!(routing.usd_debt_to_stability_pools[0], USDH::from(90.0));
+        assert_eq!(routing.usd_debt_not_covered, USDH::from(90.0));
+        assert_eq!(
+            routing.coll_to_stability_pools[0],
+            CollateralAmounts::of_token(
+                coll_to_lamports(10.0, CollateralToken::SOL),
+                CollateralToken::SOL
+            )
+        );
+        assert_eq!(
+            routing.coll_not_covered,
+            CollateralAmounts::of_token(
+                coll_to_lamports(10.0, CollateralToken::ETH),
+                CollateralToken::ETH
+            )
+        );
+    }
+
+    #[test]
+    fn test_tranches_capacity_limited_by_smallest_share() {
+        // The SOL pool would take half of the debt but only holds 50,
+        // so no more than 100 can be offset in total
+        let (sol_pool, _) = new_pool(SOL_ONLY, 50.0);
+        let (eth_pool, _) = new_pool(ETH_AND_BTC, 1000.0);
+
+        let collateral = CollateralAmounts {
+            sol: coll_to_lamports(10.0, CollateralToken::SOL),
+            eth: coll_to_lamports(10.0, CollateralToken::ETH),
+            ..Default::default()
+        };
+
+        let capacity = stability_pool_operations::stability_pools_capacity(
+            &[&sol_pool, &eth_pool],
+            &collateral,
+            &TokenPrices::new_all(10.0),
+        );
+
+        assert_eq!(capacity, USDH::from(100.0));
+    }
+
+    #[test]
+    fn test_tranches_liquidation_split_between_pools() {
+        // Main pool covers SOL, the tranche ETH and BTC. The borrower
+        // holds as much SOL as ETH in value and is liquidated at 109%,
+        // each pool absorbs half of the debt and its own token.
+        let (mut market, _, _, _, mut staking_pool_state, now_timestamp) = set_up_market();
+
+        let (mut stability_pool_state, mut epoch_to_scale_to_sum) = new_pool(SOL_ONLY, 1000.0);
+        let liquidations = RefCell::new(LiquidationsQueue::default());
+        let (mut tranche_pool_state, mut tranche_epoch_to_scale_to_sum) =
+            new_pool(ETH_AND_BTC, 1000.0);
+        let tranche_liquidations = RefCell::new(LiquidationsQueue::default());
+
+        let deposit = CollateralAmounts {
+            sol: coll_to_lamports(1.0, CollateralToken::SOL),
+            eth: coll_to_lamports(1.0, CollateralToken::ETH),
+            ..Default::default()
+        };
+        let mut borrowers = new_borrowing_users_with_amounts_and_price(
+            &mut market,
+            &mut staking_pool_state,
+            2,
+            &[USDH::from(200.0); 2],
+            &[deposit; 2],
+            200.0,
+            now_timestamp,
+        );
+        let borrowed_stablecoin = borrowers[0].borrowed_stablecoin;
+
+        let mut tranche_queue = tranche_liquidations.borrow_mut();
+        let mut tranches = [StabilityPoolTranche {
+            stability_pool_state: &mut tranche_pool_state,
+            epoch_to_scale_to_sum: &mut tranche_epoch_to_scale_to_sum,
+            liquidations_queue: &mut tranche_queue,
+        }];
+
+        let TrancheLiquidationEffects {
+            liquidation_event,
+            usd_to_burn_from_stability_pool,
+            usd_to_burn_from_tranches,
+        } = borrowing_operations::try_liquidate_with_tranches(
+            Pubkey::new_unique(),
+            &mut market,
+            &mut borrowers[0],
+            &mut stability_pool_state,
+            &mut epoch_to_scale_to_sum,
+            &TokenPrices::new_all(110.0),
+            &mut liquidations.borrow_mut(),
+            &mut tranches,
+            now_timestamp,
+        )
+        .unwrap();
+
+        assert_eq!(
+            usd_to_burn_from_stability_pool + usd_to_burn_from_tranches[0],
+            borrowed_stablecoin
+        );
+        assert_fuzzy_eq!(
+            usd_to_burn_from_stability_pool,
+            usd_to_burn_from_tranches[0],
+            1
+        );
+        assert_eq!(
+            stability_pool_state.stablecoin_deposited,
+            USDH::from(1000.0) - usd_to_burn_from_stability_pool
+        );
+        assert_eq!(
+            tranche_pool_state.stablecoin_deposited,
+            USDH::from(1000.0) - usd_to_burn_from_tranches[0]
+        );
+
+        // Each event carries the liquidator and clearer gains
+        // taken from the collateral of its own pool
+        let sol = CollateralAmounts::of_token(deposit.sol, CollateralToken::SOL);
+        let eth = CollateralAmounts::of_token(deposit.eth, CollateralToken::ETH);
+        assert_eq!(
+            liquidation_event.collateral_gain_to_stability_pool,
+            sol.mul_bps(10_000 - 50)
+        );
+        assert_eq!(
+            liquidation_event.collateral_gain_to_liquidator,
+            sol.mul_bps(40)
+        );
+        assert_eq!(
+            liquidation_event.collateral_gain_to_clearer,
+            sol.mul_bps(10)
+        );
+        assert_eq!(liquidations_queue::len(&mut tranche_queue), 1);
+        let tranche_event = liquidations_queue::get(&mut tranche_queue, 0);
+        assert_eq!(tranche_event.collateral_gain_to_liquidator, eth.mul_bps(40));
+        assert_eq!(tranche_event.collateral_gain_to_clearer, eth.mul_bps(10));
+        assert_eq!(
+            tranche_event.collateral_gain_to_stability_pool,
+            eth.mul_bps(10_000 - 50)
+        );
+    }
+
+    #[test]
+    fn test_tranches_registered_on_the_market() {
+        let mut market = BorrowingMarketState::new();
+        let stability_pools: Vec<Pubkey> = (0..MAX_STABILITY_POOLS)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        for stability_pool in stability_pools.iter() {
+            borrowing_operations::register_stability_pool(&mut market, *stability_pool).unwrap();
+        }
+        assert_eq!(market.registered_stability_pools(), &stability_pools[..]);
+
+        let res = borrowing_operations::register_stability_pool(&mut market, Pubkey::new_unique());
+        assert_eq!(res.unwrap_err(), BorrowError::TooManyStabilityPools);
+        assert_eq!(market.registered_stability_pools(), &stability_pools[..]);
+    }
+}
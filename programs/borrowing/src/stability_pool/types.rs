@@ -1,12 +1,58 @@
-use crate::{StabilityCollateralAmounts, StabilityTokenMap};
+use std::cell::RefMut;
+
+use crate::{
+    state::epoch_to_scale_to_sum::EpochToScaleToSum, CollateralAmounts, LiquidationsQueue,
+    StabilityCollateralAmounts, StabilityPoolState, StabilityTokenMap,
+};
 #[derive(Debug)]
 pub struct ProvideStabilityEffects {
     pub usd_to_stability_pool_transfer: u64,
+    pub receipts_to_mint: u64,
 }
 
 #[derive(Debug)]
 pub struct WithdrawStabilityEffects {
     pub usd_remaining_to_withdraw: u64,
+    pub receipts_to_burn: u64,
+}
+
+#[derive(Debug)]
+pub struct RequestStabilityWithdrawalEffects {
+    pub usd_requested: u64,
+    pub receipts_to_burn: u64,
+}
+
+#[derive(Debug)]
+pub struct TransferStabilityReceiptsEffects {
+    pub usd_moved: u64,
+    pub gains_moved: StabilityCollateralAmounts,
+    pub receipts_to_reissue: u64,
+}
+
+#[derive(Debug)]
+pub struct SettleStabilityWithdrawalEffects {
+    pub usd_to_withdraw: u64,
+}
+
+#[derive(Debug)]
+pub struct LockStabilityDepositEffects {
+    pub lockup_expiry_ts: u64,
+}
+
+#[derive(Debug)]
+pub struct LiquidationRouting {
+    pub usd_debt_to_stability_pools: Vec<u64>,
+    pub coll_to_stability_pools: Vec<CollateralAmounts>,
+    pub usd_debt_not_covered: u64,
+    pub coll_not_covered: CollateralAmounts,
+}
+
+// A stability pool, other than the market's main one,
+// taking part in a liquidation
+pub struct StabilityPoolTranche<'a, 'b> {
+    pub stability_pool_state: &'a mut StabilityPoolState,
+    pub epoch_to_scale_to_sum: &'a mut EpochToScaleToSum,
+    pub liquidations_queue: &'a mut RefMut<'b, LiquidationsQueue>,
 }
 
 #[derive(Debug)]
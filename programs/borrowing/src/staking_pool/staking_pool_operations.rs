@@ -1,6 +1,16 @@
-use super::types::{HarvestEffects, UnstakeEffects};
-use crate::{utils::consts::DECIMAL_PRECISION, BorrowError, StakingPoolState, UserStakingState};
-use anchor_lang::prelude::ProgramError;
+use super::types::{
+    CompleteUnstakeEffects, HarvestCollateralEffects, HarvestEffects, RequestUnstakeEffects,
+    TreasuryDisbursementEffects, UnstakeEffects, WithdrawLockedHbbEffects,
+};
+use crate::{
+    utils::consts::{
+        DECIMAL_PRECISION, HBB_FACTOR, MAX_TREASURY_FEE_RATE,
+        TREASURY_DISBURSEMENT_TIMELOCK_SECONDS,
+    },
+    BoostCheckpoints, BorrowError, CollateralAmounts, CollateralToken, StakingPoolState,
+    UserStakingState,
+};
+use anchor_lang::prelude::{ProgramError, Pubkey};
 
 pub fn initialize_staking_pool(staking_pool_state: &mut StakingPoolState) {
     staking_pool_state.initialize_staking_pool();
@@ -27,105 +37,689 @@ pub fn approve_new_user(
 pub fn user_stake(
     staking_pool_state: &mut StakingPoolState,
     user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
     amount: u64,
+    now_timestamp: u64,
 ) {
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
     user_staking_state.user_stake = user_staking_state
         .user_stake
         .checked_add(amount as u128)
         .unwrap();
 
-    user_staking_state.rewards_tally = user_staking_state
-        .rewards_tally
-        .checked_add(
-            (amount as u128)
-                .checked_mul(staking_pool_state.reward_per_token)
-                .unwrap(),
-        )
-        .unwrap();
-
     staking_pool_state.total_stake = staking_pool_state
         .total_stake
         .checked_add(amount as u128)
         .unwrap();
+
+    // Liquid stake is not boosted
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
 }
 
 pub fn user_harvest(
     staking_pool_state: &mut StakingPoolState,
     user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    now_timestamp: u64,
 ) -> Result<HarvestEffects, ProgramError> {
-    // Unscale the reward
-    let reward = user_staking_state
-        .user_stake
-        .checked_mul(staking_pool_state.reward_per_token)
-        .unwrap()
-        .checked_sub(user_staking_state.rewards_tally)
-        .unwrap();
-    let reward = reward.checked_div(DECIMAL_PRECISION).unwrap();
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
 
-    user_staking_state.rewards_tally = user_staking_state
-        .user_stake
-        .checked_mul(staking_pool_state.reward_per_token)
-        .unwrap();
+    let reward = std::mem::take(&mut user_staking_state.pending_rewards);
 
     staking_pool_state.rewards_not_yet_claimed = staking_pool_state
         .rewards_not_yet_claimed
         .checked_sub(reward)
-        .unwrap();
+        .ok_or(BorrowError::StakingRewardsAccountingMismatch)?;
 
     Ok(HarvestEffects { reward })
 }
 
+pub fn user_harvest_collateral(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    token: CollateralToken,
+    now_timestamp: u64,
+) -> Result<HarvestCollateralEffects, BorrowError> {
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    let collateral_reward = CollateralAmounts::of_token(
+        user_staking_state
+            .pending_collateral_rewards
+            .token_amount(token),
+        token,
+    );
+    if collateral_reward.is_zero() {
+        return Err(BorrowError::NoRewardToWithdraw);
+    }
+
+    user_staking_state
+        .pending_collateral_rewards
+        .sub_assign(&collateral_reward);
+    staking_pool_state
+        .collateral_rewards_not_yet_claimed
+        .sub_assign(&collateral_reward);
+
+    Ok(HarvestCollateralEffects { collateral_reward })
+}
+
 pub fn user_unstake(
     staking_pool_state: &mut StakingPoolState,
     user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
     amount: u64,
+    now_timestamp: u64,
 ) -> Result<UnstakeEffects, ProgramError> {
-    // Unscale the reward
-    let reward = user_staking_state
-        .user_stake
-        .checked_mul(staking_pool_state.reward_per_token)
-        .unwrap()
-        .checked_sub(user_staking_state.rewards_tally)
-        .unwrap();
-    let reward = reward.checked_div(DECIMAL_PRECISION).unwrap();
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
 
-    let amount_to_withdraw = std::cmp::min(amount as u128, user_staking_state.user_stake);
+    let reward = std::mem::take(&mut user_staking_state.pending_rewards);
 
-    user_staking_state.rewards_tally = user_staking_state
-        .user_stake
-        .checked_mul(staking_pool_state.reward_per_token)
-        .unwrap();
+    // Locked HBB is withdrawn through withdraw_locked_hbb
+    let amount_to_withdraw = std::cmp::min(amount as u128, user_staking_state.user_stake);
 
     staking_pool_state.rewards_not_yet_claimed = staking_pool_state
         .rewards_not_yet_claimed
         .checked_sub(reward)
+        .ok_or(BorrowError::StakingRewardsAccountingMismatch)?;
+
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
+    user_staking_state.user_stake = user_staking_state
+        .user_stake
+        .checked_sub(amount_to_withdraw)
+        .unwrap();
+
+    staking_pool_state.total_stake = staking_pool_state
+        .total_stake
+        .checked_sub(amount_to_withdraw)
         .unwrap();
 
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    Ok(UnstakeEffects {
+        reward,
+        amount_to_withdraw: amount_to_withdraw as u64,
+    })
+}
+
+pub fn request_unstake(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    amount: u64,
+    cooldown_seconds: u64,
+    now_timestamp: u64,
+) -> Result<RequestUnstakeEffects, BorrowError> {
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    let amount_requested = std::cmp::min(amount as u128, user_staking_state.user_stake);
+    if amount_requested == 0 {
+        return Err(BorrowError::NothingToUnstake);
+    }
+
+    // The requested HBB stops earning right away, what it
+    // earned so far stays pending until harvested
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
     user_staking_state.user_stake = user_staking_state
         .user_stake
+        .checked_sub(amount_requested)
+        .unwrap();
+
+    staking_pool_state.total_stake = staking_pool_state
+        .total_stake
+        .checked_sub(amount_requested)
+        .unwrap();
+
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    // A new request restarts the cooldown of everything pending
+    let withdrawable_after_ts = now_timestamp.checked_add(cooldown_seconds).unwrap();
+
+    user_staking_state.pending_unstake = user_staking_state
+        .pending_unstake
+        .checked_add(amount_requested)
+        .unwrap();
+    user_staking_state.unstake_withdrawable_after_ts = withdrawable_after_ts;
+
+    staking_pool_state.total_pending_unstake = staking_pool_state
+        .total_pending_unstake
+        .checked_add(amount_requested)
+        .unwrap();
+
+    Ok(RequestUnstakeEffects {
+        amount_requested: amount_requested as u64,
+        withdrawable_after_ts,
+    })
+}
+
+pub fn complete_unstake(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    now_timestamp: u64,
+) -> Result<CompleteUnstakeEffects, BorrowError> {
+    if user_staking_state.pending_unstake == 0 {
+        return Err(BorrowError::NothingToUnstake);
+    }
+    if user_staking_state.unstake_withdrawable_after_ts > now_timestamp {
+        return Err(BorrowError::UnstakeCooldownNotElapsed);
+    }
+
+    let amount_to_withdraw = std::mem::take(&mut user_staking_state.pending_unstake);
+    user_staking_state.unstake_withdrawable_after_ts = 0;
+
+    staking_pool_state.total_pending_unstake = staking_pool_state
+        .total_pending_unstake
         .checked_sub(amount_to_withdraw)
         .unwrap();
 
-    user_staking_state.rewards_tally = user_staking_state
-        .rewards_tally
-        .checked_sub(
-            amount_to_withdraw
-                .checked_mul(staking_pool_state.reward_per_token)
-                .unwrap(),
-        )
+    Ok(CompleteUnstakeEffects {
+        amount_to_withdraw: amount_to_withdraw as u64,
+    })
+}
+
+pub fn lock_hbb(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    amount: u64,
+    lock_expiry_ts: u64,
+    now_timestamp: u64,
+) -> Result<(), BorrowError> {
+    if amount == 0 {
+        return Err(BorrowError::StakingZero);
+    }
+    if user_staking_state.locked_stake > 0 {
+        return Err(BorrowError::HbbAlreadyLocked);
+    }
+    let lock_expiry_ts = utils::round_lock_expiry(lock_expiry_ts);
+    utils::assert_valid_lock_expiry(0, lock_expiry_ts, now_timestamp)?;
+
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
+    user_staking_state.locked_stake = amount as u128;
+    user_staking_state.lock_expiry_ts = lock_expiry_ts;
+
+    staking_pool_state.total_stake = staking_pool_state
+        .total_stake
+        .checked_add(amount as u128)
+        .unwrap();
+
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    Ok(())
+}
+
+pub fn increase_lock_amount(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    amount: u64,
+    now_timestamp: u64,
+) -> Result<(), BorrowError> {
+    if amount == 0 {
+        return Err(BorrowError::StakingZero);
+    }
+    utils::assert_active_lock(user_staking_state, now_timestamp)?;
+
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
+    user_staking_state.locked_stake = user_staking_state
+        .locked_stake
+        .checked_add(amount as u128)
+        .unwrap();
+
+    staking_pool_state.total_stake = staking_pool_state
+        .total_stake
+        .checked_add(amount as u128)
         .unwrap();
 
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    Ok(())
+}
+
+pub fn extend_lock(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    lock_expiry_ts: u64,
+    now_timestamp: u64,
+) -> Result<(), BorrowError> {
+    utils::assert_active_lock(user_staking_state, now_timestamp)?;
+    let lock_expiry_ts = utils::round_lock_expiry(lock_expiry_ts);
+    utils::assert_valid_lock_expiry(
+        user_staking_state.lock_expiry_ts,
+        lock_expiry_ts,
+        now_timestamp,
+    )?;
+
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
+    user_staking_state.lock_expiry_ts = lock_expiry_ts;
+
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    Ok(())
+}
+
+pub fn withdraw_locked_hbb(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    now_timestamp: u64,
+) -> Result<WithdrawLockedHbbEffects, BorrowError> {
+    if user_staking_state.locked_stake == 0 {
+        return Err(BorrowError::NoActiveHbbLock);
+    }
+    if user_staking_state.lock_expiry_ts > now_timestamp {
+        return Err(BorrowError::HbbLockNotExpired);
+    }
+
+    // Rewards earned so far stay pending until harvested
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+
+    let amount_to_withdraw = std::mem::take(&mut user_staking_state.locked_stake);
+    user_staking_state.lock_expiry_ts = 0;
+
     staking_pool_state.total_stake = staking_pool_state
         .total_stake
         .checked_sub(amount_to_withdraw)
         .unwrap();
 
-    Ok(UnstakeEffects {
-        reward,
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    Ok(WithdrawLockedHbbEffects {
         amount_to_withdraw: amount_to_withdraw as u64,
     })
 }
 
+pub fn refresh_boosted_stake(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    now_timestamp: u64,
+) {
+    // The boost decays on its own, this only settles what the stake
+    // earned so far, stakes the HBB compounded so far and checkpoints
+    // the expired locks
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+}
+
+pub fn set_auto_compound(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    auto_compound: bool,
+    now_timestamp: u64,
+) {
+    if user_staking_state.auto_compound == auto_compound {
+        return;
+    }
+
+    // Rewards earned so far stay pending until harvested
+    utils::settle_rewards(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+    utils::sub_boosted_stake(staking_pool_state, user_staking_state, boost_checkpoints);
+    user_staking_state.auto_compound = auto_compound;
+    utils::add_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+}
+
+pub fn transfer_staking_position(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    new_owner: Pubkey,
+    now_timestamp: u64,
+) -> Result<(), BorrowError> {
+    if new_owner == user_staking_state.owner {
+        return Err(BorrowError::InvalidStakingPositionOwner);
+    }
+
+    // Everything earned so far stays with the position,
+    // the new owner harvests it along with the stake
+    refresh_boosted_stake(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        now_timestamp,
+    );
+
+    user_staking_state.owner = new_owner;
+
+    Ok(())
+}
+
+pub fn tokenize_staking_position(
+    user_staking_state: &mut UserStakingState,
+    position_mint: Pubkey,
+) -> Result<(), BorrowError> {
+    if user_staking_state.position_mint != Pubkey::default() {
+        return Err(BorrowError::StakingPositionAlreadyTokenized);
+    }
+
+    // Nobody can sign for the default key, the position keeps
+    // earning and only moves again by redeeming its token
+    user_staking_state.owner = Pubkey::default();
+    user_staking_state.position_mint = position_mint;
+
+    Ok(())
+}
+
+pub fn redeem_staking_position(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &mut UserStakingState,
+    boost_checkpoints: &mut BoostCheckpoints,
+    new_owner: Pubkey,
+    now_timestamp: u64,
+) -> Result<(), BorrowError> {
+    if user_staking_state.position_mint == Pubkey::default() {
+        return Err(BorrowError::StakingPositionNotTokenized);
+    }
+
+    transfer_staking_position(
+        staking_pool_state,
+        user_staking_state,
+        boost_checkpoints,
+        new_owner,
+        now_timestamp,
+    )?;
+
+    user_staking_state.position_mint = Pubkey::default();
+
+    Ok(())
+}
+
+pub fn close_user_staking_state(
+    staking_pool_state: &mut StakingPoolState,
+    user_staking_state: &UserStakingState,
+) -> Result<(), BorrowError> {
+    // Without any stake nothing accrues, only what
+    // was settled earlier can still be owed
+    if user_staking_state.user_stake > 0
+        || user_staking_state.locked_stake > 0
+        || user_staking_state.pending_unstake > 0
+        || user_staking_state.pending_rewards > 0
+        || !user_staking_state.pending_collateral_rewards.is_zero()
+    {
+        return Err(BorrowError::StakingAccountNotEmpty);
+    }
+
+    staking_pool_state.num_closed_users =
+        staking_pool_state.num_closed_users.checked_add(1).unwrap();
+
+    Ok(())
+}
+
+pub fn rewards_to_compound(staking_pool_state: &StakingPoolState) -> Result<u64, BorrowError> {
+    if staking_pool_state.rewards_to_compound == 0
+        || staking_pool_state.total_compounding_stake == 0
+    {
+        Err(BorrowError::NothingToCompound)
+    } else {
+        Ok(staking_pool_state.rewards_to_compound as u64)
+    }
+}
+
+pub fn compound_rewards(
+    staking_pool_state: &mut StakingPoolState,
+    usdh_spent: u64,
+    hbb_bought: u64,
+    now_timestamp: u64,
+) -> Result<(), BorrowError> {
+    // Whatever the order filled at, not less HBB than the max price allows
+    if (usdh_spent as u128)
+        .checked_mul(HBB_FACTOR as u128)
+        .unwrap()
+        > (hbb_bought as u128)
+            .checked_mul(staking_pool_state.compound_max_hbb_price as u128)
+            .unwrap()
+    {
+        return Err(BorrowError::CompoundPriceAboveMaximum);
+    }
+
+    let boost_ts = utils::boost_time(staking_pool_state, now_timestamp);
+    let compounding_stake = utils::total_compounding_stake(staking_pool_state, boost_ts);
+    if compounding_stake == 0 {
+        return Err(BorrowError::NothingToCompound);
+    }
+
+    staking_pool_state.rewards_to_compound = staking_pool_state
+        .rewards_to_compound
+        .checked_sub(usdh_spent as u128)
+        .unwrap();
+
+    staking_pool_state.compounded_hbb_not_yet_staked = staking_pool_state
+        .compounded_hbb_not_yet_staked
+        .checked_add(hbb_bought as u128)
+        .unwrap();
+
+    // Same as distribute_fees, over the compounding stakes
+    let extra_hbb_scaled = (hbb_bought as u128)
+        .checked_mul(DECIMAL_PRECISION)
+        .unwrap()
+        .checked_add(staking_pool_state.prev_compounded_hbb_loss)
+        .unwrap();
+
+    let extra_hbb_per_token = extra_hbb_scaled.checked_div(compounding_stake).unwrap();
+
+    staking_pool_state.prev_compounded_hbb_loss = extra_hbb_scaled
+        .checked_sub(extra_hbb_per_token.checked_mul(compounding_stake).unwrap())
+        .unwrap();
+
+    staking_pool_state.compounded_hbb_per_token = staking_pool_state
+        .compounded_hbb_per_token
+        .checked_add(extra_hbb_per_token)
+        .unwrap();
+    staking_pool_state.time_weighted_compounded_hbb_per_token = staking_pool_state
+        .time_weighted_compounded_hbb_per_token
+        .checked_add(extra_hbb_per_token.checked_mul(boost_ts as u128).unwrap())
+        .unwrap();
+
+    Ok(())
+}
+
+pub fn update_compound_config(
+    staking_pool_state: &mut StakingPoolState,
+    dex_market: Pubkey,
+    max_hbb_price: u64,
+) -> Result<(), BorrowError> {
+    if max_hbb_price == 0 {
+        return Err(BorrowError::InvalidCompoundMaxPrice);
+    }
+
+    staking_pool_state.compound_dex_market = dex_market;
+    staking_pool_state.compound_max_hbb_price = max_hbb_price;
+
+    Ok(())
+}
+
+pub fn update_treasury_fee_rate(
+    staking_pool_state: &mut StakingPoolState,
+    treasury_fee_rate: u16,
+) -> Result<(), BorrowError> {
+    if treasury_fee_rate > MAX_TREASURY_FEE_RATE {
+        return Err(BorrowError::TreasuryFeeRateAboveMaximum);
+    }
+
+    staking_pool_state.treasury_fee_rate = treasury_fee_rate;
+
+    Ok(())
+}
+
+pub fn schedule_treasury_disbursement(
+    staking_pool_state: &mut StakingPoolState,
+    amount: u64,
+    recipient: Pubkey,
+    now_timestamp: u64,
+) -> Result<u64, BorrowError> {
+    if amount == 0 {
+        return Err(BorrowError::InvalidTreasuryDisbursement);
+    }
+    if staking_pool_state.treasury_disbursement_amount > 0 {
+        return Err(BorrowError::TreasuryDisbursementAlreadyScheduled);
+    }
+
+    let executable_ts = now_timestamp
+        .checked_add(TREASURY_DISBURSEMENT_TIMELOCK_SECONDS)
+        .unwrap();
+
+    staking_pool_state.treasury_disbursement_amount = amount;
+    staking_pool_state.treasury_disbursement_recipient = recipient;
+    staking_pool_state.treasury_disbursement_executable_ts = executable_ts;
+
+    Ok(executable_ts)
+}
+
+pub fn cancel_treasury_disbursement(
+    staking_pool_state: &mut StakingPoolState,
+) -> Result<TreasuryDisbursementEffects, BorrowError> {
+    if staking_pool_state.treasury_disbursement_amount == 0 {
+        return Err(BorrowError::NoTreasuryDisbursementScheduled);
+    }
+
+    Ok(utils::take_treasury_disbursement(staking_pool_state))
+}
+
+pub fn execute_treasury_disbursement(
+    staking_pool_state: &mut StakingPoolState,
+    now_timestamp: u64,
+) -> Result<TreasuryDisbursementEffects, BorrowError> {
+    if staking_pool_state.treasury_disbursement_amount == 0 {
+        return Err(BorrowError::NoTreasuryDisbursementScheduled);
+    }
+    if staking_pool_state.treasury_disbursement_executable_ts > now_timestamp {
+        return Err(BorrowError::TreasuryDisbursementTimelocked);
+    }
+
+    Ok(utils::take_treasury_disbursement(staking_pool_state))
+}
+
+pub fn reconcile_borrowing_fees_vault(
+    staking_pool_state: &StakingPoolState,
+    borrowing_fees_vault_balance: u64,
+) -> Result<u64, BorrowError> {
+    // The vault must cover everything still owed to stakers,
+    // anything above that (rounding dust, rewards distributed
+    // while nothing was staked, donations) is surplus
+    let liabilities = staking_pool_state
+        .rewards_not_yet_claimed
+        .checked_add(staking_pool_state.rewards_to_compound)
+        .unwrap();
+
+    (borrowing_fees_vault_balance as u128)
+        .checked_sub(liabilities)
+        .map(|surplus| surplus as u64)
+        .ok_or(BorrowError::StakingRewardsAccountingMismatch)
+}
+
 pub fn split_fees(fees_to_pay: u64, treasury_fee_rate: u16) -> (u64, u64) {
     let treasury_fee = fees_to_pay * (treasury_fee_rate as u64) / 10_000;
     let staking_fee = fees_to_pay.checked_sub(treasury_fee).unwrap();
@@ -133,16 +727,36 @@ pub fn split_fees(fees_to_pay: u64, treasury_fee_rate: u16) -> (u64, u64) {
     (staking_fee, treasury_fee)
 }
 
-pub fn distribute_fees(staking_pool_state: &mut StakingPoolState, fees_to_pay: u64) {
+pub fn distribute_fees(
+    staking_pool_state: &mut StakingPoolState,
+    fees_to_pay: u64,
+    now_timestamp: u64,
+) {
     staking_pool_state.total_distributed_rewards = staking_pool_state
         .total_distributed_rewards
         .checked_add(fees_to_pay as u128)
         .unwrap();
 
-    staking_pool_state.rewards_not_yet_claimed = staking_pool_state
-        .rewards_not_yet_claimed
-        .checked_add(fees_to_pay as u128)
+    let boost_ts = utils::boost_time(staking_pool_state, now_timestamp);
+    let reward_stake = utils::total_reward_stake(staking_pool_state, boost_ts);
+    let compounding_stake = utils::total_compounding_stake(staking_pool_state, boost_ts);
+    staking_pool_state.total_boosted_stake = reward_stake.checked_add(compounding_stake).unwrap();
+
+    // The auto-compounding stakers share is kept aside to be swapped for HBB
+    let fees_to_compound = if staking_pool_state.total_boosted_stake != 0 {
+        (fees_to_pay as u128)
+            .checked_mul(compounding_stake)
+            .unwrap()
+            .checked_div(staking_pool_state.total_boosted_stake)
+            .unwrap()
+    } else {
+        0
+    };
+    staking_pool_state.rewards_to_compound = staking_pool_state
+        .rewards_to_compound
+        .checked_add(fees_to_compound)
         .unwrap();
+    let fees_to_pay = fees_to_pay - fees_to_compound as u64;
 
     // scale the reward
     let extra_reward_scaled = (fees_to_pay as u128)
@@ -151,17 +765,18 @@ pub fn distribute_fees(staking_pool_state: &mut StakingPoolState, fees_to_pay: u
         .checked_add(staking_pool_state.prev_reward_loss)
         .unwrap();
 
-    if staking_pool_state.total_stake != 0 {
-        let extra_reward_per_token = extra_reward_scaled
-            .checked_div(staking_pool_state.total_stake)
+    // With nobody to credit, the fees are left in the vault
+    // as surplus, to be swept by reconcile_borrowing_fees_vault
+    if reward_stake != 0 {
+        staking_pool_state.rewards_not_yet_claimed = staking_pool_state
+            .rewards_not_yet_claimed
+            .checked_add(fees_to_pay as u128)
             .unwrap();
 
+        let extra_reward_per_token = extra_reward_scaled.checked_div(reward_stake).unwrap();
+
         let reward_loss = extra_reward_scaled
-            .checked_sub(
-                extra_reward_per_token
-                    .checked_mul(staking_pool_state.total_stake)
-                    .unwrap(),
-            )
+            .checked_sub(extra_reward_per_token.checked_mul(reward_stake).unwrap())
             .unwrap();
 
         // println!(
@@ -173,7 +788,597 @@ pub fn distribute_fees(staking_pool_state: &mut StakingPoolState, fees_to_pay: u
             .reward_per_token
             .checked_add(extra_reward_per_token)
             .unwrap();
+        staking_pool_state.time_weighted_reward_per_token = staking_pool_state
+            .time_weighted_reward_per_token
+            .checked_add(
+                extra_reward_per_token
+                    .checked_mul(boost_ts as u128)
+                    .unwrap(),
+            )
+            .unwrap();
 
         staking_pool_state.prev_reward_loss = reward_loss;
     }
 }
+
+pub fn distribute_collateral_fees(
+    staking_pool_state: &mut StakingPoolState,
+    fees_to_pay: &CollateralAmounts,
+    now_timestamp: u64,
+) {
+    // Same as distribute_fees, for each collateral token
+    staking_pool_state
+        .collateral_rewards_not_yet_claimed
+        .add_assign(fees_to_pay);
+
+    let extra_reward_scaled = fees_to_pay
+        .to_token_map()
+        .mul_scalar(DECIMAL_PRECISION)
+        .add(&staking_pool_state.prev_collateral_reward_loss);
+
+    let boost_ts = utils::boost_time(staking_pool_state, now_timestamp);
+    let boosted_stake = utils::total_reward_stake(staking_pool_state, boost_ts)
+        .checked_add(utils::total_compounding_stake(staking_pool_state, boost_ts))
+        .unwrap();
+    staking_pool_state.total_boosted_stake = boosted_stake;
+
+    if boosted_stake != 0 {
+        let extra_reward_per_token = extra_reward_scaled.div_scalar(boosted_stake);
+
+        let reward_loss =
+            extra_reward_scaled.sub(&extra_reward_per_token.mul_scalar(boosted_stake));
+
+        staking_pool_state
+            .collateral_reward_per_token
+            .add_assign(&extra_reward_per_token);
+        staking_pool_state
+            .time_weighted_collateral_reward_per_token
+            .add_assign(&extra_reward_per_token.mul_scalar(boost_ts as u128));
+
+        staking_pool_state.prev_collateral_reward_loss = reward_loss;
+    } else {
+        // With nobody staking, the collateral is carried
+        // forward and goes to the next stakers instead
+        staking_pool_state.prev_collateral_reward_loss = extra_reward_scaled;
+    }
+}
+
+mod utils {
+    use crate::{
+        staking_pool::types::TreasuryDisbursementEffects,
+        utils::consts::{
+            DECIMAL_PRECISION, HBB_LOCK_EXPIRY_SECONDS, LOCKUP_NONE_HBB_BOOST_BPS,
+            MAX_BOOST_CHECKPOINTS, MAX_HBB_LOCK_BOOST_BPS, MAX_HBB_LOCK_SECONDS,
+        },
+        BoostCheckpoints, BorrowError, CollateralToken, StakingPoolState, TokenMap,
+        UserStakingState, U256,
+    };
+
+    // A lock boosts its stake by boost_slope * (expiry - now) / BOOST_DENOMINATOR,
+    // which decays linearly from the maximum to none at the lock expiry
+    const BOOST_DENOMINATOR: u128 =
+        LOCKUP_NONE_HBB_BOOST_BPS as u128 * MAX_HBB_LOCK_SECONDS as u128;
+
+    pub fn take_treasury_disbursement(
+        staking_pool_state: &mut StakingPoolState,
+    ) -> TreasuryDisbursementEffects {
+        staking_pool_state.treasury_disbursement_executable_ts = 0;
+        TreasuryDisbursementEffects {
+            amount: std::mem::take(&mut staking_pool_state.treasury_disbursement_amount),
+            recipient: std::mem::take(&mut staking_pool_state.treasury_disbursement_recipient),
+        }
+    }
+
+    pub fn assert_active_lock(
+        user_staking_state: &UserStakingState,
+        now_timestamp: u64,
+    ) -> Result<(), BorrowError> {
+        if user_staking_state.locked_stake == 0
+            || user_staking_state.lock_expiry_ts <= now_timestamp
+        {
+            Err(BorrowError::NoActiveHbbLock)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn assert_valid_lock_expiry(
+        current_lock_expiry_ts: u64,
+        lock_expiry_ts: u64,
+        now_timestamp: u64,
+    ) -> Result<(), BorrowError> {
+        if lock_expiry_ts <= current_lock_expiry_ts
+            || lock_expiry_ts <= now_timestamp
+            || lock_expiry_ts - now_timestamp > MAX_HBB_LOCK_SECONDS
+        {
+            Err(BorrowError::InvalidHbbLockExpiry)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn round_lock_expiry(lock_expiry_ts: u64) -> u64 {
+        lock_expiry_ts / HBB_LOCK_EXPIRY_SECONDS * HBB_LOCK_EXPIRY_SECONDS
+    }
+
+    pub fn stake(user_staking_state: &UserStakingState) -> u128 {
+        user_staking_state
+            .user_stake
+            .checked_add(user_staking_state.locked_stake)
+            .unwrap()
+    }
+
+    pub fn boosted_stake(user_staking_state: &UserStakingState, now_timestamp: u64) -> u128 {
+        let remaining_seconds = user_staking_state
+            .lock_expiry_ts
+            .saturating_sub(now_timestamp);
+        let extra_boost = user_staking_state
+            .boost_slope
+            .checked_mul(remaining_seconds as u128)
+            .unwrap()
+            .checked_div(BOOST_DENOMINATOR)
+            .unwrap();
+
+        stake(user_staking_state).checked_add(extra_boost).unwrap()
+    }
+
+    pub fn reward_stake(user_staking_state: &UserStakingState) -> u128 {
+        // Auto-compounding stakes earn HBB instead of USDH
+        if user_staking_state.auto_compound {
+            0
+        } else {
+            stake(user_staking_state)
+        }
+    }
+
+    pub fn compounding_stake(user_staking_state: &UserStakingState) -> u128 {
+        if user_staking_state.auto_compound {
+            stake(user_staking_state)
+        } else {
+            0
+        }
+    }
+
+    pub fn boost_time(staking_pool_state: &StakingPoolState, now_timestamp: u64) -> u64 {
+        // Rewards credited past the next lock expiry would still count
+        // its boost, until a staking instruction checkpoints it they are
+        // credited as of the expiry
+        match staking_pool_state.next_boost_expiry_ts {
+            0 => now_timestamp,
+            next_boost_expiry_ts => std::cmp::min(now_timestamp, next_boost_expiry_ts),
+        }
+    }
+
+    fn total_boost(boost_bias: u128, boost_slope: u128, boost_ts: u64) -> u128 {
+        // Rounded up, the locks never earn more than what is credited
+        let boost = boost_bias
+            .checked_sub(boost_slope.checked_mul(boost_ts as u128).unwrap())
+            .unwrap();
+        boost
+            .checked_add(BOOST_DENOMINATOR - 1)
+            .unwrap()
+            .checked_div(BOOST_DENOMINATOR)
+            .unwrap()
+    }
+
+    pub fn total_reward_stake(staking_pool_state: &StakingPoolState, boost_ts: u64) -> u128 {
+        staking_pool_state
+            .total_stake
+            .checked_sub(staking_pool_state.total_compounding_stake)
+            .unwrap()
+            .checked_add(total_boost(
+                staking_pool_state.boost_bias,
+                staking_pool_state.boost_slope,
+                boost_ts,
+            ))
+            .unwrap()
+    }
+
+    pub fn total_compounding_stake(staking_pool_state: &StakingPoolState, boost_ts: u64) -> u128 {
+        staking_pool_state
+            .total_compounding_stake
+            .checked_add(total_boost(
+                staking_pool_state.compounding_boost_bias,
+                staking_pool_state.compounding_boost_slope,
+                boost_ts,
+            ))
+            .unwrap()
+    }
+
+    pub fn checkpoint_boosts(
+        staking_pool_state: &mut StakingPoolState,
+        boost_checkpoints: &mut BoostCheckpoints,
+        now_timestamp: u64,
+    ) {
+        let checkpoint_ts = round_lock_expiry(now_timestamp);
+        if staking_pool_state.boost_checkpoint_ts >= checkpoint_ts {
+            return;
+        }
+
+        // Takes the expired locks off the boosted stake, keeping
+        // what their boosts had earned by then
+        let mut expiry_ts = staking_pool_state.next_boost_expiry_ts;
+        while expiry_ts != 0 && expiry_ts <= now_timestamp {
+            let mut checkpoint = boost_checkpoints.get_or_new(expiry_ts);
+
+            checkpoint.reward_per_token = time_weighted_reward_per_token(
+                staking_pool_state.reward_per_token,
+                staking_pool_state.time_weighted_reward_per_token,
+                expiry_ts,
+            );
+            checkpoint.compounded_hbb_per_token = time_weighted_reward_per_token(
+                staking_pool_state.compounded_hbb_per_token,
+                staking_pool_state.time_weighted_compounded_hbb_per_token,
+                expiry_ts,
+            );
+            checkpoint.collateral_reward_per_token = staking_pool_state
+                .collateral_reward_per_token
+                .mul_scalar(expiry_ts as u128)
+                .sub(&staking_pool_state.time_weighted_collateral_reward_per_token);
+
+            let boost_slope = checkpoint.boost_slope;
+            checkpoint.boost_slope = 0;
+            staking_pool_state.boost_bias = staking_pool_state
+                .boost_bias
+                .checked_sub(boost_slope.checked_mul(expiry_ts as u128).unwrap())
+                .unwrap();
+            staking_pool_state.boost_slope = staking_pool_state
+                .boost_slope
+                .checked_sub(boost_slope)
+                .unwrap();
+
+            let compounding_boost_slope = checkpoint.compounding_boost_slope;
+            checkpoint.compounding_boost_slope = 0;
+            staking_pool_state.compounding_boost_bias = staking_pool_state
+                .compounding_boost_bias
+                .checked_sub(
+                    compounding_boost_slope
+                        .checked_mul(expiry_ts as u128)
+                        .unwrap(),
+                )
+                .unwrap();
+            staking_pool_state.compounding_boost_slope = staking_pool_state
+                .compounding_boost_slope
+                .checked_sub(compounding_boost_slope)
+                .unwrap();
+
+            boost_checkpoints.set(checkpoint);
+            expiry_ts = next_boost_expiry(staking_pool_state, boost_checkpoints, expiry_ts);
+        }
+
+        staking_pool_state.next_boost_expiry_ts = expiry_ts;
+        staking_pool_state.boost_checkpoint_ts = checkpoint_ts;
+    }
+
+    fn next_boost_expiry(
+        staking_pool_state: &StakingPoolState,
+        boost_checkpoints: &BoostCheckpoints,
+        expiry_ts: u64,
+    ) -> u64 {
+        if staking_pool_state.boost_slope == 0 && staking_pool_state.compounding_boost_slope == 0 {
+            return 0;
+        }
+
+        (1..MAX_BOOST_CHECKPOINTS as u64)
+            .map(|weeks| expiry_ts + weeks * HBB_LOCK_EXPIRY_SECONDS)
+            .find(|expiry_ts| {
+                boost_checkpoints
+                    .get(*expiry_ts)
+                    .map(|checkpoint| {
+                        checkpoint.boost_slope != 0 || checkpoint.compounding_boost_slope != 0
+                    })
+                    .unwrap_or(false)
+            })
+            .unwrap_or(0)
+    }
+
+    fn time_weighted_reward_per_token(
+        reward_per_token: u128,
+        time_weighted_reward_per_token: u128,
+        expiry_ts: u64,
+    ) -> u128 {
+        // The sum of (expiry - credited at) * reward credited, what a
+        // unit of boost slope expiring then earns over all rewards so far
+        reward_per_token
+            .checked_mul(expiry_ts as u128)
+            .unwrap()
+            .checked_sub(time_weighted_reward_per_token)
+            .unwrap()
+    }
+
+    fn boost_reward(boost_slope: u128, time_weighted_reward_per_token: u128) -> u128 {
+        (U256::from(boost_slope) * U256::from(time_weighted_reward_per_token)
+            / U256::from(BOOST_DENOMINATOR)
+            / U256::from(DECIMAL_PRECISION))
+        .as_u128()
+    }
+
+    // The lock's time weighted rewards per token, up to now
+    // or up to its expiry once that was checkpointed
+    fn boost_rewards_per_token(
+        staking_pool_state: &StakingPoolState,
+        user_staking_state: &UserStakingState,
+        boost_checkpoints: &BoostCheckpoints,
+    ) -> (u128, TokenMap) {
+        let expiry_ts = user_staking_state.lock_expiry_ts;
+        if expiry_ts > staking_pool_state.boost_checkpoint_ts {
+            let reward_per_token = if user_staking_state.auto_compound {
+                time_weighted_reward_per_token(
+                    staking_pool_state.compounded_hbb_per_token,
+                    staking_pool_state.time_weighted_compounded_hbb_per_token,
+                    expiry_ts,
+                )
+            } else {
+                time_weighted_reward_per_token(
+                    staking_pool_state.reward_per_token,
+                    staking_pool_state.time_weighted_reward_per_token,
+                    expiry_ts,
+                )
+            };
+            let collateral_reward_per_token = staking_pool_state
+                .collateral_reward_per_token
+                .mul_scalar(expiry_ts as u128)
+                .sub(&staking_pool_state.time_weighted_collateral_reward_per_token);
+            return (reward_per_token, collateral_reward_per_token);
+        }
+
+        // Locks left expired for longer than the checkpoints
+        // are kept forfeit what their boost earned since
+        match boost_checkpoints.get(expiry_ts) {
+            Some(checkpoint) if user_staking_state.auto_compound => (
+                checkpoint.compounded_hbb_per_token,
+                checkpoint.collateral_reward_per_token,
+            ),
+            Some(checkpoint) => (
+                checkpoint.reward_per_token,
+                checkpoint.collateral_reward_per_token,
+            ),
+            None => (
+                user_staking_state.boost_rewards_tally,
+                user_staking_state.boost_collateral_rewards_tally,
+            ),
+        }
+    }
+
+    pub fn settle_rewards(
+        staking_pool_state: &mut StakingPoolState,
+        user_staking_state: &mut UserStakingState,
+        boost_checkpoints: &mut BoostCheckpoints,
+        now_timestamp: u64,
+    ) {
+        checkpoint_boosts(staking_pool_state, boost_checkpoints, now_timestamp);
+
+        // Moves what the stake earned so far to the pending rewards,
+        // USDH or compounded HBB depending on the stake, and collateral
+        let mut reward = reward_stake(user_staking_state)
+            .checked_mul(staking_pool_state.reward_per_token)
+            .unwrap()
+            .checked_sub(user_staking_state.rewards_tally)
+            .unwrap()
+            .checked_div(DECIMAL_PRECISION)
+            .unwrap();
+        let mut compounded_hbb = compounding_stake(user_staking_state)
+            .checked_mul(staking_pool_state.compounded_hbb_per_token)
+            .unwrap()
+            .checked_sub(user_staking_state.compounded_hbb_tally)
+            .unwrap()
+            .checked_div(DECIMAL_PRECISION)
+            .unwrap();
+        let mut collateral_reward = staking_pool_state
+            .collateral_reward_per_token
+            .mul_scalar(stake(user_staking_state))
+            .sub(&user_staking_state.collateral_rewards_tally)
+            .div_scalar(DECIMAL_PRECISION);
+
+        let boost_slope = user_staking_state.boost_slope;
+        if boost_slope > 0 {
+            let (reward_per_token, collateral_reward_per_token) =
+                boost_rewards_per_token(staking_pool_state, user_staking_state, boost_checkpoints);
+
+            let extra_reward = boost_reward(
+                boost_slope,
+                reward_per_token
+                    .checked_sub(user_staking_state.boost_rewards_tally)
+                    .unwrap(),
+            );
+            if user_staking_state.auto_compound {
+                compounded_hbb = compounded_hbb.checked_add(extra_reward).unwrap();
+            } else {
+                reward = reward.checked_add(extra_reward).unwrap();
+            }
+
+            let extra_collateral_reward_per_token =
+                collateral_reward_per_token.sub(&user_staking_state.boost_collateral_rewards_tally);
+            for token in (0..6).map(CollateralToken::from) {
+                collateral_reward.add_assign(&TokenMap::of_token(
+                    boost_reward(
+                        boost_slope,
+                        extra_collateral_reward_per_token.token_amount(token),
+                    ),
+                    token,
+                ));
+            }
+
+            user_staking_state.boost_rewards_tally = reward_per_token;
+            user_staking_state.boost_collateral_rewards_tally = collateral_reward_per_token;
+
+            // The pool took the slope off when checkpointing the expiry
+            if user_staking_state.lock_expiry_ts <= staking_pool_state.boost_checkpoint_ts {
+                user_staking_state.boost_slope = 0;
+                user_staking_state.boost_rewards_tally = 0;
+                user_staking_state.boost_collateral_rewards_tally = TokenMap::default();
+            }
+        }
+
+        user_staking_state.pending_rewards = user_staking_state
+            .pending_rewards
+            .checked_add(reward)
+            .unwrap();
+        user_staking_state
+            .pending_collateral_rewards
+            .add_assign(&collateral_reward.to_collateral_amounts());
+
+        // The compounded HBB joins the liquid stake
+        if compounded_hbb > 0 {
+            user_staking_state.user_stake = user_staking_state
+                .user_stake
+                .checked_add(compounded_hbb)
+                .unwrap();
+            staking_pool_state.total_stake = staking_pool_state
+                .total_stake
+                .checked_add(compounded_hbb)
+                .unwrap();
+            staking_pool_state.total_compounding_stake = staking_pool_state
+                .total_compounding_stake
+                .checked_add(compounded_hbb)
+                .unwrap();
+            staking_pool_state.compounded_hbb_not_yet_staked = staking_pool_state
+                .compounded_hbb_not_yet_staked
+                .checked_sub(compounded_hbb)
+                .unwrap();
+        }
+
+        reset_rewards_tallies(staking_pool_state, user_staking_state);
+        user_staking_state.boosted_stake = boosted_stake(user_staking_state, now_timestamp);
+    }
+
+    pub fn sub_boosted_stake(
+        staking_pool_state: &mut StakingPoolState,
+        user_staking_state: &mut UserStakingState,
+        boost_checkpoints: &mut BoostCheckpoints,
+    ) {
+        // Only called once the stake is settled
+        if user_staking_state.auto_compound {
+            staking_pool_state.total_compounding_stake = staking_pool_state
+                .total_compounding_stake
+                .checked_sub(stake(user_staking_state))
+                .unwrap();
+        }
+
+        let boost_slope = std::mem::take(&mut user_staking_state.boost_slope);
+        if boost_slope == 0 {
+            return;
+        }
+
+        let expiry_ts = user_staking_state.lock_expiry_ts;
+        let boost_bias = boost_slope.checked_mul(expiry_ts as u128).unwrap();
+        let mut checkpoint = boost_checkpoints.get_or_new(expiry_ts);
+        if user_staking_state.auto_compound {
+            checkpoint.compounding_boost_slope = checkpoint
+                .compounding_boost_slope
+                .checked_sub(boost_slope)
+                .unwrap();
+            staking_pool_state.compounding_boost_slope = staking_pool_state
+                .compounding_boost_slope
+                .checked_sub(boost_slope)
+                .unwrap();
+            staking_pool_state.compounding_boost_bias = staking_pool_state
+                .compounding_boost_bias
+                .checked_sub(boost_bias)
+                .unwrap();
+        } else {
+            checkpoint.boost_slope = checkpoint.boost_slope.checked_sub(boost_slope).unwrap();
+            staking_pool_state.boost_slope = staking_pool_state
+                .boost_slope
+                .checked_sub(boost_slope)
+                .unwrap();
+            staking_pool_state.boost_bias = staking_pool_state
+                .boost_bias
+                .checked_sub(boost_bias)
+                .unwrap();
+        }
+        boost_checkpoints.set(checkpoint);
+    }
+
+    pub fn add_boosted_stake(
+        staking_pool_state: &mut StakingPoolState,
+        user_staking_state: &mut UserStakingState,
+        boost_checkpoints: &mut BoostCheckpoints,
+        now_timestamp: u64,
+    ) {
+        if user_staking_state.auto_compound {
+            staking_pool_state.total_compounding_stake = staking_pool_state
+                .total_compounding_stake
+                .checked_add(stake(user_staking_state))
+                .unwrap();
+        }
+
+        // Expiries are whole weeks, anything past the
+        // last checkpoint is still ahead of now
+        let expiry_ts = user_staking_state.lock_expiry_ts;
+        if user_staking_state.locked_stake > 0 && expiry_ts > staking_pool_state.boost_checkpoint_ts
+        {
+            let max_extra_boost_bps = (MAX_HBB_LOCK_BOOST_BPS - LOCKUP_NONE_HBB_BOOST_BPS) as u128;
+            let boost_slope = user_staking_state
+                .locked_stake
+                .checked_mul(max_extra_boost_bps)
+                .unwrap();
+            let boost_bias = boost_slope.checked_mul(expiry_ts as u128).unwrap();
+
+            let mut checkpoint = boost_checkpoints.get_or_new(expiry_ts);
+            if user_staking_state.auto_compound {
+                checkpoint.compounding_boost_slope = checkpoint
+                    .compounding_boost_slope
+                    .checked_add(boost_slope)
+                    .unwrap();
+                staking_pool_state.compounding_boost_slope = staking_pool_state
+                    .compounding_boost_slope
+                    .checked_add(boost_slope)
+                    .unwrap();
+                staking_pool_state.compounding_boost_bias = staking_pool_state
+                    .compounding_boost_bias
+                    .checked_add(boost_bias)
+                    .unwrap();
+            } else {
+                checkpoint.boost_slope = checkpoint.boost_slope.checked_add(boost_slope).unwrap();
+                staking_pool_state.boost_slope = staking_pool_state
+                    .boost_slope
+                    .checked_add(boost_slope)
+                    .unwrap();
+                staking_pool_state.boost_bias = staking_pool_state
+                    .boost_bias
+                    .checked_add(boost_bias)
+                    .unwrap();
+            }
+            boost_checkpoints.set(checkpoint);
+
+            user_staking_state.boost_slope = boost_slope;
+            if staking_pool_state.next_boost_expiry_ts == 0
+                || expiry_ts < staking_pool_state.next_boost_expiry_ts
+            {
+                staking_pool_state.next_boost_expiry_ts = expiry_ts;
+            }
+
+            let (reward_per_token, collateral_reward_per_token) =
+                boost_rewards_per_token(staking_pool_state, user_staking_state, boost_checkpoints);
+            user_staking_state.boost_rewards_tally = reward_per_token;
+            user_staking_state.boost_collateral_rewards_tally = collateral_reward_per_token;
+        } else {
+            user_staking_state.boost_rewards_tally = 0;
+            user_staking_state.boost_collateral_rewards_tally = TokenMap::default();
+        }
+
+        reset_rewards_tallies(staking_pool_state, user_staking_state);
+        user_staking_state.boosted_stake = boosted_stake(user_staking_state, now_timestamp);
+
+        let boost_ts = boost_time(staking_pool_state, now_timestamp);
+        staking_pool_state.total_boosted_stake = total_reward_stake(staking_pool_state, boost_ts)
+            .checked_add(total_compounding_stake(staking_pool_state, boost_ts))
+            .unwrap();
+    }
+
+    fn reset_rewards_tallies(
+        staking_pool_state: &StakingPoolState,
+        user_staking_state: &mut UserStakingState,
+    ) {
+        user_staking_state.rewards_tally = reward_stake(user_staking_state)
+            .checked_mul(staking_pool_state.reward_per_token)
+            .unwrap();
+        user_staking_state.compounded_hbb_tally = compounding_stake(user_staking_state)
+            .checked_mul(staking_pool_state.compounded_hbb_per_token)
+            .unwrap();
+        user_staking_state.collateral_rewards_tally = staking_pool_state
+            .collateral_reward_per_token
+            .mul_scalar(stake(user_staking_state));
+    }
+}
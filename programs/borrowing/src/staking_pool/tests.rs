@@ -1,18 +1,29 @@
 #[cfg(test)]
 mod tests {
 
+    use anchor_lang::prelude::Pubkey;
+
     use crate::{
         borrowing_market::{borrowing_operations, borrowing_rate::BorrowSplit},
         staking_pool::{
             staking_pool_operations,
             tests::utils,
-            types::{HarvestEffects, UnstakeEffects},
+            types::{
+                CompleteUnstakeEffects, HarvestCollateralEffects, HarvestEffects,
+                RequestUnstakeEffects, TreasuryDisbursementEffects, UnstakeEffects,
+                WithdrawLockedHbbEffects,
+            },
         },
         utils::{
-            consts::DECIMAL_PRECISION,
+            consts::{
+                DECIMAL_PRECISION, HBB_LOCK_EXPIRY_SECONDS, MAX_HBB_LOCK_SECONDS,
+                MAX_TREASURY_FEE_RATE, TREASURY_DISBURSEMENT_TIMELOCK_SECONDS,
+                UNSTAKE_COOLDOWN_SECONDS,
+            },
             coretypes::{HBB, SOL, USDH},
         },
-        BorrowingMarketState, StakingPoolState, UserStakingState,
+        BoostCheckpoints, BorrowError, BorrowingMarketState, CollateralAmounts, CollateralToken,
+        StakingPoolState, UserStakingState,
     };
 
     #[test]
@@ -76,6 +87,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut user = UserStakingState::default();
 
         staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
@@ -83,7 +95,13 @@ mod tests {
 
         let hbb_deposited = HBB::from(100.0);
 
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            0,
+        );
 
         println!("user {:}", user.to_state_string());
 
@@ -99,6 +117,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
 
         staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
 
@@ -110,7 +129,13 @@ mod tests {
 
             staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
 
-            staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+            staking_pool_operations::user_stake(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                hbb_deposited,
+                0,
+            );
 
             assert_eq!(user.user_stake, hbb_deposited as u128);
             assert_eq!(user.rewards_tally, 0);
@@ -128,6 +153,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut user = UserStakingState::default();
 
         staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
@@ -135,9 +161,21 @@ mod tests {
 
         let hbb_deposited = HBB::from(100.0);
 
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
-        staking_pool_operations::user_unstake(&mut staking_pool_state, &mut user, hbb_deposited)
-            .unwrap();
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            0,
+        );
+        staking_pool_operations::user_unstake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            0,
+        )
+        .unwrap();
 
         println!("user {:}", user.to_state_string());
         println!("staking pool {:}", staking_pool_state.to_state_string());
@@ -154,6 +192,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
 
         staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
 
@@ -165,11 +204,19 @@ mod tests {
 
             staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
 
-            staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+            staking_pool_operations::user_stake(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                hbb_deposited,
+                0,
+            );
             staking_pool_operations::user_unstake(
                 &mut staking_pool_state,
                 &mut user,
+                &mut boost_checkpoints,
                 hbb_deposited,
+                0,
             )
             .unwrap();
 
@@ -186,6 +233,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut market = BorrowingMarketState::default();
         let mut user = UserStakingState::default();
 
@@ -200,7 +248,13 @@ mod tests {
         let now_timestamp = 0;
 
         println!("borrow_split {:?}", borrow_split);
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
 
         utils::new_borrowing_users(
             &mut market,
@@ -211,8 +265,13 @@ mod tests {
             now_timestamp,
         );
 
-        let HarvestEffects { reward } =
-            staking_pool_operations::user_harvest(&mut staking_pool_state, &mut user).unwrap();
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
 
         println!("user {:}", user.to_state_string());
         println!("staking pool {:}", staking_pool_state.to_state_string());
@@ -240,6 +299,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut market = BorrowingMarketState::default();
 
         borrowing_operations::initialize_borrowing_market(&mut market, 0);
@@ -260,7 +320,13 @@ mod tests {
             let mut user = UserStakingState::default();
             staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
 
-            staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+            staking_pool_operations::user_stake(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                hbb_deposited,
+                now_timestamp,
+            );
 
             utils::new_borrowing_users(
                 &mut market,
@@ -271,7 +337,13 @@ mod tests {
                 now_timestamp,
             );
 
-            staking_pool_operations::user_harvest(&mut staking_pool_state, &mut user).unwrap();
+            staking_pool_operations::user_harvest(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                now_timestamp,
+            )
+            .unwrap();
 
             let treasury_fee = borrow_split.fees_to_pay * 1_500 / 10_000;
             let staking_fee = borrow_split.fees_to_pay - treasury_fee;
@@ -314,6 +386,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut market = BorrowingMarketState::default();
 
         borrowing_operations::initialize_borrowing_market(&mut market, 0);
@@ -329,7 +402,13 @@ mod tests {
         for _ in 0..count {
             let mut user = UserStakingState::default();
             staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
-            staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+            staking_pool_operations::user_stake(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                hbb_deposited,
+                now_timestamp,
+            );
 
             utils::new_borrowing_users(
                 &mut market,
@@ -346,7 +425,9 @@ mod tests {
             } = staking_pool_operations::user_unstake(
                 &mut staking_pool_state,
                 &mut user,
+                &mut boost_checkpoints,
                 hbb_deposited,
+                now_timestamp,
             )
             .unwrap();
             let treasury_fee = borrow_split.fees_to_pay * 1_500 / 10_000;
@@ -371,6 +452,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut user = UserStakingState::default();
         let mut market = BorrowingMarketState::default();
         borrowing_operations::initialize_borrowing_market(&mut market, 0);
@@ -383,7 +465,13 @@ mod tests {
         let now_timestamp = 0;
 
         staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited);
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
 
         assert_eq!(user.rewards_tally, 0);
 
@@ -405,14 +493,26 @@ mod tests {
             reward_scaled / (hbb_deposited as u128),
         );
 
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user, hbb_deposited / 2);
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited / 2,
+            now_timestamp,
+        );
 
-        // Before harvest, rewards tally is only modified by the staking done by the user
+        // Staking again settles what the first stake earned, the tally restarts on the whole stake
         let fees_to_pay = staking_fee as u128;
-        assert_eq!(user.rewards_tally, fees_to_pay * DECIMAL_PRECISION / 2);
+        assert_eq!(user.pending_rewards, fees_to_pay);
+        assert_eq!(user.rewards_tally, fees_to_pay * DECIMAL_PRECISION * 3 / 2);
 
-        let HarvestEffects { reward } =
-            staking_pool_operations::user_harvest(&mut staking_pool_state, &mut user).unwrap();
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
 
         assert_eq!(reward, staking_fee as u128);
 
@@ -440,16 +540,24 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
 
         let hbb_deposited = HBB::from(100.0);
-        let mut users = utils::new_staking_users(&mut staking_pool_state, 2, hbb_deposited);
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            2,
+            hbb_deposited,
+        );
 
         // User wants to unstake more than he deposited, but he only receives his fair share
         staking_pool_operations::user_unstake(
             &mut staking_pool_state,
             &mut users[0],
+            &mut boost_checkpoints,
             hbb_deposited * 2,
+            0,
         )
         .unwrap();
 
@@ -465,6 +573,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut market = BorrowingMarketState::default();
 
         let amount_to_borrow = USDH::from(200.0);
@@ -479,8 +588,12 @@ mod tests {
         borrowing_operations::initialize_borrowing_market(&mut market, 0);
         staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
 
-        let mut users =
-            utils::new_staking_users(&mut staking_pool_state, count_staked, hbb_deposited);
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            count_staked,
+            hbb_deposited,
+        );
 
         utils::new_borrowing_users(
             &mut market,
@@ -495,7 +608,13 @@ mod tests {
 
         let HarvestEffects {
             reward: reward_u_one,
-        } = staking_pool_operations::user_harvest(&mut staking_pool_state, &mut users[0]).unwrap();
+        } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
 
         let treasury_fee = borrow_split.fees_to_pay * 1_500 / 10_000;
         let staking_fee = borrow_split.fees_to_pay - treasury_fee;
@@ -508,14 +627,26 @@ mod tests {
 
         let HarvestEffects {
             reward: reward_u_two,
-        } = staking_pool_operations::user_harvest(&mut staking_pool_state, &mut users[1]).unwrap();
+        } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[1],
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
         assert_eq!(reward_u_two, 3 * staking_fee as u128);
 
         println!("staking pool {:}", staking_pool_state.to_state_string());
 
         let HarvestEffects {
             reward: reward_u_three,
-        } = staking_pool_operations::user_harvest(&mut staking_pool_state, &mut users[2]).unwrap();
+        } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[2],
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
 
         // minor precision loss
         assert_eq!(reward_u_three, 3 * staking_fee as u128);
@@ -543,6 +674,7 @@ mod tests {
             treasury_fee_rate: 1_500,
             ..Default::default()
         };
+        let mut boost_checkpoints = BoostCheckpoints::default();
         let mut user_one = UserStakingState::default();
         let mut user_two = UserStakingState::default();
         let mut market = BorrowingMarketState::default();
@@ -558,7 +690,13 @@ mod tests {
         let amount_to_borrow = USDH::from(200.0);
         let borrow_split = BorrowSplit::from_amount(amount_to_borrow, market.base_rate_bps);
 
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user_one, hbb_deposited);
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
         utils::new_borrowing_users(
             &mut market,
             &mut staking_pool_state,
@@ -567,7 +705,13 @@ mod tests {
             deposit_collateral,
             now_timestamp,
         );
-        staking_pool_operations::user_stake(&mut staking_pool_state, &mut user_two, hbb_deposited);
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_two,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
 
         let treasury_fee = borrow_split.fees_to_pay * 1_500 / 10_000;
         let staking_fee = borrow_split.fees_to_pay - treasury_fee;
@@ -587,7 +731,12 @@ mod tests {
             now_timestamp,
         );
 
-        let res_one = staking_pool_operations::user_harvest(&mut staking_pool_state, &mut user_one);
+        let res_one = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            now_timestamp,
+        );
         match res_one {
             Ok(res) => {
                 assert_eq!(res.reward, staking_fee as u128 * 3 / 2);
@@ -595,7 +744,12 @@ mod tests {
             Err(e) => println!("Error {}", e),
         }
 
-        let res_two = staking_pool_operations::user_harvest(&mut staking_pool_state, &mut user_two);
+        let res_two = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user_two,
+            &mut boost_checkpoints,
+            now_timestamp,
+        );
         match res_two {
             Ok(res) => {
                 assert_eq!(res.reward, staking_fee as u128 / 2);
@@ -620,34 +774,1252 @@ mod tests {
             staking_fee as u128 * DECIMAL_PRECISION * 3 / 2 / hbb_deposited as u128,
         ); // reward/hbb_deposited + reward / 2 * hbb_deposited
     }
-}
 
-#[cfg(test)]
-mod utils {
+    #[test]
+    fn test_staking_harvest_collateral_two_users() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut user_one = UserStakingState::default();
+        let mut user_two = UserStakingState::default();
 
-    use crate::borrowing_market::borrowing_rate::BorrowSplit;
-    use crate::utils::coretypes::{HBB, USDH};
-    use crate::UserMetadata;
-    use crate::{
-        borrowing_market::borrowing_operations, BorrowingMarketState, CollateralToken, TokenPrices,
-    };
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user_one).unwrap();
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user_two).unwrap();
 
-    use crate::{
-        staking_pool::staking_pool_operations, utils::math, StakingPoolState, UserStakingState,
-    };
-    #[allow(dead_code)]
-    const SE: u64 = 10;
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            HBB::from(300.0),
+            0,
+        );
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_two,
+            &mut boost_checkpoints,
+            HBB::from(100.0),
+            0,
+        );
 
-    pub fn new_staking_users(
-        staking_pool_state: &mut StakingPoolState,
-        count: usize,
-        staked_hbb: u64,
-    ) -> Vec<UserStakingState> {
-        (0..count)
-            .map(|_| {
-                let mut user = UserStakingState::default();
-                staking_pool_operations::approve_new_user(staking_pool_state, &mut user).unwrap();
-                staking_pool_operations::user_stake(staking_pool_state, &mut user, staked_hbb);
+        let fees = CollateralAmounts {
+            sol: SOL::from(4.0),
+            eth: 800,
+            ..Default::default()
+        };
+        staking_pool_operations::distribute_collateral_fees(&mut staking_pool_state, &fees, 0);
+
+        let HarvestCollateralEffects { collateral_reward } =
+            staking_pool_operations::user_harvest_collateral(
+                &mut staking_pool_state,
+                &mut user_one,
+                &mut boost_checkpoints,
+                CollateralToken::SOL,
+                0,
+            )
+            .unwrap();
+        assert_eq!(collateral_reward.sol, SOL::from(3.0));
+        assert_eq!(collateral_reward.eth, 0);
+
+        // Other tokens stay pending until harvested
+        assert_eq!(user_one.pending_collateral_rewards.eth, 600);
+        assert_eq!(user_one.pending_collateral_rewards.sol, 0);
+
+        let HarvestCollateralEffects { collateral_reward } =
+            staking_pool_operations::user_harvest_collateral(
+                &mut staking_pool_state,
+                &mut user_two,
+                &mut boost_checkpoints,
+                CollateralToken::ETH,
+                0,
+            )
+            .unwrap();
+        assert_eq!(collateral_reward.eth, 200);
+
+        // Already harvested
+        let res = staking_pool_operations::user_harvest_collateral(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            CollateralToken::SOL,
+            0,
+        );
+        assert_eq!(res.err(), Some(BorrowError::NoRewardToWithdraw));
+
+        assert_eq!(
+            staking_pool_state.collateral_rewards_not_yet_claimed.sol,
+            SOL::from(1.0)
+        );
+        assert_eq!(
+            staking_pool_state.collateral_rewards_not_yet_claimed.eth,
+            600
+        );
+    }
+
+    #[test]
+    fn test_staking_harvest_collateral_settled_on_stake_change() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut user_one = UserStakingState::default();
+        let mut user_two = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user_one).unwrap();
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user_two).unwrap();
+
+        let hbb_deposited = HBB::from(100.0);
+        let fees = CollateralAmounts {
+            sol: SOL::from(2.0),
+            ..Default::default()
+        };
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            0,
+        );
+        staking_pool_operations::distribute_collateral_fees(&mut staking_pool_state, &fees, 0);
+
+        // Joining later does not earn the previous rewards
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_two,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            0,
+        );
+        staking_pool_operations::distribute_collateral_fees(&mut staking_pool_state, &fees, 0);
+
+        // Unstaking keeps the rewards earned so far
+        staking_pool_operations::user_unstake(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            0,
+        )
+        .unwrap();
+        staking_pool_operations::distribute_collateral_fees(&mut staking_pool_state, &fees, 0);
+
+        let HarvestCollateralEffects { collateral_reward } =
+            staking_pool_operations::user_harvest_collateral(
+                &mut staking_pool_state,
+                &mut user_one,
+                &mut boost_checkpoints,
+                CollateralToken::SOL,
+                0,
+            )
+            .unwrap();
+        assert_eq!(collateral_reward.sol, SOL::from(3.0));
+
+        let HarvestCollateralEffects { collateral_reward } =
+            staking_pool_operations::user_harvest_collateral(
+                &mut staking_pool_state,
+                &mut user_two,
+                &mut boost_checkpoints,
+                CollateralToken::SOL,
+                0,
+            )
+            .unwrap();
+        assert_eq!(collateral_reward.sol, SOL::from(3.0));
+
+        assert!(staking_pool_state
+            .collateral_rewards_not_yet_claimed
+            .is_zero());
+    }
+
+    #[test]
+    fn test_staking_collateral_fees_carried_forward_without_stakers() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut user = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
+
+        // Nobody to credit yet, the first stakers get it
+        let fees = CollateralAmounts {
+            sol: SOL::from(2.0),
+            ..Default::default()
+        };
+        staking_pool_operations::distribute_collateral_fees(&mut staking_pool_state, &fees, 0);
+        assert_eq!(staking_pool_state.collateral_reward_per_token.sol, 0);
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            HBB::from(100.0),
+            0,
+        );
+        staking_pool_operations::distribute_collateral_fees(&mut staking_pool_state, &fees, 0);
+
+        let HarvestCollateralEffects { collateral_reward } =
+            staking_pool_operations::user_harvest_collateral(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                CollateralToken::SOL,
+                0,
+            )
+            .unwrap();
+        assert_eq!(collateral_reward.sol, SOL::from(4.0));
+        assert!(staking_pool_state
+            .collateral_rewards_not_yet_claimed
+            .is_zero());
+    }
+
+    #[test]
+    fn test_staking_lock_boost_decays_to_expiry() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut liquid = UserStakingState::default();
+        let mut locker = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut liquid).unwrap();
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut locker).unwrap();
+
+        let hbb_deposited = HBB::from(100.0);
+        // Max locks end on a whole week
+        let now_timestamp =
+            HBB_LOCK_EXPIRY_SECONDS - MAX_HBB_LOCK_SECONDS % HBB_LOCK_EXPIRY_SECONDS;
+        let lock_expiry_ts = now_timestamp + MAX_HBB_LOCK_SECONDS;
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut liquid,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+        staking_pool_operations::lock_hbb(
+            &mut staking_pool_state,
+            &mut locker,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            lock_expiry_ts,
+            now_timestamp,
+        )
+        .unwrap();
+
+        // Max lock is boosted 2.5x
+        assert_eq!(locker.boosted_stake, hbb_deposited as u128 * 5 / 2);
+        assert_eq!(staking_pool_state.total_stake, 2 * hbb_deposited as u128);
+        assert_eq!(
+            staking_pool_state.total_boosted_stake,
+            hbb_deposited as u128 * 7 / 2
+        );
+
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(70.0),
+            now_timestamp,
+        );
+
+        // Half way to expiry the boost is 1.75x
+        let half_way_ts = now_timestamp + MAX_HBB_LOCK_SECONDS / 2;
+        staking_pool_operations::refresh_boosted_stake(
+            &mut staking_pool_state,
+            &mut locker,
+            &mut boost_checkpoints,
+            half_way_ts,
+        );
+        assert_eq!(locker.boosted_stake, hbb_deposited as u128 * 7 / 4);
+        assert_eq!(locker.pending_rewards, USDH::from(50.0) as u128);
+
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(110.0),
+            half_way_ts,
+        );
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut locker,
+            &mut boost_checkpoints,
+            half_way_ts,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(50.0 + 70.0) as u128);
+        assert_eq!(locker.pending_rewards, 0);
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut liquid,
+            &mut boost_checkpoints,
+            half_way_ts,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(20.0 + 40.0) as u128);
+
+        // No boost left once expired
+        staking_pool_operations::refresh_boosted_stake(
+            &mut staking_pool_state,
+            &mut locker,
+            &mut boost_checkpoints,
+            lock_expiry_ts,
+        );
+        assert_eq!(locker.boosted_stake, hbb_deposited as u128);
+        assert_eq!(
+            staking_pool_state.total_boosted_stake,
+            2 * hbb_deposited as u128
+        );
+    }
+
+    #[test]
+    fn test_staking_lock_boost_decays_without_refresh() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut liquid = UserStakingState::default();
+        let mut locker = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut liquid).unwrap();
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut locker).unwrap();
+
+        let hbb_deposited = HBB::from(100.0);
+        // Max locks end on a whole week
+        let now_timestamp =
+            HBB_LOCK_EXPIRY_SECONDS - MAX_HBB_LOCK_SECONDS % HBB_LOCK_EXPIRY_SECONDS;
+        let lock_expiry_ts = now_timestamp + MAX_HBB_LOCK_SECONDS;
+        let half_way_ts = now_timestamp + MAX_HBB_LOCK_SECONDS / 2;
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut liquid,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+        staking_pool_operations::lock_hbb(
+            &mut staking_pool_state,
+            &mut locker,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            lock_expiry_ts,
+            now_timestamp,
+        )
+        .unwrap();
+
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(70.0),
+            now_timestamp,
+        );
+
+        // Nobody refreshes the lock, the pool still weighs it 1.75x half way
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(110.0),
+            half_way_ts,
+        );
+        assert_eq!(
+            staking_pool_state.total_boosted_stake,
+            hbb_deposited as u128 * 11 / 4
+        );
+
+        // And 1x once expired
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(20.0),
+            lock_expiry_ts + HBB_LOCK_EXPIRY_SECONDS,
+        );
+        assert_eq!(
+            staking_pool_state.total_boosted_stake,
+            2 * hbb_deposited as u128
+        );
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut locker,
+            &mut boost_checkpoints,
+            lock_expiry_ts + HBB_LOCK_EXPIRY_SECONDS,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(50.0 + 70.0 + 10.0) as u128);
+        assert_eq!(locker.boosted_stake, hbb_deposited as u128);
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut liquid,
+            &mut boost_checkpoints,
+            lock_expiry_ts + HBB_LOCK_EXPIRY_SECONDS,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(20.0 + 40.0 + 10.0) as u128);
+    }
+
+    #[test]
+    fn test_staking_lock_increase_extend_and_withdraw() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut user = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user).unwrap();
+
+        let hbb_deposited = HBB::from(100.0);
+        // Max locks end on a whole week
+        let now_timestamp =
+            HBB_LOCK_EXPIRY_SECONDS - MAX_HBB_LOCK_SECONDS % HBB_LOCK_EXPIRY_SECONDS;
+        let lock_expiry_ts = now_timestamp + MAX_HBB_LOCK_SECONDS / 4;
+
+        let res = staking_pool_operations::increase_lock_amount(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::NoActiveHbbLock));
+
+        let res = staking_pool_operations::lock_hbb(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp + MAX_HBB_LOCK_SECONDS + HBB_LOCK_EXPIRY_SECONDS,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::InvalidHbbLockExpiry));
+
+        staking_pool_operations::lock_hbb(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            lock_expiry_ts,
+            now_timestamp,
+        )
+        .unwrap();
+
+        let res = staking_pool_operations::lock_hbb(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            lock_expiry_ts,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::HbbAlreadyLocked));
+
+        staking_pool_operations::increase_lock_amount(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(user.locked_stake, 2 * hbb_deposited as u128);
+
+        // Cannot shorten the lock
+        let res = staking_pool_operations::extend_lock(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            lock_expiry_ts - 1,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::InvalidHbbLockExpiry));
+
+        staking_pool_operations::extend_lock(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            now_timestamp + MAX_HBB_LOCK_SECONDS,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(user.boosted_stake, hbb_deposited as u128 * 5);
+
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(10.0),
+            now_timestamp,
+        );
+
+        let res = staking_pool_operations::withdraw_locked_hbb(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            now_timestamp + MAX_HBB_LOCK_SECONDS - 1,
+        );
+        assert_eq!(res.err(), Some(BorrowError::HbbLockNotExpired));
+
+        let WithdrawLockedHbbEffects { amount_to_withdraw } =
+            staking_pool_operations::withdraw_locked_hbb(
+                &mut staking_pool_state,
+                &mut user,
+                &mut boost_checkpoints,
+                now_timestamp + MAX_HBB_LOCK_SECONDS,
+            )
+            .unwrap();
+        assert_eq!(amount_to_withdraw, 2 * hbb_deposited);
+        assert_eq!(user.boosted_stake, 0);
+        assert_eq!(staking_pool_state.total_stake, 0);
+        assert_eq!(staking_pool_state.total_boosted_stake, 0);
+
+        // Rewards earned while locked can still be harvested
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(10.0) as u128);
+        assert_eq!(staking_pool_state.rewards_not_yet_claimed, 0);
+    }
+
+    #[test]
+    fn test_staking_request_unstake_stops_earning_until_cooldown() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut user_one = UserStakingState::default();
+        let mut user_two = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user_one).unwrap();
+        staking_pool_operations::approve_new_user(&mut staking_pool_state, &mut user_two).unwrap();
+
+        let hbb_deposited = HBB::from(100.0);
+        let now_timestamp = 1_000;
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut user_two,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(10.0),
+            now_timestamp,
+        );
+
+        let RequestUnstakeEffects {
+            amount_requested,
+            withdrawable_after_ts,
+        } = staking_pool_operations::request_unstake(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            hbb_deposited * 2,
+            UNSTAKE_COOLDOWN_SECONDS,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(amount_requested, hbb_deposited);
+        assert_eq!(
+            withdrawable_after_ts,
+            now_timestamp + UNSTAKE_COOLDOWN_SECONDS
+        );
+
+        assert_eq!(user_one.user_stake, 0);
+        assert_eq!(user_one.pending_unstake, hbb_deposited as u128);
+        assert_eq!(staking_pool_state.total_stake, hbb_deposited as u128);
+        assert_eq!(
+            staking_pool_state.total_pending_unstake,
+            hbb_deposited as u128
+        );
+
+        // Fees distributed during the cooldown only go to the remaining stakers
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(10.0),
+            now_timestamp,
+        );
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user_one,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(5.0) as u128);
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut user_two,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(15.0) as u128);
+
+        let res = staking_pool_operations::complete_unstake(
+            &mut staking_pool_state,
+            &mut user_one,
+            withdrawable_after_ts - 1,
+        );
+        assert_eq!(res.err(), Some(BorrowError::UnstakeCooldownNotElapsed));
+
+        let CompleteUnstakeEffects { amount_to_withdraw } =
+            staking_pool_operations::complete_unstake(
+                &mut staking_pool_state,
+                &mut user_one,
+                withdrawable_after_ts,
+            )
+            .unwrap();
+        assert_eq!(amount_to_withdraw, hbb_deposited);
+        assert_eq!(user_one.pending_unstake, 0);
+        assert_eq!(staking_pool_state.total_pending_unstake, 0);
+
+        let res = staking_pool_operations::complete_unstake(
+            &mut staking_pool_state,
+            &mut user_one,
+            withdrawable_after_ts,
+        );
+        assert_eq!(res.err(), Some(BorrowError::NothingToUnstake));
+    }
+
+    #[test]
+    fn test_staking_auto_compound_credits_bought_hbb() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut compounder = UserStakingState::default();
+        let mut other_compounder = UserStakingState::default();
+        let mut harvester = UserStakingState::default();
+
+        staking_pool_operations::initialize_staking_pool(&mut staking_pool_state);
+        for user in [&mut compounder, &mut other_compounder, &mut harvester] {
+            staking_pool_operations::approve_new_user(&mut staking_pool_state, user).unwrap();
+        }
+
+        let hbb_deposited = HBB::from(100.0);
+        let now_timestamp = 1_000;
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut compounder,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut other_compounder,
+            &mut boost_checkpoints,
+            hbb_deposited * 3,
+            now_timestamp,
+        );
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut harvester,
+            &mut boost_checkpoints,
+            hbb_deposited,
+            now_timestamp,
+        );
+
+        let res = staking_pool_operations::rewards_to_compound(&staking_pool_state);
+        assert_eq!(res.err(), Some(BorrowError::NothingToCompound));
+
+        staking_pool_operations::set_auto_compound(
+            &mut staking_pool_state,
+            &mut compounder,
+            &mut boost_checkpoints,
+            true,
+            now_timestamp,
+        );
+        staking_pool_operations::set_auto_compound(
+            &mut staking_pool_state,
+            &mut other_compounder,
+            &mut boost_checkpoints,
+            true,
+            now_timestamp,
+        );
+        assert_eq!(
+            staking_pool_state.total_compounding_stake,
+            hbb_deposited as u128 * 4
+        );
+
+        // The compounders share is kept aside for the swap
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(50.0),
+            now_timestamp,
+        );
+        assert_eq!(
+            staking_pool_operations::rewards_to_compound(&staking_pool_state).unwrap(),
+            USDH::from(40.0)
+        );
+
+        let res = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut compounder,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(res.reward, 0);
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut harvester,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(10.0) as u128);
+
+        let res = staking_pool_operations::update_compound_config(
+            &mut staking_pool_state,
+            Pubkey::new_unique(),
+            0,
+        );
+        assert_eq!(res.err(), Some(BorrowError::InvalidCompoundMaxPrice));
+        staking_pool_operations::update_compound_config(
+            &mut staking_pool_state,
+            Pubkey::new_unique(),
+            USDH::from(2.0),
+        )
+        .unwrap();
+
+        // Cannot pay more than 2 USDH per HBB
+        let res = staking_pool_operations::compound_rewards(
+            &mut staking_pool_state,
+            USDH::from(40.0),
+            HBB::from(19.0),
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::CompoundPriceAboveMaximum));
+
+        // Bought 20 HBB with 40 USDH
+        staking_pool_operations::compound_rewards(
+            &mut staking_pool_state,
+            USDH::from(40.0),
+            HBB::from(20.0),
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(staking_pool_state.rewards_to_compound, 0);
+        assert_eq!(
+            staking_pool_state.compounded_hbb_not_yet_staked,
+            HBB::from(20.0) as u128
+        );
+
+        // Staked once settled
+        staking_pool_operations::refresh_boosted_stake(
+            &mut staking_pool_state,
+            &mut compounder,
+            &mut boost_checkpoints,
+            now_timestamp,
+        );
+        assert_eq!(compounder.user_stake, HBB::from(105.0) as u128);
+        assert_eq!(compounder.boosted_stake, HBB::from(105.0) as u128);
+
+        staking_pool_operations::user_stake(
+            &mut staking_pool_state,
+            &mut other_compounder,
+            &mut boost_checkpoints,
+            0,
+            now_timestamp,
+        );
+        assert_eq!(other_compounder.user_stake, HBB::from(315.0) as u128);
+
+        assert_eq!(staking_pool_state.compounded_hbb_not_yet_staked, 0);
+        assert_eq!(staking_pool_state.total_stake, HBB::from(520.0) as u128);
+        assert_eq!(
+            staking_pool_state.total_compounding_stake,
+            HBB::from(420.0) as u128
+        );
+
+        // Opting out earns USDH again
+        staking_pool_operations::set_auto_compound(
+            &mut staking_pool_state,
+            &mut compounder,
+            &mut boost_checkpoints,
+            false,
+            now_timestamp,
+        );
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(52.0),
+            now_timestamp,
+        );
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut compounder,
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(10.5) as u128);
+        assert_eq!(
+            staking_pool_state.rewards_to_compound,
+            USDH::from(31.5) as u128
+        );
+    }
+
+    #[test]
+    fn test_staking_update_treasury_fee_rate() {
+        let mut staking_pool_state = StakingPoolState {
+            treasury_fee_rate: 1_500,
+            ..Default::default()
+        };
+
+        let res = staking_pool_operations::update_treasury_fee_rate(
+            &mut staking_pool_state,
+            MAX_TREASURY_FEE_RATE + 1,
+        );
+        assert_eq!(res.err(), Some(BorrowError::TreasuryFeeRateAboveMaximum));
+        assert_eq!(staking_pool_state.treasury_fee_rate, 1_500);
+
+        staking_pool_operations::update_treasury_fee_rate(&mut staking_pool_state, 2_000).unwrap();
+        assert_eq!(staking_pool_state.treasury_fee_rate, 2_000);
+
+        let (staking_fee, treasury_fee) = staking_pool_operations::split_fees(
+            USDH::from(10.0),
+            staking_pool_state.treasury_fee_rate,
+        );
+        assert_eq!(staking_fee, USDH::from(8.0));
+        assert_eq!(treasury_fee, USDH::from(2.0));
+    }
+
+    #[test]
+    fn test_staking_treasury_disbursement_timelock() {
+        let mut staking_pool_state = StakingPoolState::default();
+        let recipient = Pubkey::new_unique();
+        let now_timestamp = 1_000;
+
+        let res = staking_pool_operations::schedule_treasury_disbursement(
+            &mut staking_pool_state,
+            0,
+            recipient,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::InvalidTreasuryDisbursement));
+
+        let executable_ts = staking_pool_operations::schedule_treasury_disbursement(
+            &mut staking_pool_state,
+            USDH::from(500.0),
+            recipient,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(
+            executable_ts,
+            now_timestamp + TREASURY_DISBURSEMENT_TIMELOCK_SECONDS
+        );
+
+        let res = staking_pool_operations::schedule_treasury_disbursement(
+            &mut staking_pool_state,
+            USDH::from(1.0),
+            recipient,
+            now_timestamp,
+        );
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::TreasuryDisbursementAlreadyScheduled)
+        );
+
+        let res = staking_pool_operations::execute_treasury_disbursement(
+            &mut staking_pool_state,
+            executable_ts - 1,
+        );
+        assert_eq!(res.err(), Some(BorrowError::TreasuryDisbursementTimelocked));
+
+        let TreasuryDisbursementEffects {
+            amount,
+            recipient: paid_to,
+        } = staking_pool_operations::execute_treasury_disbursement(
+            &mut staking_pool_state,
+            executable_ts,
+        )
+        .unwrap();
+        assert_eq!(amount, USDH::from(500.0));
+        assert_eq!(paid_to, recipient);
+
+        let res = staking_pool_operations::execute_treasury_disbursement(
+            &mut staking_pool_state,
+            executable_ts,
+        );
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::NoTreasuryDisbursementScheduled)
+        );
+
+        // Cancelled disbursements cannot be executed
+        staking_pool_operations::schedule_treasury_disbursement(
+            &mut staking_pool_state,
+            USDH::from(500.0),
+            recipient,
+            now_timestamp,
+        )
+        .unwrap();
+        staking_pool_operations::cancel_treasury_disbursement(&mut staking_pool_state).unwrap();
+
+        let res = staking_pool_operations::execute_treasury_disbursement(
+            &mut staking_pool_state,
+            executable_ts,
+        );
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::NoTreasuryDisbursementScheduled)
+        );
+    }
+
+    #[test]
+    fn test_staking_reconcile_borrowing_fees_vault() {
+        let mut staking_pool_state = StakingPoolState::default();
+        let mut boost_checkpoints = BoostCheckpoints::default();
+
+        // Fees distributed before anyone stakes are not owed to anyone
+        let orphaned_fees = USDH::from(10.0);
+        staking_pool_operations::distribute_fees(&mut staking_pool_state, orphaned_fees, 0);
+        assert_eq!(staking_pool_state.rewards_not_yet_claimed, 0);
+        let mut vault_balance = orphaned_fees;
+
+        let surplus = staking_pool_operations::reconcile_borrowing_fees_vault(
+            &staking_pool_state,
+            vault_balance,
+        )
+        .unwrap();
+        assert_eq!(surplus, orphaned_fees);
+        vault_balance -= surplus;
+
+        // Uneven split, every harvest rounds down
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            3,
+            HBB::from(1.0),
+        );
+        let fees = 1_000;
+        staking_pool_operations::distribute_fees(&mut staking_pool_state, fees, 0);
+        vault_balance += fees;
+
+        for user in users.iter_mut() {
+            let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+                &mut staking_pool_state,
+                user,
+                &mut boost_checkpoints,
+                0,
+            )
+            .unwrap();
+            assert_eq!(reward, 333);
+            vault_balance -= reward as u64;
+        }
+
+        // The rounding dust stays booked as owed and is never swept
+        assert_eq!(staking_pool_state.rewards_not_yet_claimed, 1);
+        let surplus = staking_pool_operations::reconcile_borrowing_fees_vault(
+            &staking_pool_state,
+            vault_balance,
+        )
+        .unwrap();
+        assert_eq!(surplus, 0);
+
+        // Tokens sent straight to the vault are surplus
+        let donation = USDH::from(2.0);
+        let surplus = staking_pool_operations::reconcile_borrowing_fees_vault(
+            &staking_pool_state,
+            vault_balance + donation,
+        )
+        .unwrap();
+        assert_eq!(surplus, donation);
+
+        let res = staking_pool_operations::reconcile_borrowing_fees_vault(
+            &staking_pool_state,
+            vault_balance - 1,
+        );
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::StakingRewardsAccountingMismatch)
+        );
+    }
+
+    #[test]
+    fn test_staking_harvest_accounting_mismatch_does_not_panic() {
+        let mut staking_pool_state = StakingPoolState::default();
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            2,
+            HBB::from(1.0),
+        );
+
+        staking_pool_operations::distribute_fees(&mut staking_pool_state, USDH::from(10.0), 0);
+        staking_pool_state.rewards_not_yet_claimed = 0;
+
+        let res = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            0,
+        );
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::StakingRewardsAccountingMismatch.into())
+        );
+
+        let res = staking_pool_operations::user_unstake(
+            &mut staking_pool_state,
+            &mut users[1],
+            &mut boost_checkpoints,
+            HBB::from(1.0),
+            0,
+        );
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::StakingRewardsAccountingMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_staking_transfer_position_keeps_rewards() {
+        let mut staking_pool_state = StakingPoolState::default();
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            2,
+            HBB::from(100.0),
+        );
+        let new_owner = Pubkey::new_unique();
+        let now_timestamp = 1_000;
+
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(10.0),
+            now_timestamp,
+        );
+        staking_pool_operations::distribute_collateral_fees(
+            &mut staking_pool_state,
+            &CollateralAmounts::of_token(SOL::from(2.0), CollateralToken::SOL),
+            now_timestamp,
+        );
+
+        let owner = users[0].owner;
+        let res = staking_pool_operations::transfer_staking_position(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            owner,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::InvalidStakingPositionOwner));
+
+        staking_pool_operations::transfer_staking_position(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            new_owner,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(users[0].owner, new_owner);
+        assert_eq!(users[0].user_stake, HBB::from(100.0) as u128);
+        assert_eq!(users[0].pending_rewards, USDH::from(5.0) as u128);
+
+        // Rewards earned before and after the transfer are both harvested
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(10.0),
+            now_timestamp,
+        );
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(10.0) as u128);
+
+        let HarvestCollateralEffects { collateral_reward } =
+            staking_pool_operations::user_harvest_collateral(
+                &mut staking_pool_state,
+                &mut users[0],
+                &mut boost_checkpoints,
+                CollateralToken::SOL,
+                now_timestamp,
+            )
+            .unwrap();
+        assert_eq!(collateral_reward.sol, SOL::from(1.0));
+
+        // The other staker is unaffected
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[1],
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(10.0) as u128);
+    }
+
+    #[test]
+    fn test_staking_tokenized_position_redeemed_by_holder() {
+        let mut staking_pool_state = StakingPoolState::default();
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            2,
+            HBB::from(100.0),
+        );
+        let position_mint = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+        let now_timestamp = 1_000;
+
+        let res = staking_pool_operations::redeem_staking_position(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            holder,
+            now_timestamp,
+        );
+        assert_eq!(res.err(), Some(BorrowError::StakingPositionNotTokenized));
+
+        staking_pool_operations::tokenize_staking_position(&mut users[0], position_mint).unwrap();
+        assert_eq!(users[0].owner, Pubkey::default());
+        assert_eq!(users[0].position_mint, position_mint);
+
+        let res =
+            staking_pool_operations::tokenize_staking_position(&mut users[0], Pubkey::new_unique());
+        assert_eq!(
+            res.err(),
+            Some(BorrowError::StakingPositionAlreadyTokenized)
+        );
+
+        // Still earning while held as a token
+        staking_pool_operations::distribute_fees(
+            &mut staking_pool_state,
+            USDH::from(10.0),
+            now_timestamp,
+        );
+
+        staking_pool_operations::redeem_staking_position(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            holder,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(users[0].owner, holder);
+        assert_eq!(users[0].position_mint, Pubkey::default());
+        assert_eq!(users[0].user_stake, HBB::from(100.0) as u128);
+        assert_eq!(users[0].pending_rewards, USDH::from(5.0) as u128);
+
+        let HarvestEffects { reward } = staking_pool_operations::user_harvest(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            now_timestamp,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(5.0) as u128);
+    }
+
+    #[test]
+    fn test_staking_close_account() {
+        let mut staking_pool_state = StakingPoolState::default();
+        let mut boost_checkpoints = BoostCheckpoints::default();
+        let mut users = utils::new_staking_users(
+            &mut staking_pool_state,
+            &mut boost_checkpoints,
+            1,
+            HBB::from(100.0),
+        );
+
+        staking_pool_operations::distribute_fees(&mut staking_pool_state, USDH::from(10.0), 0);
+
+        let res =
+            staking_pool_operations::close_user_staking_state(&mut staking_pool_state, &users[0]);
+        assert_eq!(res.err(), Some(BorrowError::StakingAccountNotEmpty));
+
+        // Unstaking everything also pays out the rewards
+        let UnstakeEffects { reward, .. } = staking_pool_operations::user_unstake(
+            &mut staking_pool_state,
+            &mut users[0],
+            &mut boost_checkpoints,
+            HBB::from(100.0),
+            0,
+        )
+        .unwrap();
+        assert_eq!(reward, USDH::from(10.0) as u128);
+
+        staking_pool_operations::close_user_staking_state(&mut staking_pool_state, &users[0])
+            .unwrap();
+        assert_eq!(staking_pool_state.num_closed_users, 1);
+        assert_eq!(staking_pool_state.num_users, 1);
+    }
+}
+
+#[cfg(test)]
+mod utils {
+
+    use crate::borrowing_market::borrowing_rate::BorrowSplit;
+    use crate::utils::coretypes::{HBB, USDH};
+    use crate::UserMetadata;
+    use crate::{
+        borrowing_market::borrowing_operations, BorrowingMarketState, CollateralToken, TokenPrices,
+    };
+
+    use crate::{
+        staking_pool::staking_pool_operations, utils::math, BoostCheckpoints, StakingPoolState,
+        UserStakingState,
+    };
+    #[allow(dead_code)]
+    const SE: u64 = 10;
+
+    pub fn new_staking_users(
+        staking_pool_state: &mut StakingPoolState,
+        boost_checkpoints: &mut BoostCheckpoints,
+        count: usize,
+        staked_hbb: u64,
+    ) -> Vec<UserStakingState> {
+        (0..count)
+            .map(|_| {
+                let mut user = UserStakingState::default();
+                staking_pool_operations::approve_new_user(staking_pool_state, &mut user).unwrap();
+                staking_pool_operations::user_stake(
+                    staking_pool_state,
+                    &mut user,
+                    boost_checkpoints,
+                    staked_hbb,
+                    0,
+                );
                 user
             })
             .collect()
@@ -671,6 +2043,7 @@ mod utils {
                     &mut user,
                     deposit_collateral,
                     CollateralToken::SOL,
+                    0,
                 )
                 .unwrap();
 
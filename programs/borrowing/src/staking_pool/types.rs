@@ -1,7 +1,33 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::CollateralAmounts;
+
 pub struct HarvestEffects {
     pub reward: u128,
 }
 
+pub struct HarvestCollateralEffects {
+    pub collateral_reward: CollateralAmounts,
+}
+
+pub struct WithdrawLockedHbbEffects {
+    pub amount_to_withdraw: u64,
+}
+
+pub struct RequestUnstakeEffects {
+    pub amount_requested: u64,
+    pub withdrawable_after_ts: u64,
+}
+
+pub struct CompleteUnstakeEffects {
+    pub amount_to_withdraw: u64,
+}
+
+pub struct TreasuryDisbursementEffects {
+    pub amount: u64,
+    pub recipient: Pubkey,
+}
+
 pub struct UnstakeEffects {
     pub reward: u128,
     pub amount_to_withdraw: u64,
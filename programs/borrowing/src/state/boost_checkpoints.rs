@@ -0,0 +1,50 @@
+use crate::{
+    utils::consts::{HBB_LOCK_EXPIRY_SECONDS, MAX_BOOST_CHECKPOINTS},
+    BoostCheckpoint, BoostCheckpoints,
+};
+
+impl Default for BoostCheckpoints {
+    #[cfg(not(test))]
+    fn default() -> Self {
+        unimplemented!()
+    }
+
+    #[cfg(test)]
+    #[inline(never)]
+    fn default() -> Self {
+        let checkpoints: [BoostCheckpoint; MAX_BOOST_CHECKPOINTS] =
+            unsafe { std::mem::MaybeUninit::zeroed().assume_init() };
+
+        BoostCheckpoints {
+            staking_pool_state: Default::default(),
+            checkpoints,
+        }
+    }
+}
+
+impl BoostCheckpoints {
+    fn index(expiry_ts: u64) -> usize {
+        (expiry_ts / HBB_LOCK_EXPIRY_SECONDS) as usize % MAX_BOOST_CHECKPOINTS
+    }
+
+    // None once the slot was reused for an expiry MAX_BOOST_CHECKPOINTS weeks later
+    pub fn get(&self, expiry_ts: u64) -> Option<BoostCheckpoint> {
+        let checkpoint = self.checkpoints[Self::index(expiry_ts)];
+        if checkpoint.expiry_ts == expiry_ts {
+            Some(checkpoint)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_or_new(&self, expiry_ts: u64) -> BoostCheckpoint {
+        self.get(expiry_ts).unwrap_or(BoostCheckpoint {
+            expiry_ts,
+            ..Default::default()
+        })
+    }
+
+    pub fn set(&mut self, checkpoint: BoostCheckpoint) {
+        self.checkpoints[Self::index(checkpoint.expiry_ts)] = checkpoint;
+    }
+}
@@ -1,3 +1,5 @@
+use anchor_lang::prelude::Pubkey;
+
 use crate::BorrowingMarketState;
 
 impl BorrowingMarketState {
@@ -7,6 +9,10 @@ impl BorrowingMarketState {
         }
     }
 
+    pub fn registered_stability_pools(&self) -> &[Pubkey] {
+        &self.stability_pools[..self.num_stability_pools as usize]
+    }
+
     pub fn to_state_string(&self) -> String {
         format!(
             "BorrowingMarketState {{
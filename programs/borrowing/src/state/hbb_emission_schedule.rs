@@ -0,0 +1,57 @@
+use decimal_wad::common::WAD;
+
+use crate::{
+    utils::consts::{HBB_ISSUANCE_FACTOR, TOTAL_HBB_SUPPLY, TOTAL_HBB_TO_STABILITY_POOL},
+    HbbEmissionSchedule,
+};
+
+impl Default for HbbEmissionSchedule {
+    // Yearly halving of the stability pool allocation
+    fn default() -> Self {
+        Self {
+            total_allocation: TOTAL_HBB_TO_STABILITY_POOL,
+            decay_factor: HBB_ISSUANCE_FACTOR,
+            cliff_seconds: 0,
+            linear_tail_start_seconds: 0,
+            linear_tail_duration_seconds: 0,
+            stopped_at_ts: 0,
+        }
+    }
+}
+
+impl HbbEmissionSchedule {
+    pub fn new(
+        total_allocation: u64,
+        decay_factor: u64,
+        cliff_seconds: u64,
+        linear_tail_start_seconds: u64,
+        linear_tail_duration_seconds: u64,
+    ) -> Self {
+        Self {
+            total_allocation,
+            decay_factor,
+            cliff_seconds,
+            linear_tail_start_seconds,
+            linear_tail_duration_seconds,
+            stopped_at_ts: 0,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        // A tail start needs a tail, and without decay the tail
+        // is the only way anything is ever issued
+        self.total_allocation <= TOTAL_HBB_SUPPLY
+            && self.decay_factor > 0
+            && self.decay_factor <= WAD
+            && (self.decay_factor < WAD || self.has_linear_tail())
+            && (self.linear_tail_start_seconds == 0 || self.has_linear_tail())
+    }
+
+    pub fn has_linear_tail(&self) -> bool {
+        self.linear_tail_duration_seconds > 0
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped_at_ts > 0
+    }
+}
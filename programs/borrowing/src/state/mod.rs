@@ -2,15 +2,27 @@ use anchor_lang::prelude::*;
 use anchor_lang::zero_copy;
 use borsh::{BorshDeserialize, BorshSerialize};
 use num_derive::FromPrimitive;
+use std::convert::TryFrom;
 use struct_arithmetic::StructArithmetic;
 
+use crate::{
+    utils::consts::{
+        LOCKUP_NONE_HBB_BOOST_BPS, LOCKUP_ONE_MONTH_HBB_BOOST_BPS, LOCKUP_SECONDS_PER_MONTH,
+        LOCKUP_SIX_MONTHS_HBB_BOOST_BPS, LOCKUP_THREE_MONTHS_HBB_BOOST_BPS, MAX_STABILITY_POOLS,
+    },
+    BorrowError,
+};
+
+mod boost_checkpoints;
 mod borrowing_market_state;
 mod borrowing_vaults;
 mod collateral_amounts;
 mod deposit_snapshot;
 pub mod epoch_to_scale_to_sum;
+mod hbb_emission_schedule;
 mod liquidations_queue;
 pub mod redemptions_queue;
+mod sorted_troves;
 mod stability_collateral_amounts;
 mod stability_pool_state;
 mod stability_provider_state;
@@ -27,7 +39,14 @@ pub struct GlobalConfig {
     pub initial_market_owner: Pubkey,
     pub is_borrowing_allowed: bool,
     pub borrow_limit_usdh: u64,
-    _padding: [u8; 1024],
+    pub redemption_order_expiry_seconds: u64,
+    pub redemption_stakers_fee_in_usdh: bool,
+    pub unstake_cooldown_seconds: u64,
+    // New fields take their bytes out of the padding so that existing
+    // accounts keep their size and layout, the padding is split in two
+    // as borsh only (de)serializes arrays of some lengths
+    _padding: [[u8; 32]; 31],
+    _padding_tail: [u8; 15],
 }
 
 impl Default for GlobalConfig {
@@ -37,7 +56,11 @@ impl Default for GlobalConfig {
             initial_market_owner: Pubkey::new(&[0; 32]),
             is_borrowing_allowed: false,
             borrow_limit_usdh: 0,
-            _padding: [0; 1024],
+            redemption_order_expiry_seconds: 0,
+            redemption_stakers_fee_in_usdh: false,
+            unstake_cooldown_seconds: 0,
+            _padding: [[0; 32]; 31],
+            _padding_tail: [0; 15],
         }
     }
 }
@@ -46,6 +69,9 @@ impl Default for GlobalConfig {
 pub enum GlobalConfigOption {
     IsBorrowingAllowed = 0,
     BorrowLimitUsdh = 1,
+    RedemptionOrderExpirySeconds = 2,
+    RedemptionStakersFeeInUsdh = 3,
+    UnstakeCooldownSeconds = 4,
 }
 
 #[account]
@@ -98,6 +124,22 @@ pub struct BorrowingMarketState {
     // As of last liquidation
     pub total_stake_snapshot: u64,
     pub borrowed_stablecoin_snapshot: u64,
+
+    // Interest accrued on troves, already added to their debt, minted
+    // to the stakers and treasury by the instruction that charged it
+    pub interest_not_yet_minted: u64,
+
+    // Trove accounts closed so far, num_users keeps
+    // counting up as it hands out the user ids
+    pub num_closed_users: u64,
+
+    // Stability pools of the market, registered as they are initialized,
+    // every one of them takes part in each liquidation
+    pub stability_pools: [Pubkey; MAX_STABILITY_POOLS],
+    pub num_stability_pools: u8,
+
+    // Troves indexed by collateral ratio, for redemptions
+    pub sorted_troves: Pubkey,
 }
 
 #[account]
@@ -163,6 +205,61 @@ pub struct StabilityPoolState {
     // Precision errors
     pub last_stablecoin_loss_error_offset: u64,
     pub last_coll_loss_error_offset: StabilityCollateralAmounts,
+
+    // HBB emissions, set by the market owner
+    pub hbb_emission_schedule: HbbEmissionSchedule,
+
+    // Stability provider accounts closed so far
+    pub num_closed_users: u64,
+
+    // Sum of the compounded deposits weighted by their
+    // lockup boost, HBB emissions are shared by this amount
+    pub boosted_stablecoin_deposited: u64,
+
+    // Withdrawn from the pool but still in the vault, waiting for
+    // the liquidations queue to be drained to be paid out
+    pub stablecoin_pending_withdrawal: u64,
+
+    // Outstanding receipt tokens, each one a share
+    // of stablecoin_deposited and of the gains it accrues
+    pub receipt_supply: u64,
+
+    // Collateral types whose liquidations this pool absorbs,
+    // one bit per CollateralToken, 0 covers all of them
+    pub collateral_coverage: u8,
+
+    // Receipt epoch receipt_supply is counted in, it starts over
+    // once the pool is emptied or a receipt is worth too little
+    pub receipt_epoch: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct HbbEmissionSchedule {
+    // Total HBB (without decimals) to be issued to the stability pool
+    pub total_allocation: u64,
+    // Per minute decay of the remaining allocation, scaled by WAD
+    // WAD (no decay) means all the allocation is left for the linear tail
+    pub decay_factor: u64,
+    // Seconds after the emissions start before anything is issued,
+    // the curve starts at the end of the cliff
+    pub cliff_seconds: u64,
+    // Seconds after the cliff from which what's left of the allocation
+    // is issued linearly over linear_tail_duration_seconds
+    // no linear tail if the duration is 0
+    pub linear_tail_start_seconds: u64,
+    pub linear_tail_duration_seconds: u64,
+    // Emissions are frozen at this timestamp, 0 if they are running
+    // resuming shifts the emissions start by the stopped period,
+    // which is never issued
+    pub stopped_at_ts: u64,
+}
+
+// Signed change of one collateral of a trove, positive
+// amounts are deposited and negative ones withdrawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct CollateralDelta {
+    pub token: u8,
+    pub amount: i64,
 }
 
 #[account]
@@ -185,6 +282,15 @@ pub struct StabilityVaults {
 
     pub liquidation_rewards_vault_authority: Pubkey,
     pub liquidation_rewards_vault_seed: u8,
+
+    // Transferable receipts minted to the stability providers
+    pub stability_receipt_mint: Pubkey,
+    pub stability_receipt_mint_authority: Pubkey,
+    pub stability_receipt_mint_seed: u8,
+
+    // Receipt epoch stability_receipt_mint represents, a new
+    // receipt epoch needs a new mint before any receipt is minted
+    pub stability_receipt_mint_epoch: u64,
 }
 
 #[account]
@@ -222,6 +328,13 @@ pub struct UserMetadata {
     pub user_stake: u64,
     pub user_collateral_reward_per_token: TokenMap,
     pub user_stablecoin_reward_per_token: u128,
+
+    // Interest
+    // Annual rate chosen by the borrower, redemptions hit
+    // the troves paying the lowest rate first
+    pub interest_rate_bps: u16,
+    pub last_interest_accrual: u64,
+    pub last_interest_rate_change: u64,
 }
 
 #[account]
@@ -248,6 +361,59 @@ pub struct StakingPoolState {
 
     pub treasury_vault: Pubkey,
     pub treasury_fee_rate: u16,
+
+    // Redemption fees are paid in collateral, tracked
+    // per token alongside the USDH borrowing fees
+    pub collateral_reward_per_token: TokenMap,
+    pub collateral_rewards_not_yet_claimed: CollateralAmounts,
+    pub prev_collateral_reward_loss: TokenMap,
+
+    // Rewards are shared by the lock-boosted stakes,
+    // as of the last time rewards were credited
+    pub total_boosted_stake: u128,
+
+    // HBB no longer staked, waiting for the unstake cooldown
+    pub total_pending_unstake: u128,
+
+    // USDH rewards of the auto-compounding stakers are swapped
+    // for HBB, which is then shared by their boosted stakes,
+    // total_compounding_stake leaves the lock boosts out
+    pub total_compounding_stake: u128,
+    pub rewards_to_compound: u128,
+    pub compounded_hbb_per_token: u128,
+    pub prev_compounded_hbb_loss: u128,
+    pub compounded_hbb_not_yet_staked: u128,
+
+    // Treasury vault is owned by the treasury PDA, spending
+    // from it is scheduled ahead of time by the admin
+    pub treasury_vault_authority: Pubkey,
+    pub treasury_vault_seed: u8,
+    pub treasury_disbursement_amount: u64,
+    pub treasury_disbursement_recipient: Pubkey,
+    pub treasury_disbursement_executable_ts: u64,
+
+    // Staking accounts closed so far
+    pub num_closed_users: u64,
+
+    // Lock boosts decay linearly to their expiry: the boosted stake of
+    // the locks is their bias less their slope times the time, and the
+    // rewards per token are also tracked weighted by the time they were
+    // credited at, for each lock to work out what its boost earned
+    pub boost_checkpoints: Pubkey,
+    pub boost_checkpoint_ts: u64,
+    pub next_boost_expiry_ts: u64,
+    pub boost_bias: u128,
+    pub boost_slope: u128,
+    pub compounding_boost_bias: u128,
+    pub compounding_boost_slope: u128,
+    pub time_weighted_reward_per_token: u128,
+    pub time_weighted_compounded_hbb_per_token: u128,
+    pub time_weighted_collateral_reward_per_token: TokenMap,
+
+    // Compounding only buys HBB on the market set by the admin,
+    // at no more than the max price in USDH per HBB (6 decimals)
+    pub compound_dex_market: Pubkey,
+    pub compound_max_hbb_price: u64,
 }
 
 #[account]
@@ -261,6 +427,37 @@ pub struct UserStakingState {
     // User data to account for rewards
     pub user_stake: u128,
     pub rewards_tally: u128,
+
+    // Collateral rewards are settled whenever the stake changes
+    // and harvested one token at a time
+    pub collateral_rewards_tally: TokenMap,
+    pub pending_collateral_rewards: CollateralAmounts,
+
+    // Locked HBB is boosted until the lock expires, rewards
+    // are accrued on the liquid stake plus the boosted locked stake,
+    // boosted_stake is as of the last time the stake was settled
+    pub locked_stake: u128,
+    pub lock_expiry_ts: u64,
+    pub boosted_stake: u128,
+    pub pending_rewards: u128,
+
+    // Unstaked HBB is released once the cooldown has elapsed
+    pub pending_unstake: u128,
+    pub unstake_withdrawable_after_ts: u64,
+
+    // Compounded HBB is added to the liquid stake whenever the stake changes
+    pub auto_compound: bool,
+    pub compounded_hbb_tally: u128,
+
+    // The lock boost earns on the time weighted rewards per token,
+    // USDH or compounded HBB, and collateral, until the lock expires
+    pub boost_slope: u128,
+    pub boost_rewards_tally: u128,
+    pub boost_collateral_rewards_tally: TokenMap,
+
+    // A tokenized position has no owner, whoever holds
+    // the one token of position_mint redeems it
+    pub position_mint: Pubkey,
 }
 
 #[account]
@@ -276,6 +473,21 @@ pub struct StabilityProviderState {
     pub user_deposit_snapshot: DepositSnapshot,
     pub cumulative_gains_per_user: StabilityTokenMap,
     pub pending_gains_per_user: StabilityCollateralAmounts,
+
+    // Lockup
+    // due to borsh we keep the tier as a u8, see LockupTier
+    pub lockup_tier: u8,
+    pub lockup_expiry_ts: u64,
+
+    // Withdrawal requested while liquidation gains were pending,
+    // recorded at request time and paid out once the queue is drained
+    pub pending_withdrawal_stablecoin: u64,
+    pub pending_withdrawal_ts: u64,
+
+    // Receipts backing the deposit, minted, burnt and transferred
+    // through the program, only valid in the receipt epoch they were minted in
+    pub receipt_shares: u64,
+    pub receipt_epoch: u64,
 }
 
 #[zero_copy]
@@ -309,27 +521,88 @@ pub struct CandidateRedemptionUser {
     pub debt: u64,
     pub collateral_ratio: u64,
     pub filler_metadata: Pubkey,
+    pub holds_preferred_collateral: u8,
+    pub interest_rate_bps: u16,
 }
 
-#[zero_copy]
-#[derive(Debug, PartialEq, Eq)]
+#[account(zero_copy)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct RedemptionOrder {
+    pub redemptions_queue: Pubkey,
     pub id: u64,
     pub status: u8,
     pub base_rate: u16,
     pub last_reset: u64,
+    pub created_at: u64,
     pub redeemer_user_metadata: Pubkey,
     pub redeemer: Pubkey,
     pub requested_amount: u64,
     pub remaining_amount: u64,
     pub redemption_prices: TokenPrices,
+    pub candidate_pages: u64,
+    // 0 for the whole basket, otherwise the CollateralToken + 1
+    pub preferred_collateral: u8,
+    pub stakers_fee_in_usdh: u8,
+    // 0 when the redeemer set no minimum
+    pub min_collateral_value_usdh: u64,
+    // Stakers fee paid in USDH, held in the burning pot
+    // until the amount it is charged for is cleared
+    pub escrowed_fee_usdh: u64,
+}
+
+#[account(zero_copy)]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RedemptionCandidatesPage {
+    pub redemption_order: Pubkey,
+    pub page: u64,
     pub candidate_users: [CandidateRedemptionUser; 32],
 }
 
 #[account(zero_copy)]
+#[derive(Debug, Default)]
 pub struct RedemptionsQueue {
-    pub orders: [RedemptionOrder; 15],
     pub next_index: u64,
+    pub outstanding_amount: u64,
+}
+
+#[zero_copy]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SortedTrove {
+    pub user_id: u64,
+    pub user_metadata: Pubkey,
+    pub collateral_ratio_bps: u64,
+    pub interest_rate_bps: u16,
+}
+
+#[zero_copy]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BoostCheckpoint {
+    pub expiry_ts: u64,
+    pub boost_slope: u128,
+    pub compounding_boost_slope: u128,
+
+    // Time weighted rewards per token as of the expiry,
+    // what a lock expiring then earned from its boost
+    pub reward_per_token: u128,
+    pub compounded_hbb_per_token: u128,
+    pub collateral_reward_per_token: TokenMap,
+}
+
+// Staking lock expiries, one per week, each holding the slope the
+// locks expiring then take off the boosted stake once it is reached
+#[account(zero_copy)]
+pub struct BoostCheckpoints {
+    pub staking_pool_state: Pubkey,
+    pub checkpoints: [BoostCheckpoint; 512],
+}
+
+// Active troves ordered by interest rate then collateral ratio,
+// lowest first, as of the last time each of them was repositioned
+#[account(zero_copy)]
+pub struct SortedTroves {
+    pub borrowing_market_state: Pubkey,
+    pub len: u64,
+    pub troves: [SortedTrove; 1000],
 }
 
 #[zero_copy]
@@ -443,6 +716,7 @@ pub enum UserStatus {
     Inactive = 0,
     Active = 1,
     Liquidated = 2,
+    Redeemed = 3,
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
@@ -472,6 +746,49 @@ impl StabilityToken {
     }
 }
 
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum LockupTier {
+    None = 0,
+    OneMonth = 1,
+    ThreeMonths = 2,
+    SixMonths = 3,
+}
+
+impl TryFrom<u8> for LockupTier {
+    type Error = BorrowError;
+
+    fn try_from(num: u8) -> Result<Self, Self::Error> {
+        use LockupTier::*;
+        match num {
+            0 => Ok(None),
+            1 => Ok(OneMonth),
+            2 => Ok(ThreeMonths),
+            3 => Ok(SixMonths),
+            _ => Err(BorrowError::InvalidLockupTier),
+        }
+    }
+}
+
+impl LockupTier {
+    pub fn duration_seconds(&self) -> u64 {
+        match self {
+            LockupTier::None => 0,
+            LockupTier::OneMonth => LOCKUP_SECONDS_PER_MONTH,
+            LockupTier::ThreeMonths => 3 * LOCKUP_SECONDS_PER_MONTH,
+            LockupTier::SixMonths => 6 * LOCKUP_SECONDS_PER_MONTH,
+        }
+    }
+
+    pub fn hbb_boost_bps(&self) -> u16 {
+        match self {
+            LockupTier::None => LOCKUP_NONE_HBB_BOOST_BPS,
+            LockupTier::OneMonth => LOCKUP_ONE_MONTH_HBB_BOOST_BPS,
+            LockupTier::ThreeMonths => LOCKUP_THREE_MONTHS_HBB_BOOST_BPS,
+            LockupTier::SixMonths => LOCKUP_SIX_MONTHS_HBB_BOOST_BPS,
+        }
+    }
+}
+
 impl CollateralToken {
     pub fn from(num: u8) -> CollateralToken {
         use CollateralToken::*;
@@ -487,14 +804,42 @@ impl CollateralToken {
     }
 }
 
+impl TryFrom<u8> for CollateralToken {
+    type Error = BorrowError;
+
+    fn try_from(num: u8) -> Result<Self, Self::Error> {
+        match num {
+            0..=5 => Ok(CollateralToken::from(num)),
+            _ => Err(BorrowError::InvalidCollateralToken),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::CollateralToken;
+    use super::{CollateralToken, GlobalConfig};
+    use crate::BorrowError;
+    use anchor_lang::AnchorSerialize;
+    use std::convert::TryFrom;
 
     #[test]
     fn test_token_convert() {
         let btc = CollateralToken::from(2);
-        assert_eq!(btc, CollateralToken::BTC)
+        assert_eq!(btc, CollateralToken::BTC);
+
+        assert_eq!(CollateralToken::try_from(5), Ok(CollateralToken::FTT));
+        assert_eq!(
+            CollateralToken::try_from(6),
+            Err(BorrowError::InvalidCollateralToken)
+        );
+    }
+
+    #[test]
+    fn test_global_config_keeps_its_size() {
+        // version, initial_market_owner, is_borrowing_allowed,
+        // borrow_limit_usdh and the original 1024 bytes of padding
+        let size = GlobalConfig::default().try_to_vec().unwrap().len();
+        assert_eq!(size, 1 + 32 + 1 + 8 + 1024);
     }
 }
 
@@ -1,7 +1,4 @@
-use anchor_lang::prelude::Pubkey;
-
-use crate::state::RedemptionsQueue;
-use crate::RedemptionOrder;
+use crate::{CollateralToken, RedemptionOrder};
 
 pub enum RedemptionOrderStatus {
     Inactive = 0,
@@ -46,48 +43,16 @@ impl From<u8> for RedemptionOrderStatus {
     }
 }
 
-impl Default for RedemptionsQueue {
-    #[cfg(test)]
-    fn default() -> Self {
-        use crate::utils::consts::MAX_REDEMPTION_EVENTS;
-        let orders: [RedemptionOrder; MAX_REDEMPTION_EVENTS] =
-            unsafe { std::mem::MaybeUninit::zeroed().assume_init() };
-        RedemptionsQueue {
-            orders,
-            next_index: 0,
-        }
-    }
-
-    #[cfg(not(test))]
-    fn default() -> Self {
-        unimplemented!()
-    }
-}
-
 impl RedemptionOrder {
-    #[cfg(not(test))]
-    pub fn new(_id: u64, _redeemer: Pubkey) -> Self {
-        unimplemented!()
+    pub fn preferred_collateral(&self) -> Option<CollateralToken> {
+        match self.preferred_collateral {
+            0 => None,
+            token => Some(CollateralToken::from(token - 1)),
+        }
     }
 
-    #[cfg(test)]
-    pub fn new(id: u64, redeemer: Pubkey) -> Self {
-        use crate::state::{CandidateRedemptionUser, TokenPrices};
-        let candidate_users: [CandidateRedemptionUser; 32] =
-            unsafe { std::mem::MaybeUninit::zeroed().assume_init() };
-
-        RedemptionOrder {
-            last_reset: 0,
-            id,
-            redeemer,
-            base_rate: 0,
-            status: 0,
-            redeemer_user_metadata: Pubkey::default(),
-            requested_amount: 0,
-            remaining_amount: 0,
-            redemption_prices: TokenPrices::default(),
-            candidate_users,
-        }
+    pub fn set_preferred_collateral(&mut self, token: Option<CollateralToken>) {
+        self.preferred_collateral = token.map_or(0, |token| token as u8 + 1);
     }
 
     #[cfg(test)]
@@ -95,6 +60,7 @@ impl RedemptionOrder {
         format!(
             "RedemptionOrder {{
     last_reset: {},
+    created_at: {},
     id: {},
     status: {:?},
     redeemer_user_metadata: {:?},
@@ -102,10 +68,12 @@ impl RedemptionOrder {
     requested_amount: {:?},
     remaining_amount: {:?},
     redemption_prices: {:?},
-    candidate_users: {:?}
+    candidate_pages: {:?},
+    preferred_collateral: {:?}
 }}
 ",
             self.last_reset,
+            self.created_at,
             self.id,
             self.status,
             self.redeemer_user_metadata,
@@ -113,10 +81,8 @@ impl RedemptionOrder {
             self.requested_amount,
             self.remaining_amount,
             self.redemption_prices,
-            self.candidate_users
-                .iter()
-                .filter(|order| order.status == RedemptionCandidateStatus::Active as u8)
-                .count(),
+            self.candidate_pages,
+            self.preferred_collateral(),
         )
     }
 }
@@ -0,0 +1,23 @@
+use crate::SortedTroves;
+
+impl Default for SortedTroves {
+    #[cfg(not(test))]
+    fn default() -> Self {
+        unimplemented!()
+    }
+
+    #[cfg(test)]
+    #[inline(never)]
+    fn default() -> Self {
+        use crate::utils::consts::MAX_SORTED_TROVES;
+        use crate::SortedTrove;
+        let troves: [SortedTrove; MAX_SORTED_TROVES] =
+            unsafe { std::mem::MaybeUninit::zeroed().assume_init() };
+
+        SortedTroves {
+            borrowing_market_state: Default::default(),
+            len: 0,
+            troves,
+        }
+    }
+}
@@ -1,6 +1,10 @@
-use crate::{StabilityPoolState, StabilityTokenMap};
+use crate::{state::CollateralToken, StabilityPoolState, StabilityTokenMap};
 
 impl StabilityPoolState {
+    pub fn covers(&self, token: CollateralToken) -> bool {
+        self.collateral_coverage == 0 || self.collateral_coverage & (1 << token as u8) != 0
+    }
+
     pub fn new(
         num_users: u64,
         total_users_providing_stability: u64,
@@ -1,4 +1,4 @@
-use crate::{DepositSnapshot, StabilityProviderState, StabilityTokenMap};
+use crate::{DepositSnapshot, LockupTier, StabilityProviderState, StabilityTokenMap};
 
 impl StabilityProviderState {
     pub fn approve_stability(&mut self, user_id: u64) {
@@ -6,6 +6,9 @@ impl StabilityProviderState {
         self.deposited_stablecoin = 0;
         self.user_deposit_snapshot = DepositSnapshot::default();
         self.cumulative_gains_per_user = StabilityTokenMap::default();
+        self.lockup_tier = LockupTier::None as u8;
+        self.lockup_expiry_ts = 0;
+        self.receipt_shares = 0;
     }
 
     pub fn to_state_string(&self) -> String {
@@ -1,4 +1,4 @@
-use crate::StakingPoolState;
+use crate::{StakingPoolState, TokenMap};
 
 impl StakingPoolState {
     pub fn new(
@@ -10,6 +10,7 @@ impl StakingPoolState {
         StakingPoolState {
             reward_per_token,
             total_stake,
+            total_boosted_stake: total_stake,
             total_distributed_rewards,
             rewards_not_yet_claimed,
             ..Default::default()
@@ -23,7 +24,9 @@ impl StakingPoolState {
 
         // State data -- used to calculate rewards
         self.total_stake = 0;
+        self.total_boosted_stake = 0;
         self.reward_per_token = 0;
+        self.collateral_reward_per_token = TokenMap::default();
     }
 
     pub fn to_state_string(&self) -> String {
@@ -1,4 +1,6 @@
 pub mod hbb;
 pub mod soltoken;
 pub mod spltoken;
+pub mod stability_receipt;
 pub mod stablecoin;
+pub mod staking_position;
@@ -80,6 +80,25 @@ pub fn burn<'info>(
     result
 }
 
+pub fn burn_from_user<'info>(
+    amount: u64,
+    mint: &AccountInfo<'info>,
+    burn_from: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    let cpi_burn_accounts = Burn {
+        mint: mint.clone(),
+        to: burn_from.clone(),
+        authority: authority.clone(),
+    };
+    let cpi_ctx = CpiContext::new(token_program.clone(), cpi_burn_accounts);
+
+    let result = token::burn(cpi_ctx, amount);
+    msg!("Burned {:?}", result);
+    result
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn transfer_from_vault<'info>(
     amount: u64,
@@ -0,0 +1,163 @@
+use anchor_lang::{
+    prelude::{AccountInfo, ProgramResult, Pubkey},
+    solana_program::program_pack::Pack,
+    CpiContext,
+};
+use anchor_spl::token::{self, FreezeAccount, ThawAccount};
+
+use crate::{pda, token_operations::spltoken};
+
+// Receipt accounts are kept frozen by the receipt mint authority so that
+// receipts only move through the program, along with the deposit they back
+
+#[allow(clippy::too_many_arguments)]
+pub fn mint<'info>(
+    amount: u64,
+    receipt_mint_seed: u8,
+    owner: Pubkey,
+    program_id: &Pubkey,
+    receipt_mint: &AccountInfo<'info>,
+    mint_to: &AccountInfo<'info>,
+    receipt_mint_authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let pda_mode = pda::PDA::StabilityReceiptMint { owner };
+    let freeze = FreezeOperation {
+        pda_mode,
+        receipt_mint_seed,
+        program_id,
+        receipt_mint,
+        receipt_mint_authority,
+        token_program,
+    };
+
+    freeze.thaw(mint_to)?;
+    spltoken::mint(
+        receipt_mint,
+        mint_to,
+        receipt_mint_authority,
+        receipt_mint_seed,
+        pda_mode,
+        token_program,
+        program_id,
+        amount,
+    )?;
+    freeze.freeze(mint_to)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn burn<'info>(
+    amount: u64,
+    receipt_mint_seed: u8,
+    owner: Pubkey,
+    program_id: &Pubkey,
+    burn_from: &AccountInfo<'info>,
+    receipt_mint: &AccountInfo<'info>,
+    receipt_mint_authority: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let freeze = FreezeOperation {
+        pda_mode: pda::PDA::StabilityReceiptMint { owner },
+        receipt_mint_seed,
+        program_id,
+        receipt_mint,
+        receipt_mint_authority,
+        token_program,
+    };
+
+    freeze.thaw(burn_from)?;
+    spltoken::burn_from_user(amount, receipt_mint, burn_from, authority, token_program)?;
+    freeze.freeze(burn_from)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer<'info>(
+    amount: u64,
+    receipt_mint_seed: u8,
+    owner: Pubkey,
+    program_id: &Pubkey,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    receipt_mint: &AccountInfo<'info>,
+    receipt_mint_authority: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    let freeze = FreezeOperation {
+        pda_mode: pda::PDA::StabilityReceiptMint { owner },
+        receipt_mint_seed,
+        program_id,
+        receipt_mint,
+        receipt_mint_authority,
+        token_program,
+    };
+
+    freeze.thaw(from)?;
+    freeze.thaw(to)?;
+    spltoken::transfer_from_user(amount, from, to, authority, token_program)?;
+    freeze.freeze(from)?;
+    freeze.freeze(to)
+}
+
+struct FreezeOperation<'a, 'info> {
+    pda_mode: pda::PDA,
+    receipt_mint_seed: u8,
+    program_id: &'a Pubkey,
+    receipt_mint: &'a AccountInfo<'info>,
+    receipt_mint_authority: &'a AccountInfo<'info>,
+    token_program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> FreezeOperation<'a, 'info> {
+    fn freeze(&self, account: &AccountInfo<'info>) -> ProgramResult {
+        if is_frozen(account)? {
+            return Ok(());
+        }
+
+        let seed = vec![self.receipt_mint_seed];
+        let pda_seeds = pda::make_pda_seeds(&self.pda_mode, self.program_id);
+        let seeds = [pda_seeds[0].as_ref(), pda_seeds[1].as_ref(), seed.as_ref()];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = FreezeAccount {
+            account: account.clone(),
+            mint: self.receipt_mint.clone(),
+            authority: self.receipt_mint_authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.clone(), cpi_accounts).with_signer(signer);
+        token::freeze_account(cpi_ctx)
+    }
+
+    fn thaw(&self, account: &AccountInfo<'info>) -> ProgramResult {
+        if !is_frozen(account)? {
+            return Ok(());
+        }
+
+        let seed = vec![self.receipt_mint_seed];
+        let pda_seeds = pda::make_pda_seeds(&self.pda_mode, self.program_id);
+        let seeds = [pda_seeds[0].as_ref(), pda_seeds[1].as_ref(), seed.as_ref()];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = ThawAccount {
+            account: account.clone(),
+            mint: self.receipt_mint.clone(),
+            authority: self.receipt_mint_authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.clone(), cpi_accounts).with_signer(signer);
+        token::thaw_account(cpi_ctx)
+    }
+}
+
+fn is_frozen(account: &AccountInfo) -> Result<bool, anchor_lang::prelude::ProgramError> {
+    let token_account = spl_token::state::Account::unpack(&account.data.borrow())?;
+    Ok(token_account.is_frozen())
+}
@@ -26,6 +26,40 @@ pub fn mint<'info>(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn mint_accrued_interest<'info>(
+    amount_to_fees_vault: u64,
+    amount_to_treasury_vault: u64,
+    stablecoin_mint_seed: u8,
+    owner: Pubkey,
+    program_id: &Pubkey,
+    stablecoin_mint: AccountInfo<'info>,
+    borrowing_fees_vault: AccountInfo<'info>,
+    treasury_vault: AccountInfo<'info>,
+    stablecoin_mint_authority: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> ProgramResult {
+    for (amount, mint_to) in [
+        (amount_to_fees_vault, &borrowing_fees_vault),
+        (amount_to_treasury_vault, &treasury_vault),
+    ] {
+        if amount > 0 {
+            mint(
+                amount,
+                stablecoin_mint_seed,
+                owner,
+                program_id,
+                stablecoin_mint.clone(),
+                mint_to.clone(),
+                stablecoin_mint_authority.clone(),
+                token_program.clone(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn burn<'info>(
     amount: u64,
@@ -83,6 +117,30 @@ pub fn transfer_from_stability_pool<'info>(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_from_burning_vault<'info>(
+    amount: u64,
+    owner: Pubkey,
+    to_vault: &AccountInfo<'info>,
+    from_vault: &AccountInfo<'info>,
+    from_vault_authority: &AccountInfo<'info>,
+    from_vault_authority_seed: u8,
+    token_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let mode = pda::PDA::BurningPotAccount { owner };
+    spltoken::transfer_from_vault(
+        amount,
+        mode,
+        to_vault,
+        from_vault,
+        from_vault_authority,
+        from_vault_authority_seed,
+        token_program,
+        program_id,
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn transfer_from_borrowing_fees_vault<'info>(
     amount: u64,
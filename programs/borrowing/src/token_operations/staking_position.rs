@@ -0,0 +1,58 @@
+use anchor_lang::{
+    prelude::{AccountInfo, ProgramResult, Pubkey},
+    CpiContext,
+};
+use anchor_spl::token::{self, SetAuthority};
+
+use crate::{pda, token_operations::spltoken};
+
+/// Mints the one token of a staking position,
+/// then drops the mint authority so no other can be minted
+#[allow(clippy::too_many_arguments)]
+pub fn mint<'info>(
+    staking_vault_seed: u8,
+    owner: Pubkey,
+    program_id: &Pubkey,
+    position_mint: &AccountInfo<'info>,
+    mint_to: &AccountInfo<'info>,
+    staking_vault_authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    let pda_mode = pda::PDA::StakingPool { owner };
+    spltoken::mint(
+        position_mint,
+        mint_to,
+        staking_vault_authority,
+        staking_vault_seed,
+        pda_mode,
+        token_program,
+        program_id,
+        1,
+    )?;
+
+    let seed = vec![staking_vault_seed];
+    let pda_seeds = pda::make_pda_seeds(&pda_mode, program_id);
+    let seeds = [pda_seeds[0].as_ref(), pda_seeds[1].as_ref(), seed.as_ref()];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = SetAuthority {
+        account_or_mint: position_mint.clone(),
+        current_authority: staking_vault_authority.clone(),
+    };
+    let cpi_ctx = CpiContext::new(token_program.clone(), cpi_accounts).with_signer(signer);
+
+    token::set_authority(
+        cpi_ctx,
+        spl_token::instruction::AuthorityType::MintTokens,
+        None,
+    )
+}
+
+pub fn burn<'info>(
+    burn_from: &AccountInfo<'info>,
+    position_mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    spltoken::burn_from_user(1, position_mint, burn_from, authority, token_program)
+}
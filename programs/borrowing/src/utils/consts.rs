@@ -31,11 +31,12 @@ pub const REDEMPTION_CLEARER: u16 = 5; // 0.005%
 
 pub const LIQUIDATIONS_SECONDS_TO_CLAIM_GAINS: u64 = 5;
 pub const MAX_LIQUIDATION_EVENTS: usize = 300;
+pub const MAX_SORTED_TROVES: usize = 1000;
 
-// can make this bigger and run tests with RUST_MIN_STACK=8388608 cargo test
-// but we need to make this a seed-generated address and keep track of index
-pub const MAX_REDEMPTION_EVENTS: usize = 15;
+// each page holds 32 candidates, the first one is created with the order
+pub const MAX_REDEMPTION_CANDIDATE_PAGES: u64 = 4;
 pub const REDEMPTIONS_SECONDS_TO_FILL_ORDER: u64 = 5;
+pub const REDEMPTION_ORDER_EXPIRY_SECONDS: u64 = 60 * 60;
 pub const MIN_REDEMPTIONS_AMOUNT_USDH: u64 = 2000 * STABLECOIN_FACTOR;
 
 pub const LIQUIDATOR_RATE: u16 = 40; // 0.004 -> 0.4% -> bps
@@ -51,6 +52,31 @@ pub const TOTAL_HBB_SUPPLY: u64 = 100_000_000;
 pub const TOTAL_HBB_TO_STABILITY_POOL: u64 = 31_000_000;
 pub const HBB_ISSUANCE_FACTOR: u64 = 999998681227695000;
 
+// Stability deposits lockups, boosts are applied to the HBB share of the deposit
+pub const LOCKUP_SECONDS_PER_MONTH: u64 = 30 * 24 * 60 * 60;
+pub const LOCKUP_NONE_HBB_BOOST_BPS: u16 = 10_000; // 1x
+pub const LOCKUP_ONE_MONTH_HBB_BOOST_BPS: u16 = 12_500; // 1.25x
+pub const LOCKUP_THREE_MONTHS_HBB_BOOST_BPS: u16 = 15_000; // 1.5x
+pub const LOCKUP_SIX_MONTHS_HBB_BOOST_BPS: u16 = 20_000; // 2x
+
+// Staked HBB locks, the boost decays linearly to 1x at the lock expiry
+pub const MAX_HBB_LOCK_SECONDS: u64 = 4 * SECONDS_PER_YEAR;
+pub const MAX_HBB_LOCK_BOOST_BPS: u16 = 25_000; // 2.5x
+                                                // Lock expiries are rounded down to whole weeks, the expiries
+                                                // of the last weeks are kept to settle the locks left behind
+pub const HBB_LOCK_EXPIRY_SECONDS: u64 = 7 * 24 * 60 * 60;
+pub const MAX_BOOST_CHECKPOINTS: usize = 512;
+
+pub const MAX_STABILITY_RECEIPT_RATE: u64 = 1_000; // receipts per stablecoin unit
+
+pub const UNSTAKE_COOLDOWN_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+pub const MAX_TREASURY_FEE_RATE: u16 = 5_000; // 5_000 bps, 50% of the fees
+pub const TREASURY_DISBURSEMENT_TIMELOCK_SECONDS: u64 = 2 * 24 * 60 * 60;
+
+pub const ALL_COLLATERAL_COVERAGE: u8 = 0b0011_1111; // one bit per CollateralToken
+pub const MAX_STABILITY_POOLS: usize = 4; // main pool and tranches of a market
+
 pub const BORROW_MIN: u64 = 200_000_000;
 
 /*
@@ -62,6 +88,8 @@ pub const REDEMPTION_FEE_FLOOR: u16 = 50; // 50 bps, 0.5%
 pub const MAX_REDEMPTION_FEE: u16 = 10000; // 10_000 bps, 100%
 pub const MAX_BORROWING_FEE: u16 = 500; // 500 bps, 5%
 pub const BORROWING_FEE_FLOOR: u16 = 50; // 50 bps, 0.5%
+pub const MAX_INTEREST_RATE: u16 = 2500; // 2_500 bps, 25% a year
+pub const INTEREST_RATE_ADJUSTMENT_COOLDOWN: u64 = 7 * 24 * 60 * 60; // 7 days
 pub const BOOTSTRAP_PERIOD: u64 = 0; // 14 days
                                      // pub const BOOTSTRAP_PERIOD: u64 = 14 * 24 * 60 * 60; // 14 days
 
@@ -10,6 +10,8 @@ pub enum PDA {
     HbbMint { owner: Pubkey },
     CollateralVault { owner: Pubkey },
     LiquidationsVault { owner: Pubkey },
+    StabilityReceiptMint { owner: Pubkey },
+    Treasury { owner: Pubkey },
 }
 
 impl PDA {
@@ -35,6 +37,10 @@ pub const STAKING_POOL_TAG: &str = "stpa";
 pub const HBB_MINT_TAG: &str = "hma";
 pub const COLL_VAULT_TAG: &str = "colv";
 pub const LIQ_VAULT_TAG: &str = "liqv";
+pub const STABILITY_RECEIPT_MINT_TAG: &str = "srma";
+pub const TREASURY_TAG: &str = "trsv";
+pub const REDEMPTION_ORDER_TAG: &str = "rdo";
+pub const REDEMPTION_CANDIDATES_PAGE_TAG: &str = "rdcp";
 
 pub fn make_pda_pubkey(mode: PDA, program: &Pubkey) -> PdaAddress {
     match &mode {
@@ -46,6 +52,8 @@ pub fn make_pda_pubkey(mode: PDA, program: &Pubkey) -> PdaAddress {
         PDA::HbbMint { owner } => make_pda(owner, HBB_MINT_TAG, program),
         PDA::CollateralVault { owner } => make_pda(owner, COLL_VAULT_TAG, program),
         PDA::LiquidationsVault { owner } => make_pda(owner, LIQ_VAULT_TAG, program),
+        PDA::StabilityReceiptMint { owner } => make_pda(owner, STABILITY_RECEIPT_MINT_TAG, program),
+        PDA::Treasury { owner } => make_pda(owner, TREASURY_TAG, program),
     }
 }
 
@@ -59,6 +67,8 @@ pub fn make_pda_seeds<'a>(mode: &'a PDA, _program: &'a Pubkey) -> [Vec<u8>; 2] {
         PDA::HbbMint { owner } => make_seeds(owner, HBB_MINT_TAG),
         PDA::CollateralVault { owner } => make_seeds(owner, COLL_VAULT_TAG),
         PDA::LiquidationsVault { owner } => make_seeds(owner, LIQ_VAULT_TAG),
+        PDA::StabilityReceiptMint { owner } => make_seeds(owner, STABILITY_RECEIPT_MINT_TAG),
+        PDA::Treasury { owner } => make_seeds(owner, TREASURY_TAG),
     }
 }
 
@@ -4,7 +4,7 @@ import { TokenInstructions } from "@project-serum/serum";
 import { CollateralToken, collateralTokenToNumber, StabilityToken, stabilityTokenToNumber } from '../tests/types';
 import { BorrowingGlobalAccounts, LiquidatorAccounts, PythPrices, StabilityPoolAccounts, StabilityProviderAccounts } from './set_up';
 import { getBorrowingMarketState, getStabilityVaults, getBorrowingVaults, getStakingPoolState, getGlobalConfig } from "../tests/data_provider";
-import { mapAnchorError } from "./utils";
+import { findAssociatedTokenAddress, mapAnchorError } from "./utils";
 import { GlobalConfigOption } from "./config";
 
 export async function initializeBorrowingMarket(
@@ -21,6 +21,7 @@ export async function initializeBorrowingMarket(
             borrowingVaults: borrowingVaults.publicKey,
             globalConfig: globalConfig.publicKey,
             redemptionsQueue: borrowingGlobalAccounts.redemptionsQueue,
+            sortedTroves: borrowingGlobalAccounts.sortedTroves,
             borrowingFeesVault: borrowingGlobalAccounts.borrowingFeesVault,
             burningVault: borrowingGlobalAccounts.burningVault,
             collateralVaultSol: borrowingGlobalAccounts.collateralVaultSol,
@@ -116,6 +117,9 @@ export async function provideStability(
     amount: number,
     signers: Array<Signer>) {
 
+    const { stabilityReceiptMint, stabilityReceiptMintAuthority } = await getStabilityVaults(program, stabilityVaults);
+    const stabilityReceiptAta = await findAssociatedTokenAddress(owner, stabilityReceiptMint);
+
     const tx = await mapAnchorError(program.rpc.stabilityProvide(
         new anchor.BN(amount), {
         accounts: getProvideStabilityAccounts(
@@ -126,7 +130,10 @@ export async function provideStability(
             stabilityVaults,
             epochToScaleToSum,
             stablecoinStabilityPoolVault,
-            stablecoinAta
+            stablecoinAta,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta
         ),
         signers
     }));
@@ -141,7 +148,10 @@ export function getProvideStabilityAccounts(
     stabilityVaults: PublicKey,
     epochToScaleToSum: PublicKey,
     stablecoinStabilityPoolVault: PublicKey,
-    stablecoinAta: PublicKey
+    stablecoinAta: PublicKey,
+    stabilityReceiptMint: PublicKey,
+    stabilityReceiptMintAuthority: PublicKey,
+    stabilityReceiptAta: PublicKey
 ): any {
     return {
         owner,
@@ -152,6 +162,9 @@ export function getProvideStabilityAccounts(
         epochToScaleToSum,
         stablecoinStabilityPoolVault,
         stablecoinAta,
+        stabilityReceiptMint,
+        stabilityReceiptMintAuthority,
+        stabilityReceiptAta,
         tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
     };
@@ -170,7 +183,12 @@ export async function withdrawStability(
     amount: number,
     signers: Array<Signer>) {
 
-    const { stablecoinStabilityPoolVaultAuthority } = await getStabilityVaults(program, stabilityVaults);
+    const {
+        stablecoinStabilityPoolVaultAuthority,
+        stabilityReceiptMint,
+        stabilityReceiptMintAuthority
+    } = await getStabilityVaults(program, stabilityVaults);
+    const stabilityReceiptAta = await findAssociatedTokenAddress(owner, stabilityReceiptMint);
 
     const tx = await mapAnchorError(program.rpc.stabilityWithdraw(
         new anchor.BN(amount), {
@@ -182,7 +200,10 @@ export async function withdrawStability(
             epochToScaleToSum,
             stablecoinStabilityPoolVault,
             stablecoinStabilityPoolVaultAuthority,
-            stablecoinAta
+            stablecoinAta,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta
         ),
         signers
     }));
@@ -198,7 +219,10 @@ export function getWithdrawStabilityAccounts(
     epochToScaleToSum: PublicKey,
     stablecoinStabilityPoolVault: PublicKey,
     stablecoinStabilityPoolVaultAuthority: PublicKey,
-    stablecoinAta: PublicKey
+    stablecoinAta: PublicKey,
+    stabilityReceiptMint: PublicKey,
+    stabilityReceiptMintAuthority: PublicKey,
+    stabilityReceiptAta: PublicKey
 ): any {
 
     return {
@@ -211,6 +235,9 @@ export function getWithdrawStabilityAccounts(
         stablecoinStabilityPoolVault,
         stablecoinStabilityPoolVaultAuthority,
         stablecoinAta,
+        stabilityReceiptMint,
+        stabilityReceiptMintAuthority,
+        stabilityReceiptAta,
         tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
     };
@@ -224,10 +251,14 @@ export async function depositCollateral(
     collateralFrom: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
+    pythPrices: PythPrices,
     amount: number,
     signers: Array<Signer>,
     token: CollateralToken = "SOL") {
 
+    const interestAccounts = await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
+
     const tx = await mapAnchorError(program.rpc.depositCollateral(
         new anchor.BN(amount), new anchor.BN(collateralTokenToNumber(token)),
         {
@@ -235,11 +266,20 @@ export async function depositCollateral(
                 owner,
                 borrowingMarketState,
                 borrowingVaults,
+                stakingPoolState,
                 userMetadata,
                 collateralFrom,
                 collateralTo: collateralVaultTo,
+                ...interestAccounts,
+                pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
+                pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
+                pythEthPriceInfo: pythPrices.ethPythPrice.publicKey,
+                pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
+                pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
+                pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
                 systemProgram: anchor.web3.SystemProgram.programId,
                 tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
             },
             signers
         }
@@ -262,7 +302,7 @@ export async function borrowStablecoin(
     pythPrices: PythPrices,
     amount: number,
     signers: Array<Signer>) {
-    const { stablecoinMintAuthority } = await getBorrowingMarketState(program, borrowingMarketState);
+    const { stablecoinMintAuthority, sortedTroves } = await getBorrowingMarketState(program, borrowingMarketState);
 
     const tx = await mapAnchorError(program.rpc.borrowStablecoin(
         new anchor.BN(amount),
@@ -270,6 +310,7 @@ export async function borrowStablecoin(
             accounts: utils.getBorrowStablecoinAccounts(
                 owner,
                 userMetadata,
+                sortedTroves,
                 stablecoinMint,
                 stablecoinMintAuthority,
                 stablecoinBorrowingAssociatedAccount,
@@ -306,7 +347,7 @@ export async function depositAndBorrow(
     borrowAmount: number,
     signers: Array<Signer>,
 ) {
-    const { stablecoinMintAuthority } = await getBorrowingMarketState(program, borrowingMarketState);
+    const { stablecoinMintAuthority, sortedTroves } = await getBorrowingMarketState(program, borrowingMarketState);
 
     const tx = await mapAnchorError(program.rpc.depositCollateralAndBorrowStablecoin(
         new anchor.BN(depositAmount),
@@ -316,6 +357,7 @@ export async function depositAndBorrow(
             accounts: utils.getDepositAndBorrowAccounts(
                 owner,
                 userMetadata,
+                sortedTroves,
                 stablecoinMint,
                 stablecoinMintAuthority,
                 stablecoinBorrowingAssociatedAccount,
@@ -343,12 +385,14 @@ export async function repayLoan(
     stablecoinBorrowingAssociatedAccount: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
     burningVault: PublicKey,
     pythPrices: PythPrices,
     amount: number,
     signers: Array<Signer>) {
 
-    const { stablecoinMintAuthority } = await getBorrowingMarketState(program, borrowingMarketState);
+    const { stablecoinMintAuthority, sortedTroves, borrowingFeesVault, treasuryVault } =
+        await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
     const { burningVaultAuthority } = await getBorrowingVaults(program, borrowingVaults);
 
     const tx = await mapAnchorError(program.rpc.repayLoan(new anchor.BN(amount), {
@@ -357,11 +401,16 @@ export async function repayLoan(
             userMetadata,
             borrowingMarketState,
             borrowingVaults,
+            stakingPoolState,
+            sortedTroves,
             stablecoinMint,
             stablecoinMintAuthority,
             burningVault,
             burningVaultAuthority,
+            borrowingFeesVault,
+            treasuryVault,
             stablecoinBorrowingAssociatedAccount,
+            pythPrices,
         ),
         signers
     }));
@@ -374,6 +423,7 @@ export async function withdrawCollateral(
     owner: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
     userMetadata: PublicKey,
     collateralFrom: PublicKey,
     collateralTo: PublicKey,
@@ -383,6 +433,7 @@ export async function withdrawCollateral(
     token: CollateralToken = "SOL") {
 
     const { collateralVaultsAuthority } = await getBorrowingVaults(program, borrowingVaults);
+    const interestAccounts = await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
 
     console.log("Withdrawing collateralFrom", collateralFrom.toString());
     console.log("Withdrawing collateralFromAuth", collateralVaultsAuthority.toString());
@@ -395,10 +446,12 @@ export async function withdrawCollateral(
                 owner,
                 borrowingMarketState,
                 borrowingVaults,
+                stakingPoolState,
                 userMetadata,
                 collateralFrom,
                 collateralFromAuthority: collateralVaultsAuthority,
                 collateralTo,
+                ...interestAccounts,
                 tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
                 systemProgram: anchor.web3.SystemProgram.programId,
                 pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
@@ -407,6 +460,7 @@ export async function withdrawCollateral(
                 pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
                 pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
                 pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
             },
             signers
         }
@@ -491,12 +545,14 @@ export async function tryLiquidate(
     stabilityVaults: PublicKey,
     borrowingVaults: PublicKey,
     liquidationsQueue: PublicKey,
+    stakingPoolState: PublicKey,
     stablecoinMint: PublicKey,
     stablecoinStabilityPoolVault: PublicKey,
     pythPrices: PythPrices,
     signers: Array<Signer>
 ) {
-    const { stablecoinMintAuthority } = await getBorrowingMarketState(program, borrowingMarketState);
+    const { stablecoinMintAuthority, sortedTroves, borrowingFeesVault, treasuryVault } =
+        await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
 
     const { stablecoinStabilityPoolVaultAuthority } = await getStabilityVaults(program, stabilityVaults);
 
@@ -506,12 +562,16 @@ export async function tryLiquidate(
             borrowingMarketState,
             stabilityPoolState,
             userMetadata,
+            sortedTroves,
             epochToScaleToSum,
             stabilityVaults,
             borrowingVaults,
             liquidationsQueue,
+            stakingPoolState,
             stablecoinMint,
             stablecoinMintAuthority,
+            borrowingFeesVault,
+            treasuryVault,
             stablecoinStabilityPoolVault,
             stablecoinStabilityPoolVaultAuthority,
             pythPrices
@@ -521,6 +581,33 @@ export async function tryLiquidate(
     console.log('tryLiquidate done signature:', tx);
 }
 
+// Accounts an instruction touching troves needs on top of its own: the sorted
+// troves they are repositioned in and where the interest they accrued is minted
+export async function getAccruedInterestAccounts(
+    program: anchor.Program,
+    borrowingMarketState: PublicKey,
+    borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
+): Promise<{
+    sortedTroves: PublicKey,
+    stablecoinMint: PublicKey,
+    stablecoinMintAuthority: PublicKey,
+    borrowingFeesVault: PublicKey,
+    treasuryVault: PublicKey,
+}> {
+    const { sortedTroves, stablecoinMint, stablecoinMintAuthority } = await getBorrowingMarketState(program, borrowingMarketState);
+    const { borrowingFeesVault } = await getBorrowingVaults(program, borrowingVaults);
+    const { treasuryVault } = await getStakingPoolState(program, stakingPoolState);
+
+    return {
+        sortedTroves,
+        stablecoinMint,
+        stablecoinMintAuthority,
+        borrowingFeesVault,
+        treasuryVault,
+    };
+}
+
 
 export namespace utils {
 
@@ -529,29 +616,46 @@ export namespace utils {
         userMetadata: PublicKey,
         borrowingMarketState: PublicKey,
         borrowingVaults: PublicKey,
+        stakingPoolState: PublicKey,
+        sortedTroves: PublicKey,
         stablecoinMint: PublicKey,
         stablecoinMintAuthority: PublicKey,
         burningVault: PublicKey,
         burningVaultAuthority: PublicKey,
+        borrowingFeesVault: PublicKey,
+        treasuryVault: PublicKey,
         stablecoinBorrowingAssociatedAccount: PublicKey,
+        pythPrices: PythPrices,
     ): any {
         return {
             owner,
             userMetadata,
             borrowingMarketState,
             borrowingVaults,
+            stakingPoolState,
+            sortedTroves,
             stablecoinMint,
             stablecoinMintAuthority,
             burningVault,
             burningVaultAuthority,
+            borrowingFeesVault,
+            treasuryVault,
             stablecoinBorrowingAssociatedAccount,
+            pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
+            pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
+            pythEthPriceInfo: pythPrices.ethPythPrice.publicKey,
+            pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
+            pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
+            pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         };
     }
 
     export function getBorrowStablecoinAccounts(
         owner: PublicKey,
         userMetadata: PublicKey,
+        sortedTroves: PublicKey,
         stablecoinMint: PublicKey,
         stablecoinMintAuthority: PublicKey,
         stablecoinBorrowingAssociatedAccount: PublicKey,
@@ -567,6 +671,7 @@ export namespace utils {
             borrowingVaults,
             stakingPoolState,
             userMetadata,
+            sortedTroves,
             stablecoinMint,
             stablecoinMintAuthority,
             stablecoinBorrowingAssociatedAccount,
@@ -586,6 +691,7 @@ export namespace utils {
     export function getDepositAndBorrowAccounts(
         owner: PublicKey,
         userMetadata: PublicKey,
+        sortedTroves: PublicKey,
         stablecoinMint: PublicKey,
         stablecoinMintAuthority: PublicKey,
         stablecoinBorrowingAssociatedAccount: PublicKey,
@@ -603,6 +709,7 @@ export namespace utils {
             borrowingVaults,
             stakingPoolState,
             userMetadata,
+            sortedTroves,
             stablecoinMint,
             stablecoinMintAuthority,
             collateralFrom,
@@ -627,12 +734,16 @@ export namespace utils {
         borrowingMarketState: PublicKey,
         stabilityPoolState: PublicKey,
         userMetadata: PublicKey,
+        sortedTroves: PublicKey,
         epochToScaleToSum: PublicKey,
         stabilityVaults: PublicKey,
         borrowingVaults: PublicKey,
         liquidationsQueue: PublicKey,
+        stakingPoolState: PublicKey,
         stablecoinMint: PublicKey,
         stablecoinMintAuthority: PublicKey,
+        borrowingFeesVault: PublicKey,
+        treasuryVault: PublicKey,
         stablecoinStabilityPoolVault: PublicKey,
         stablecoinStabilityPoolVaultAuthority: PublicKey,
         pythPrices: PythPrices
@@ -642,12 +753,16 @@ export namespace utils {
             borrowingMarketState,
             stabilityPoolState,
             userMetadata,
+            sortedTroves,
             epochToScaleToSum,
             stabilityVaults,
             borrowingVaults,
             liquidationsQueue,
+            stakingPoolState,
             stablecoinMint,
             stablecoinMintAuthority,
+            borrowingFeesVault,
+            treasuryVault,
             stablecoinStabilityPoolVault,
             stablecoinStabilityPoolVaultAuthority,
             pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
@@ -2,10 +2,14 @@ import * as anchor from "@project-serum/anchor";
 import { Keypair, PublicKey, Transaction, TransactionInstruction, TransactionSignature } from "@solana/web3.js";
 import * as utils from "./utils";
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
-import { PythPrices } from "./set_up";
-import { getBorrowingVaults } from "../tests/data_provider";
+import { BorrowingUserAccounts, PythPrices } from "./set_up";
+import { getBorrowingVaults, getUserMetadata } from "../tests/data_provider";
 import { CLEAR_INST_METADATA_ACCS_SIZE, FILL_INST_METADATA_ACCS_SIZE } from "../tests/tests_redemption";
 import { mapAnchorError, publicKeyReplacer } from "./utils";
+import { getAccruedInterestAccounts } from "./instructions_borrow";
+
+// The whole amount may be taken as fee
+export const REDEMPTION_MAX_FEE_BPS: number = 10000;
 
 export async function addRedemptionOrder(
     program: anchor.Program,
@@ -14,19 +18,31 @@ export async function addRedemptionOrder(
     redeemerStablecoinAssociatedAccount: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    globalConfig: PublicKey,
     redemptionsQueue: PublicKey,
     burningVault: PublicKey,
     pythPrices: PythPrices,
     redeemStablecoin: number,
+    maxFeeBps: number = REDEMPTION_MAX_FEE_BPS,
+    minCollateralValueUsdh: number | null = null,
+    preferredCollateral: number | null = null,
 ): Promise<TransactionSignature> {
 
+    // The order takes the next id of the queue, its first candidates page is created along with it
+    const { nextIndex }: any = await program.account.redemptionsQueue.fetch(redemptionsQueue);
+    const redemptionOrder = await utils.findRedemptionOrderAddress(redemptionsQueue, nextIndex.toNumber(), program.programId);
+    const candidatesPage = await utils.findRedemptionCandidatesPageAddress(redemptionOrder, 0, program.programId);
+
     const accounts = getAddRedemptionOrderAccounts(
         redeemer,
         redeemerMetadata,
         redeemerStablecoinAssociatedAccount,
         borrowingMarketState,
         borrowingVaults,
+        globalConfig,
         redemptionsQueue,
+        redemptionOrder,
+        candidatesPage,
         burningVault,
         pythPrices
     );
@@ -34,7 +50,10 @@ export async function addRedemptionOrder(
     console.log(`Adding redemption order for ${utils.u64ToDecimal(redeemStablecoin)} stablecoin...\n${JSON.stringify(accounts, publicKeyReplacer, 2)}`);
 
     const txid = await mapAnchorError(program.rpc.addRedemptionOrder(
-        new anchor.BN(redeemStablecoin), {
+        new anchor.BN(redeemStablecoin),
+        maxFeeBps,
+        minCollateralValueUsdh === null ? null : new anchor.BN(minCollateralValueUsdh),
+        preferredCollateral, {
         accounts: accounts,
         signers: [redeemer]
     }));
@@ -44,14 +63,17 @@ export async function addRedemptionOrder(
     return txid;
 }
 
-export function getAddRedemptionOrderAccounts(redeemer: Keypair, redeemerMetadata: PublicKey, redeemerStablecoinAssociatedAccount: PublicKey, borrowingMarketState: PublicKey, borrowingVaults: PublicKey, redemptionsQueue: PublicKey, burningVault: PublicKey, pythPrices: PythPrices) {
+export function getAddRedemptionOrderAccounts(redeemer: Keypair, redeemerMetadata: PublicKey, redeemerStablecoinAssociatedAccount: PublicKey, borrowingMarketState: PublicKey, borrowingVaults: PublicKey, globalConfig: PublicKey, redemptionsQueue: PublicKey, redemptionOrder: PublicKey, candidatesPage: PublicKey, burningVault: PublicKey, pythPrices: PythPrices) {
     return {
         redeemer: redeemer.publicKey,
         redeemerMetadata,
         redeemerStablecoinAssociatedAccount,
         borrowingMarketState,
         borrowingVaults,
+        globalConfig,
         redemptionsQueue,
+        redemptionOrder,
+        candidatesPage,
         burningVault,
         pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
         pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
@@ -66,20 +88,58 @@ export function getAddRedemptionOrderAccounts(redeemer: Keypair, redeemerMetadat
     };
 }
 
+export async function addRedemptionCandidatesPage(
+    program: anchor.Program,
+    payer: Keypair,
+    redemptionsQueue: PublicKey,
+    orderId: number,
+): Promise<TransactionSignature> {
+
+    const redemptionOrder = await utils.findRedemptionOrderAddress(redemptionsQueue, orderId, program.programId);
+    const { candidatePages }: any = await program.account.redemptionOrder.fetch(redemptionOrder);
+    const candidatesPage = await utils.findRedemptionCandidatesPageAddress(redemptionOrder, candidatePages.toNumber(), program.programId);
+
+    console.log(`Adding candidates page ${candidatePages.toNumber()} to redemption order ${orderId}...`);
+
+    const txid: TransactionSignature = await mapAnchorError(program.rpc.addRedemptionCandidatesPage({
+        accounts: {
+            payer: payer.publicKey,
+            redemptionOrder,
+            candidatesPage,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        signers: [payer]
+    }));
+
+    console.log(`Add redemption candidates page transaction signature: ${txid}`);
+
+    return txid;
+}
+
 export async function fillRedemptionOrder(
     program: anchor.Program,
     filler: Keypair,
     fillerMetadata: PublicKey,
     borrowingMarketState: PublicKey,
+    borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
     redemptionsQueue: PublicKey,
     orderId: number,
     candidateMetadatas: PublicKey[],
 ): Promise<TransactionSignature> {
 
     console.log('Filling redemption order...');
+    const redemptionOrder = await utils.findRedemptionOrderAddress(redemptionsQueue, orderId, program.programId);
+    const pageAccounts = await getCandidatesPageAccounts(program, redemptionOrder);
     let metadataAccounts: any = getMetadataAccounts(candidateMetadatas, FILL_INST_METADATA_ACCS_SIZE);
 
-    console.log()
+    const {
+        stablecoinMint,
+        stablecoinMintAuthority,
+        borrowingFeesVault,
+        treasuryVault
+    } = await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
 
     const txid: TransactionSignature = await mapAnchorError(program.rpc.fillRedemptionOrder(
         new anchor.BN(orderId), {
@@ -87,9 +147,16 @@ export async function fillRedemptionOrder(
             filler,
             fillerMetadata,
             borrowingMarketState,
-            redemptionsQueue
+            redemptionsQueue,
+            redemptionOrder,
+            borrowingVaults,
+            stakingPoolState,
+            stablecoinMint,
+            stablecoinMintAuthority,
+            borrowingFeesVault,
+            treasuryVault
         ),
-        remainingAccounts: metadataAccounts,
+        remainingAccounts: [...pageAccounts, ...metadataAccounts],
         signers: [filler]
     }));
 
@@ -102,13 +169,28 @@ export function getFillRedemptionOrderAccounts(
     filler: Keypair,
     fillerMetadata: PublicKey,
     borrowingMarketState: PublicKey,
-    redemptionsQueue: PublicKey
+    redemptionsQueue: PublicKey,
+    redemptionOrder: PublicKey,
+    borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
+    stablecoinMint: PublicKey,
+    stablecoinMintAuthority: PublicKey,
+    borrowingFeesVault: PublicKey,
+    treasuryVault: PublicKey,
 ) {
     return {
         filler: filler.publicKey,
         fillerMetadata,
         borrowingMarketState,
         redemptionsQueue,
+        redemptionOrder,
+        borrowingVaults,
+        stakingPoolState,
+        stablecoinMint,
+        stablecoinMintAuthority,
+        borrowingFeesVault,
+        treasuryVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
     };
 }
@@ -120,18 +202,34 @@ export async function clearRedemptionOrder(
     redeemerMetadata: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
     redemptionsQueue: PublicKey,
     burningVault: PublicKey,
     stablecoinMint: PublicKey,
+    pythPrices: PythPrices,
     orderId: number,
     borrowerAndFillerMetadatas: PublicKey[],
+    closedTroves: PublicKey[] = [],
 ): Promise<TransactionSignature> {
 
     console.log('Clearing redemption order...');
 
     const { burningVaultAuthority } = await getBorrowingVaults(program, borrowingVaults);
+    const {
+        sortedTroves,
+        stablecoinMintAuthority,
+        borrowingFeesVault,
+        treasuryVault
+    } = await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
+
+    // The redeemer gets back the rent of the order, and its fee if the collateral falls short
+    const { owner: redeemer, stablecoinAta: redeemerStablecoinAssociatedAccount } = await getUserMetadata(program, redeemerMetadata);
 
+    const redemptionOrder = await utils.findRedemptionOrderAddress(redemptionsQueue, orderId, program.programId);
+    const pageAccounts = await getCandidatesPageAccounts(program, redemptionOrder);
     let metadataAccounts: any = getMetadataAccounts(borrowerAndFillerMetadatas, CLEAR_INST_METADATA_ACCS_SIZE);
+    // Candidates whose trove was closed since the fill, their metadata no longer exists
+    let closedAccounts: any = getMetadataAccounts(closedTroves, closedTroves.length);
 
     const txid: TransactionSignature = await mapAnchorError(program.rpc.clearRedemptionOrder(
         new anchor.BN(orderId), {
@@ -139,14 +237,23 @@ export async function clearRedemptionOrder(
             clearer,
             clearerMetadata,
             redeemerMetadata,
+            redeemer,
+            redeemerStablecoinAssociatedAccount,
             borrowingMarketState,
             borrowingVaults,
+            stakingPoolState,
             redemptionsQueue,
+            redemptionOrder,
+            sortedTroves,
             burningVault,
             burningVaultAuthority,
-            stablecoinMint
+            borrowingFeesVault,
+            treasuryVault,
+            stablecoinMint,
+            stablecoinMintAuthority,
+            pythPrices
         ),
-        remainingAccounts: metadataAccounts,
+        remainingAccounts: [...pageAccounts, ...metadataAccounts, ...closedAccounts],
         signers: [clearer]
     }));
 
@@ -159,28 +266,189 @@ export function getClearRedemptionOrderAccounts(
     clearer: Keypair,
     clearerMetadata: PublicKey,
     redeemerMetadata: PublicKey,
+    redeemer: PublicKey,
+    redeemerStablecoinAssociatedAccount: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
     redemptionsQueue: PublicKey,
+    redemptionOrder: PublicKey,
+    sortedTroves: PublicKey,
     burningVault: PublicKey,
     burningVaultAuthority: PublicKey,
+    borrowingFeesVault: PublicKey,
+    treasuryVault: PublicKey,
     stablecoinMint: PublicKey,
+    stablecoinMintAuthority: PublicKey,
+    pythPrices: PythPrices,
 ): any {
     return {
         clearer: clearer.publicKey,
         clearerMetadata,
         redeemerMetadata,
+        redeemer,
+        redeemerStablecoinAssociatedAccount,
         borrowingMarketState,
         borrowingVaults,
+        stakingPoolState,
         redemptionsQueue,
+        redemptionOrder,
+        sortedTroves,
         burningVault,
         burningVaultAuthority,
+        borrowingFeesVault,
+        treasuryVault,
         stablecoinMint,
+        stablecoinMintAuthority,
+        pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
+        pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
+        pythEthPriceInfo: pythPrices.ethPythPrice.publicKey,
+        pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
+        pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
+        pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         tokenProgram: TOKEN_PROGRAM_ID,
     }
 }
 
+export async function cancelRedemptionOrder(
+    program: anchor.Program,
+    redeemer: Keypair,
+    redeemerMetadata: PublicKey,
+    redeemerStablecoinAssociatedAccount: PublicKey,
+    borrowingMarketState: PublicKey,
+    borrowingVaults: PublicKey,
+    globalConfig: PublicKey,
+    redemptionsQueue: PublicKey,
+    orderId: number,
+): Promise<TransactionSignature> {
+
+    console.log('Cancelling redemption order...');
+
+    const { burningVault, burningVaultAuthority } = await getBorrowingVaults(program, borrowingVaults);
+    const redemptionOrder = await utils.findRedemptionOrderAddress(redemptionsQueue, orderId, program.programId);
+    const pageAccounts = await getCandidatesPageAccounts(program, redemptionOrder);
+
+    const txid: TransactionSignature = await mapAnchorError(program.rpc.cancelRedemptionOrder(
+        new anchor.BN(orderId), {
+        accounts: {
+            redeemer: redeemer.publicKey,
+            redeemerMetadata,
+            redeemerStablecoinAssociatedAccount,
+            borrowingMarketState,
+            borrowingVaults,
+            globalConfig,
+            redemptionsQueue,
+            redemptionOrder,
+            burningVault,
+            burningVaultAuthority,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        },
+        remainingAccounts: pageAccounts,
+        signers: [redeemer]
+    }));
+
+    console.log(`Cancel redemption order transaction signature: ${txid}`);
+
+    return txid;
+}
+
+export async function instantRedeem(
+    program: anchor.Program,
+    redeemer: Keypair,
+    redeemerAccounts: BorrowingUserAccounts,
+    borrowingMarketState: PublicKey,
+    borrowingVaults: PublicKey,
+    globalConfig: PublicKey,
+    stakingPoolState: PublicKey,
+    redemptionsQueue: PublicKey,
+    pythPrices: PythPrices,
+    redeemStablecoin: number,
+    borrowerMetadatas: PublicKey[],
+    maxFeeBps: number = REDEMPTION_MAX_FEE_BPS,
+    minCollateralValueUsdh: number | null = null,
+    preferredCollateral: number | null = null,
+): Promise<TransactionSignature> {
+
+    console.log(`Instantly redeeming ${utils.u64ToDecimal(redeemStablecoin)} stablecoin...`);
+
+    const vaults = await getBorrowingVaults(program, borrowingVaults);
+    const {
+        sortedTroves,
+        stablecoinMint,
+        stablecoinMintAuthority,
+        borrowingFeesVault,
+        treasuryVault
+    } = await getAccruedInterestAccounts(program, borrowingMarketState, borrowingVaults, stakingPoolState);
+
+    // Borrowers are redeemed against in the order of the sorted troves
+    let metadataAccounts: any = getMetadataAccounts(borrowerMetadatas, borrowerMetadatas.length);
+
+    const txid: TransactionSignature = await mapAnchorError(program.rpc.instantRedeem(
+        new anchor.BN(redeemStablecoin),
+        maxFeeBps,
+        minCollateralValueUsdh === null ? null : new anchor.BN(minCollateralValueUsdh),
+        preferredCollateral, {
+        accounts: {
+            redeemer: redeemer.publicKey,
+            redeemerMetadata: redeemerAccounts.userMetadata.publicKey,
+            redeemerStablecoinAssociatedAccount: redeemerAccounts.stablecoinAta,
+            borrowingMarketState,
+            borrowingVaults,
+            globalConfig,
+            collateralVaultSol: vaults.collateralVaultSol,
+            collateralVaultSrm: vaults.collateralVaultSrm,
+            collateralVaultEth: vaults.collateralVaultEth,
+            collateralVaultBtc: vaults.collateralVaultBtc,
+            collateralVaultRay: vaults.collateralVaultRay,
+            collateralVaultFtt: vaults.collateralVaultFtt,
+            collateralVaultsAuthority: vaults.collateralVaultsAuthority,
+            redeemerSrmAta: redeemerAccounts.srmAta,
+            redeemerEthAta: redeemerAccounts.ethAta,
+            redeemerBtcAta: redeemerAccounts.btcAta,
+            redeemerRayAta: redeemerAccounts.rayAta,
+            redeemerFttAta: redeemerAccounts.fttAta,
+            stakingPoolState,
+            redemptionsQueue,
+            sortedTroves,
+            burningVault: vaults.burningVault,
+            burningVaultAuthority: vaults.burningVaultAuthority,
+            borrowingFeesVault,
+            treasuryVault,
+            stablecoinMint,
+            stablecoinMintAuthority,
+            pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
+            pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
+            pythEthPriceInfo: pythPrices.ethPythPrice.publicKey,
+            pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
+            pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
+            pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        },
+        remainingAccounts: metadataAccounts,
+        signers: [redeemer]
+    }));
+
+    console.log(`Instant redeem transaction signature: ${txid}`);
+
+    return txid;
+}
+
+export async function getCandidatesPageAccounts(program: anchor.Program, redemptionOrder: PublicKey): Promise<any[]> {
+    // Every page of the order, in order, ahead of the other remaining accounts
+    const { candidatePages }: any = await program.account.redemptionOrder.fetch(redemptionOrder);
+    let accounts = [];
+    for (let page = 0; page < candidatePages.toNumber(); page++) {
+        accounts.push({
+            pubkey: await utils.findRedemptionCandidatesPageAddress(redemptionOrder, page, program.programId),
+            isWritable: true,
+            isSigner: false
+        });
+    }
+    return accounts;
+}
 
 export function getMetadataAccounts(metadatas: PublicKey[], maximumMetadataAccounts: number): any[] {
     let accounts = [];
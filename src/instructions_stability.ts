@@ -2,7 +2,7 @@ import * as anchor from '@project-serum/anchor';
 import { Keypair, PublicKey, Signer } from "@solana/web3.js";
 import { TokenInstructions } from "@project-serum/serum";
 import { getBorrowingMarketState, getBorrowingVaults, getStabilityVaults } from "../tests/data_provider";
-import { mapAnchorError } from "./utils";
+import { findAssociatedTokenAddress, mapAnchorError } from "./utils";
 import { BorrowingGlobalAccounts, StabilityPoolAccounts } from './set_up';
 import { CollateralToken, collateralTokenToNumber, StabilityToken, stabilityTokenToNumber } from '../tests/types';
 
@@ -11,11 +11,12 @@ export async function initializeStabilityPool(
     program: anchor.Program,
     initialMarketOwner: PublicKey,
     borrowingGlobalAccounts: BorrowingGlobalAccounts,
-    stabilityAccounts: StabilityPoolAccounts
+    stabilityAccounts: StabilityPoolAccounts,
+    collateralCoverage: number = 0 // bitmask of the covered collateral, 0 covers all of it
 ) {
     const { stabilityPoolState } = borrowingGlobalAccounts;
     const { stabilityVaults } = stabilityAccounts;
-    const tx = await program.rpc.stabilityInitialize({
+    const tx = await program.rpc.stabilityInitialize(collateralCoverage, {
         accounts: utils.initializeStabilityPoolAccounts(initialMarketOwner, borrowingGlobalAccounts, stabilityAccounts),
         signers: [stabilityPoolState, stabilityVaults]
     });
@@ -53,6 +54,9 @@ export async function provideStability(
     amount: number,
     signers: Array<Signer>) {
 
+    const { stabilityReceiptMint, stabilityReceiptMintAuthority } = await getStabilityVaults(program, stabilityVaults);
+    const stabilityReceiptAta = await findAssociatedTokenAddress(owner, stabilityReceiptMint);
+
     const tx = await mapAnchorError(program.rpc.stabilityProvide(
         new anchor.BN(amount), {
         accounts: utils.getProvideStabilityAccounts(
@@ -63,7 +67,10 @@ export async function provideStability(
             stabilityVaults,
             epochToScaleToSum,
             stablecoinStabilityPoolVault,
-            stablecoinAta
+            stablecoinAta,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta
         ),
         signers
     }));
@@ -83,7 +90,12 @@ export async function withdrawStability(
     amount: number,
     signers: Array<Signer>) {
 
-    const { stablecoinStabilityPoolVaultAuthority } = await getStabilityVaults(program, stabilityVaults);
+    const {
+        stablecoinStabilityPoolVaultAuthority,
+        stabilityReceiptMint,
+        stabilityReceiptMintAuthority
+    } = await getStabilityVaults(program, stabilityVaults);
+    const stabilityReceiptAta = await findAssociatedTokenAddress(owner, stabilityReceiptMint);
 
     const tx = await mapAnchorError(program.rpc.stabilityWithdraw(
         new anchor.BN(amount), {
@@ -95,13 +107,50 @@ export async function withdrawStability(
             epochToScaleToSum,
             stablecoinStabilityPoolVault,
             stablecoinStabilityPoolVaultAuthority,
-            stablecoinAta
+            stablecoinAta,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta
         ),
         signers
     }));
     console.log('withdrawStability done signature:', tx);
 }
 
+export async function requestStabilityWithdrawal(
+    program: anchor.Program,
+    owner: PublicKey,
+    stabilityProviderState: PublicKey,
+    borrowingMarketState: PublicKey,
+    stabilityPoolState: PublicKey,
+    stabilityVaults: PublicKey,
+    epochToScaleToSum: PublicKey,
+    amount: number,
+    signers: Array<Signer>) {
+
+    const { stabilityReceiptMint, stabilityReceiptMintAuthority } = await getStabilityVaults(program, stabilityVaults);
+    const stabilityReceiptAta = await findAssociatedTokenAddress(owner, stabilityReceiptMint);
+
+    const tx = await mapAnchorError(program.rpc.stabilityRequestWithdrawal(
+        new anchor.BN(amount), {
+        accounts: {
+            owner,
+            stabilityProviderState,
+            borrowingMarketState,
+            stabilityPoolState,
+            stabilityVaults,
+            epochToScaleToSum,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta,
+            tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        },
+        signers
+    }));
+    console.log('requestStabilityWithdrawal done signature:', tx);
+}
+
 
 export async function clearLiquidationGains(
     program: anchor.Program,
@@ -118,6 +167,10 @@ export async function clearLiquidationGains(
     token: CollateralToken = "SOL",
 ) {
     const { collateralVaultsAuthority } = await getBorrowingVaults(program, borrowingVaults);
+    const {
+        stablecoinStabilityPoolVault,
+        stablecoinStabilityPoolVaultAuthority
+    } = await getStabilityVaults(program, stabilityVaults);
 
     const tx = await mapAnchorError(program.rpc.clearLiquidationGains(
         new anchor.BN(collateralTokenToNumber(token)), {
@@ -132,6 +185,8 @@ export async function clearLiquidationGains(
             collateralVault,
             collateralVaultsAuthority,
             liquidationRewardsVault,
+            stablecoinStabilityPoolVault,
+            stablecoinStabilityPoolVaultAuthority,
         ),
         signers
     }));
@@ -206,6 +261,7 @@ export namespace utils {
             liquidationRewardsVaultRay: stabilityAccounts.liquidationRewardsVaultRay,
             liquidationRewardsVaultFtt: stabilityAccounts.liquidationRewardsVaultFtt,
             stablecoinStabilityPoolVault: stabilityAccounts.stablecoinStabilityPoolVault,
+            stabilityReceiptMint: stabilityAccounts.stabilityReceiptMint,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
             systemProgram: anchor.web3.SystemProgram.programId,
             rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -222,7 +278,10 @@ export namespace utils {
         stabilityVaults: PublicKey,
         epochToScaleToSum: PublicKey,
         stablecoinStabilityPoolVault: PublicKey,
-        stablecoinAta: PublicKey
+        stablecoinAta: PublicKey,
+        stabilityReceiptMint: PublicKey,
+        stabilityReceiptMintAuthority: PublicKey,
+        stabilityReceiptAta: PublicKey
     ): any {
         return {
             owner,
@@ -233,6 +292,9 @@ export namespace utils {
             epochToScaleToSum,
             stablecoinStabilityPoolVault,
             stablecoinAta,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
             clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         };
@@ -247,7 +309,10 @@ export namespace utils {
         epochToScaleToSum: PublicKey,
         stablecoinStabilityPoolVault: PublicKey,
         stablecoinStabilityPoolVaultAuthority: PublicKey,
-        stablecoinAta: PublicKey
+        stablecoinAta: PublicKey,
+        stabilityReceiptMint: PublicKey,
+        stabilityReceiptMintAuthority: PublicKey,
+        stabilityReceiptAta: PublicKey
     ): any {
 
         return {
@@ -260,6 +325,9 @@ export namespace utils {
             stablecoinStabilityPoolVault,
             stablecoinStabilityPoolVaultAuthority,
             stablecoinAta,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority,
+            stabilityReceiptAta,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
             clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         };
@@ -276,6 +344,8 @@ export namespace utils {
         collateralVault: PublicKey,
         collateralVaultsAuthority: PublicKey,
         liquidationRewardsVault: PublicKey,
+        stablecoinStabilityPoolVault: PublicKey,
+        stablecoinStabilityPoolVaultAuthority: PublicKey,
     ): any {
         return {
             clearingAgent,
@@ -288,6 +358,8 @@ export namespace utils {
             collateralVault,
             collateralVaultsAuthority,
             liquidationRewardsVault,
+            stablecoinStabilityPoolVault,
+            stablecoinStabilityPoolVaultAuthority,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
             clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         }
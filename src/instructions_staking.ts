@@ -8,6 +8,7 @@ export async function initializeStakingPool(
     initialMarketOwner: PublicKey,
     borrowingMarketState: PublicKey,
     stakingPoolState: Keypair,
+    boostCheckpoints: PublicKey,
     stakingVault: PublicKey,
     treasuryVault: PublicKey,
     treasuryFeeRate: number
@@ -19,6 +20,7 @@ export async function initializeStakingPool(
                 initialMarketOwner,
                 borrowingMarketState,
                 stakingPoolState: stakingPoolState.publicKey,
+                boostCheckpoints,
                 stakingVault,
                 treasuryVault,
                 tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
@@ -69,15 +71,18 @@ export async function stake(
     amount: number
 ) {
     console.log("Staking HBB");
+    const { boostCheckpoints } = await getStakingPoolState(program, stakingPoolState);
     const tx = await program.rpc.stakingStakeHbb(new anchor.BN(amount), {
         accounts: {
             owner,
             userStakingState,
             borrowingMarketState,
             stakingPoolState,
+            boostCheckpoints,
             stakingVault,
             userHbbStakingAta,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         },
         signers,
     });
@@ -90,6 +95,7 @@ export async function unstake(
     owner: PublicKey,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    globalConfig: PublicKey,
     stakingPoolState: PublicKey,
     userStakingState: PublicKey,
     userHbbStakingAta: PublicKey,
@@ -99,7 +105,7 @@ export async function unstake(
     signers: Array<Signer>,
     amount: number
 ) {
-    const stakingVaultAuthority = (await getStakingPoolState(program, stakingPoolState)).stakingVaultAuthority;
+    const { stakingVaultAuthority, boostCheckpoints } = await getStakingPoolState(program, stakingPoolState);
     const borrowingFeesVaultAuthority = (await getBorrowingVaults(program, borrowingVaults)).borrowingFeesVaultAuthority;
 
     const tx = await program.rpc.unstakeHbb(new anchor.BN(amount), {
@@ -107,7 +113,9 @@ export async function unstake(
             owner,
             borrowingMarketState,
             borrowingVaults,
+            globalConfig,
             stakingPoolState,
+            boostCheckpoints,
             userStakingState,
             borrowingFeesVault,
             borrowingFeesVaultAuthority,
@@ -117,6 +125,7 @@ export async function unstake(
             userHbbStakingAta,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
             rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         },
         signers,
     });
@@ -136,12 +145,14 @@ export async function harvestReward(
     signers: Array<Signer>
 ) {
     const borrowingFeesVaultAuthority = (await getBorrowingVaults(program, borrowingVaults)).borrowingFeesVaultAuthority;
+    const { boostCheckpoints } = await getStakingPoolState(program, stakingPoolState);
 
     const tx = await program.rpc.stakingHarvestReward({
         accounts: {
             borrowingMarketState,
             borrowingVaults,
             stakingPoolState,
+            boostCheckpoints,
             userStakingState,
             owner,
             borrowingFeesVault,
@@ -149,6 +160,7 @@ export async function harvestReward(
             userStablecoinRewardsAta,
             tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
             rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         },
         signers,
     });
@@ -12,6 +12,7 @@ import {
     OpenOrders,
 } from "@project-serum/serum";
 import { CollateralToken, collateralTokenToNumber, UserMetadata } from "../tests/types";
+import { getAccruedInterestAccounts } from "./instructions_borrow";
 
 export async function serumInitAccount(
     program: anchor.Program,
@@ -146,6 +147,17 @@ export async function swapToUsdc(
     }
     let borrowingMarketState = borrowingAccounts.borrowingMarketState;
     let borrowingVaults = borrowingAccounts.borrowingVaults;
+    const {
+        stablecoinMint,
+        stablecoinMintAuthority,
+        borrowingFeesVault,
+        treasuryVault
+    } = await getAccruedInterestAccounts(
+        program,
+        borrowingMarketState.publicKey,
+        borrowingVaults.publicKey,
+        borrowingAccounts.stakingPoolState.publicKey
+    );
 
     const tx = await program.rpc.serumSwapUsdc(0, new anchor.BN(baseAmount), new anchor.BN(collateralTokenToNumber(token)), {
         accounts: {
@@ -165,6 +177,7 @@ export async function swapToUsdc(
             collateralVault, // the account that receives the base tokens back, if the orders aren't matched
             borrowingMarketState: borrowingMarketState.publicKey,
             borrowingVaults: borrowingVaults.publicKey,
+            stakingPoolState: borrowingAccounts.stakingPoolState.publicKey,
             userMetadata: userMetadata,
             collateralFromAuthority: collateralVaultAuthority,
             pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
@@ -174,8 +187,13 @@ export async function swapToUsdc(
             pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
             pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
             usdcMint,
+            stablecoinMint,
+            stablecoinMintAuthority,
+            borrowingFeesVault,
+            treasuryVault,
             tokenProgram: TOKEN_PROGRAM_ID,
             rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         },
         signers: [user]
     });
@@ -22,6 +22,7 @@ export type BorrowingGlobalAccounts = {
     stabilityPoolState: Keypair;
     stakingPoolState: Keypair;
     redemptionsQueue: PublicKey;
+    sortedTroves: PublicKey;
     collateralVaultSol: PublicKey;
     collateralVaultEth: PublicKey;
     collateralVaultBtc: PublicKey;
@@ -66,6 +67,7 @@ export type StabilityPoolAccounts = {
     stablecoinStabilityPoolVault: PublicKey;
     epochToScaleToSum: PublicKey;
     liquidationsQueue: PublicKey;
+    stabilityReceiptMint: PublicKey;
     stabilityVaults: Keypair
 };
 
@@ -80,6 +82,7 @@ export type StabilityProviderAccounts = UserAtas & {
     hbbAta: PublicKey,
     solCollateralLiquidationsRewardsPending: PublicKey,
     hbbEmissionRewardsPending: PublicKey,
+    stabilityReceiptAta: PublicKey,
 }
 
 export type LiquidatorAccounts = {
@@ -107,6 +110,7 @@ export type RewardsUserAccounts = {
 export type StakingPoolAccounts = {
     stakingVault: PublicKey;
     treasuryVault: PublicKey;
+    boostCheckpoints: PublicKey;
 };
 
 export type StakingPoolUserAccounts = {
@@ -324,6 +328,7 @@ export async function setUpBorrowingGlobalAccounts(
     const stakingPoolState = new Keypair();
 
     const redemptionsQueue = await newRedemptionsQueueAccount(provider, program);
+    const sortedTroves = await newSortedTrovesAccount(provider, program);
 
     return {
         stablecoinMint,
@@ -336,6 +341,7 @@ export async function setUpBorrowingGlobalAccounts(
         stabilityPoolState,
         stakingPoolState,
         redemptionsQueue,
+        sortedTroves,
         collateralVaultSol,
         collateralVaultEth,
         collateralVaultBtc,
@@ -363,6 +369,19 @@ export async function newRedemptionsQueueAccount(
     ).publicKey;
 }
 
+export async function newSortedTrovesAccount(
+    provider: anchor.Provider,
+    program: anchor.Program,
+): Promise<PublicKey> {
+    return (
+        await serumCmn.createAccountRentExempt(
+            provider,
+            program.programId,
+            program.account.sortedTroves.size
+        )
+    ).publicKey;
+}
+
 export async function setUpStabilityPoolAccounts(
     provider: anchor.Provider,
     program: anchor.Program,
@@ -383,6 +402,15 @@ export async function setUpStabilityPoolAccounts(
     const liquidationRewardsVaultRay = await utils.createTokenAccount(provider, globalAccounts.rayMint, initialMarketOwner);
     const liquidationRewardsVaultFtt = await utils.createTokenAccount(provider, globalAccounts.fttMint, initialMarketOwner);
 
+    // Handed over to the program on initialization, which
+    // also keeps the receipt accounts frozen
+    const stabilityReceiptMint = await utils.createMint(
+        provider,
+        initialMarketOwner,
+        6,
+        initialMarketOwner
+    );
+
     let stabilityVaults = new Keypair();
 
     let epochToScaleToSum = (
@@ -411,6 +439,7 @@ export async function setUpStabilityPoolAccounts(
         stablecoinStabilityPoolVault,
         epochToScaleToSum,
         liquidationsQueue,
+        stabilityReceiptMint,
         stabilityVaults
     };
 }
@@ -553,7 +582,8 @@ export async function setUpStabilityProviderUserAccounts(
     signers: Array<Keypair>,
     user: PublicKey,
     program: anchor.Program,
-    globalAccounts: BorrowingGlobalAccounts
+    globalAccounts: BorrowingGlobalAccounts,
+    stabilityPoolAccounts: StabilityPoolAccounts
 ): Promise<StabilityProviderAccounts> {
 
     return setUpStabilityProviderUserAccountsWithPubkeys(
@@ -561,6 +591,7 @@ export async function setUpStabilityProviderUserAccounts(
         signers,
         user,
         program,
+        stabilityPoolAccounts.stabilityReceiptMint,
         globalAccounts.hbbMint,
         globalAccounts.stablecoinMint,
         globalAccounts.ethMint,
@@ -576,6 +607,7 @@ export async function setUpStabilityProviderUserAccountsWithPubkeys(
     signers: Array<Keypair>,
     user: PublicKey,
     program: anchor.Program,
+    stabilityReceiptMint: PublicKey,
     hbbMint: PublicKey,
     stablecoinMint: PublicKey,
     mintEth: PublicKey,
@@ -612,6 +644,13 @@ export async function setUpStabilityProviderUserAccountsWithPubkeys(
         hbbMint,
         user);
 
+    let stabilityReceiptAta = await setUpAssociatedTokenAccount(
+        provider,
+        user,
+        signers,
+        user,
+        stabilityReceiptMint);
+
     let stabilityProviderState = new Keypair();
 
     let ethAta = await setUpAta(provider, signers[0], mintEth);
@@ -625,6 +664,7 @@ export async function setUpStabilityProviderUserAccountsWithPubkeys(
         stablecoinAta,
         solCollateralLiquidationsRewardsPending,
         hbbEmissionRewardsPending,
+        stabilityReceiptAta,
         stabilityProviderState,
         ethAta,
         btcAta,
@@ -910,9 +950,18 @@ export async function setUpStakingPoolAccounts(
         initialMarketOwner
     );
 
+    const boostCheckpoints: PublicKey = (
+        await serumCmn.createAccountRentExempt(
+            provider,
+            program.programId,
+            program.account.boostCheckpoints.size
+        )
+    ).publicKey;
+
     return {
         stakingVault,
-        treasuryVault
+        treasuryVault,
+        boostCheckpoints
     };
 }
 
@@ -29,6 +29,8 @@ const programPublicKey = "UpbA7oUWbQiXyvbkrMtfMF2gZ3W7F6U3jqxXbUvyPrD";
 const programId = new anchor.web3.PublicKey(programPublicKey);
 
 export const TROVE_DATA_SEED = "trove_data_7";
+export const REDEMPTION_ORDER_TAG = "rdo";
+export const REDEMPTION_CANDIDATES_PAGE_TAG = "rdcp";
 export const SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID = new PublicKey(
     "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
 );
@@ -51,6 +53,40 @@ export async function findAssociatedTokenAddress(
     return res;
 }
 
+export async function findRedemptionOrderAddress(
+    redemptionsQueue: PublicKey,
+    orderId: number,
+    borrowingProgramId: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [
+                redemptionsQueue.toBuffer(),
+                Buffer.from(REDEMPTION_ORDER_TAG),
+                new anchor.BN(orderId).toArrayLike(Buffer, "le", 8),
+            ],
+            borrowingProgramId
+        )
+    )[0];
+}
+
+export async function findRedemptionCandidatesPageAddress(
+    redemptionOrder: PublicKey,
+    page: number,
+    borrowingProgramId: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [
+                redemptionOrder.toBuffer(),
+                Buffer.from(REDEMPTION_CANDIDATES_PAGE_TAG),
+                new anchor.BN(page).toArrayLike(Buffer, "le", 8),
+            ],
+            borrowingProgramId
+        )
+    )[0];
+}
+
 export async function executeTransaction(
     provider: anchor.Provider,
     ix: TransactionInstruction
@@ -174,14 +210,16 @@ export async function getMintOwnerAndNonce(marketPublicKey: PublicKey) {
 export async function createMint(
     provider: anchor.Provider,
     authority: PublicKey,
-    decimals: number = 6
+    decimals: number = 6,
+    freezeAuthority: PublicKey | null = null
 ): Promise<PublicKey> {
     const mint = anchor.web3.Keypair.generate();
     const instructions = await createMintInstructions(
         provider,
         authority,
         mint.publicKey,
-        decimals
+        decimals,
+        freezeAuthority
     );
 
     const tx = new anchor.web3.Transaction();
@@ -215,7 +253,8 @@ async function createMintInstructions(
     provider: anchor.Provider,
     authority: PublicKey,
     mint: PublicKey,
-    decimals: number
+    decimals: number,
+    freezeAuthority: PublicKey | null
 ): Promise<TransactionInstruction[]> {
     let instructions = [
         anchor.web3.SystemProgram.createAccount({
@@ -229,6 +268,7 @@ async function createMintInstructions(
             mint,
             decimals,
             mintAuthority: authority,
+            freezeAuthority,
         }),
     ];
     return instructions;
@@ -153,10 +153,29 @@ export async function getRedemptionsQueueData(
     account: PublicKey
 ): Promise<RedemptionOrder[]> {
     let redemptionsQueue: any = await program.account.redemptionsQueue.fetch(account);
-    const orders: any[] = redemptionsQueue.orders;
-    return orders.map((raw) => {
-        return {
-            id: raw.id,
+    const nextIndex = redemptionsQueue.nextIndex.toNumber();
+
+    // Each order lives in its own account, closed once the order completes
+    const orders: RedemptionOrder[] = [];
+    for (let orderId = 0; orderId < nextIndex; orderId++) {
+        const address = await utils.findRedemptionOrderAddress(account, orderId, program.programId);
+        if (!(await utils.checkIfAccountExists(program.provider.connection, address))) {
+            continue;
+        }
+        const raw: any = await program.account.redemptionOrder.fetch(address);
+
+        const candidatePages: PublicKey[] = [];
+        let candidateUsers: any[] = [];
+        for (let page = 0; page < raw.candidatePages.toNumber(); page++) {
+            const pageAddress = await utils.findRedemptionCandidatesPageAddress(address, page, program.programId);
+            const candidatesPage: any = await program.account.redemptionCandidatesPage.fetch(pageAddress);
+            candidatePages.push(pageAddress);
+            candidateUsers = candidateUsers.concat(candidatesPage.candidateUsers);
+        }
+
+        orders.push({
+            address,
+            id: raw.id.toNumber(),
             status: raw.status,
             lastReset: raw.lastReset.toNumber(),
             redeemerUserMetadata: raw.redeemerUserMetadata,
@@ -164,9 +183,11 @@ export async function getRedemptionsQueueData(
             requestedAmount: raw.requestedAmount.toNumber(),
             remainingAmount: raw.remainingAmount.toNumber(),
             redemptionPrices: raw.redemptionPrices,
-            candidateUsers: raw.candidateUsers,
-        }
-    })
+            candidatePages,
+            candidateUsers,
+        });
+    }
+    return orders;
 }
 
 export async function getUserStakingStateData(
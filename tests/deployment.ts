@@ -240,6 +240,7 @@ async function generateBorrowers(
                 borrowerAccounts,
                 new PublicKey(config.borrowingMarketState),
                 new PublicKey(config.borrowingVaults),
+                new PublicKey(config.stakingPoolState),
                 new PublicKey(config.collateralVault.SOL),
                 new PublicKey(config.collateralVault.ETH),
                 new PublicKey(config.collateralVault.BTC),
@@ -253,6 +254,7 @@ async function generateBorrowers(
                 new PublicKey(config.mint.RAY),
                 new PublicKey(config.mint.FTT),
                 new PublicKey(config.mint.HBB),
+                pythPrices,
                 token as CollateralToken);
         }
 
@@ -382,6 +384,7 @@ async function initializeProtocol(
             initialMarketOwner,
             borrowingAccounts.borrowingMarketState.publicKey,
             borrowingAccounts.stakingPoolState,
+            stakingAccounts.boostCheckpoints,
             stakingAccounts.stakingVault,
             stakingAccounts.treasuryVault,
             treasuryFeeRate
@@ -52,6 +52,7 @@ export async function setUpMarketWithUser(
             borrower,
             borrowerAccounts,
             borrowingMarketAccounts,
+            prices,
             asset
         );
     }
@@ -418,6 +419,7 @@ export async function depositCollateral(
     user: Keypair,
     userAccounts: BorrowingUserAccounts,
     globalAccounts: BorrowingGlobalAccounts,
+    pythPrices: set_up.PythPrices,
     token: CollateralToken = "SOL") {
 
     await depositCollateralWithPubkey(
@@ -428,6 +430,7 @@ export async function depositCollateral(
         userAccounts,
         globalAccounts.borrowingMarketState.publicKey,
         globalAccounts.borrowingVaults.publicKey,
+        globalAccounts.stakingPoolState.publicKey,
         globalAccounts.collateralVaultSol,
         globalAccounts.collateralVaultEth,
         globalAccounts.collateralVaultBtc,
@@ -441,6 +444,7 @@ export async function depositCollateral(
         globalAccounts.rayMint,
         globalAccounts.fttMint,
         globalAccounts.hbbMint,
+        pythPrices,
         token);
 
     await displayData(program, provider, userAccounts, globalAccounts, user);
@@ -454,6 +458,7 @@ export async function depositCollateralWithPubkey(
     userAccounts: BorrowingUserAccounts,
     borrowingMarketState: PublicKey,
     borrowingVaults: PublicKey,
+    stakingPoolState: PublicKey,
     collateralVaultSol: PublicKey,
     collateralVaultEth: PublicKey,
     collateralVaultBtc: PublicKey,
@@ -467,6 +472,7 @@ export async function depositCollateralWithPubkey(
     rayMint: PublicKey,
     fttMint: PublicKey,
     hbbMint: PublicKey,
+    pythPrices: set_up.PythPrices,
     token: CollateralToken = "SOL") {
 
     console.log(`${user.publicKey} Depositing collateral ${token} ${depositAmount}`);
@@ -491,6 +497,8 @@ export async function depositCollateralWithPubkey(
             collateralFrom,
             borrowingMarketState,
             borrowingVaults,
+            stakingPoolState,
+            pythPrices,
             utils.collToLamports(depositAmount, token),
             [user],
             token);
@@ -577,6 +585,7 @@ export async function repay(
             userAccounts.stablecoinAta,
             globalAccounts.borrowingMarketState.publicKey,
             globalAccounts.borrowingVaults.publicKey,
+            globalAccounts.stakingPoolState.publicKey,
             globalAccounts.burningVault,
             pythPrices,
             utils.decimalToU64(repayStablecoin),
@@ -602,6 +611,7 @@ export async function withdrawSolCollateral(
             user.publicKey,
             globalAccounts.borrowingMarketState.publicKey,
             globalAccounts.borrowingVaults.publicKey,
+            globalAccounts.stakingPoolState.publicKey,
             userAccounts.userMetadata.publicKey,
             globalAccounts.collateralVaultSol,
             user.publicKey,
@@ -627,7 +637,7 @@ export async function newLoanee(
     const response = await newBorrowingUser(env, globalAccounts, minBalances);
     for (const [token, amount] of collateral.entries()) {
         if (amount > 0) {
-            await depositCollateral(env.provider, env.program, amount, response.borrower, response.borrowerAccounts, globalAccounts, token);
+            await depositCollateral(env.provider, env.program, amount, response.borrower, response.borrowerAccounts, globalAccounts, pythPrices, token);
         }
     }
     if (borrowStablecoin > 0) {
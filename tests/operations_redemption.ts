@@ -72,6 +72,9 @@ export async function add_redemption_order(
     redemptionUser: BorrowingUserState,
     pythPrices: PythPrices,
     redeemStablecoin: number,
+    maxFeeBps: number = instructions_redeem.REDEMPTION_MAX_FEE_BPS,
+    minCollateralValueUsdh: number | null = null,
+    preferredCollateral: number | null = null,
 ): Promise<TransactionSignature> {
     return instructions_redeem.addRedemptionOrder(
         program,
@@ -80,10 +83,14 @@ export async function add_redemption_order(
         redemptionUser.borrowerAccounts.stablecoinAta,
         borrowingGlobalAccounts.borrowingMarketState.publicKey,
         borrowingGlobalAccounts.borrowingVaults.publicKey,
+        borrowingGlobalAccounts.globalConfig.publicKey,
         borrowingGlobalAccounts.redemptionsQueue,
         borrowingGlobalAccounts.burningVault,
         pythPrices,
         utils.decimalToU64(redeemStablecoin),
+        maxFeeBps,
+        minCollateralValueUsdh === null ? null : utils.decimalToU64(minCollateralValueUsdh),
+        preferredCollateral,
     );
 }
 
@@ -100,6 +107,8 @@ export async function fill_redemption_order(
         filler.borrower,
         filler.borrowerAccounts.userMetadata.publicKey,
         borrowingGlobalAccounts.borrowingMarketState.publicKey,
+        borrowingGlobalAccounts.borrowingVaults.publicKey,
+        borrowingGlobalAccounts.stakingPoolState.publicKey,
         borrowingGlobalAccounts.redemptionsQueue,
         orderId,
         candidateMetadata,
@@ -112,8 +121,10 @@ export async function clear_redemption_order(
     borrowingGlobalAccounts: BorrowingGlobalAccounts,
     clearer: BorrowingUserState,
     redeemer: BorrowingUserState,
+    pythPrices: PythPrices,
     orderId: number,
     borrowerAndFillerMetadatas: PublicKey[],
+    closedTroves: PublicKey[] = [],
 ): Promise<TransactionSignature> {
     return instructions_redeem.clearRedemptionOrder(
         program,
@@ -122,10 +133,57 @@ export async function clear_redemption_order(
         redeemer.borrowerAccounts.userMetadata.publicKey,
         borrowingGlobalAccounts.borrowingMarketState.publicKey,
         borrowingGlobalAccounts.borrowingVaults.publicKey,
+        borrowingGlobalAccounts.stakingPoolState.publicKey,
         borrowingGlobalAccounts.redemptionsQueue,
         borrowingGlobalAccounts.burningVault,
         borrowingGlobalAccounts.stablecoinMint,
+        pythPrices,
         orderId,
         borrowerAndFillerMetadatas,
+        closedTroves,
+    );
+}
+
+export async function cancel_redemption_order(
+    provider: anchor.Provider,
+    program: anchor.Program,
+    borrowingGlobalAccounts: BorrowingGlobalAccounts,
+    redeemer: BorrowingUserState,
+    orderId: number,
+): Promise<TransactionSignature> {
+    return instructions_redeem.cancelRedemptionOrder(
+        program,
+        redeemer.borrower,
+        redeemer.borrowerAccounts.userMetadata.publicKey,
+        redeemer.borrowerAccounts.stablecoinAta,
+        borrowingGlobalAccounts.borrowingMarketState.publicKey,
+        borrowingGlobalAccounts.borrowingVaults.publicKey,
+        borrowingGlobalAccounts.globalConfig.publicKey,
+        borrowingGlobalAccounts.redemptionsQueue,
+        orderId,
+    );
+}
+
+export async function instant_redeem(
+    provider: anchor.Provider,
+    program: anchor.Program,
+    borrowingGlobalAccounts: BorrowingGlobalAccounts,
+    redeemer: BorrowingUserState,
+    pythPrices: PythPrices,
+    redeemStablecoin: number,
+    borrowerMetadatas: PublicKey[],
+): Promise<TransactionSignature> {
+    return instructions_redeem.instantRedeem(
+        program,
+        redeemer.borrower,
+        redeemer.borrowerAccounts,
+        borrowingGlobalAccounts.borrowingMarketState.publicKey,
+        borrowingGlobalAccounts.borrowingVaults.publicKey,
+        borrowingGlobalAccounts.globalConfig.publicKey,
+        borrowingGlobalAccounts.stakingPoolState.publicKey,
+        borrowingGlobalAccounts.redemptionsQueue,
+        pythPrices,
+        utils.decimalToU64(redeemStablecoin),
+        borrowerMetadatas,
     );
 }
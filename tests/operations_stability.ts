@@ -12,7 +12,7 @@ import { displayBorrowingMarketState, displayStabilityPoolState, displayStabilit
 import { CollateralToken, numberToCollateralToken, StabilityToken } from './types';
 import * as utils from "../src/utils";
 import { decimalToU64, lamportsToColl, solAccountWithMinBalance } from "../src/utils";
-import { getForcedSolBalanceInLamports, getTokenAccountBalance } from "./data_provider";
+import { getForcedSolBalanceInLamports, getStabilityVaults, getTokenAccountBalance } from "./data_provider";
 import { initialiseBorrowingMarkets } from "./operations_borrowing";
 
 export async function initialiseStabilityPool(
@@ -60,12 +60,14 @@ export async function newStabilityPoolUserWithPubkeys(
 ): Promise<StabilityProviderState> {
 
     const { keyPair: stabilityProvider } = await solAccountWithMinBalance(provider, 3);
+    const { stabilityReceiptMint } = await getStabilityVaults(program, stabilityVaults);
 
     const stabilityProviderAccounts = await set_up.setUpStabilityProviderUserAccountsWithPubkeys(
         provider,
         [stabilityProvider],
         stabilityProvider.publicKey,
         program,
+        stabilityReceiptMint,
         hbbMint,
         stablecoinMint,
         mintEth,
@@ -257,6 +259,7 @@ export async function tryLiquidate(
         stabilityPoolGlobalAccounts.stabilityVaults.publicKey,
         borrowingGlobalAccounts.borrowingVaults.publicKey,
         stabilityPoolGlobalAccounts.liquidationsQueue,
+        borrowingGlobalAccounts.stakingPoolState.publicKey,
         borrowingGlobalAccounts.stablecoinMint,
         stabilityPoolGlobalAccounts.stablecoinStabilityPoolVault,
         pythPrices,
@@ -37,6 +37,7 @@ export async function initialiseStakingPool(
         initialMarketOwner,
         borrowingAccounts.borrowingMarketState.publicKey,
         borrowingAccounts.stakingPoolState,
+        stakingAccounts.boostCheckpoints,
         stakingAccounts.stakingVault,
         stakingAccounts.treasuryVault,
         treasuryFeeRate
@@ -158,7 +159,7 @@ export async function triggerFees(
         ["SOL", depositSol + 1]
     ]));
 
-    await operations_borrowing.depositCollateral(env.provider, env.program, depositSol, borrower, borrowerAccounts, borrowingAccounts);
+    await operations_borrowing.depositCollateral(env.provider, env.program, depositSol, borrower, borrowerAccounts, borrowingAccounts, pythPrices);
     await sleep(1000);
 
     // borrow stable
@@ -47,16 +47,25 @@ describe('tests_security_add_redemption_order', () => {
 
         const { borrower: redeemer2 } = await newRedemptionUser(env, borrowingAccounts, redeemAmount, 5);
 
+        const redemptionOrder = await utils.findRedemptionOrderAddress(borrowingAccounts.redemptionsQueue, 0, program.programId);
+        const candidatesPage = await utils.findRedemptionCandidatesPageAddress(redemptionOrder, 0, program.programId);
+
         // redeemer2 sends an account which it does not sign for
         const ix = await program.instruction.addRedemptionOrder(
-            new anchor.BN(decimalToU64(redeemAmount)), {
+            new anchor.BN(decimalToU64(redeemAmount)),
+            instructions_redeem.REDEMPTION_MAX_FEE_BPS,
+            null,
+            null, {
             accounts: getAddRedemptionOrderAccounts(
                 redeemer1,
                 redeemer1Accounts.userMetadata.publicKey,
                 redeemer1Accounts.stablecoinAta,
                 borrowingAccounts.borrowingMarketState.publicKey,
                 borrowingAccounts.borrowingVaults.publicKey,
+                borrowingAccounts.globalConfig.publicKey,
                 borrowingAccounts.redemptionsQueue,
+                redemptionOrder,
+                candidatesPage,
                 borrowingAccounts.burningVault,
                 pythPrices,
             ),
@@ -101,6 +110,7 @@ describe('tests_security_add_redemption_order', () => {
             redeemer1Accounts.stablecoinAta,
             borrowingAccounts.borrowingMarketState.publicKey,
             borrowingAccounts.borrowingVaults.publicKey,
+            borrowingAccounts.globalConfig.publicKey,
             borrowingAccounts.redemptionsQueue,
             borrowingAccounts.burningVault,
             pythPrices,
@@ -125,6 +135,7 @@ describe('tests_security_add_redemption_order', () => {
             redeemer2Accounts.stablecoinAta, // redeemer2 stablecoinAta
             borrowingAccounts.borrowingMarketState.publicKey,
             borrowingAccounts.borrowingVaults.publicKey,
+            borrowingAccounts.globalConfig.publicKey,
             borrowingAccounts.redemptionsQueue,
             borrowingAccounts.burningVault,
             pythPrices,
@@ -151,6 +162,7 @@ describe('tests_security_add_redemption_order', () => {
             redeemerAccounts.stablecoinAta,
             borrowingAccounts2.borrowingMarketState.publicKey, // borrowingAccounts2 borrowingMarketState
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.globalConfig.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts1.burningVault,
             pythPrices,
@@ -177,6 +189,7 @@ describe('tests_security_add_redemption_order', () => {
             redeemerAccounts.stablecoinAta,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts2.borrowingVaults.publicKey, // borrowingAccounts2 borrowingVaults
+            borrowingAccounts1.globalConfig.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts1.burningVault,
             pythPrices,
@@ -203,6 +216,7 @@ describe('tests_security_add_redemption_order', () => {
             redeemerAccounts.stablecoinAta,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.globalConfig.publicKey,
             borrowingAccounts2.redemptionsQueue, // borrowingAccounts2 redemptionsQueue
             borrowingAccounts1.burningVault,
             pythPrices,
@@ -229,6 +243,7 @@ describe('tests_security_add_redemption_order', () => {
             redeemerAccounts.stablecoinAta,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.globalConfig.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts2.burningVault, // borrowingAccounts2 burningVault
             pythPrices,
@@ -201,6 +201,7 @@ describe('tests_security_borrow_stablecoin', () => {
         );
 
         const stablecoinMintAuthority2 = (await getBorrowingMarketState(program, borrowingGlobalAccounts2.borrowingMarketState.publicKey)).stablecoinMintAuthority;
+        const { sortedTroves } = await getBorrowingMarketState(program, borrowingGlobalAccounts1.borrowingMarketState.publicKey);
 
         // user borrowingMarketState2 stablecoin mint and mint authority
         const ix = await program.instruction.borrowStablecoin(
@@ -209,6 +210,7 @@ describe('tests_security_borrow_stablecoin', () => {
                 accounts: instructions_borrow.utils.getBorrowStablecoinAccounts(
                     user.publicKey,
                     userAccounts.userMetadata.publicKey,
+                    sortedTroves,
                     borrowingGlobalAccounts2.stablecoinMint, // borrowingMarketState2 mint
                     stablecoinMintAuthority2, // borrowingMarketState2 mint auth
                     stablecoin2Ata, // borrowingMarketState2 stablecoin ATA
@@ -15,7 +15,7 @@ import * as operations_borrowing from "../operations_borrowing";
 import { newLoanee } from "../operations_borrowing";
 import { CollateralToken, stabilityTokenToNumber } from "../types";
 import { PythUtils } from "../../src/pyth";
-import { getBorrowingVaults } from "../data_provider";
+import { getBorrowingVaults, getStabilityVaults } from "../data_provider";
 
 chai.use(chaiAsPromised)
 
@@ -190,6 +190,10 @@ describe('tests_security_clear_liquidation_gains', () => {
         const { liquidator: liquidator2, } = await newLiquidator(provider, program, borrowingAccounts);
 
         const { collateralVaultsAuthority } = await getBorrowingVaults(program, borrowingAccounts.borrowingVaults.publicKey);
+        const {
+            stablecoinStabilityPoolVault,
+            stablecoinStabilityPoolVaultAuthority
+        } = await getStabilityVaults(program, stabilityPoolAccounts.stabilityVaults.publicKey);
 
         // clearing agent sends an account which it does not sign for
         const ix = await program.instruction.clearLiquidationGains(
@@ -205,6 +209,8 @@ describe('tests_security_clear_liquidation_gains', () => {
                 borrowingAccounts.collateralVaultSol,
                 collateralVaultsAuthority,
                 stabilityPoolAccounts.liquidationRewardsVaultSol,
+                stablecoinStabilityPoolVault,
+                stablecoinStabilityPoolVaultAuthority,
             ),
             remainingAccounts: [
                 {
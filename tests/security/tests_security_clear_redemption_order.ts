@@ -1,5 +1,5 @@
 import * as instructions_redeem from '../../src/instructions_redeem';
-import { getClearRedemptionOrderAccounts, getMetadataAccounts } from '../../src/instructions_redeem';
+import { getCandidatesPageAccounts, getClearRedemptionOrderAccounts, getMetadataAccounts } from '../../src/instructions_redeem';
 import * as chai from 'chai'
 import { expect } from 'chai'
 import chaiAsPromised from 'chai-as-promised'
@@ -15,8 +15,8 @@ import { newClearUser, newFillUser } from "../operations_redemption";
 import * as utils from "../../src/utils";
 import { FILL_INST_METADATA_ACCS_SIZE, waitAndClear } from "../tests_redemption";
 import BN from "bn.js";
-import { getBorrowingVaults } from "../data_provider";
-import { airdropStablecoin } from "../../src/instructions_borrow";
+import { getBorrowingVaults, getUserMetadata } from "../data_provider";
+import { airdropStablecoin, getAccruedInterestAccounts } from "../../src/instructions_borrow";
 import { decimalToU64 } from "../../src/utils";
 
 chai.use(chaiAsPromised)
@@ -53,6 +53,15 @@ describe('tests_security_clear_redemption_order', () => {
         const { borrower: clearer2 } = await newClearUser(env, borrowingAccounts);
 
         const { burningVaultAuthority } = await getBorrowingVaults(program, borrowingAccounts.borrowingVaults.publicKey);
+        const {
+            sortedTroves,
+            stablecoinMintAuthority,
+            borrowingFeesVault,
+            treasuryVault
+        } = await getAccruedInterestAccounts(program, borrowingAccounts.borrowingMarketState.publicKey,
+            borrowingAccounts.borrowingVaults.publicKey, borrowingAccounts.stakingPoolState.publicKey);
+        const { owner: redeemer, stablecoinAta: redeemerStablecoinAta } = await getUserMetadata(program, redeemerMetadata);
+        const redemptionOrder = await utils.findRedemptionOrderAddress(borrowingAccounts.redemptionsQueue, orderId, program.programId);
 
         // clearer2 sends an account which it does not sign for
         const ix = await program.instruction.clearRedemptionOrder(
@@ -61,12 +70,21 @@ describe('tests_security_clear_redemption_order', () => {
                 clearer1,
                 clearer1Accounts.userMetadata.publicKey,
                 redeemerMetadata,
+                redeemer,
+                redeemerStablecoinAta,
                 borrowingAccounts.borrowingMarketState.publicKey,
                 borrowingAccounts.borrowingVaults.publicKey,
+                borrowingAccounts.stakingPoolState.publicKey,
                 borrowingAccounts.redemptionsQueue,
+                redemptionOrder,
+                sortedTroves,
                 borrowingAccounts.burningVault,
                 burningVaultAuthority,
+                borrowingFeesVault,
+                treasuryVault,
                 borrowingAccounts.stablecoinMint,
+                stablecoinMintAuthority,
+                pythPrices,
             ),
             remainingAccounts: [
                 {
@@ -74,6 +92,7 @@ describe('tests_security_clear_redemption_order', () => {
                     isWritable: true,
                     isSigner: true
                 },
+                ...await getCandidatesPageAccounts(program, redemptionOrder),
                 ...getMetadataAccounts(borrowerAndFillerMetadatas, FILL_INST_METADATA_ACCS_SIZE),
             ],
             signers: [clearer2], // clearer2 signs
@@ -112,9 +131,11 @@ describe('tests_security_clear_redemption_order', () => {
             redeemerMetadata,
             borrowingAccounts.borrowingMarketState.publicKey,
             borrowingAccounts.borrowingVaults.publicKey,
+            borrowingAccounts.stakingPoolState.publicKey,
             borrowingAccounts.redemptionsQueue,
             borrowingAccounts.burningVault,
             borrowingAccounts.stablecoinMint,
+            pythPrices,
             orderId,
             borrowerAndFillerMetadatas,
         )).to.be.rejectedWith("A raw constraint was violated");
@@ -138,9 +159,11 @@ describe('tests_security_clear_redemption_order', () => {
             clearer2Accounts.userMetadata.publicKey, // clearer2 userMetadata
             borrowingAccounts.borrowingMarketState.publicKey,
             borrowingAccounts.borrowingVaults.publicKey,
+            borrowingAccounts.stakingPoolState.publicKey,
             borrowingAccounts.redemptionsQueue,
             borrowingAccounts.burningVault,
             borrowingAccounts.stablecoinMint,
+            pythPrices,
             orderId,
             borrowerAndFillerMetadatas,
         )).to.be.rejectedWith("Redeemer does not match with the order being redeemed");
@@ -167,9 +190,11 @@ describe('tests_security_clear_redemption_order', () => {
             redeemerMetadata,
             borrowingAccounts2.borrowingMarketState.publicKey, // borrowingAccounts2 borrowingMarketState
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts1.burningVault,
             borrowingAccounts1.stablecoinMint,
+            pythPrices,
             orderId,
             borrowerAndFillerMetadatas,
         )).to.be.rejectedWith("A has_one constraint was violated");
@@ -196,9 +221,11 @@ describe('tests_security_clear_redemption_order', () => {
             redeemerMetadata,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts2.borrowingVaults.publicKey, // borrowingAccounts2 borrowingVaults
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts1.burningVault,
             borrowingAccounts1.stablecoinMint,
+            pythPrices,
             orderId,
             borrowerAndFillerMetadatas,
         )).to.be.rejectedWith("A has_one constraint was violated");
@@ -225,9 +252,11 @@ describe('tests_security_clear_redemption_order', () => {
             redeemerMetadata,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts2.redemptionsQueue, // borrowingAccounts2 redemptionsQueue
             borrowingAccounts1.burningVault,
             borrowingAccounts1.stablecoinMint,
+            pythPrices,
             orderId,
             borrowerAndFillerMetadatas,
         )).to.be.rejectedWith("A has_one constraint was violated");
@@ -258,9 +287,11 @@ describe('tests_security_clear_redemption_order', () => {
             redeemerMetadata,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts2.burningVault, // borrowingAccounts2 burningVault
             borrowingAccounts2.stablecoinMint, // borrowingAccounts2 stablecoinMint
+            pythPrices,
             orderId,
             borrowerAndFillerMetadatas,
         )).to.be.rejectedWith("A has_one constraint was violated");
@@ -286,9 +317,11 @@ describe('tests_security_clear_redemption_order', () => {
             redeemerMetadata,
             borrowingAccounts1.borrowingMarketState.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.redemptionsQueue,
             borrowingAccounts1.burningVault,
             borrowingAccounts1.stablecoinMint,
+            pythPrices,
             orderId,
             borrowingAccounts2Candidates, // borrowingAccounts2 candidateMetadatas
         )).to.be.rejectedWith("A has_one constraint was violated");
@@ -358,7 +391,7 @@ const newClearRedemptionOrderScenario = async (
     const clearer = await newClearUser(env, borrowingAccounts);
 
     // Clear the fish user to ensure order is in clearing state
-    await waitAndClear(env.provider, env.program, borrowingAccounts, clearer, redemptionUser, orderId, [
+    await waitAndClear(env.provider, env.program, borrowingAccounts, clearer, redemptionUser, pythPrices, orderId, [
         fishUser.borrowerAccounts.userMetadata.publicKey,
         fillUser.borrowerAccounts.userMetadata.publicKey,
     ])
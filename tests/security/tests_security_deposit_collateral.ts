@@ -9,14 +9,19 @@ import chaiAsPromised from 'chai-as-promised'
 import { expect } from "chai";
 import { TokenInstructions } from "@project-serum/serum";
 import { Transaction } from "@solana/web3.js";
-import { Env, setUpProgram } from "../../src/set_up";
+import { Env, PythPrices, setUpProgram, setUpPythPrices } from "../../src/set_up";
 
 chai.use(chaiAsPromised)
 
 describe('tests_security_deposit_collateral', () => {
-    const { initialMarketOwner, provider, program, } = setUpProgram();
+    const { initialMarketOwner, provider, program, pyth, } = setUpProgram();
     const env = { provider, program, initialMarketOwner } as Env;
 
+    let pythPrices: PythPrices;
+    beforeEach('set_up_prices', async () => {
+        pythPrices = await setUpPythPrices(provider, pyth);
+    })
+
     it('security_deposit_collateral_different_token_to_mint', async () => {
         const borrowingGlobalAccounts = (await operations_borrowing.initialiseBorrowingMarkets(env)).borrowingAccounts;
         const {
@@ -38,6 +43,8 @@ describe('tests_security_deposit_collateral', () => {
                 userAccounts.srmAta, // SRM ATA
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(10, "ETH"),
                 [user],
                 "ETH") // ETH token
@@ -65,6 +72,8 @@ describe('tests_security_deposit_collateral', () => {
                 user.publicKey, // SOL account
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(0.5, "SRM"),
                 [user],
                 "SRM") // SRM token
@@ -98,6 +107,8 @@ describe('tests_security_deposit_collateral', () => {
                 user1Accounts.ethAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(10, "ETH"),
                 [user1],
                 "ETH")
@@ -131,6 +142,8 @@ describe('tests_security_deposit_collateral', () => {
                 user1Accounts.ethAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(10, "ETH"),
                 [user1],
                 "ETH")
@@ -154,6 +167,13 @@ describe('tests_security_deposit_collateral', () => {
             ["ETH", 22],
         ]));
 
+        const interestAccounts = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingGlobalAccounts.borrowingMarketState.publicKey,
+            borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
+        );
+
         // user2 passes their ATA and user1's account and metadata, user2 co-signs
         const ix = await program.instruction.depositCollateral(
             new anchor.BN(utils.collToLamports(10, "ETH")), new anchor.BN(collateralTokenToNumber("ETH")),
@@ -162,12 +182,21 @@ describe('tests_security_deposit_collateral', () => {
                     owner: user1.publicKey,
                     borrowingMarketState: borrowingGlobalAccounts.borrowingMarketState.publicKey,
                     borrowingVaults: borrowingGlobalAccounts.borrowingVaults.publicKey,
+                    stakingPoolState: borrowingGlobalAccounts.stakingPoolState.publicKey,
                     userMetadata: user1Accounts.userMetadata.publicKey,
                     collateralFrom: user2Accounts.ethAta, // user2 ATA
                     collateralTo: borrowingGlobalAccounts.collateralVaultEth,
                     collateralTokenMint: borrowingGlobalAccounts.ethMint,
+                    ...interestAccounts,
+                    pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
+                    pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
+                    pythEthPriceInfo: pythPrices.ethPythPrice.publicKey,
+                    pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
+                    pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
+                    pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
                     systemProgram: anchor.web3.SystemProgram.programId,
                     tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
+                    clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
                 },
                 remainingAccounts: [
                     {
@@ -203,6 +232,13 @@ describe('tests_security_deposit_collateral', () => {
             ["SOL", 1],
         ]));
 
+        const interestAccounts = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingGlobalAccounts.borrowingMarketState.publicKey,
+            borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
+        );
+
         // user1 passes user2's account, user2 co-signs
         const ix = await program.instruction.depositCollateral(
             new anchor.BN(utils.collToLamports(0.5, "SOL")), new anchor.BN(collateralTokenToNumber("SOL")),
@@ -211,12 +247,21 @@ describe('tests_security_deposit_collateral', () => {
                     owner: user1.publicKey,
                     borrowingMarketState: borrowingGlobalAccounts.borrowingMarketState.publicKey,
                     borrowingVaults: borrowingGlobalAccounts.borrowingVaults.publicKey,
+                    stakingPoolState: borrowingGlobalAccounts.stakingPoolState.publicKey,
                     userMetadata: user1Accounts.userMetadata.publicKey,
                     collateralFrom: user2.publicKey, // user2 SOL account
                     collateralTo: borrowingGlobalAccounts.collateralVaultSol,
                     collateralTokenMint: borrowingGlobalAccounts.ethMint,
+                    ...interestAccounts,
+                    pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
+                    pythBtcPriceInfo: pythPrices.btcPythPrice.publicKey,
+                    pythEthPriceInfo: pythPrices.ethPythPrice.publicKey,
+                    pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
+                    pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
+                    pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
                     systemProgram: anchor.web3.SystemProgram.programId,
                     tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
+                    clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
                 },
                 remainingAccounts: [
                     {
@@ -260,6 +305,8 @@ describe('tests_security_deposit_collateral', () => {
                 user.publicKey,
                 borrowingGlobalAccounts2.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts2.borrowingVaults.publicKey,
+                borrowingGlobalAccounts2.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(0.5, "SOL"),
                 [user],
                 "SOL")
@@ -291,6 +338,8 @@ describe('tests_security_deposit_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts1.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts2.borrowingVaults.publicKey,
+                borrowingGlobalAccounts1.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(0.5, "SOL"),
                 [user1],
                 "SOL")
@@ -1,5 +1,6 @@
 import * as instructions_redeem from '../../src/instructions_redeem';
-import { getAddRedemptionOrderAccounts, getFillRedemptionOrderAccounts, getMetadataAccounts } from '../../src/instructions_redeem';
+import { getAddRedemptionOrderAccounts, getCandidatesPageAccounts, getFillRedemptionOrderAccounts, getMetadataAccounts } from '../../src/instructions_redeem';
+import { getAccruedInterestAccounts } from '../../src/instructions_borrow';
 import * as chai from 'chai'
 import { expect } from 'chai'
 import chaiAsPromised from 'chai-as-promised'
@@ -49,6 +50,15 @@ describe('tests_security_fill_redemption_order', () => {
         } = await newFillRedemptionOrderScenario(env, initialMarketOwner, pythPrices);
 
         const { borrower: filler2 } = await newFillUser(env, borrowingAccounts);
+        const redemptionOrder = await utils.findRedemptionOrderAddress(borrowingAccounts.redemptionsQueue, orderId, program.programId);
+        const {
+            stablecoinMint,
+            stablecoinMintAuthority,
+            borrowingFeesVault,
+            treasuryVault
+        } = await getAccruedInterestAccounts(program, borrowingAccounts.borrowingMarketState.publicKey,
+            borrowingAccounts.borrowingVaults.publicKey, borrowingAccounts.stakingPoolState.publicKey);
+
         // filler2 sends an account which it does not sign for
         const ix = await program.instruction.fillRedemptionOrder(
             new BN(orderId), {
@@ -57,6 +67,13 @@ describe('tests_security_fill_redemption_order', () => {
                 filler1Accounts.userMetadata.publicKey,
                 borrowingAccounts.borrowingMarketState.publicKey,
                 borrowingAccounts.redemptionsQueue,
+                redemptionOrder,
+                borrowingAccounts.borrowingVaults.publicKey,
+                borrowingAccounts.stakingPoolState.publicKey,
+                stablecoinMint,
+                stablecoinMintAuthority,
+                borrowingFeesVault,
+                treasuryVault,
             ),
             remainingAccounts: [
                 {
@@ -64,6 +81,7 @@ describe('tests_security_fill_redemption_order', () => {
                     isWritable: true,
                     isSigner: true
                 },
+                ...await getCandidatesPageAccounts(program, redemptionOrder),
                 ...getMetadataAccounts(borrowingUserMetadatas, FILL_INST_METADATA_ACCS_SIZE),
             ],
             signers: [filler2], // filler2 signs
@@ -99,6 +117,8 @@ describe('tests_security_fill_redemption_order', () => {
             filler1,
             filler2Accounts.userMetadata.publicKey, // filler2 userMetadata
             borrowingAccounts.borrowingMarketState.publicKey,
+            borrowingAccounts.borrowingVaults.publicKey,
+            borrowingAccounts.stakingPoolState.publicKey,
             borrowingAccounts.redemptionsQueue,
             orderId,
             borrowingUserMetadatas,
@@ -123,6 +143,8 @@ describe('tests_security_fill_redemption_order', () => {
             filler,
             fillerAccounts.userMetadata.publicKey,
             borrowingAccounts2.borrowingMarketState.publicKey, // borrowingAccounts2 borrowingMarketState
+            borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.redemptionsQueue,
             orderId,
             borrowingUserMetadatas,
@@ -147,6 +169,8 @@ describe('tests_security_fill_redemption_order', () => {
             filler,
             fillerAccounts.userMetadata.publicKey,
             borrowingAccounts1.borrowingMarketState.publicKey,
+            borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts2.redemptionsQueue, // borrowingAccounts2 redemptionsQueue
             orderId,
             borrowingUserMetadatas,
@@ -170,6 +194,8 @@ describe('tests_security_fill_redemption_order', () => {
             filler,
             fillerAccounts.userMetadata.publicKey,
             borrowingAccounts1.borrowingMarketState.publicKey,
+            borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.redemptionsQueue,
             orderId,
             borrowingAccounts2Candidates, // borrowingAccounts2 candidateMetadatas
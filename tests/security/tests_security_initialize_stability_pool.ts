@@ -41,7 +41,7 @@ describe('tests_security_initialize_stability_pool', () => {
         await displayBorrowingMarketState(program, borrowingAccounts.borrowingMarketState.publicKey)
 
         // different initial market owner
-        const ix = program.instruction.stabilityInitialize({
+        const ix = program.instruction.stabilityInitialize(0, {
             accounts: instructions_stability.utils.initializeStabilityPoolAccounts(
                 differentOwner.publicKey, // different owner
                 borrowingAccounts,
@@ -89,7 +89,7 @@ describe('tests_security_initialize_stability_pool', () => {
         await displayBorrowingMarketState(program, borrowingAccounts.borrowingMarketState.publicKey)
 
         // different stability accounts owner
-        const ix: TransactionInstruction = program.instruction.stabilityInitialize({
+        const ix: TransactionInstruction = program.instruction.stabilityInitialize(0, {
             accounts: instructions_stability.utils.initializeStabilityPoolAccounts(
                 initialMarketOwner,
                 borrowingAccounts,
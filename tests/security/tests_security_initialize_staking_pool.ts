@@ -45,6 +45,7 @@ describe('tests_security_initialize_staking_pool', () => {
                 initialMarketOwner: differentOwner.publicKey, // different initial market owner
                 borrowingMarketState: borrowingAccounts.borrowingMarketState.publicKey,
                 stakingPoolState: borrowingAccounts.stakingPoolState.publicKey,
+                boostCheckpoints: differentOwnerAccounts.boostCheckpoints,
                 stakingVault: differentOwnerAccounts.stakingVault,
                 treasuryVault: differentOwnerAccounts.treasuryVault,
                 tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
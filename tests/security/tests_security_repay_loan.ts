@@ -67,6 +67,7 @@ describe('tests_security_repay_loan', () => {
             userAccounts.stablecoinAta,
             borrowingGlobalAccounts2.borrowingMarketState.publicKey, // borrowingMarketState2
             borrowingGlobalAccounts1.borrowingVaults.publicKey,
+            borrowingGlobalAccounts1.stakingPoolState.publicKey,
             borrowingGlobalAccounts1.burningVault,
             pythPrices,
             2000,
@@ -106,6 +107,7 @@ describe('tests_security_repay_loan', () => {
             userAccounts.stablecoinAta,
             borrowingGlobalAccounts1.borrowingMarketState.publicKey,
             borrowingGlobalAccounts2.borrowingVaults.publicKey, // borrowingMarketState2 borrowingVaults
+            borrowingGlobalAccounts1.stakingPoolState.publicKey,
             borrowingGlobalAccounts1.burningVault,
             pythPrices,
             2000,
@@ -145,6 +147,7 @@ describe('tests_security_repay_loan', () => {
             userAccounts.stablecoinAta,
             borrowingGlobalAccounts.borrowingMarketState.publicKey,
             borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
             stablecoinAta, // different burningVault
             pythPrices,
             2000,
@@ -183,6 +186,7 @@ describe('tests_security_repay_loan', () => {
             user1Accounts.stablecoinAta,
             borrowingGlobalAccounts.borrowingMarketState.publicKey,
             borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
             borrowingGlobalAccounts.burningVault,
             pythPrices,
             2000,
@@ -220,6 +224,12 @@ describe('tests_security_repay_loan', () => {
 
         const { stablecoinMintAuthority: stablecoinMintAuthority2, } = await getBorrowingMarketState(program, borrowingGlobalAccounts2.borrowingMarketState.publicKey);
         const { burningVaultAuthority: burningVaultAuthority2, } = await getBorrowingVaults(program, borrowingGlobalAccounts2.borrowingVaults.publicKey);
+        const { sortedTroves, borrowingFeesVault, treasuryVault } = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingGlobalAccounts1.borrowingMarketState.publicKey,
+            borrowingGlobalAccounts1.borrowingVaults.publicKey,
+            borrowingGlobalAccounts1.stakingPoolState.publicKey,
+        );
 
         // user borrowingMarketState2 stablecoin mint and mint authority
         const ix = await program.instruction.repayLoan(
@@ -230,11 +240,16 @@ describe('tests_security_repay_loan', () => {
                     userAccounts.userMetadata.publicKey,
                     borrowingGlobalAccounts1.borrowingMarketState.publicKey,
                     borrowingGlobalAccounts1.borrowingVaults.publicKey,
+                    borrowingGlobalAccounts1.stakingPoolState.publicKey,
+                    sortedTroves,
                     borrowingGlobalAccounts2.stablecoinMint, // borrowingMarketState2 mint
                     stablecoinMintAuthority2, // borrowingMarketState2 mint auth
                     borrowingGlobalAccounts2.burningVault, // borrowingMarketState2 burningVault
                     burningVaultAuthority2, // borrowingMarketState2 burningVaultAuthority
+                    borrowingFeesVault,
+                    treasuryVault,
                     stablecoin2Ata, // borrowingMarketState2 stablecoin ATA
+                    pythPrices,
                 ),
                 signers: [user]
             });
@@ -270,6 +285,12 @@ describe('tests_security_repay_loan', () => {
 
         const { stablecoinMintAuthority, } = await getBorrowingMarketState(program, borrowingGlobalAccounts.borrowingMarketState.publicKey);
         const { burningVaultAuthority, } = await getBorrowingVaults(program, borrowingGlobalAccounts.borrowingVaults.publicKey);
+        const { sortedTroves, borrowingFeesVault, treasuryVault } = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingGlobalAccounts.borrowingMarketState.publicKey,
+            borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
+        );
 
         // user1 sends user2's stablecoin ATA, user2 co-signs
         const ix = await program.instruction.repayLoan(
@@ -280,11 +301,16 @@ describe('tests_security_repay_loan', () => {
                     user1Accounts.userMetadata.publicKey,
                     borrowingGlobalAccounts.borrowingMarketState.publicKey,
                     borrowingGlobalAccounts.borrowingVaults.publicKey,
+                    borrowingGlobalAccounts.stakingPoolState.publicKey,
+                    sortedTroves,
                     borrowingGlobalAccounts.stablecoinMint,
                     stablecoinMintAuthority,
                     borrowingGlobalAccounts.burningVault,
                     burningVaultAuthority,
+                    borrowingFeesVault,
+                    treasuryVault,
                     user2Accounts.stablecoinAta, // user2 stablecoin ATA
+                    pythPrices,
                 ),
                 remainingAccounts: [
                     {
@@ -14,7 +14,7 @@ describe('tests_security_stability_approve', () => {
     const env = { provider, program, initialMarketOwner } as set_up.Env;
 
     it('security_stability_approve_same_state_account_twice', async () => {
-        const { borrowingAccounts, } = await operations_stability.createMarketAndStabilityPool(env);
+        const { borrowingAccounts, stabilityPoolAccounts } = await operations_stability.createMarketAndStabilityPool(env);
 
         const { keyPair: user } = await utils.solAccountWithMinBalance(provider, 1);
 
@@ -24,7 +24,8 @@ describe('tests_security_stability_approve', () => {
             [user],
             user.publicKey,
             program,
-            borrowingAccounts
+            borrowingAccounts,
+            stabilityPoolAccounts
         );
 
         await instructions_stability.approveStability(
@@ -12,6 +12,7 @@ import * as anchor from "@project-serum/anchor";
 import { airdropStablecoin } from "../../src/instructions_borrow";
 import { initialiseBorrowingMarkets, mintToAta } from "../operations_borrowing";
 import { decimalToU64 } from "../../src/utils";
+import { getStabilityVaults } from "../data_provider";
 
 chai.use(chaiAsPromised)
 
@@ -261,6 +262,7 @@ describe('tests_security_stability_provide', () => {
         const { stabilityProvider: stabilityProvider2, stabilityProviderAccounts: stabilityProvider2Accounts } = await newStabilityPoolUser(provider, program, borrowingAccounts,
             stabilityPoolAccounts, stablecoinToProvide);
 
+        const { stabilityReceiptMint, stabilityReceiptMintAuthority } = await getStabilityVaults(program, stabilityPoolAccounts.stabilityVaults.publicKey);
 
         const ix = program.instruction.stabilityProvide(
             new anchor.BN(stablecoinToProvide), {
@@ -273,6 +275,9 @@ describe('tests_security_stability_provide', () => {
                 stabilityPoolAccounts.epochToScaleToSum,
                 stabilityPoolAccounts.stablecoinStabilityPoolVault,
                 stabilityProvider2Accounts.stablecoinAta, // stabilityProvider2's stablecoin ATA
+                stabilityReceiptMint,
+                stabilityReceiptMintAuthority,
+                stabilityProvider1Accounts.stabilityReceiptAta,
             ),
             remainingAccounts: [
                 {
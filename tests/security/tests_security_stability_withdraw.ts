@@ -239,7 +239,11 @@ describe('tests_security_stability_withdraw', () => {
         const { stabilityProvider: stabilityProvider2, stabilityProviderAccounts: stabilityProvider2Accounts } = await newStabilityProvider(provider, program, borrowingAccounts,
             stabilityPoolAccounts, stablecoinToWithdraw);
 
-        const { stablecoinStabilityPoolVaultAuthority } = await getStabilityVaults(program, stabilityPoolAccounts.stabilityVaults.publicKey);
+        const {
+            stablecoinStabilityPoolVaultAuthority,
+            stabilityReceiptMint,
+            stabilityReceiptMintAuthority
+        } = await getStabilityVaults(program, stabilityPoolAccounts.stabilityVaults.publicKey);
 
         const ix = program.instruction.stabilityWithdraw(
             new anchor.BN(stablecoinToWithdraw), {
@@ -253,6 +257,9 @@ describe('tests_security_stability_withdraw', () => {
                 stabilityPoolAccounts.stablecoinStabilityPoolVault,
                 stablecoinStabilityPoolVaultAuthority,
                 stabilityProvider2Accounts.stablecoinAta, // stabilityProvider2's stablecoin ATA
+                stabilityReceiptMint,
+                stabilityReceiptMintAuthority,
+                stabilityProvider1Accounts.stabilityReceiptAta,
             ),
             remainingAccounts: [
                 {
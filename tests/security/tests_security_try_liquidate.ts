@@ -48,6 +48,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
             stabilityPool1Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault,
             liquidationPrices1,
@@ -78,6 +79,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPoolAccounts.stabilityVaults.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
             stabilityPoolAccounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPoolAccounts.stablecoinStabilityPoolVault,
             liquidationPrices,
@@ -113,6 +115,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
             stabilityPool1Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault,
             liquidationPrices1,
@@ -146,6 +149,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
             stabilityPool1Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault,
             liquidationPrices,
@@ -179,6 +183,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool2Accounts.stabilityVaults.publicKey, // stabilityPool2 stabilityVaults
             borrowingAccounts1.borrowingVaults.publicKey,
             stabilityPool1Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault,
             liquidationPrices,
@@ -212,6 +217,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts2.borrowingVaults.publicKey, // borrowingAccounts2 borrowingVaults
             stabilityPool1Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault,
             liquidationPrices,
@@ -245,6 +251,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts2.borrowingVaults.publicKey,
             stabilityPool2Accounts.liquidationsQueue, // stabilityPool2 liquidationsQueue
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault,
             liquidationPrices,
@@ -278,6 +285,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts2.borrowingVaults.publicKey,
             stabilityPool2Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault, // stabilityPool2 stablecoinStabilityPoolVault
             liquidationPrices,
@@ -311,6 +319,7 @@ describe('tests_security_try_liquidate', () => {
             stabilityPool1Accounts.stabilityVaults.publicKey,
             borrowingAccounts1.borrowingVaults.publicKey,
             stabilityPool2Accounts.liquidationsQueue,
+            borrowingAccounts1.stakingPoolState.publicKey,
             borrowingAccounts1.stablecoinMint,
             stabilityPool1Accounts.stablecoinStabilityPoolVault, // stabilityPool2 stablecoinStabilityPoolVault
             liquidationPrices,
@@ -338,6 +347,13 @@ describe('tests_security_try_liquidate', () => {
 
         const { stablecoinStabilityPoolVaultAuthority } = await getStabilityVaults(program, stabilityPool2Accounts.stabilityVaults.publicKey);
 
+        const { sortedTroves, borrowingFeesVault, treasuryVault } = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingAccounts1.borrowingMarketState.publicKey,
+            borrowingAccounts1.borrowingVaults.publicKey,
+            borrowingAccounts1.stakingPoolState.publicKey,
+        );
+
         // liquidator sends stabilityPool2 stablecoinMint + stablecoinMintAuthority
         const ix = await program.instruction.tryLiquidate({
             accounts: instructions_borrow.utils.getTryLiquidateAccounts(
@@ -345,12 +361,16 @@ describe('tests_security_try_liquidate', () => {
                 borrowingAccounts1.borrowingMarketState.publicKey,
                 borrowingAccounts1.stabilityPoolState.publicKey,
                 borrowerAccounts.userMetadata.publicKey,
+                sortedTroves,
                 stabilityPool1Accounts.epochToScaleToSum,
                 stabilityPool2Accounts.stabilityVaults.publicKey,
                 borrowingAccounts1.borrowingVaults.publicKey,
                 stabilityPool1Accounts.liquidationsQueue,
+                borrowingAccounts1.stakingPoolState.publicKey,
                 borrowingAccounts2.stablecoinMint, // stabilityPool2 stablecoinMint
                 stablecoinMintAuthority, // stabilityPool2 stablecoinMintAuthority
+                borrowingFeesVault,
+                treasuryVault,
                 stabilityPool2Accounts.stablecoinStabilityPoolVault, // stabilityPool2 stablecoinStabilityPoolVault
                 stablecoinStabilityPoolVaultAuthority, // stabilityPool2 stablecoinStabilityPoolVault
                 liquidationPrices,
@@ -381,6 +401,13 @@ describe('tests_security_try_liquidate', () => {
 
         const { stablecoinStabilityPoolVaultAuthority } = await getStabilityVaults(program, stabilityPoolAccounts.stabilityVaults.publicKey);
 
+        const { sortedTroves, borrowingFeesVault, treasuryVault } = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingAccounts.borrowingMarketState.publicKey,
+            borrowingAccounts.borrowingVaults.publicKey,
+            borrowingAccounts.stakingPoolState.publicKey,
+        );
+
         // liquidator1 sends liquidator2
         const ix = await program.instruction.tryLiquidate({
             accounts: instructions_borrow.utils.getTryLiquidateAccounts(
@@ -388,12 +415,16 @@ describe('tests_security_try_liquidate', () => {
                 borrowingAccounts.borrowingMarketState.publicKey,
                 borrowingAccounts.stabilityPoolState.publicKey,
                 borrowerAccounts.userMetadata.publicKey,
+                sortedTroves,
                 stabilityPoolAccounts.epochToScaleToSum,
                 stabilityPoolAccounts.stabilityVaults.publicKey,
                 borrowingAccounts.borrowingVaults.publicKey,
                 stabilityPoolAccounts.liquidationsQueue,
+                borrowingAccounts.stakingPoolState.publicKey,
                 borrowingAccounts.stablecoinMint,
                 stablecoinMintAuthority,
+                borrowingFeesVault,
+                treasuryVault,
                 stabilityPoolAccounts.stablecoinStabilityPoolVault,
                 stablecoinStabilityPoolVaultAuthority,
                 liquidationPrices,
@@ -56,6 +56,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultSrm, // SRM vault
                 userAccounts.srmAta, // SRM ATA
@@ -86,6 +87,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultSol, // SOL vault
                 user.publicKey, // SOL account
@@ -120,6 +122,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultSrm, // SRM vault holding SOL
                 userAccounts.srmAta, // SRM ATA can hold SOL
@@ -158,6 +161,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 user2Accounts.userMetadata.publicKey, // user 2 metadata
                 borrowingGlobalAccounts.collateralVaultEth,
                 user1Accounts.ethAta,
@@ -196,6 +200,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 user2Accounts.userMetadata.publicKey, // user 2 metadata
                 borrowingGlobalAccounts.collateralVaultEth,
                 user2Accounts.ethAta, // user 2 ATA
@@ -227,6 +232,13 @@ describe('tests_security_withdraw_collateral', () => {
             ["ETH", 22],
         ]));
 
+        const interestAccounts = await instructions_borrow.getAccruedInterestAccounts(
+            program,
+            borrowingGlobalAccounts.borrowingMarketState.publicKey,
+            borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
+        );
+
         // user1 passes user2's ATA and authority instead of collateral vault, user2 co-signs
         const ix = await program.instruction.withdrawCollateral(
             new anchor.BN(utils.collToLamports(10, "ETH")), new anchor.BN(collateralTokenToNumber("ETH")),
@@ -235,10 +247,12 @@ describe('tests_security_withdraw_collateral', () => {
                     owner: user1.publicKey,
                     borrowingMarketState: borrowingGlobalAccounts.borrowingMarketState.publicKey,
                     borrowingVaults: borrowingGlobalAccounts.borrowingVaults.publicKey,
+                    stakingPoolState: borrowingGlobalAccounts.stakingPoolState.publicKey,
                     userMetadata: user1Accounts.userMetadata.publicKey,
                     collateralFrom: user2Accounts.ethAta, // user2 ATA instead of vault
                     collateralFromAuthority: user2.publicKey, // user2 authority
                     collateralTo: user1Accounts.ethAta,
+                    ...interestAccounts,
                     systemProgram: anchor.web3.SystemProgram.programId,
                     tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
                     pythSolPriceInfo: pythPrices.solPythPrice.publicKey,
@@ -247,6 +261,7 @@ describe('tests_security_withdraw_collateral', () => {
                     pythSrmPriceInfo: pythPrices.srmPythPrice.publicKey,
                     pythRayPriceInfo: pythPrices.rayPythPrice.publicKey,
                     pythFttPriceInfo: pythPrices.fttPythPrice.publicKey,
+                    clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
                 },
                 remainingAccounts: [
                     {
@@ -293,6 +308,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 user1Accounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultEth,
                 user2Accounts.ethAta, // user 2 ATA
@@ -331,6 +347,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 user1Accounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultSol,
                 user2.publicKey, // user2 account
@@ -372,6 +389,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts2.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts2.borrowingVaults.publicKey,
+                borrowingGlobalAccounts2.stakingPoolState.publicKey,
                 user1Accounts.userMetadata.publicKey,
                 borrowingGlobalAccounts2.collateralVaultSol,
                 user1.publicKey,
@@ -413,6 +431,7 @@ describe('tests_security_withdraw_collateral', () => {
                 user1.publicKey,
                 borrowingGlobalAccounts1.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts2.borrowingVaults.publicKey,
+                borrowingGlobalAccounts1.stakingPoolState.publicKey,
                 user1Accounts.userMetadata.publicKey,
                 borrowingGlobalAccounts2.collateralVaultSol,
                 user1.publicKey,
@@ -325,6 +325,7 @@ describe('tests_borrowing', () => {
     });
 
     it('tests_borrowing_deposit_collateral_expanded', async () => {
+        const pythPrices = await set_up.setUpPythPrices(provider, pyth);
 
         const user = anchor.web3.Keypair.generate();
         await provider.connection.requestAirdrop(user.publicKey, utils.collToLamports(10, "SOL"));
@@ -389,6 +390,8 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositSol, "SOL"),
                 [user]);
 
@@ -407,6 +410,7 @@ describe('tests_borrowing', () => {
     });
 
     it('tests_borrowing_deposit_collateral_simple', async () => {
+        const pythPrices = await set_up.setUpPythPrices(provider, pyth);
         let {
             user,
             userAccounts,
@@ -424,6 +428,8 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositSol, "SOL"),
                 [user]);
 
@@ -441,6 +447,7 @@ describe('tests_borrowing', () => {
     });
 
     it('tests_borrowing_deposit_collateral_eth', async () => {
+        const pythPrices = await set_up.setUpPythPrices(provider, pyth);
         let {
             user,
             userAccounts,
@@ -460,6 +467,8 @@ describe('tests_borrowing', () => {
                 userAccounts.ethAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositEth, "ETH"),
                 [user],
                 "ETH");
@@ -479,6 +488,7 @@ describe('tests_borrowing', () => {
     });
 
     it('tests_borrowing_deposit_collateral_sol_eth_btc', async () => {
+        const pythPrices = await set_up.setUpPythPrices(provider, pyth);
         let {
             user,
             userAccounts,
@@ -508,6 +518,8 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositSol, "SOL"),
                 [user],
                 "SOL");
@@ -522,6 +534,8 @@ describe('tests_borrowing', () => {
                 userAccounts.ethAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositEth, "ETH"),
                 [user],
                 "ETH");
@@ -536,6 +550,8 @@ describe('tests_borrowing', () => {
                 userAccounts.btcAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositBtc, "BTC"),
                 [user],
                 "BTC");
@@ -602,6 +618,8 @@ describe('tests_borrowing', () => {
                 userAccounts.ethAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositEth, "ETH"),
                 [user],
                 "ETH");
@@ -613,6 +631,7 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultEth,
                 userAccounts.ethAta,
@@ -676,6 +695,8 @@ describe('tests_borrowing', () => {
                 userAccounts.srmAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositSrm, "SRM"),
                 [user],
                 "SRM");
@@ -689,6 +710,8 @@ describe('tests_borrowing', () => {
                 userAccounts.fttAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositFtt, "FTT"),
                 [user],
                 "FTT");
@@ -702,6 +725,8 @@ describe('tests_borrowing', () => {
                 userAccounts.rayAta,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositRay, "RAY"),
                 [user],
                 "RAY");
@@ -714,6 +739,7 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultFtt,
                 userAccounts.fttAta,
@@ -727,6 +753,7 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultSrm,
                 userAccounts.srmAta,
@@ -740,6 +767,7 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
                 userAccounts.userMetadata.publicKey,
                 borrowingGlobalAccounts.collateralVaultRay,
                 userAccounts.rayAta,
@@ -860,6 +888,8 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositSol, "SOL"),
                 [user]);
 
@@ -957,6 +987,8 @@ describe('tests_borrowing', () => {
                 user.publicKey,
                 borrowingGlobalAccounts.borrowingMarketState.publicKey,
                 borrowingGlobalAccounts.borrowingVaults.publicKey,
+                borrowingGlobalAccounts.stakingPoolState.publicKey,
+                pythPrices,
                 utils.collToLamports(depositSol, "SOL"),
                 [user]);
 
@@ -996,6 +1028,7 @@ describe('tests_borrowing', () => {
     });
 
     it('tests_borrowing_when_borrow_not_initialized_trove_then_error', async () => {
+        const pythPrices = await set_up.setUpPythPrices(provider, pyth);
 
         const user = anchor.web3.Keypair.generate();
         await provider.connection.requestAirdrop(user.publicKey, utils.collToLamports(10, "SOL"));
@@ -1046,6 +1079,8 @@ describe('tests_borrowing', () => {
                     user.publicKey,
                     borrowingGlobalAccounts.borrowingMarketState.publicKey,
                     borrowingGlobalAccounts.borrowingVaults.publicKey,
+                    borrowingGlobalAccounts.stakingPoolState.publicKey,
+                    pythPrices,
                     utils.collToLamports(5, "SOL"),
                     [user]);
             assert.fail("Should not reach this");
@@ -1068,7 +1103,7 @@ describe('tests_borrowing', () => {
 
         // deposit SOL
         const depositSol = 5;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingGlobalAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingGlobalAccounts, pythPrices);
         await sleep(1000)
         await assertGlobalCollateral(
             program,
@@ -1108,7 +1143,7 @@ describe('tests_borrowing', () => {
 
         // deposit SOL
         const depositSol = 5;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingGlobalAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingGlobalAccounts, pythPrices);
         await sleep(1000)
         await assertGlobalCollateral(
             program,
@@ -1153,9 +1188,9 @@ describe('tests_borrowing', () => {
 
         // deposit SOL
         const depositUser1Sol = 10;
-        await operations_borrowing.depositCollateral(provider, program, depositUser1Sol, user1, user1Accounts, borrowingGlobalAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositUser1Sol, user1, user1Accounts, borrowingGlobalAccounts, pythPrices);
         const depositUser2Sol = 20;
-        await operations_borrowing.depositCollateral(provider, program, depositUser2Sol, user2, user2Accounts, borrowingGlobalAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositUser2Sol, user2, user2Accounts, borrowingGlobalAccounts, pythPrices);
         await sleep(1000)
         await assertGlobalCollateral(
             program, provider,
@@ -1206,9 +1241,9 @@ describe('tests_borrowing', () => {
 
         // deposit SOL
         const depositUser1Sol = 10;
-        await operations_borrowing.depositCollateral(provider, program, depositUser1Sol, user1, user1Accounts, borrowingGlobalAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositUser1Sol, user1, user1Accounts, borrowingGlobalAccounts, pythPrices);
         const depositUser2Sol = 20;
-        await operations_borrowing.depositCollateral(provider, program, depositUser2Sol, user2, user2Accounts, borrowingGlobalAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositUser2Sol, user2, user2Accounts, borrowingGlobalAccounts, pythPrices);
         await sleep(1000)
         await assertGlobalCollateral(
             program, provider,
@@ -18,7 +18,7 @@ import chaiAsPromised from 'chai-as-promised'
 chai.use(chaiAsPromised)
 
 const REDEMPTION_CLEAR_WAIT_TIME: number = 5000;
-const REDEMPTION_ORDERS_QUEUE_SIZE: number = 15;
+const REDEMPTION_ORDERS_BATCH_SIZE: number = 15;
 const REDEMPTION_CANDIDATE_QUEUE_SIZE: number = 32;
 export const FILL_INST_METADATA_ACCS_SIZE: number = 3;
 export const CLEAR_INST_METADATA_ACCS_SIZE: number = 6;
@@ -97,7 +97,7 @@ describe('tests_redemption', () => {
         ]);
 
         await waitAndClear(provider, program, borrowingGlobalAccounts,
-            clearUser, redemptionUser, 0, [
+            clearUser, redemptionUser, pythPrices, 0, [
             loanee.borrowerAccounts.userMetadata.publicKey,
             fillUser.borrowerAccounts.userMetadata.publicKey,
         ]
@@ -194,7 +194,7 @@ describe('tests_redemption', () => {
             },
         ]);
 
-        await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, 0, [
+        await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, pythPrices, 0, [
             loanee1.borrowerAccounts.userMetadata.publicKey,
             loanee2.borrowerAccounts.userMetadata.publicKey,
             fillUser.borrowerAccounts.userMetadata.publicKey,
@@ -309,7 +309,7 @@ describe('tests_redemption', () => {
         for (let i = 0, batch = 0; i < REDEMPTION_CANDIDATE_QUEUE_SIZE; i += batch) {
             // subtract 1 to allow space for fill user
             batch = Math.min(CLEAR_INST_METADATA_ACCS_SIZE - 1, REDEMPTION_CANDIDATE_QUEUE_SIZE - i);
-            await clearWithRetry(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, 0, [
+            await clearWithRetry(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, pythPrices, 0, [
                 ...takeRangeOfLoanees(loanees, i, i + batch),
                 fillUser.borrowerAccounts.userMetadata.publicKey,
             ]);
@@ -346,7 +346,7 @@ describe('tests_redemption', () => {
                 ...takeRangeOfLoanees(loanees, i, i + batch),
                 fillUser.borrowerAccounts.userMetadata.publicKey,
             ];
-            await clearWithRetry(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, 0, borrowerAndFillerMetadatas);
+            await clearWithRetry(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, pythPrices, 0, borrowerAndFillerMetadatas);
         }
 
         await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, 0);
@@ -464,49 +464,40 @@ describe('tests_redemption', () => {
             ["ETH", 100],
         ]));
 
-        // fill redemption orders queue
-        for (let i = 0; i < REDEMPTION_ORDERS_QUEUE_SIZE; i++) {
+        // add a batch of redemption orders
+        for (let i = 0; i < REDEMPTION_ORDERS_BATCH_SIZE; i++) {
             await operations_redemption.add_redemption_order(provider, program, borrowingGlobalAccounts, redemptionUser1, pythPrices,
-                user1RedeemAmount / REDEMPTION_ORDERS_QUEUE_SIZE);
+                user1RedeemAmount / REDEMPTION_ORDERS_BATCH_SIZE);
         }
 
-        // redemption queue full
-        await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, REDEMPTION_ORDERS_QUEUE_SIZE);
+        // whole batch pending
+        await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, REDEMPTION_ORDERS_BATCH_SIZE);
         await assertBurningVaultBalance(provider, borrowingGlobalAccounts.burningVault, user1RedeemAmount);
         await assertBorrowerBalance(provider, program, redemptionUser1.borrower, redemptionUser1.borrowerAccounts, borrowingGlobalAccounts, 0, redemptionUser1.borrowerInitialBalance, 0);
 
-        // adding more orders is forbidden
-        console.log("ERROR BELOW IS EXPECTED 1...");
-        await expect(
-            operations_redemption.add_redemption_order(provider, program, borrowingGlobalAccounts,
-                redemptionUser2, pythPrices, user2RedeemAmount / REDEMPTION_ORDERS_QUEUE_SIZE
-            ), `Attempting to add order to full queue was not rejected`)
-            .to.be.rejected;
-        console.log(`ERROR ABOVE WAS EXPECTED!`)
-
         // fill and clear all orders - add new order after each clear
-        for (let i = 0; i < REDEMPTION_ORDERS_QUEUE_SIZE; i++) {
+        for (let i = 0; i < REDEMPTION_ORDERS_BATCH_SIZE; i++) {
             await operations_redemption.fill_redemption_order(provider, program, borrowingGlobalAccounts, fillUser, i,
                 takeRangeOfLoanees([loanee1, loanee2], 0, 2)
             );
-            await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser1, i, [
+            await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser1, pythPrices, i, [
                 ...takeRangeOfLoanees([loanee1, loanee2], 0, 2),
                 fillUser.borrowerAccounts.userMetadata.publicKey,
             ]);
-            await operations_redemption.add_redemption_order(provider, program, borrowingGlobalAccounts, redemptionUser2, pythPrices, user2RedeemAmount / REDEMPTION_ORDERS_QUEUE_SIZE);
+            await operations_redemption.add_redemption_order(provider, program, borrowingGlobalAccounts, redemptionUser2, pythPrices, user2RedeemAmount / REDEMPTION_ORDERS_BATCH_SIZE);
         }
 
-        // redemption queue still full
-        await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, REDEMPTION_ORDERS_QUEUE_SIZE);
+        // second batch pending
+        await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, REDEMPTION_ORDERS_BATCH_SIZE);
         await assertBurningVaultBalance(provider, borrowingGlobalAccounts.burningVault, user2RedeemAmount);
         await assertBorrowerBalance(provider, program, redemptionUser2.borrower, redemptionUser2.borrowerAccounts, borrowingGlobalAccounts, 0, redemptionUser2.borrowerInitialBalance, 0);
 
-        // fill and clear all orders
-        for (let i = 0; i < REDEMPTION_ORDERS_QUEUE_SIZE; i++) {
+        // fill and clear all orders, order ids keep counting up from the first batch
+        for (let i = REDEMPTION_ORDERS_BATCH_SIZE; i < 2 * REDEMPTION_ORDERS_BATCH_SIZE; i++) {
             await operations_redemption.fill_redemption_order(provider, program, borrowingGlobalAccounts, fillUser, i,
                 takeRangeOfLoanees([loanee1, loanee2], 0, 2)
             )
-            await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser2, i, [
+            await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser2, pythPrices, i, [
                 ...takeRangeOfLoanees([loanee1, loanee2], 0, 2),
                 fillUser.borrowerAccounts.userMetadata.publicKey,
             ]);
@@ -522,7 +513,7 @@ describe('tests_redemption', () => {
         await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, 1);
 
         // fill order
-        await operations_redemption.fill_redemption_order(provider, program, borrowingGlobalAccounts, fillUser, 0,
+        await operations_redemption.fill_redemption_order(provider, program, borrowingGlobalAccounts, fillUser, 2 * REDEMPTION_ORDERS_BATCH_SIZE,
             takeRangeOfLoanees([loanee1, loanee2], 1, 2)
         )
 
@@ -533,17 +524,17 @@ describe('tests_redemption', () => {
         await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, 2);
 
         // clear the active order
-        await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser3, 0, [
+        await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser3, pythPrices, 2 * REDEMPTION_ORDERS_BATCH_SIZE, [
             ...takeRangeOfLoanees([loanee1, loanee2], 1, 2),
             fillUser.borrowerAccounts.userMetadata.publicKey,
         ]);
 
         // fill and clear the final order
-        await operations_redemption.fill_redemption_order(provider, program, borrowingGlobalAccounts, fillUser, 1,
+        await operations_redemption.fill_redemption_order(provider, program, borrowingGlobalAccounts, fillUser, 2 * REDEMPTION_ORDERS_BATCH_SIZE + 1,
             takeRangeOfLoanees([loanee1, loanee2], 1, 2)
         )
 
-        await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser3, 1, [
+        await waitAndClear(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser3, pythPrices, 2 * REDEMPTION_ORDERS_BATCH_SIZE + 1, [
             ...takeRangeOfLoanees([loanee1, loanee2], 1, 2),
             fillUser.borrowerAccounts.userMetadata.publicKey,
         ]);
@@ -718,7 +709,7 @@ describe('tests_redemption', () => {
             [loanee.borrowerAccounts.userMetadata.publicKey]
         );
         await expect(waitAndClear(provider, program, borrowingGlobalAccounts,
-            clearUser, redemptionUser, 0, fillerRepeated
+            clearUser, redemptionUser, pythPrices, 0, fillerRepeated
         )).to.be.rejected;
         console.log(`ABOVE ERRORS WERE EXPECTED!`)
 
@@ -889,7 +880,7 @@ describe('tests_redemption', () => {
                 ...takeRangeOfLoanees(loanees, i, i + batch),
                 ...takeRangeOfLoanees(fillUsers, i, i + batch),
             ];
-            await clearWithRetry(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, 0, borrowerAndFillerMetadatas);
+            await clearWithRetry(provider, program, borrowingGlobalAccounts, clearUser, redemptionUser, pythPrices, 0, borrowerAndFillerMetadatas);
         }
 
         await assertRedemptionsQueueSize(provider, program, borrowingGlobalAccounts, 0);
@@ -984,11 +975,12 @@ export const waitAndClear = async (provider: anchor.Provider,
     borrowingGlobalAccounts: BorrowingGlobalAccounts,
     clearer: BorrowingUserState,
     redeemer: BorrowingUserState,
+    pythPrices: set_up.PythPrices,
     orderId: number,
     borrowerAndFillerMetadatas: PublicKey[],
 ): Promise<TransactionSignature> => {
     await waitToClear();
-    return clearWithRetry(provider, program, borrowingGlobalAccounts, clearer, redeemer, orderId, borrowerAndFillerMetadatas);
+    return clearWithRetry(provider, program, borrowingGlobalAccounts, clearer, redeemer, pythPrices, orderId, borrowerAndFillerMetadatas);
 }
 
 const clearWithRetry = async (provider: anchor.Provider,
@@ -996,6 +988,7 @@ const clearWithRetry = async (provider: anchor.Provider,
     borrowingGlobalAccounts: BorrowingGlobalAccounts,
     clearer: BorrowingUserState,
     redeemer: BorrowingUserState,
+    pythPrices: set_up.PythPrices,
     orderId: number,
     borrowerAndFillerMetadatas: PublicKey[],
 ): Promise<TransactionSignature> => {
@@ -1004,7 +997,7 @@ const clearWithRetry = async (provider: anchor.Provider,
 
     while (true) {
         try {
-            return await operations_redemption.clear_redemption_order(provider, program, borrowingGlobalAccounts, clearer, redeemer, orderId, borrowerAndFillerMetadatas);
+            return await operations_redemption.clear_redemption_order(provider, program, borrowingGlobalAccounts, clearer, redeemer, pythPrices, orderId, borrowerAndFillerMetadatas);
         } catch (e) {
             lastErr = e;
         }
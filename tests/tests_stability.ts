@@ -93,7 +93,8 @@ describe('tests_stability', () => {
             [user],
             user.publicKey,
             program,
-            borrowingAccounts
+            borrowingAccounts,
+            stabilityPoolAccounts
         );
 
         await instructions_stability.approveStability(
@@ -121,7 +122,8 @@ describe('tests_stability', () => {
             [user],
             user.publicKey,
             program,
-            borrowingAccounts
+            borrowingAccounts,
+            stabilityPoolAccounts
         );
 
         await instructions_stability.approveStability(
@@ -178,7 +180,8 @@ describe('tests_stability', () => {
             [user],
             user.publicKey,
             program,
-            borrowingAccounts
+            borrowingAccounts,
+            stabilityPoolAccounts
         );
 
         await instructions_stability.approveStability(
@@ -73,6 +73,7 @@ describe('tests_staking', () => {
             initialMarketOwner,
             borrowingAccounts.borrowingMarketState.publicKey,
             borrowingAccounts.stakingPoolState,
+            stakingAccounts.boostCheckpoints,
             stakingAccounts.stakingVault,
             stakingAccounts.treasuryVault,
             treasuryFeeRate
@@ -213,6 +214,7 @@ describe('tests_staking', () => {
             user.publicKey,
             borrowingMarketAccounts.borrowingMarketState.publicKey,
             borrowingMarketAccounts.borrowingVaults.publicKey,
+            borrowingMarketAccounts.globalConfig.publicKey,
             borrowingMarketAccounts.stakingPoolState.publicKey,
             userStakingPoolAccounts.userStakingState.publicKey,
             userStakingPoolAccounts.userHbbAta,
@@ -266,6 +268,7 @@ describe('tests_staking', () => {
             user.publicKey,
             borrowingMarketAccounts.borrowingMarketState.publicKey,
             borrowingMarketAccounts.borrowingVaults.publicKey,
+            borrowingMarketAccounts.globalConfig.publicKey,
             borrowingMarketAccounts.stakingPoolState.publicKey,
             userStakingPoolAccounts.userStakingState.publicKey,
             userStakingPoolAccounts.userHbbAta,
@@ -292,6 +295,7 @@ describe('tests_staking', () => {
             alice.publicKey,
             borrowingMarketAccounts.borrowingMarketState.publicKey,
             borrowingMarketAccounts.borrowingVaults.publicKey,
+            borrowingMarketAccounts.globalConfig.publicKey,
             borrowingMarketAccounts.stakingPoolState.publicKey,
             aliceStakingPoolAccounts.userStakingState.publicKey,
             aliceStakingPoolAccounts.userHbbAta,
@@ -321,6 +325,7 @@ describe('tests_staking', () => {
                 user.publicKey,
                 borrowingMarketAccounts.borrowingMarketState.publicKey,
                 borrowingMarketAccounts.borrowingVaults.publicKey,
+                borrowingMarketAccounts.globalConfig.publicKey,
                 borrowingMarketAccounts.stakingPoolState.publicKey,
                 userStakingPoolAccounts.userStakingState.publicKey,
                 userStakingPoolAccounts.userHbbAta,
@@ -351,7 +356,7 @@ describe('tests_staking', () => {
         ]));
 
         const depositSol = 5;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts, pythPrices);
         await sleep(1000);
         await assertGlobalCollateral(
             program,
@@ -398,7 +403,7 @@ describe('tests_staking', () => {
     //         const { borrower, borrowerAccounts } = await operations_borrowing.newBorrowingUser(provider, program, 10000, borrowingMarketAccounts);
 
     //         const depositSol = 9000;
-    //         await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts);
+    //         await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts, pythPrices);
     //         await sleep(500);
     //         await assertGlobalCollateral(program, provider, borrowingMarketAccounts.borrowingMarketState.publicKey, 9000 * (i + 1));
 
@@ -434,7 +439,7 @@ describe('tests_staking', () => {
         ]));
 
         const depositSol = 10;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts, pythPrices);
         await sleep(1000);
         await assertGlobalCollateral(
             program,
@@ -504,6 +509,7 @@ describe('tests_staking', () => {
             user.publicKey,
             borrowingMarketAccounts.borrowingMarketState.publicKey,
             borrowingMarketAccounts.borrowingVaults.publicKey,
+            borrowingMarketAccounts.globalConfig.publicKey,
             borrowingMarketAccounts.stakingPoolState.publicKey,
             userStakingPoolAccounts.userStakingState.publicKey,
             userStakingPoolAccounts.userStablecoinAta,
@@ -521,6 +527,7 @@ describe('tests_staking', () => {
             user.publicKey,
             borrowingMarketAccounts.borrowingMarketState.publicKey,
             borrowingMarketAccounts.borrowingVaults.publicKey,
+            borrowingMarketAccounts.globalConfig.publicKey,
             borrowingMarketAccounts.stakingPoolState.publicKey,
             userStakingPoolAccounts.userStakingState.publicKey,
             userStakingPoolAccounts.userStablecoinAta,
@@ -542,7 +549,7 @@ describe('tests_staking', () => {
         ]));
 
         const depositSol = 10;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts, pythPrices);
         await sleep(1000);
         await assertGlobalCollateral(
             program, provider,
@@ -569,7 +576,7 @@ describe('tests_staking', () => {
         assert.strictEqual(Number.parseInt(treasuryVaultBalance.value.amount), utils.decimalToU64(0.225), 'TREASURY VAULT BALANCE');
         await assertStakingPoolBalance(provider, program, borrowingMarketAccounts, stakingPoolAccounts, hbbToStake, 1.275, 1.275, 1.275 / 10);
 
-        await instructions_staking.unstake(program, user.publicKey, borrowingMarketAccounts.borrowingMarketState.publicKey, borrowingMarketAccounts.borrowingVaults.publicKey, borrowingMarketAccounts.stakingPoolState.publicKey, userStakingPoolAccounts.userStakingState.publicKey, userStakingPoolAccounts.userHbbAta, userStakingPoolAccounts.userStablecoinAta, stakingPoolAccounts.stakingVault, borrowingMarketAccounts.borrowingFeesVault, [user], hbbToStake);
+        await instructions_staking.unstake(program, user.publicKey, borrowingMarketAccounts.borrowingMarketState.publicKey, borrowingMarketAccounts.borrowingVaults.publicKey, borrowingMarketAccounts.globalConfig.publicKey, borrowingMarketAccounts.stakingPoolState.publicKey, userStakingPoolAccounts.userStakingState.publicKey, userStakingPoolAccounts.userHbbAta, userStakingPoolAccounts.userStablecoinAta, stakingPoolAccounts.stakingVault, borrowingMarketAccounts.borrowingFeesVault, [user], hbbToStake);
 
         await assertStakerBalance(provider, program, user.publicKey, borrowingMarketAccounts, hbbToStake, 1.275);
         {
@@ -629,7 +636,7 @@ describe('tests_staking', () => {
         ]));
 
         const depositSol = 10;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts, pythPrices);
         await sleep(1000);
         await assertGlobalCollateral(
             program,
@@ -693,7 +700,7 @@ describe('tests_staking', () => {
         ]));
 
         const depositSol = 10;
-        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts);
+        await operations_borrowing.depositCollateral(provider, program, depositSol, borrower, borrowerAccounts, borrowingMarketAccounts, pythPrices);
         await sleep(1000);
         await assertGlobalCollateral(
             program,
@@ -299,6 +299,8 @@ describe("serum_swap", () => {
             bobAccounts.btcAta,
             borrowingGlobalAccounts.borrowingMarketState.publicKey,
             borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
+            pythPrices,
             collToLamports(depositBtc, "BTC"),
             [bob],
             "BTC"
@@ -979,6 +981,8 @@ export async function setupBorrowingMarketSingle(provider: anchor.Provider, prog
         userAta,
         borrowingGlobalAccounts.borrowingMarketState.publicKey,
         borrowingGlobalAccounts.borrowingVaults.publicKey,
+        borrowingGlobalAccounts.stakingPoolState.publicKey,
+        pythPrices,
         collToLamports(depositAsset, asset),
         [user],
         asset
@@ -1061,6 +1065,8 @@ export async function setupBorrowingMarketMultiple(provider: anchor.Provider, pr
             userAta,
             borrowingGlobalAccounts.borrowingMarketState.publicKey,
             borrowingGlobalAccounts.borrowingVaults.publicKey,
+            borrowingGlobalAccounts.stakingPoolState.publicKey,
+            pythPrices,
             collToLamports(depositAsset, collateral[i]),
             [user],
             collateral[i]
@@ -18,6 +18,7 @@ export type BorrowingMarketState = {
     hbbMintAuthority: PublicKey,
     hbbMintSeed: number,
     redemptionsQueue: PublicKey,
+    sortedTroves: PublicKey,
 };
 
 export type UserMetadata = {
@@ -88,6 +89,7 @@ export type CollateralAmounts = {
 }
 
 export type RedemptionOrder = {
+    address: PublicKey,
     id: number,
     status: number;
     lastReset: number;
@@ -96,6 +98,7 @@ export type RedemptionOrder = {
     requestedAmount: number;
     remainingAmount: number;
     redemptionPrices: TokenMap;
+    candidatePages: PublicKey[];
     candidateUsers: CandidateRedemptionUser[];
 };
 
@@ -129,6 +132,9 @@ export type StabilityVaults = {
     liquidationRewardsVaultFtt: PublicKey,
     liquidationRewardsVaultAuthority: PublicKey,
     liquidationRewardsVaultSeed: number,
+    stabilityReceiptMint: PublicKey,
+    stabilityReceiptMintAuthority: PublicKey,
+    stabilityReceiptMintSeed: number,
     srmMint: PublicKey,
     ethMint: PublicKey,
     btcMint: PublicKey,
@@ -151,6 +157,8 @@ export type StakingPoolState = {
     stakingVault: PublicKey,
     stakingVaultAuthority: PublicKey,
     stakingVaultSeed: number,
+    treasuryVault: PublicKey,
+    boostCheckpoints: PublicKey,
 };
 
 export type UserStakingState = {